@@ -0,0 +1,359 @@
+//! Khafi Admin
+//!
+//! Shared protection for operator-only endpoints (reindex, manual polls,
+//! payment insertion, registration retries). Wrap a service's admin routes
+//! in [`AdminLayer`] so every service authenticates them the same way:
+//!
+//! - Requests must carry `Authorization: Bearer <ADMIN_TOKEN>` (401 otherwise)
+//! - Without `ADMIN_TOKEN` the admin routes are disabled (503)
+//! - All admin routes of a service share one rate limit (429 with `Retry-After`)
+//!
+//! Rejections use the services' error envelope, `{ "error": "..." }`.
+
+use anyhow::{Context, Result};
+use axum::{
+    http::{header, HeaderMap, HeaderValue, Request, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context as TaskContext, Poll};
+use std::time::Instant;
+use tower::{Layer, Service};
+use tracing::warn;
+
+/// Admin requests allowed per minute when `ADMIN_RATE_LIMIT_PER_MINUTE` is unset
+pub const DEFAULT_RATE_LIMIT_PER_MINUTE: u32 = 30;
+
+/// Admin endpoint configuration
+#[derive(Clone)]
+pub struct AdminConfig {
+    /// Bearer token admin requests must present; admin routes are disabled without one
+    pub token: Option<String>,
+
+    /// Admin requests allowed per minute, across all admin routes of a service
+    pub rate_limit_per_minute: u32,
+}
+
+// Keep the token out of logged configuration
+impl std::fmt::Debug for AdminConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminConfig")
+            .field("token", &self.token.as_ref().map(|_| "<redacted>"))
+            .field("rate_limit_per_minute", &self.rate_limit_per_minute)
+            .finish()
+    }
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            token: None,
+            rate_limit_per_minute: DEFAULT_RATE_LIMIT_PER_MINUTE,
+        }
+    }
+}
+
+impl AdminConfig {
+    /// Load from `ADMIN_TOKEN` and `ADMIN_RATE_LIMIT_PER_MINUTE`
+    pub fn from_env() -> Result<Self> {
+        let rate_limit_per_minute = match std::env::var("ADMIN_RATE_LIMIT_PER_MINUTE") {
+            Ok(v) => v.parse().context("Invalid ADMIN_RATE_LIMIT_PER_MINUTE")?,
+            Err(_) => DEFAULT_RATE_LIMIT_PER_MINUTE,
+        };
+        if rate_limit_per_minute == 0 {
+            anyhow::bail!("ADMIN_RATE_LIMIT_PER_MINUTE must be greater than 0");
+        }
+
+        Ok(Self {
+            token: std::env::var("ADMIN_TOKEN")
+                .ok()
+                .filter(|t| !t.trim().is_empty()),
+            rate_limit_per_minute,
+        })
+    }
+}
+
+/// Tower layer guarding admin routes with a bearer token and a rate limit
+///
+/// Clones share one rate limit, so apply a single layer to all of a
+/// service's admin routes (e.g. with axum's `route_layer`).
+#[derive(Clone)]
+pub struct AdminLayer {
+    guard: Arc<Guard>,
+}
+
+impl AdminLayer {
+    /// Create a layer from configuration
+    pub fn new(config: AdminConfig) -> Self {
+        if config.token.is_none() {
+            warn!("ADMIN_TOKEN not set; admin endpoints are disabled");
+        }
+
+        Self {
+            guard: Arc::new(Guard {
+                token: config.token,
+                bucket: Mutex::new(TokenBucket::new(config.rate_limit_per_minute)),
+            }),
+        }
+    }
+}
+
+impl<S> Layer<S> for AdminLayer {
+    type Service = AdminService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AdminService {
+            inner,
+            guard: self.guard.clone(),
+        }
+    }
+}
+
+/// Service produced by [`AdminLayer`]
+#[derive(Clone)]
+pub struct AdminService<S> {
+    inner: S,
+    guard: Arc<Guard>,
+}
+
+impl<S, B> Service<Request<B>> for AdminService<S>
+where
+    S: Service<Request<B>, Response = Response>,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        match self.guard.check(request.headers()) {
+            Ok(()) => Box::pin(self.inner.call(request)),
+            Err(rejection) => Box::pin(async move { Ok(rejection.into_response()) }),
+        }
+    }
+}
+
+/// Shared state behind every clone of an [`AdminLayer`]
+struct Guard {
+    token: Option<String>,
+    bucket: Mutex<TokenBucket>,
+}
+
+impl Guard {
+    /// Admit a request, or say why it is rejected
+    ///
+    /// Failed authentications count against the rate limit, which bounds
+    /// how fast a token can be guessed.
+    fn check(&self, headers: &HeaderMap) -> std::result::Result<(), Rejection> {
+        let Some(expected) = &self.token else {
+            return Err(Rejection::Disabled);
+        };
+
+        self.bucket
+            .lock()
+            .unwrap()
+            .take(Instant::now())
+            .map_err(|retry_after_secs| Rejection::RateLimited { retry_after_secs })?;
+
+        if bearer_token_matches(headers, expected) {
+            Ok(())
+        } else {
+            Err(Rejection::Unauthorized)
+        }
+    }
+}
+
+/// Whether `headers` carry `Authorization: Bearer <expected>`
+///
+/// The comparison takes the same time wherever the tokens differ, so
+/// services guarding non-admin routes with their own token can reuse it.
+pub fn bearer_token_matches(headers: &HeaderMap, expected: &str) -> bool {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|token| constant_time_eq(token.as_bytes(), expected.as_bytes()))
+}
+
+/// Why an admin request was turned away
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rejection {
+    /// No `ADMIN_TOKEN` configured
+    Disabled,
+    /// Missing or wrong bearer token
+    Unauthorized,
+    /// Rate limit exhausted
+    RateLimited { retry_after_secs: u64 },
+}
+
+impl IntoResponse for Rejection {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            Rejection::Disabled => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Admin API disabled: ADMIN_TOKEN is not set",
+            ),
+            Rejection::Unauthorized => (StatusCode::UNAUTHORIZED, "Missing or invalid admin token"),
+            Rejection::RateLimited { .. } => {
+                (StatusCode::TOO_MANY_REQUESTS, "Admin rate limit exceeded")
+            }
+        };
+
+        let mut response = (status, Json(serde_json::json!({ "error": message }))).into_response();
+        if let Rejection::RateLimited { retry_after_secs } = self {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+        }
+        response
+    }
+}
+
+/// Compare secrets without exiting early on the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Token bucket allowing bursts of up to a minute's quota
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(per_minute: u32) -> Self {
+        let capacity = f64::from(per_minute.max(1));
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_sec: capacity / 60.0,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Take a token, or return the whole seconds until one is available
+    fn take(&mut self, now: Instant) -> std::result::Result<(), u64> {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - self.tokens) / self.refill_per_sec).ceil() as u64)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::post, Router};
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    fn app(token: Option<&str>, rate_limit_per_minute: u32) -> Router {
+        Router::new()
+            .route("/admin/reindex", post(|| async { "ok" }))
+            .route_layer(AdminLayer::new(AdminConfig {
+                token: token.map(str::to_string),
+                rate_limit_per_minute,
+            }))
+            .route("/health", post(|| async { "healthy" }))
+    }
+
+    async fn send(app: &Router, uri: &str, token: Option<&str>) -> Response {
+        let mut request = Request::post(uri);
+        if let Some(token) = token {
+            request = request.header("authorization", format!("Bearer {}", token));
+        }
+        app.clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_requires_admin_token() {
+        let app = app(Some("s3cret"), 100);
+
+        assert_eq!(
+            send(&app, "/admin/reindex", None).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            send(&app, "/admin/reindex", Some("wrong")).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            send(&app, "/admin/reindex", Some("s3cret")).await.status(),
+            StatusCode::OK
+        );
+
+        // Routes outside the layer are untouched
+        assert_eq!(send(&app, "/health", None).await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_without_configured_token() {
+        let app = app(None, 100);
+
+        let response = send(&app, "/admin/reindex", Some("anything")).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(json["error"].as_str().unwrap().contains("ADMIN_TOKEN"));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_applies() {
+        let app = app(Some("s3cret"), 2);
+
+        assert_eq!(
+            send(&app, "/admin/reindex", Some("s3cret")).await.status(),
+            StatusCode::OK
+        );
+        // Failed attempts use up the quota too
+        assert_eq!(
+            send(&app, "/admin/reindex", Some("guess")).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        let response = send(&app, "/admin/reindex", Some("s3cret")).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+    }
+
+    #[test]
+    fn test_token_bucket_refills() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(60);
+        bucket.last_refill = start;
+        bucket.tokens = 0.0;
+
+        assert_eq!(bucket.take(start), Err(1));
+        assert_eq!(bucket.take(start + Duration::from_millis(1500)), Ok(()));
+        assert!(bucket.take(start + Duration::from_millis(1500)).is_err());
+
+        // Idle time never banks more than a minute's quota
+        let later = start + Duration::from_secs(3600);
+        for _ in 0..60 {
+            assert_eq!(bucket.take(later), Ok(()));
+        }
+        assert!(bucket.take(later).is_err());
+    }
+}
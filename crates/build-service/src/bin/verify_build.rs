@@ -0,0 +1,50 @@
+//! Reproducible-build verifier
+//!
+//! Rebuilds the guest for a DSL file and checks it has the claimed image ID.
+//! Prints the result as JSON and exits non-zero on a mismatch.
+//!
+//! Builds with the same `BUILD_DIR`, `BUILD_COMMAND` and `BUILD_ARGS` settings
+//! as the build service, so the defaults reproduce its builds.
+
+use anyhow::{Context, Result};
+use build_service::{verify_build, Config};
+use clap::Parser;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "khafi-verify-build")]
+#[command(about = "Check a DSL rebuilds to a claimed image ID")]
+struct Cli {
+    /// DSL (JSON) the deployment was built from
+    #[arg(short, long)]
+    dsl: PathBuf,
+
+    /// Image ID the deployment claims (hex)
+    #[arg(short, long)]
+    image_id: String,
+}
+
+fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+
+    let dsl_json = std::fs::read_to_string(&cli.dsl)
+        .with_context(|| format!("Failed to read DSL file: {}", cli.dsl.display()))?;
+    let dsl: serde_json::Value =
+        serde_json::from_str(&dsl_json).context("DSL file is not valid JSON")?;
+
+    let config = Config::from_env()?;
+    config.ensure_directories()?;
+
+    let verification = verify_build(&config.worker_config(), &dsl, &cli.image_id)?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&verification).context("Failed to serialize result")?
+    );
+
+    Ok(if verification.matches {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
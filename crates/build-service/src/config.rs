@@ -0,0 +1,292 @@
+//! Configuration management for Build Service
+//!
+//! Loads configuration from environment variables with sensible defaults.
+
+use anyhow::{Context, Result};
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::worker::{WebhookRetryPolicy, WorkerConfig};
+use khafi_admin::AdminConfig;
+
+/// Application configuration
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Redis connection URL
+    pub redis_url: String,
+
+    /// Redis database index, overriding the one in `redis_url` (optional)
+    pub redis_db: Option<i64>,
+
+    /// API server host
+    pub host: String,
+
+    /// API server port
+    pub port: u16,
+
+    /// Directory where build workspaces and artifacts are written
+    pub build_dir: PathBuf,
+
+    /// Image ID Registry URL
+    pub registry_url: String,
+
+    /// Gateway URL written into generated SDKs
+    pub gateway_url: String,
+
+    /// Gzip guest ELFs before registering them
+    pub compress_elf: bool,
+
+    /// Remove build workspaces after a successful build
+    pub cleanup_after_build: bool,
+
+    /// HMAC secret for signing webhook payloads (optional)
+    pub webhook_secret: Option<String>,
+
+    /// Webhook delivery retry policy
+    pub webhook_retry: WebhookRetryPolicy,
+
+    /// Program that builds guest programs
+    pub build_command: String,
+
+    /// Arguments for `build_command`
+    pub build_args: Vec<String>,
+
+    /// Generate the next job's sources while the current job builds
+    pub prefetch_next_job: bool,
+
+    /// Token and rate limit for operator endpoints
+    pub admin: AdminConfig,
+}
+
+impl Config {
+    /// Load configuration from environment variables
+    pub fn from_env() -> Result<Self> {
+        let mut webhook_retry = WebhookRetryPolicy::default();
+        if let Some(attempts) = parse_var("WEBHOOK_MAX_ATTEMPTS")? {
+            webhook_retry.max_attempts = attempts;
+        }
+        if let Some(secs) = parse_var("WEBHOOK_TIMEOUT_SECS")? {
+            webhook_retry.attempt_timeout = Duration::from_secs(secs);
+        }
+
+        let config = Config {
+            redis_url: env::var("REDIS_URL")
+                .unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string()),
+
+            redis_db: parse_var("REDIS_DB")?,
+
+            host: env::var("BUILD_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
+
+            port: parse_var("BUILD_PORT")?.unwrap_or(8085),
+
+            build_dir: env::var("BUILD_DIR")
+                .unwrap_or_else(|_| "/tmp/builds".to_string())
+                .into(),
+
+            registry_url: env::var("REGISTRY_URL")
+                .unwrap_or_else(|_| "http://127.0.0.1:8083".to_string()),
+
+            gateway_url: env::var("GATEWAY_URL")
+                .unwrap_or_else(|_| "http://localhost:8080".to_string()),
+
+            compress_elf: env::var("COMPRESS_ELF")
+                .map(|v| v.to_lowercase() != "false" && v != "0")
+                .unwrap_or(true),
+
+            cleanup_after_build: env::var("CLEANUP_AFTER_BUILD")
+                .map(|v| v.to_lowercase() == "true" || v == "1")
+                .unwrap_or(false),
+
+            webhook_secret: env::var("WEBHOOK_SECRET").ok().filter(|s| !s.is_empty()),
+
+            webhook_retry,
+
+            build_command: env::var("BUILD_COMMAND").unwrap_or_else(|_| "cargo".to_string()),
+
+            // Whitespace-separated, e.g. "risczero build --release"
+            build_args: env::var("BUILD_ARGS")
+                .unwrap_or_else(|_| "risczero build".to_string())
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+
+            prefetch_next_job: env::var("BUILD_PREFETCH")
+                .map(|v| v.to_lowercase() == "true" || v == "1")
+                .unwrap_or(false),
+
+            admin: AdminConfig::from_env()?,
+        };
+
+        // Validate configuration
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Validate configuration
+    fn validate(&self) -> Result<()> {
+        if self.port == 0 {
+            anyhow::bail!("BUILD_PORT must be greater than 0");
+        }
+
+        if self.build_dir.exists() && !self.build_dir.is_dir() {
+            anyhow::bail!("BUILD_DIR is not a directory: {}", self.build_dir.display());
+        }
+
+        for (name, url) in [
+            ("REGISTRY_URL", &self.registry_url),
+            ("GATEWAY_URL", &self.gateway_url),
+        ] {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                anyhow::bail!("{} must be an http(s) URL, got {}", name, url);
+            }
+        }
+
+        if self.build_command.trim().is_empty() {
+            anyhow::bail!("BUILD_COMMAND must not be empty");
+        }
+
+        if self.webhook_retry.max_attempts == 0 {
+            anyhow::bail!("WEBHOOK_MAX_ATTEMPTS must be greater than 0");
+        }
+
+        Ok(())
+    }
+
+    /// Get the API server address
+    pub fn api_address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    /// Ensure the build directory exists
+    pub fn ensure_directories(&self) -> Result<()> {
+        std::fs::create_dir_all(&self.build_dir).with_context(|| {
+            format!(
+                "Failed to create build directory: {}",
+                self.build_dir.display()
+            )
+        })
+    }
+
+    /// Worker settings derived from this configuration
+    pub fn worker_config(&self) -> WorkerConfig {
+        WorkerConfig {
+            build_dir: self.build_dir.clone(),
+            registry_url: self.registry_url.clone(),
+            gateway_url: self.gateway_url.clone(),
+            num_workers: 1,
+            compress_elf: self.compress_elf,
+            cleanup_after_build: self.cleanup_after_build,
+            webhook_secret: self.webhook_secret.clone(),
+            webhook_retry: self.webhook_retry.clone(),
+            build_command: self.build_command.clone(),
+            build_args: self.build_args.clone(),
+            prefetch_next_job: self.prefetch_next_job,
+        }
+    }
+}
+
+/// Parse an optional environment variable, failing on malformed values
+fn parse_var<T>(name: &str) -> Result<Option<T>>
+where
+    T: std::str::FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    env::var(name)
+        .ok()
+        .map(|v| v.parse())
+        .transpose()
+        .with_context(|| format!("Invalid {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // Tests share process environment variables
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    const VARS: &[&str] = &[
+        "REDIS_URL",
+        "REDIS_DB",
+        "BUILD_HOST",
+        "BUILD_PORT",
+        "BUILD_DIR",
+        "REGISTRY_URL",
+        "GATEWAY_URL",
+        "COMPRESS_ELF",
+        "CLEANUP_AFTER_BUILD",
+        "WEBHOOK_SECRET",
+        "WEBHOOK_MAX_ATTEMPTS",
+        "WEBHOOK_TIMEOUT_SECS",
+        "BUILD_COMMAND",
+        "BUILD_ARGS",
+        "BUILD_PREFETCH",
+        "ADMIN_TOKEN",
+        "ADMIN_RATE_LIMIT_PER_MINUTE",
+    ];
+
+    #[test]
+    fn test_config_defaults() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        for var in VARS {
+            env::remove_var(var);
+        }
+
+        let config = Config::from_env().expect("Failed to load config");
+
+        assert_eq!(config.redis_url, "redis://127.0.0.1:6379");
+        assert_eq!(config.redis_db, None);
+        assert_eq!(config.api_address(), "0.0.0.0:8085");
+        assert_eq!(config.build_dir, PathBuf::from("/tmp/builds"));
+        assert_eq!(config.registry_url, "http://127.0.0.1:8083");
+        assert!(config.compress_elf);
+        assert!(!config.cleanup_after_build);
+        assert!(config.webhook_secret.is_none());
+        assert_eq!(config.webhook_retry.max_attempts, 5);
+        assert_eq!(config.build_command, "cargo");
+        assert_eq!(config.build_args, ["risczero", "build"]);
+        assert!(!config.prefetch_next_job);
+        assert!(config.admin.token.is_none());
+        assert_eq!(config.admin.rate_limit_per_minute, 30);
+    }
+
+    #[test]
+    fn test_custom_build_command() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        for var in VARS {
+            env::remove_var(var);
+        }
+
+        env::set_var("BUILD_COMMAND", "/usr/local/bin/build-guest");
+        env::set_var("BUILD_ARGS", "--release  --features ci");
+        let worker = Config::from_env().unwrap().worker_config();
+        assert_eq!(worker.build_command, "/usr/local/bin/build-guest");
+        assert_eq!(worker.build_args, ["--release", "--features", "ci"]);
+
+        env::set_var("BUILD_COMMAND", " ");
+        assert!(Config::from_env().is_err());
+
+        env::remove_var("BUILD_COMMAND");
+        env::remove_var("BUILD_ARGS");
+    }
+
+    #[test]
+    fn test_invalid_port_rejected() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        for var in VARS {
+            env::remove_var(var);
+        }
+
+        env::set_var("BUILD_PORT", "not-a-port");
+        let err = Config::from_env().unwrap_err();
+        assert!(err.to_string().contains("BUILD_PORT"));
+
+        env::set_var("BUILD_PORT", "0");
+        assert!(Config::from_env().is_err());
+
+        env::remove_var("BUILD_PORT");
+    }
+}
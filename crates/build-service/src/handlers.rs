@@ -1,24 +1,47 @@
 //! API handlers for Build Service
+//!
+//! Successful JSON responses are wrapped as `{ "data": ... }` and failures
+//! as `{ "error": "..." }`.
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    response::{IntoResponse, Response},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     Json,
 };
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+use std::convert::Infallible;
+use std::path::Path as FsPath;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
-use tracing::info;
+use tracing::{info, warn};
 use uuid::Uuid;
 
 use crate::{
-    models::{BuildJob, BuildStatusResponse, QueueBuildRequest, QueueBuildResponse},
-    storage::Storage,
+    models::{
+        BuildJob, BuildStatus, BuildStatusEvent, BuildStatusResponse, CustomerJobsQuery,
+        QueueBuildRequest, QueueBuildResponse,
+    },
+    storage::{JobStore, Storage},
+    worker,
 };
 
 /// Shared application state
-pub struct AppState {
-    pub storage: Mutex<Storage>,
+///
+/// Generic over the job store; the service itself uses Redis [`Storage`].
+pub struct AppState<S = Storage> {
+    pub storage: Mutex<S>,
+
+    /// Image ID Registry URL, for retrying registration
+    pub registry_url: String,
+
+    /// HTTP client for the Image ID Registry
+    pub http_client: reqwest::Client,
 }
 
 /// API Error type
@@ -38,6 +61,25 @@ impl IntoResponse for ApiError {
     }
 }
 
+/// Successful response envelope
+#[derive(Debug, Serialize)]
+pub struct ApiResponse<T> {
+    pub data: T,
+}
+
+impl<T> ApiResponse<T> {
+    /// Wrap a response payload
+    pub fn new(data: T) -> Self {
+        Self { data }
+    }
+}
+
+impl<T: Serialize> IntoResponse for ApiResponse<T> {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
 impl From<anyhow::Error> for ApiError {
     fn from(err: anyhow::Error) -> Self {
         ApiError {
@@ -47,19 +89,54 @@ impl From<anyhow::Error> for ApiError {
     }
 }
 
+/// How long the health check waits on the Image ID Registry
+const REGISTRY_HEALTH_TIMEOUT: Duration = Duration::from_secs(2);
+
 /// Health check
-pub async fn health_handler() -> impl IntoResponse {
-    Json(serde_json::json!({
-        "status": "healthy",
-        "service": "build-service"
+///
+/// Reports "degraded" while the Image ID Registry is unreachable, since
+/// completed builds can't be registered until it's back.
+pub async fn health_handler<S: JobStore>(
+    State(state): State<Arc<AppState<S>>>,
+) -> ApiResponse<serde_json::Value> {
+    let registry = match state
+        .http_client
+        .get(format!("{}/health", state.registry_url))
+        .timeout(REGISTRY_HEALTH_TIMEOUT)
+        .send()
+        .await
+    {
+        Ok(response) if response.status().is_success() => {
+            serde_json::json!({ "status": "healthy" })
+        }
+        Ok(response) => serde_json::json!({
+            "status": "unhealthy",
+            "error": format!("Registry returned {}", response.status())
+        }),
+        Err(e) => serde_json::json!({
+            "status": "unhealthy",
+            "error": format!("Registry unreachable: {}", e)
+        }),
+    };
+    let status = if registry["status"] == "healthy" {
+        "healthy"
+    } else {
+        "degraded"
+    };
+
+    ApiResponse::new(serde_json::json!({
+        "status": status,
+        "service": "build-service",
+        "dependencies": { "registry": registry }
     }))
 }
 
 /// Queue a new build job
-pub async fn queue_build_handler(
-    State(state): State<Arc<AppState>>,
+#[tracing::instrument(skip_all, fields(customer_id = %payload.customer_id))]
+pub async fn queue_build_handler<S: JobStore>(
+    State(state): State<Arc<AppState<S>>>,
     Json(payload): Json<QueueBuildRequest>,
-) -> Result<Json<QueueBuildResponse>, ApiError> {
+) -> Result<ApiResponse<QueueBuildResponse>, ApiError> {
     info!("Queueing build for customer: {}", payload.customer_id);
 
     // Generate job ID
@@ -72,6 +149,7 @@ pub async fn queue_build_handler(
         payload.dsl,
     );
     job.webhook_url = payload.webhook_url;
+    job.trace_context = khafi_telemetry::current_trace_context();
 
     // Queue job
     let mut storage = state.storage.lock().await;
@@ -79,7 +157,7 @@ pub async fn queue_build_handler(
 
     info!("Build job queued: {} for customer: {}", job_id, payload.customer_id);
 
-    Ok(Json(QueueBuildResponse {
+    Ok(ApiResponse::new(QueueBuildResponse {
         success: true,
         job_id: Some(job_id),
         error: None,
@@ -87,17 +165,17 @@ pub async fn queue_build_handler(
 }
 
 /// Get build job status
-pub async fn get_job_status_handler(
-    State(state): State<Arc<AppState>>,
+pub async fn get_job_status_handler<S: JobStore>(
+    State(state): State<Arc<AppState<S>>>,
     Path(job_id): Path<String>,
-) -> Result<Json<BuildStatusResponse>, ApiError> {
+) -> Result<ApiResponse<BuildStatusResponse>, ApiError> {
     info!("Getting status for job: {}", job_id);
 
     let mut storage = state.storage.lock().await;
     let job = storage.get_job(&job_id).await?;
 
     match job {
-        Some(j) => Ok(Json(BuildStatusResponse { job: j })),
+        Some(j) => Ok(ApiResponse::new(BuildStatusResponse { job: j })),
         None => Err(ApiError {
             status: StatusCode::NOT_FOUND,
             message: format!("Job not found: {}", job_id),
@@ -105,34 +183,530 @@ pub async fn get_job_status_handler(
     }
 }
 
-/// Get all jobs for a customer
-pub async fn get_customer_jobs_handler(
-    State(state): State<Arc<AppState>>,
+/// Retry Image ID Registry registration for a completed build
+///
+/// Uses the job's stored image ID and ELF path, so nothing is rebuilt.
+/// Returns the updated job; `registration_error` is cleared on success.
+#[tracing::instrument(skip_all, fields(job_id = %job_id))]
+pub async fn register_job_handler<S: JobStore>(
+    State(state): State<Arc<AppState<S>>>,
+    Path(job_id): Path<String>,
+) -> Result<ApiResponse<BuildStatusResponse>, ApiError> {
+    info!("Retrying registration for job: {}", job_id);
+
+    let mut job = {
+        let mut storage = state.storage.lock().await;
+        storage.get_job(&job_id).await?
+    }
+    .ok_or_else(|| ApiError {
+        status: StatusCode::NOT_FOUND,
+        message: format!("Job not found: {}", job_id),
+    })?;
+
+    let conflict = |message: String| ApiError {
+        status: StatusCode::CONFLICT,
+        message,
+    };
+    if job.status != BuildStatus::Completed {
+        return Err(conflict(format!(
+            "Job {} is {:?}, only completed jobs can be registered",
+            job_id, job.status
+        )));
+    }
+    if job.registration_error.is_none() {
+        return Err(conflict(format!("Job {} is already registered", job_id)));
+    }
+    let (Some(image_id), Some(elf_path)) = (job.image_id.clone(), job.elf_path.clone()) else {
+        return Err(conflict(format!(
+            "Job {} has no stored build output",
+            job_id
+        )));
+    };
+    let elf_path = FsPath::new(&elf_path);
+    if !elf_path.exists() {
+        return Err(conflict(format!(
+            "Guest ELF for job {} no longer exists: {}",
+            job_id,
+            elf_path.display()
+        )));
+    }
+
+    let result = worker::register_deployment(
+        &state.http_client,
+        &state.registry_url,
+        &job,
+        &image_id,
+        elf_path,
+        worker::elf_compression(elf_path),
+    )
+    .await;
+
+    job.registration_error = result.as_ref().err().map(|e| e.to_string());
+    state.storage.lock().await.update_job(&job).await?;
+
+    if let Err(e) = result {
+        warn!("Registration retry failed for job {}: {}", job_id, e);
+        return Err(ApiError {
+            status: StatusCode::BAD_GATEWAY,
+            message: e.to_string(),
+        });
+    }
+
+    Ok(ApiResponse::new(BuildStatusResponse { job }))
+}
+
+/// Stream a job's status transitions as Server-Sent Events
+///
+/// Emits the current status first, then each transition published by the
+/// worker, and closes once the job reaches a terminal state.
+pub async fn watch_job_handler<S: JobStore>(
+    State(state): State<Arc<AppState<S>>>,
+    Path(job_id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    info!("Watching job: {}", job_id);
+
+    let mut storage = state.storage.lock().await;
+
+    // Subscribe before reading the job so no transition is missed in between
+    let updates = storage.subscribe_job_events(&job_id).await?;
+    let job = storage.get_job(&job_id).await?.ok_or_else(|| ApiError {
+        status: StatusCode::NOT_FOUND,
+        message: format!("Job not found: {}", job_id),
+    })?;
+    drop(storage);
+
+    let current = BuildStatusEvent::from_job(&job);
+    let events = futures::stream::once(async move { current })
+        .chain(updates)
+        .scan(false, |done, event| {
+            // Stop after the first terminal event
+            if *done {
+                return futures::future::ready(None);
+            }
+            *done = event.status.is_terminal();
+            futures::future::ready(Some(event))
+        })
+        .map(|event| {
+            Ok(Event::default()
+                .event("status")
+                .json_data(&event)
+                .unwrap_or_else(|_| Event::default().event("status")))
+        });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// Get a page of jobs for a customer
+///
+/// Supports `limit`, `offset` and `status` query parameters. Offsets above
+/// [`CustomerJobsQuery::MAX_OFFSET`] are rejected with 400.
+pub async fn get_customer_jobs_handler<S: JobStore>(
+    State(state): State<Arc<AppState<S>>>,
     Path(customer_id): Path<String>,
-) -> Result<Json<serde_json::Value>, ApiError> {
-    info!("Getting jobs for customer: {}", customer_id);
+    Query(query): Query<CustomerJobsQuery>,
+) -> Result<ApiResponse<serde_json::Value>, ApiError> {
+    info!("Getting jobs for customer: {} ({:?})", customer_id, query);
+
+    query.validate().map_err(|message| ApiError {
+        status: StatusCode::BAD_REQUEST,
+        message,
+    })?;
 
     let mut storage = state.storage.lock().await;
-    let jobs = storage.get_customer_jobs(&customer_id).await?;
+    let page = storage.get_customer_jobs(&customer_id, &query).await?;
 
-    Ok(Json(serde_json::json!({
+    Ok(ApiResponse::new(serde_json::json!({
         "customer_id": customer_id,
-        "jobs": jobs,
-        "total": jobs.len()
+        "jobs": page.jobs,
+        "total": page.total,
+        "limit": query.limit(),
+        "offset": query.offset()
     })))
 }
 
 /// Get service stats
-pub async fn get_stats_handler(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<serde_json::Value>, ApiError> {
+pub async fn get_stats_handler<S: JobStore>(
+    State(state): State<Arc<AppState<S>>>,
+) -> Result<ApiResponse<serde_json::Value>, ApiError> {
     let mut storage = state.storage.lock().await;
     let stats = storage.get_stats().await?;
     let queue_len = storage.queue_length().await?;
 
-    Ok(Json(serde_json::json!({
+    Ok(ApiResponse::new(serde_json::json!({
         "service": "build-service",
         "queue_length": queue_len,
         "stats": stats
     })))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{BuildStats, CustomerJobsPage};
+    use futures::stream::BoxStream;
+    use std::collections::HashMap;
+    use std::sync::Mutex as StdMutex;
+
+    /// In-memory [`JobStore`] for handler tests
+    ///
+    /// Publishes no status events, so watch streams end after the current status.
+    #[derive(Default)]
+    struct MemoryStore {
+        jobs: HashMap<String, BuildJob>,
+        queue: Vec<String>,
+    }
+
+    #[async_trait::async_trait]
+    impl JobStore for MemoryStore {
+        async fn queue_job(&mut self, job: &BuildJob) -> anyhow::Result<()> {
+            self.jobs.insert(job.job_id.clone(), job.clone());
+            self.queue.push(job.job_id.clone());
+            Ok(())
+        }
+
+        async fn get_job(&mut self, job_id: &str) -> anyhow::Result<Option<BuildJob>> {
+            Ok(self.jobs.get(job_id).cloned())
+        }
+
+        async fn update_job(&mut self, job: &BuildJob) -> anyhow::Result<()> {
+            self.jobs.insert(job.job_id.clone(), job.clone());
+            Ok(())
+        }
+
+        async fn subscribe_job_events(
+            &self,
+            _job_id: &str,
+        ) -> anyhow::Result<BoxStream<'static, BuildStatusEvent>> {
+            Ok(futures::stream::empty().boxed())
+        }
+
+        async fn get_customer_jobs(
+            &mut self,
+            customer_id: &str,
+            query: &CustomerJobsQuery,
+        ) -> anyhow::Result<CustomerJobsPage> {
+            let mut matching: Vec<BuildJob> = self
+                .jobs
+                .values()
+                .filter(|job| job.customer_id == customer_id)
+                .filter(|job| query.status.is_none_or(|status| job.status == status))
+                .cloned()
+                .collect();
+            matching.sort_by_key(|job| std::cmp::Reverse(job.created_at));
+
+            let total = matching.len();
+            let jobs = matching
+                .into_iter()
+                .skip(query.offset())
+                .take(query.limit())
+                .collect();
+            Ok(CustomerJobsPage { jobs, total })
+        }
+
+        async fn queue_length(&mut self) -> anyhow::Result<usize> {
+            Ok(self.queue.len())
+        }
+
+        async fn get_stats(&mut self) -> anyhow::Result<BuildStats> {
+            Ok(BuildStats {
+                queued: self.queue.len(),
+                building: 0,
+                completed: 0,
+                failed: 0,
+            })
+        }
+    }
+
+    fn memory_state() -> Arc<AppState<MemoryStore>> {
+        Arc::new(AppState {
+            storage: Mutex::new(MemoryStore::default()),
+            registry_url: "http://127.0.0.1:1".to_string(),
+            http_client: reqwest::Client::new(),
+        })
+    }
+
+    async fn queue_build(state: &Arc<AppState<MemoryStore>>, customer_id: &str) -> String {
+        let ApiResponse { data: response } = queue_build_handler(
+            State(state.clone()),
+            Json(QueueBuildRequest {
+                customer_id: customer_id.to_string(),
+                dsl: serde_json::json!({ "use_case": "test" }),
+                webhook_url: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert!(response.success);
+        response.job_id.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_queue_then_get_status() {
+        let state = memory_state();
+        let job_id = queue_build(&state, "customer-123").await;
+
+        let ApiResponse { data: response } =
+            get_job_status_handler(State(state.clone()), Path(job_id.clone()))
+                .await
+                .unwrap();
+        assert_eq!(response.job.job_id, job_id);
+        assert_eq!(response.job.customer_id, "customer-123");
+        assert_eq!(response.job.status, BuildStatus::Queued);
+
+        let ApiResponse { data: stats } = get_stats_handler(State(state.clone())).await.unwrap();
+        assert_eq!(stats["queue_length"], 1);
+
+        let err = get_job_status_handler(State(state), Path("missing".to_string()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_customer_jobs_filtered_by_status() {
+        let state = memory_state();
+        let mut job_ids = Vec::new();
+        for _ in 0..3 {
+            job_ids.push(queue_build(&state, "customer-123").await);
+        }
+        queue_build(&state, "customer-other").await;
+
+        // Fail one job the way the worker would
+        {
+            let mut storage = state.storage.lock().await;
+            let mut job = storage.get_job(&job_ids[1]).await.unwrap().unwrap();
+            job.mark_failed("compile error".to_string()).unwrap();
+            storage.update_job(&job).await.unwrap();
+        }
+
+        let ApiResponse { data: page } = get_customer_jobs_handler(
+            State(state.clone()),
+            Path("customer-123".to_string()),
+            Query(CustomerJobsQuery::default()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(page["total"], 3);
+
+        let ApiResponse { data: page } = get_customer_jobs_handler(
+            State(state),
+            Path("customer-123".to_string()),
+            Query(CustomerJobsQuery {
+                status: Some(BuildStatus::Failed),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(page["total"], 1);
+        assert_eq!(page["jobs"][0]["job_id"], job_ids[1].as_str());
+    }
+
+    #[tokio::test]
+    async fn test_customer_jobs_rejects_out_of_range_offset() {
+        let state = memory_state();
+        queue_build(&state, "customer-123").await;
+
+        let err = get_customer_jobs_handler(
+            State(state.clone()),
+            Path("customer-123".to_string()),
+            Query(CustomerJobsQuery {
+                offset: Some(usize::MAX),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+        assert!(err.message.contains("offset"));
+
+        // The largest accepted offset is just an empty page
+        let ApiResponse { data: page } = get_customer_jobs_handler(
+            State(state),
+            Path("customer-123".to_string()),
+            Query(CustomerJobsQuery {
+                offset: Some(CustomerJobsQuery::MAX_OFFSET),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(page["total"], 1);
+        assert_eq!(page["jobs"].as_array().unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_register_requires_completed_job() {
+        let state = memory_state();
+        let job_id = queue_build(&state, "customer-123").await;
+
+        let err = register_job_handler(State(state.clone()), Path(job_id))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::CONFLICT);
+
+        let err = register_job_handler(State(state), Path("missing".to_string()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_register_route_requires_admin_token() {
+        use tower::ServiceExt;
+
+        let state = Arc::into_inner(memory_state()).unwrap();
+        let app = crate::create_router(
+            state,
+            khafi_admin::AdminConfig {
+                token: Some("s3cret".to_string()),
+                rate_limit_per_minute: 2,
+            },
+        );
+        let register = |token: Option<&str>| {
+            let mut request = axum::http::Request::post("/api/build/missing/register");
+            if let Some(token) = token {
+                request = request.header("authorization", format!("Bearer {}", token));
+            }
+            app.clone()
+                .oneshot(request.body(axum::body::Body::empty()).unwrap())
+        };
+
+        let response = register(None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            body_json(response).await["error"],
+            "Missing or invalid admin token"
+        );
+
+        // Authenticated requests reach the handler
+        let response = register(Some("s3cret")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = register(Some("s3cret")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    /// Serve a registry that records every deployment it's sent
+    async fn recording_registry() -> (String, Arc<StdMutex<Vec<serde_json::Value>>>) {
+        let received = Arc::new(StdMutex::new(Vec::new()));
+        let recorder = received.clone();
+        let app = axum::Router::new().route(
+            "/api/deployments",
+            axum::routing::post(move |Json(body): Json<serde_json::Value>| {
+                let recorder = recorder.clone();
+                async move {
+                    recorder.lock().unwrap().push(body);
+                    StatusCode::CREATED
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        (format!("http://{}", addr), received)
+    }
+
+    #[tokio::test]
+    async fn test_register_completed_but_unregistered_job() {
+        let (registry_url, received) = recording_registry().await;
+        let storage = Storage::new("redis://127.0.0.1:6379", Some(15))
+            .await
+            .expect("Failed to connect to test Redis");
+
+        // A build that finished while the registry was down
+        let build_dir = tempfile::tempdir().unwrap();
+        let elf_path = build_dir.path().join("job.elf");
+        std::fs::write(&elf_path, b"\x7fELF guest").unwrap();
+        let job_id = format!("register-{}", Uuid::new_v4());
+        let mut job = BuildJob::new(
+            job_id.clone(),
+            "customer-register".to_string(),
+            serde_json::json!({
+                "use_case": "test",
+                "private_inputs": { "type": "object", "fields": {} },
+                "public_params": {},
+                "validation_rules": []
+            }),
+        );
+        job.mark_building().unwrap();
+        job.mark_completed("ab".repeat(32), elf_path.to_string_lossy().to_string())
+            .unwrap();
+        job.registration_error = Some("Failed to connect to Image ID Registry".to_string());
+
+        let state = Arc::new(AppState {
+            storage: Mutex::new(storage),
+            registry_url,
+            http_client: reqwest::Client::new(),
+        });
+        state.storage.lock().await.update_job(&job).await.unwrap();
+
+        let ApiResponse { data: response } =
+            register_job_handler(State(state.clone()), Path(job_id.clone()))
+                .await
+                .unwrap();
+        assert!(response.job.registration_error.is_none());
+
+        let received = received.lock().unwrap().clone();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0]["image_id"], "ab".repeat(32));
+        assert_eq!(received[0]["customer_id"], "customer-register");
+        assert_eq!(
+            received[0]["guest_program_path"],
+            elf_path.to_string_lossy().as_ref()
+        );
+
+        // The stored job is updated, and a second attempt is refused
+        let stored = state
+            .storage
+            .lock()
+            .await
+            .get_job(&job_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(stored.registration_error.is_none());
+        let err = register_job_handler(State(state), Path(job_id))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::CONFLICT);
+    }
+
+    async fn body_json(response: Response) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_health_uses_data_envelope() {
+        let response = health_handler(State(memory_state())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The registry at 127.0.0.1:1 is unreachable
+        let body = body_json(response).await;
+        assert_eq!(body["data"]["service"], "build-service");
+        assert_eq!(body["data"]["status"], "degraded");
+        assert_eq!(
+            body["data"]["dependencies"]["registry"]["status"],
+            "unhealthy"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_errors_use_error_envelope() {
+        let response = get_job_status_handler(State(memory_state()), Path("missing".to_string()))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let body = body_json(response).await;
+        assert_eq!(body["error"], "Job not found: missing");
+        assert!(body.get("data").is_none());
+    }
+}
@@ -4,39 +4,67 @@
 //! Processes build jobs from a Redis queue and registers
 //! completed builds with the Image ID Registry.
 
+pub mod config;
 pub mod handlers;
 pub mod models;
 pub mod storage;
+pub mod verify;
 pub mod worker;
 
 use axum::{
     routing::{get, post},
     Router,
 };
+use khafi_admin::{AdminConfig, AdminLayer};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 
+pub use config::Config;
 pub use handlers::AppState;
-pub use models::{BuildJob, BuildStatus, QueueBuildRequest, QueueBuildResponse};
-pub use storage::Storage;
-pub use worker::{Worker, WorkerConfig};
+pub use models::{
+    BuildJob, BuildStatus, BuildStatusEvent, QueueBuildRequest, QueueBuildResponse,
+};
+pub use storage::{JobStore, Storage};
+pub use verify::{verify_build, BuildVerification};
+pub use worker::{WebhookRetryPolicy, Worker, WorkerConfig};
 
 /// Create the API router
-pub fn create_router(state: AppState) -> Router {
+///
+/// Operator endpoints (registration retries) sit behind an [`AdminLayer`]
+/// built from `admin`.
+pub fn create_router<S: JobStore>(state: AppState<S>, admin: AdminConfig) -> Router {
     let shared_state = Arc::new(state);
 
+    let admin_routes = Router::new()
+        .route(
+            "/api/build/{job_id}/register",
+            post(handlers::register_job_handler::<S>),
+        )
+        .route_layer(AdminLayer::new(admin));
+
     Router::new()
-        .route("/health", get(handlers::health_handler))
-        .route("/api/stats", get(handlers::get_stats_handler))
-        .route("/api/build", post(handlers::queue_build_handler))
-        .route("/api/build/:job_id", get(handlers::get_job_status_handler))
+        .route("/health", get(handlers::health_handler::<S>))
+        .route("/api/stats", get(handlers::get_stats_handler::<S>))
+        .route("/api/build", post(handlers::queue_build_handler::<S>))
         .route(
-            "/api/customer/:customer_id/builds",
-            get(handlers::get_customer_jobs_handler),
+            "/api/build/{job_id}",
+            get(handlers::get_job_status_handler::<S>),
         )
+        .route(
+            "/api/build/{job_id}/watch",
+            get(handlers::watch_job_handler::<S>),
+        )
+        .route(
+            "/api/customer/{customer_id}/builds",
+            get(handlers::get_customer_jobs_handler::<S>),
+        )
+        .merge(admin_routes)
         .with_state(shared_state)
         .layer(CorsLayer::permissive())
-        .layer(TraceLayer::new_for_http())
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(khafi_telemetry::http_request_span::<axum::body::Body>),
+        )
 }
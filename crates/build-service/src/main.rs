@@ -3,75 +3,51 @@
 //! REST API for queuing builds + background worker for processing them
 
 use anyhow::{Context, Result};
-use build_service::{create_router, AppState, Storage, WorkerConfig};
-use std::env;
-use std::path::PathBuf;
+use build_service::{create_router, AppState, Config, Storage};
 use tokio::sync::Mutex;
 use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "build_service=debug,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
     // Load environment variables
     dotenvy::dotenv().ok();
 
+    // Initialize tracing (exports to OTLP when OTEL_EXPORTER_OTLP_ENDPOINT is set)
+    let _telemetry = khafi_telemetry::init_tracing("build-service", "build_service=debug,tower_http=debug")?;
+
     // Configuration
-    let redis_url = env::var("REDIS_URL")
-        .unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
-    let host = env::var("BUILD_HOST")
-        .unwrap_or_else(|_| "0.0.0.0".to_string());
-    let port = env::var("BUILD_PORT")
-        .unwrap_or_else(|_| "8085".to_string());
-    let build_dir = env::var("BUILD_DIR")
-        .unwrap_or_else(|_| "/tmp/builds".to_string());
-    let registry_url = env::var("REGISTRY_URL")
-        .unwrap_or_else(|_| "http://127.0.0.1:8083".to_string());
-    let gateway_url = env::var("GATEWAY_URL")
-        .unwrap_or_else(|_| "http://localhost:8080".to_string());
+    let config = Config::from_env().context("Invalid configuration")?;
 
     info!("Starting Build Service");
-    info!("Redis URL: {}", redis_url);
-    info!("Registry URL: {}", registry_url);
-    info!("Build directory: {}", build_dir);
+    info!("Redis URL: {}", config.redis_url);
+    info!("Registry URL: {}", config.registry_url);
+    info!("Build directory: {}", config.build_dir.display());
 
     // Ensure build directory exists
-    std::fs::create_dir_all(&build_dir)
-        .context("Failed to create build directory")?;
+    config.ensure_directories()?;
 
     // Initialize storage for API
-    let api_storage = Storage::new(&redis_url)
+    let api_storage = Storage::new(&config.redis_url, config.redis_db)
         .await
         .context("Failed to initialize API storage")?;
 
     // Initialize storage for worker
-    let worker_storage = Storage::new(&redis_url)
+    let worker_storage = Storage::new(&config.redis_url, config.redis_db)
         .await
         .context("Failed to initialize worker storage")?;
 
     // Create application state
     let state = AppState {
         storage: Mutex::new(api_storage),
+        registry_url: config.registry_url.clone(),
+        http_client: reqwest::Client::new(),
     };
 
     // Create router
-    let app = create_router(state);
+    let app = create_router(state, config.admin.clone());
 
     // Worker configuration
-    let worker_config = WorkerConfig {
-        build_dir: PathBuf::from(build_dir),
-        registry_url,
-        gateway_url,
-        num_workers: 1,
-    };
+    let worker_config = config.worker_config();
 
     // Spawn worker task
     let worker_handle = tokio::spawn(async move {
@@ -82,7 +58,7 @@ async fn main() -> Result<()> {
     });
 
     // Start API server
-    let addr = format!("{}:{}", host, port);
+    let addr = config.api_address();
     let listener = tokio::net::TcpListener::bind(&addr)
         .await
         .context("Failed to bind to address")?;
@@ -2,6 +2,7 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Build job status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -17,6 +18,38 @@ pub enum BuildStatus {
     Failed,
 }
 
+impl BuildStatus {
+    /// Whether the job will not change status again
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, BuildStatus::Completed | BuildStatus::Failed)
+    }
+
+    /// Whether a job in this status may move to `next`
+    ///
+    /// Jobs only move forward: a queued job starts building (or fails before
+    /// it gets the chance), and a building job completes or fails.
+    pub fn can_transition_to(&self, next: BuildStatus) -> bool {
+        matches!(
+            (self, next),
+            (BuildStatus::Queued, BuildStatus::Building)
+                | (BuildStatus::Queued, BuildStatus::Failed)
+                | (BuildStatus::Building, BuildStatus::Completed)
+                | (BuildStatus::Building, BuildStatus::Failed)
+        )
+    }
+}
+
+/// A status change the job state machine doesn't allow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid job status transition: {from:?} -> {to:?}")]
+pub struct InvalidTransition {
+    /// Status the job was in
+    pub from: BuildStatus,
+
+    /// Status it was asked to move to
+    pub to: BuildStatus,
+}
+
 /// A build job in the queue
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuildJob {
@@ -55,9 +88,22 @@ pub struct BuildJob {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 
+    /// Why registering a completed build with the Image ID Registry failed
+    /// (cleared once `POST /api/build/:job_id/register` succeeds)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registration_error: Option<String>,
+
     /// Optional webhook URL to notify on completion
     #[serde(skip_serializing_if = "Option::is_none")]
     pub webhook_url: Option<String>,
+
+    /// Trace context of the request that queued this job (W3C headers)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub trace_context: HashMap<String, String>,
+
+    /// Outcome of the completion webhook (if one was configured)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_delivery: Option<WebhookDelivery>,
 }
 
 impl BuildJob {
@@ -74,29 +120,89 @@ impl BuildJob {
             image_id: None,
             elf_path: None,
             error: None,
+            registration_error: None,
             webhook_url: None,
+            trace_context: HashMap::new(),
+            webhook_delivery: None,
+        }
+    }
+
+    /// Move the job to `next`, leaving it untouched if the transition isn't allowed
+    fn transition(&mut self, next: BuildStatus) -> Result<(), InvalidTransition> {
+        if !self.status.can_transition_to(next) {
+            return Err(InvalidTransition {
+                from: self.status,
+                to: next,
+            });
         }
+        self.status = next;
+        Ok(())
     }
 
     /// Mark job as building
-    pub fn mark_building(&mut self) {
-        self.status = BuildStatus::Building;
+    pub fn mark_building(&mut self) -> Result<(), InvalidTransition> {
+        self.transition(BuildStatus::Building)?;
         self.started_at = Some(Utc::now());
+        Ok(())
     }
 
     /// Mark job as completed
-    pub fn mark_completed(&mut self, image_id: String, elf_path: String) {
-        self.status = BuildStatus::Completed;
+    pub fn mark_completed(
+        &mut self,
+        image_id: String,
+        elf_path: String,
+    ) -> Result<(), InvalidTransition> {
+        self.transition(BuildStatus::Completed)?;
         self.completed_at = Some(Utc::now());
         self.image_id = Some(image_id);
         self.elf_path = Some(elf_path);
+        Ok(())
     }
 
     /// Mark job as failed
-    pub fn mark_failed(&mut self, error: String) {
-        self.status = BuildStatus::Failed;
+    pub fn mark_failed(&mut self, error: String) -> Result<(), InvalidTransition> {
+        self.transition(BuildStatus::Failed)?;
         self.completed_at = Some(Utc::now());
         self.error = Some(error);
+        Ok(())
+    }
+}
+
+/// A job status transition, published on `build:events` and the job's own channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildStatusEvent {
+    /// Job identifier
+    pub job_id: String,
+
+    /// Customer the job belongs to
+    pub customer_id: String,
+
+    /// New status
+    pub status: BuildStatus,
+
+    /// Resulting Image ID (once completed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_id: Option<String>,
+
+    /// Error message (once failed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    /// When the transition was published
+    pub timestamp: DateTime<Utc>,
+}
+
+impl BuildStatusEvent {
+    /// Snapshot a job's current status as an event
+    pub fn from_job(job: &BuildJob) -> Self {
+        Self {
+            job_id: job.job_id.clone(),
+            customer_id: job.customer_id.clone(),
+            status: job.status,
+            image_id: job.image_id.clone(),
+            error: job.error.clone(),
+            timestamp: Utc::now(),
+        }
     }
 }
 
@@ -136,6 +242,67 @@ pub struct BuildStatusResponse {
     pub job: BuildJob,
 }
 
+/// Query parameters for listing a customer's jobs
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CustomerJobsQuery {
+    /// Maximum number of jobs to return (default 50, capped at 100)
+    pub limit: Option<usize>,
+
+    /// Number of jobs to skip (newest first, at most 10000)
+    pub offset: Option<usize>,
+
+    /// Only return jobs with this status
+    pub status: Option<BuildStatus>,
+}
+
+impl CustomerJobsQuery {
+    /// Default page size
+    pub const DEFAULT_LIMIT: usize = 50;
+
+    /// Maximum page size
+    pub const MAX_LIMIT: usize = 100;
+
+    /// Largest accepted offset
+    pub const MAX_OFFSET: usize = 10_000;
+
+    /// Effective page size
+    pub fn limit(&self) -> usize {
+        self.limit
+            .unwrap_or(Self::DEFAULT_LIMIT)
+            .clamp(1, Self::MAX_LIMIT)
+    }
+
+    /// Effective offset
+    pub fn offset(&self) -> usize {
+        self.offset.unwrap_or(0)
+    }
+
+    /// Check the query is in range, describing the problem if not
+    pub fn validate(&self) -> Result<(), String> {
+        if self.offset() > Self::MAX_OFFSET {
+            return Err(format!("offset must be at most {}", Self::MAX_OFFSET));
+        }
+        Ok(())
+    }
+}
+
+/// Result of delivering a job's webhook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    /// Whether the receiver acknowledged with a 2xx response
+    pub delivered: bool,
+
+    /// Number of attempts made
+    pub attempts: u32,
+
+    /// Error from the last failed attempt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+
+    /// When delivery finished (successfully or not)
+    pub finished_at: DateTime<Utc>,
+}
+
 /// Webhook payload sent on job completion
 #[derive(Debug, Serialize)]
 pub struct WebhookPayload {
@@ -160,3 +327,75 @@ pub struct WebhookPayload {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_job() -> BuildJob {
+        BuildJob::new(
+            "job-1".to_string(),
+            "customer-1".to_string(),
+            serde_json::json!({}),
+        )
+    }
+
+    #[test]
+    fn test_valid_transitions() {
+        let mut job = test_job();
+        job.mark_building().unwrap();
+        job.mark_completed("image-abc".to_string(), "/path/to/guest.elf".to_string())
+            .unwrap();
+        assert_eq!(job.status, BuildStatus::Completed);
+        assert_eq!(job.image_id.as_deref(), Some("image-abc"));
+
+        let mut job = test_job();
+        job.mark_building().unwrap();
+        job.mark_failed("boom".to_string()).unwrap();
+        assert_eq!(job.status, BuildStatus::Failed);
+
+        let mut job = test_job();
+        job.mark_failed("boom".to_string()).unwrap();
+        assert_eq!(job.status, BuildStatus::Failed);
+    }
+
+    #[test]
+    fn test_invalid_transitions_rejected() {
+        // A duplicate pop must not send a finished job back to building
+        let mut job = test_job();
+        job.mark_building().unwrap();
+        job.mark_completed("image-abc".to_string(), "/path/to/guest.elf".to_string())
+            .unwrap();
+        let completed_at = job.completed_at;
+        assert_eq!(
+            job.mark_building(),
+            Err(InvalidTransition {
+                from: BuildStatus::Completed,
+                to: BuildStatus::Building,
+            })
+        );
+        assert!(job.mark_failed("boom".to_string()).is_err());
+        assert_eq!(job.status, BuildStatus::Completed);
+        assert_eq!(job.completed_at, completed_at);
+        assert!(job.error.is_none());
+
+        let mut job = test_job();
+        assert!(job
+            .mark_completed("image-abc".to_string(), "/path/to/guest.elf".to_string())
+            .is_err());
+        assert_eq!(job.status, BuildStatus::Queued);
+        assert!(job.image_id.is_none());
+
+        let mut job = test_job();
+        job.mark_building().unwrap();
+        assert!(job.mark_building().is_err());
+
+        let mut job = test_job();
+        job.mark_failed("boom".to_string()).unwrap();
+        assert!(job.mark_building().is_err());
+        assert!(job
+            .mark_completed("image-abc".to_string(), "/path/to/guest.elf".to_string())
+            .is_err());
+        assert_eq!(job.status, BuildStatus::Failed);
+    }
+}
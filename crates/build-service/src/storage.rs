@@ -1,33 +1,208 @@
 //! Redis storage for build job queue
 
-use crate::models::{BuildJob, BuildStatus};
+use crate::models::{BuildJob, BuildStatus, BuildStatusEvent, CustomerJobsQuery};
 use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use khafi_common::redis_url::connection_info;
 use redis::aio::ConnectionManager;
 use redis::AsyncCommands;
 use tracing::{debug, info};
 
+/// Job persistence used by the API handlers
+///
+/// [`Storage`] is the Redis implementation; handlers are generic over this
+/// trait so they can be tested against an in-memory store.
+#[async_trait]
+pub trait JobStore: Send + 'static {
+    /// Queue a new build job
+    async fn queue_job(&mut self, job: &BuildJob) -> Result<()>;
+
+    /// Get a job by ID
+    async fn get_job(&mut self, job_id: &str) -> Result<Option<BuildJob>>;
+
+    /// Update a job
+    async fn update_job(&mut self, job: &BuildJob) -> Result<()>;
+
+    /// Subscribe to a job's status events
+    async fn subscribe_job_events(
+        &self,
+        job_id: &str,
+    ) -> Result<BoxStream<'static, BuildStatusEvent>>;
+
+    /// Get a page of jobs for a customer, newest first
+    async fn get_customer_jobs(
+        &mut self,
+        customer_id: &str,
+        query: &CustomerJobsQuery,
+    ) -> Result<CustomerJobsPage>;
+
+    /// Get queue length
+    async fn queue_length(&mut self) -> Result<usize>;
+
+    /// Get counts by status
+    async fn get_stats(&mut self) -> Result<BuildStats>;
+}
+
 /// Storage backend for build jobs
 pub struct Storage {
     conn: ConnectionManager,
+    /// Kept for opening dedicated pub/sub connections
+    client: redis::Client,
 }
 
 impl Storage {
     /// Create a new storage instance
-    pub async fn new(redis_url: &str) -> Result<Self> {
-        let client = redis::Client::open(redis_url)
+    ///
+    /// `redis_db` selects the database index, overriding any `/<db>` in the URL.
+    pub async fn new(redis_url: &str, redis_db: Option<i64>) -> Result<Self> {
+        let info = connection_info(redis_url, redis_db).context("Invalid Redis URL")?;
+        let db = info.redis.db;
+
+        let client = redis::Client::open(info)
             .context("Failed to create Redis client")?;
 
-        let conn = ConnectionManager::new(client)
+        let conn = ConnectionManager::new(client.clone())
             .await
             .context("Failed to connect to Redis")?;
 
-        info!("Connected to Redis at {}", redis_url);
+        info!("Connected to Redis at {} (db {})", redis_url, db);
 
-        Ok(Self { conn })
+        Ok(Self { conn, client })
     }
 
-    /// Queue a new build job
-    pub async fn queue_job(&mut self, job: &BuildJob) -> Result<()> {
+    /// Publish a job's current status on `build:events` and its own channel
+    pub async fn publish_job_event(&mut self, job: &BuildJob) -> Result<()> {
+        let json = serde_json::to_string(&BuildStatusEvent::from_job(job))
+            .context("Failed to serialize job event")?;
+
+        redis::pipe()
+            .publish(BUILD_EVENTS_CHANNEL, &json)
+            .ignore()
+            .publish(job_events_channel(&job.job_id), &json)
+            .ignore()
+            .query_async::<_, ()>(&mut self.conn)
+            .await?;
+
+        debug!("Published status {:?} for job: {}", job.status, job.job_id);
+        Ok(())
+    }
+
+    /// Subscribe to status events for every job
+    pub async fn subscribe_all_events(&self) -> Result<impl Stream<Item = BuildStatusEvent>> {
+        self.subscribe_events(BUILD_EVENTS_CHANNEL).await
+    }
+
+    /// Subscribe to an events channel
+    ///
+    /// Opens a dedicated pub/sub connection that lives as long as the stream.
+    async fn subscribe_events(&self, channel: &str) -> Result<impl Stream<Item = BuildStatusEvent>> {
+        let mut pubsub = self
+            .client
+            .get_async_connection()
+            .await
+            .context("Failed to open pub/sub connection")?
+            .into_pubsub();
+        pubsub.subscribe(channel).await?;
+
+        Ok(pubsub.into_on_message().filter_map(|msg| async move {
+            let payload: String = msg.get_payload().ok()?;
+            serde_json::from_str(&payload).ok()
+        }))
+    }
+
+    /// Record a job whose webhook could not be delivered after all retries
+    ///
+    /// Failed deliveries are kept in `build:webhooks:failed` for inspection or redelivery.
+    pub async fn push_failed_webhook(&mut self, job_id: &str) -> Result<()> {
+        self.conn
+            .rpush::<_, _, ()>("build:webhooks:failed", job_id)
+            .await?;
+        Ok(())
+    }
+
+    /// Fetch several jobs in one round trip, skipping any that no longer exist
+    async fn get_jobs(&mut self, job_ids: &[String]) -> Result<Vec<BuildJob>> {
+        if job_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let keys: Vec<String> = job_ids
+            .iter()
+            .map(|job_id| format!("build:job:{}", job_id))
+            .collect();
+        let values: Vec<Option<String>> = redis::cmd("MGET")
+            .arg(&keys)
+            .query_async(&mut self.conn)
+            .await?;
+
+        values
+            .into_iter()
+            .flatten()
+            .map(|data| serde_json::from_str(&data).context("Failed to deserialize job"))
+            .collect()
+    }
+
+    /// Move a customer's jobs from the old `build:customer:{id}` set into the
+    /// `build:customer:{id}:jobs` sorted set
+    ///
+    /// Jobs queued before the index became a sorted set are only listed in
+    /// the old set; they are migrated the first time the customer's jobs are
+    /// read, after which this is a single EXISTS.
+    async fn migrate_legacy_customer_index(&mut self, customer_id: &str) -> Result<()> {
+        let legacy_key = format!("build:customer:{}", customer_id);
+        let exists: bool = self.conn.exists(&legacy_key).await?;
+        if !exists {
+            return Ok(());
+        }
+
+        let job_ids: Vec<String> = self.conn.smembers(&legacy_key).await?;
+        let jobs = self.get_jobs(&job_ids).await?;
+        let scored: Vec<(i64, &str)> = jobs
+            .iter()
+            .map(|job| (job.created_at.timestamp_millis(), job.job_id.as_str()))
+            .collect();
+
+        let customer_key = format!("build:customer:{}:jobs", customer_id);
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        if !scored.is_empty() {
+            pipe.zadd_multiple(&customer_key, &scored).ignore();
+        }
+        pipe.del(&legacy_key)
+            .ignore()
+            .query_async::<_, ()>(&mut self.conn)
+            .await?;
+
+        info!(
+            "Migrated {} job(s) of customer {} to the sorted jobs index",
+            scored.len(),
+            customer_id
+        );
+        Ok(())
+    }
+
+    /// Pop next job from queue (blocking)
+    pub async fn pop_job(&mut self, timeout_secs: f64) -> Result<Option<BuildJob>> {
+        // BLPOP with timeout
+        let result: Option<(String, String)> = self.conn
+            .blpop("build:queue", timeout_secs)
+            .await?;
+
+        match result {
+            Some((_, job_id)) => {
+                debug!("Popped job from queue: {}", job_id);
+                self.get_job(&job_id).await
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[async_trait]
+impl JobStore for Storage {
+    async fn queue_job(&mut self, job: &BuildJob) -> Result<()> {
         let key = format!("build:job:{}", job.job_id);
 
         // Serialize job
@@ -40,16 +215,17 @@ impl Storage {
         // Add to queue
         self.conn.rpush("build:queue", &job.job_id).await?;
 
-        // Add to customer's jobs index
-        let customer_key = format!("build:customer:{}", job.customer_id);
-        self.conn.sadd(&customer_key, &job.job_id).await?;
+        // Add to customer's jobs index (scored by creation time)
+        let customer_key = format!("build:customer:{}:jobs", job.customer_id);
+        self.conn
+            .zadd(&customer_key, &job.job_id, job.created_at.timestamp_millis())
+            .await?;
 
         info!("Queued build job: {} for customer: {}", job.job_id, job.customer_id);
         Ok(())
     }
 
-    /// Get a job by ID
-    pub async fn get_job(&mut self, job_id: &str) -> Result<Option<BuildJob>> {
+    async fn get_job(&mut self, job_id: &str) -> Result<Option<BuildJob>> {
         let key = format!("build:job:{}", job_id);
 
         let json: Option<String> = self.conn.get(&key).await?;
@@ -64,8 +240,7 @@ impl Storage {
         }
     }
 
-    /// Update a job
-    pub async fn update_job(&mut self, job: &BuildJob) -> Result<()> {
+    async fn update_job(&mut self, job: &BuildJob) -> Result<()> {
         let key = format!("build:job:{}", job.job_id);
 
         let json = serde_json::to_string(job)
@@ -77,49 +252,71 @@ impl Storage {
         Ok(())
     }
 
-    /// Pop next job from queue (blocking)
-    pub async fn pop_job(&mut self, timeout_secs: f64) -> Result<Option<BuildJob>> {
-        // BLPOP with timeout
-        let result: Option<(String, String)> = self.conn
-            .blpop("build:queue", timeout_secs)
-            .await?;
-
-        match result {
-            Some((_, job_id)) => {
-                debug!("Popped job from queue: {}", job_id);
-                self.get_job(&job_id).await
-            }
-            None => Ok(None),
-        }
+    async fn subscribe_job_events(
+        &self,
+        job_id: &str,
+    ) -> Result<BoxStream<'static, BuildStatusEvent>> {
+        Ok(self
+            .subscribe_events(&job_events_channel(job_id))
+            .await?
+            .boxed())
     }
 
-    /// Get all jobs for a customer
-    pub async fn get_customer_jobs(&mut self, customer_id: &str) -> Result<Vec<BuildJob>> {
-        let customer_key = format!("build:customer:{}", customer_id);
+    async fn get_customer_jobs(
+        &mut self,
+        customer_id: &str,
+        query: &CustomerJobsQuery,
+    ) -> Result<CustomerJobsPage> {
+        self.migrate_legacy_customer_index(customer_id).await?;
 
-        let job_ids: Vec<String> = self.conn.smembers(&customer_key).await?;
+        let customer_key = format!("build:customer:{}:jobs", customer_id);
+        let limit = query.limit();
+        let offset = query.offset();
 
-        let mut jobs = Vec::new();
-        for job_id in job_ids {
-            if let Some(job) = self.get_job(&job_id).await? {
-                jobs.push(job);
+        match query.status {
+            None => {
+                // Page directly over the sorted set
+                let total: usize = self.conn.zcard(&customer_key).await?;
+                let start = isize::try_from(offset).context("Offset out of range")?;
+                let stop = offset
+                    .checked_add(limit)
+                    .and_then(|end| isize::try_from(end).ok())
+                    .context("Offset out of range")?
+                    - 1;
+                let job_ids: Vec<String> =
+                    self.conn.zrevrange(&customer_key, start, stop).await?;
+
+                let jobs = self.get_jobs(&job_ids).await?;
+
+                Ok(CustomerJobsPage { jobs, total })
             }
-        }
+            Some(status) => {
+                // Status isn't indexed, so filter the customer's newest jobs,
+                // scanning a bounded window of the index
+                let last = MAX_STATUS_FILTER_SCAN as isize - 1;
+                let job_ids: Vec<String> = self.conn.zrevrange(&customer_key, 0, last).await?;
+
+                let matching: Vec<BuildJob> = self
+                    .get_jobs(&job_ids)
+                    .await?
+                    .into_iter()
+                    .filter(|job| job.status == status)
+                    .collect();
 
-        // Sort by created_at descending
-        jobs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+                let total = matching.len();
+                let jobs = matching.into_iter().skip(offset).take(limit).collect();
 
-        Ok(jobs)
+                Ok(CustomerJobsPage { jobs, total })
+            }
+        }
     }
 
-    /// Get queue length
-    pub async fn queue_length(&mut self) -> Result<usize> {
+    async fn queue_length(&mut self) -> Result<usize> {
         let len: usize = self.conn.llen("build:queue").await?;
         Ok(len)
     }
 
-    /// Get counts by status
-    pub async fn get_stats(&mut self) -> Result<BuildStats> {
+    async fn get_stats(&mut self) -> Result<BuildStats> {
         // This is a simple implementation - for production you'd want
         // to maintain counters separately for performance
         let queue_len = self.queue_length().await?;
@@ -133,6 +330,16 @@ impl Storage {
     }
 }
 
+/// A page of a customer's build jobs
+#[derive(Debug)]
+pub struct CustomerJobsPage {
+    /// Jobs in this page, newest first
+    pub jobs: Vec<BuildJob>,
+
+    /// Total number of jobs matching the query
+    pub total: usize,
+}
+
 /// Build statistics
 #[derive(Debug, serde::Serialize)]
 pub struct BuildStats {
@@ -141,3 +348,227 @@ pub struct BuildStats {
     pub completed: usize,
     pub failed: usize,
 }
+
+/// Most jobs a status-filtered listing looks at, newest first
+///
+/// Keeps `?status=` listings bounded for customers with long histories;
+/// older matching jobs are not listed or counted.
+pub const MAX_STATUS_FILTER_SCAN: usize = 1_000;
+
+/// Pub/sub channel carrying status events for all jobs
+pub const BUILD_EVENTS_CHANNEL: &str = "build:events";
+
+/// Pub/sub channel carrying status events for one job
+pub fn job_events_channel(job_id: &str) -> String {
+    format!("build:job:{}:events", job_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn get_test_storage() -> Storage {
+        Storage::new("redis://127.0.0.1:6379", Some(15))
+            .await
+            .expect("Failed to connect to test Redis")
+    }
+
+    async fn queue_test_jobs(storage: &mut Storage, customer_id: &str, count: usize) -> Vec<BuildJob> {
+        let mut jobs = Vec::new();
+        for i in 0..count {
+            let mut job = BuildJob::new(
+                format!("{}-job-{}", customer_id, i),
+                customer_id.to_string(),
+                serde_json::json!({ "use_case": "test" }),
+            );
+            job.created_at += chrono::Duration::seconds(i as i64);
+            storage.queue_job(&job).await.unwrap();
+            jobs.push(job);
+        }
+        jobs
+    }
+
+    async fn clean_up(storage: &mut Storage, customer_id: &str, jobs: &[BuildJob]) {
+        let mut keys = vec![format!("build:customer:{}:jobs", customer_id)];
+        keys.extend(jobs.iter().map(|j| format!("build:job:{}", j.job_id)));
+        let _: () = storage.conn.del(keys).await.unwrap();
+        for job in jobs {
+            let _: () = storage.conn.lrem("build:queue", 0, &job.job_id).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_customer_jobs_pagination() {
+        let mut storage = get_test_storage().await;
+        let customer_id = "customer-paging";
+        let jobs = queue_test_jobs(&mut storage, customer_id, 5).await;
+
+        let query = CustomerJobsQuery {
+            limit: Some(2),
+            offset: Some(0),
+            status: None,
+        };
+        let page = storage.get_customer_jobs(customer_id, &query).await.unwrap();
+        assert_eq!(page.total, 5);
+        let ids: Vec<_> = page.jobs.iter().map(|j| j.job_id.as_str()).collect();
+        assert_eq!(ids, vec!["customer-paging-job-4", "customer-paging-job-3"]);
+
+        let query = CustomerJobsQuery {
+            limit: Some(2),
+            offset: Some(4),
+            status: None,
+        };
+        let page = storage.get_customer_jobs(customer_id, &query).await.unwrap();
+        assert_eq!(page.total, 5);
+        assert_eq!(page.jobs.len(), 1);
+        assert_eq!(page.jobs[0].job_id, "customer-paging-job-0");
+
+        clean_up(&mut storage, customer_id, &jobs).await;
+    }
+
+    #[tokio::test]
+    async fn test_customer_jobs_status_filter() {
+        let mut storage = get_test_storage().await;
+        let customer_id = "customer-filter";
+        let mut jobs = queue_test_jobs(&mut storage, customer_id, 4).await;
+
+        jobs[0].mark_failed("boom".to_string()).unwrap();
+        storage.update_job(&jobs[0]).await.unwrap();
+        jobs[2].mark_failed("boom".to_string()).unwrap();
+        storage.update_job(&jobs[2]).await.unwrap();
+
+        let query = CustomerJobsQuery {
+            limit: Some(1),
+            offset: None,
+            status: Some(BuildStatus::Failed),
+        };
+        let page = storage.get_customer_jobs(customer_id, &query).await.unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.jobs.len(), 1);
+        assert_eq!(page.jobs[0].job_id, "customer-filter-job-2");
+
+        let query = CustomerJobsQuery {
+            limit: None,
+            offset: None,
+            status: Some(BuildStatus::Queued),
+        };
+        let page = storage.get_customer_jobs(customer_id, &query).await.unwrap();
+        assert_eq!(page.total, 2);
+        assert!(page.jobs.iter().all(|j| j.status == BuildStatus::Queued));
+
+        clean_up(&mut storage, customer_id, &jobs).await;
+    }
+
+    #[tokio::test]
+    async fn test_customer_jobs_migrates_legacy_index() {
+        let mut storage = get_test_storage().await;
+        let customer_id = "customer-legacy";
+        let jobs = queue_test_jobs(&mut storage, customer_id, 3).await;
+
+        // Rewrite the index the way older versions stored it: an unordered set
+        let customer_key = format!("build:customer:{}:jobs", customer_id);
+        let legacy_key = format!("build:customer:{}", customer_id);
+        let _: () = storage.conn.del(&customer_key).await.unwrap();
+        let ids: Vec<&str> = jobs.iter().map(|j| j.job_id.as_str()).collect();
+        let _: () = storage.conn.sadd(&legacy_key, ids).await.unwrap();
+
+        let page = storage
+            .get_customer_jobs(customer_id, &CustomerJobsQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(page.total, 3);
+        let ids: Vec<_> = page.jobs.iter().map(|j| j.job_id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "customer-legacy-job-2",
+                "customer-legacy-job-1",
+                "customer-legacy-job-0"
+            ]
+        );
+        let legacy_exists: bool = storage.conn.exists(&legacy_key).await.unwrap();
+        assert!(!legacy_exists);
+
+        clean_up(&mut storage, customer_id, &jobs).await;
+    }
+
+    #[tokio::test]
+    async fn test_job_events_stream() {
+        let mut storage = get_test_storage().await;
+        let customer_id = "customer-events";
+        let mut jobs = queue_test_jobs(&mut storage, customer_id, 1).await;
+
+        let events = storage
+            .subscribe_job_events(&jobs[0].job_id)
+            .await
+            .unwrap();
+
+        // Simulate the worker's status transitions
+        jobs[0].mark_building().unwrap();
+        storage.update_job(&jobs[0]).await.unwrap();
+        storage.publish_job_event(&jobs[0]).await.unwrap();
+
+        jobs[0]
+            .mark_completed("image-abc".to_string(), "/path/to/guest.elf".to_string())
+            .unwrap();
+        storage.update_job(&jobs[0]).await.unwrap();
+        storage.publish_job_event(&jobs[0]).await.unwrap();
+
+        let received: Vec<BuildStatusEvent> = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            events.take(2).collect(),
+        )
+        .await
+        .expect("Timed out waiting for job events");
+
+        let statuses: Vec<BuildStatus> = received.iter().map(|e| e.status).collect();
+        assert_eq!(statuses, vec![BuildStatus::Building, BuildStatus::Completed]);
+        assert_eq!(received[1].image_id.as_deref(), Some("image-abc"));
+
+        clean_up(&mut storage, customer_id, &jobs).await;
+    }
+
+    #[tokio::test]
+    async fn test_build_events_channel_sequence() {
+        let mut storage = get_test_storage().await;
+        let customer_id = "customer-all-events";
+        let mut jobs = queue_test_jobs(&mut storage, customer_id, 2).await;
+
+        let events = storage.subscribe_all_events().await.unwrap();
+
+        jobs[0].mark_building().unwrap();
+        storage.publish_job_event(&jobs[0]).await.unwrap();
+        jobs[1].mark_building().unwrap();
+        storage.publish_job_event(&jobs[1]).await.unwrap();
+        jobs[1].mark_failed("boom".to_string()).unwrap();
+        storage.publish_job_event(&jobs[1]).await.unwrap();
+        jobs[0]
+            .mark_completed("image-abc".to_string(), "/path/to/guest.elf".to_string())
+            .unwrap();
+        storage.publish_job_event(&jobs[0]).await.unwrap();
+
+        // Other tests may publish concurrently, so only look at our customer
+        let received: Vec<(String, BuildStatus)> = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            events
+                .filter(|e| futures::future::ready(e.customer_id == customer_id))
+                .map(|e| (e.job_id, e.status))
+                .take(4)
+                .collect(),
+        )
+        .await
+        .expect("Timed out waiting for build events");
+
+        assert_eq!(
+            received,
+            vec![
+                (jobs[0].job_id.clone(), BuildStatus::Building),
+                (jobs[1].job_id.clone(), BuildStatus::Building),
+                (jobs[1].job_id.clone(), BuildStatus::Failed),
+                (jobs[0].job_id.clone(), BuildStatus::Completed),
+            ]
+        );
+
+        clean_up(&mut storage, customer_id, &jobs).await;
+    }
+}
@@ -0,0 +1,152 @@
+//! Reproducible-build verification
+//!
+//! Regenerates a DSL's guest program, builds it the way the worker does and
+//! compares the resulting image ID with a claimed one, so anyone holding a
+//! deployment's DSL (see the registry's `/api/deployments/:customer_id/dsl`)
+//! can check its image ID independently.
+//!
+//! The default build, `cargo risczero build`, runs in RISC Zero's Docker
+//! image, which makes the ELF (and so the image ID) independent of the host.
+//! A custom `build_command` is only reproducible if it is deterministic too.
+
+use crate::models::BuildJob;
+use crate::worker::{build_guest, prepare_job, WorkerConfig};
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+/// Outcome of rebuilding a DSL and comparing image IDs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BuildVerification {
+    /// Image ID the deployment claims
+    pub claimed_image_id: String,
+
+    /// Image ID of the rebuilt guest
+    pub image_id: String,
+
+    /// Whether the two match
+    pub matches: bool,
+}
+
+/// Rebuild the guest for `dsl` and check it produces `claimed_image_id`
+///
+/// Builds in a fresh directory under `config.build_dir`, removed afterwards.
+/// Image IDs compare case-insensitively, with or without a `0x` prefix.
+pub fn verify_build(
+    config: &WorkerConfig,
+    dsl: &serde_json::Value,
+    claimed_image_id: &str,
+) -> Result<BuildVerification> {
+    std::fs::create_dir_all(&config.build_dir).context("Failed to create build directory")?;
+    let workspace = tempfile::Builder::new()
+        .prefix("verify-")
+        .tempdir_in(&config.build_dir)
+        .context("Failed to create verification directory")?;
+
+    let job = BuildJob::new("verify".to_string(), "verify".to_string(), dsl.clone());
+    let prepared = prepare_job(workspace.path(), &job)?;
+    let built = build_guest(config, &job.job_id, &prepared)?;
+
+    let claimed = claimed_image_id.trim();
+    let claimed = claimed.strip_prefix("0x").unwrap_or(claimed);
+    Ok(BuildVerification {
+        claimed_image_id: claimed_image_id.to_string(),
+        matches: claimed.eq_ignore_ascii_case(&built.image_id),
+        image_id: built.image_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worker::{compute_image_id_hash, WebhookRetryPolicy};
+    use logic_compiler::{CodeGenerator, DslParser};
+    use std::path::Path;
+
+    const DSL: &str = r#"{
+        "use_case": "age_check",
+        "private_inputs": { "type": "object", "fields": { "age": "u32" } },
+        "public_params": { "min_age": "u32" },
+        "validation_rules": [
+            { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 }
+        ]
+    }"#;
+
+    fn config(build_dir: &Path, build_command: &str, build_args: Vec<String>) -> WorkerConfig {
+        WorkerConfig {
+            build_dir: build_dir.to_path_buf(),
+            registry_url: "http://127.0.0.1:8083".to_string(),
+            gateway_url: "http://localhost:8080".to_string(),
+            num_workers: 1,
+            compress_elf: false,
+            cleanup_after_build: false,
+            webhook_secret: None,
+            webhook_retry: WebhookRetryPolicy::default(),
+            build_command: build_command.to_string(),
+            build_args,
+            prefetch_next_job: false,
+        }
+    }
+
+    #[test]
+    fn test_verify_build_compares_image_ids() {
+        let build_dir = tempfile::tempdir().unwrap();
+        let dsl: serde_json::Value = serde_json::from_str(DSL).unwrap();
+
+        // A stand-in build that writes a fixed ELF where risc0 would
+        let guest_name =
+            CodeGenerator::new(DslParser::parse_str(DSL).unwrap()).guest_package_name();
+        let release = "target/riscv-guest/riscv32im-risc0-zkvm-elf/release";
+        let config = config(
+            build_dir.path(),
+            "sh",
+            vec![
+                "-c".to_string(),
+                format!("mkdir -p {0} && printf stub-elf > {0}/$0", release),
+                guest_name,
+            ],
+        );
+        let image_id = compute_image_id_hash(b"stub-elf");
+
+        let verification = verify_build(&config, &dsl, &image_id).unwrap();
+        assert!(verification.matches);
+        assert_eq!(verification.image_id, image_id);
+
+        let prefixed = format!("0x{}", image_id.to_uppercase());
+        assert!(verify_build(&config, &dsl, &prefixed).unwrap().matches);
+
+        let verification = verify_build(&config, &dsl, &"00".repeat(32)).unwrap();
+        assert!(!verification.matches);
+        assert_eq!(verification.image_id, image_id);
+
+        // Nothing is left behind in the build directory
+        assert_eq!(std::fs::read_dir(build_dir.path()).unwrap().count(), 0);
+    }
+
+    /// Rebuilding from scratch reproduces the image ID of an earlier build
+    ///
+    /// Needs the RISC Zero toolchain (and Docker for `cargo risczero build`):
+    /// `cargo test -p build-service --features zkvm-tests`
+    #[cfg(feature = "zkvm-tests")]
+    #[test]
+    fn test_rebuild_reproduces_image_id() {
+        let dsl: serde_json::Value = serde_json::from_str(DSL).unwrap();
+        let build_args = vec!["risczero".to_string(), "build".to_string()];
+
+        let first_dir = tempfile::tempdir().unwrap();
+        let first = verify_build(
+            &config(first_dir.path(), "cargo", build_args.clone()),
+            &dsl,
+            "",
+        )
+        .unwrap();
+
+        let second_dir = tempfile::tempdir().unwrap();
+        let second = verify_build(
+            &config(second_dir.path(), "cargo", build_args),
+            &dsl,
+            &first.image_id,
+        )
+        .unwrap();
+        assert!(second.matches, "{:?} vs {:?}", first, second);
+    }
+}
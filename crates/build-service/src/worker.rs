@@ -1,13 +1,24 @@
 //! Build worker - processes build jobs from the queue
 
-use crate::models::{BuildJob, BuildStatus, WebhookPayload};
-use crate::storage::Storage;
+use crate::models::{BuildJob, BuildStatus, WebhookDelivery, WebhookPayload};
+use crate::storage::{JobStore, Storage};
 use anyhow::{Context, Result};
-use logic_compiler::{CodeGenerator, DslParser};
-use std::path::PathBuf;
+use hmac::{Hmac, Mac};
+use logic_compiler::codegen::manifest::{input_manifest, Visibility};
+use logic_compiler::{BusinessRulesDSL, CodeGenerator, DslParser};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::Duration;
 use tokio::sync::mpsc;
-use tracing::{error, info, warn};
+use tokio::task::JoinHandle;
+use tracing::{error, info, info_span, warn, Instrument};
+
+/// Header carrying the HMAC-SHA256 signature of a webhook body
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Khafi-Signature";
+
+/// How long to wait for a job to prefetch before starting the current build
+const PREFETCH_WAIT_SECS: f64 = 0.1;
 
 /// Build worker configuration
 pub struct WorkerConfig {
@@ -22,6 +33,87 @@ pub struct WorkerConfig {
 
     /// Number of concurrent workers
     pub num_workers: usize,
+
+    /// Store guest ELFs gzip-compressed
+    pub compress_elf: bool,
+
+    /// Move the ELF to `build_dir/artifacts` and delete the job directory after a successful build
+    pub cleanup_after_build: bool,
+
+    /// Secret used to sign webhook bodies (unsigned when `None`)
+    pub webhook_secret: Option<String>,
+
+    /// Retry behaviour for webhook delivery
+    pub webhook_retry: WebhookRetryPolicy,
+
+    /// Program that builds the guest, run in the job's `methods` directory
+    pub build_command: String,
+
+    /// Arguments passed to `build_command`
+    pub build_args: Vec<String>,
+
+    /// Generate the next queued job's sources while the current job builds
+    pub prefetch_next_job: bool,
+}
+
+/// Retry policy for webhook delivery
+#[derive(Debug, Clone)]
+pub struct WebhookRetryPolicy {
+    /// Total attempts, including the first
+    pub max_attempts: u32,
+
+    /// Delay before the first retry; doubled after each failed attempt
+    pub initial_backoff: Duration,
+
+    /// Upper bound on the delay between attempts
+    pub max_backoff: Duration,
+
+    /// Timeout for each attempt
+    pub attempt_timeout: Duration,
+}
+
+impl Default for WebhookRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            attempt_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl WebhookRetryPolicy {
+    /// Delay before the attempt following `attempt` (1-based)
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// A job whose guest sources have been generated, ready to build
+#[derive(Debug)]
+pub(crate) struct PreparedJob {
+    /// The job's build directory
+    pub(crate) job_dir: PathBuf,
+
+    /// Cargo package name of the generated guest
+    pub(crate) guest_name: String,
+}
+
+/// A built guest ELF and its image ID
+pub(crate) struct BuiltGuest {
+    pub(crate) elf_path: PathBuf,
+    pub(crate) elf_bytes: Vec<u8>,
+    pub(crate) image_id: String,
+}
+
+/// A job popped from the queue, with its sources possibly being generated ahead of time
+struct NextJob {
+    job: BuildJob,
+    prepared: Option<JoinHandle<Result<PreparedJob>>>,
 }
 
 /// Build worker
@@ -29,6 +121,8 @@ pub struct Worker {
     config: WorkerConfig,
     storage: Storage,
     http_client: reqwest::Client,
+    /// Job popped early so its sources are generated while the current job builds
+    prefetched: Option<NextJob>,
 }
 
 impl Worker {
@@ -38,6 +132,7 @@ impl Worker {
             config,
             storage,
             http_client: reqwest::Client::new(),
+            prefetched: None,
         }
     }
 
@@ -46,36 +141,74 @@ impl Worker {
         info!("Build worker started, waiting for jobs...");
 
         loop {
-            // Wait for next job (with 5 second timeout to allow graceful shutdown)
-            match self.storage.pop_job(5.0).await {
-                Ok(Some(mut job)) => {
+            // Take the prefetched job, or wait for the next one (with 5 second
+            // timeout to allow graceful shutdown)
+            let next = match self.prefetched.take() {
+                Some(next) => Ok(Some(next)),
+                None => self.storage.pop_job(5.0).await.map(|job| {
+                    job.map(|job| NextJob {
+                        job,
+                        prepared: None,
+                    })
+                }),
+            };
+
+            match next {
+                Ok(Some(NextJob { mut job, prepared })) => {
                     info!("Processing build job: {}", job.job_id);
 
-                    // Mark as building
-                    job.mark_building();
-                    if let Err(e) = self.storage.update_job(&job).await {
-                        error!("Failed to update job status: {}", e);
+                    // Continue the trace of the request that queued the job
+                    let span = info_span!(
+                        "build_job",
+                        job_id = %job.job_id,
+                        customer_id = %job.customer_id
+                    );
+                    khafi_telemetry::set_parent_from_map(&span, &job.trace_context);
+
+                    // Mark as building. A job popped twice (e.g. re-queued after it
+                    // already ran) is left as it is rather than built again
+                    if let Err(e) = job.mark_building() {
+                        warn!("Skipping build job {}: {}", job.job_id, e);
+                        continue;
+                    }
+                    self.save_job(&job).await;
+
+                    if self.config.prefetch_next_job {
+                        self.prefetch_next_job().await;
                     }
 
                     // Process the job
-                    match self.process_job(&mut job).await {
+                    match self
+                        .process_job(&mut job, prepared)
+                        .instrument(span.clone())
+                        .await
+                    {
                         Ok(()) => {
                             info!("Build job completed: {}", job.job_id);
                         }
                         Err(e) => {
                             error!("Build job failed: {} - {}", job.job_id, e);
-                            job.mark_failed(e.to_string());
+                            if let Err(e) = job.mark_failed(e.to_string()) {
+                                error!("Failed to mark job {} failed: {}", job.job_id, e);
+                            }
                         }
                     }
 
                     // Update final status
-                    if let Err(e) = self.storage.update_job(&job).await {
-                        error!("Failed to update job status: {}", e);
-                    }
+                    self.save_job(&job).await;
 
                     // Send webhook if configured
-                    if let Some(webhook_url) = &job.webhook_url {
-                        self.send_webhook(webhook_url, &job).await;
+                    if let Some(webhook_url) = job.webhook_url.clone() {
+                        let delivery = self.send_webhook(&webhook_url, &job).instrument(span).await;
+                        if !delivery.delivered {
+                            if let Err(e) = self.storage.push_failed_webhook(&job.job_id).await {
+                                error!("Failed to record undelivered webhook: {}", e);
+                            }
+                        }
+                        job.webhook_delivery = Some(delivery);
+                        if let Err(e) = self.storage.update_job(&job).await {
+                            error!("Failed to record webhook delivery: {}", e);
+                        }
                     }
                 }
                 Ok(None) => {
@@ -89,146 +222,509 @@ impl Worker {
         }
     }
 
-    /// Process a single build job
-    async fn process_job(&self, job: &mut BuildJob) -> Result<()> {
-        // Create build directory
-        let job_dir = self.config.build_dir.join(&job.job_id);
-        std::fs::create_dir_all(&job_dir)
-            .context("Failed to create job directory")?;
-
-        // Parse DSL
-        let dsl_json = serde_json::to_string(&job.dsl)
-            .context("Failed to serialize DSL")?;
-
-        let parsed_dsl = DslParser::parse_str(&dsl_json)
-            .context("Failed to parse DSL")?;
-
-        // Generate SDK package
-        info!("Generating code for job: {}", job.job_id);
-        let generator = CodeGenerator::new(parsed_dsl.clone());
-        generator.generate_sdk_package(&job_dir)
-            .context("Failed to generate SDK package")?;
-
-        // Build guest program
-        info!("Building guest program for job: {}", job.job_id);
-        let methods_dir = job_dir.join("methods");
-
-        let build_output = Command::new("cargo")
-            .arg("risczero")
-            .arg("build")
-            .current_dir(&methods_dir)
-            .output()
-            .context("Failed to execute cargo risczero build")?;
-
-        if !build_output.status.success() {
-            let stderr = String::from_utf8_lossy(&build_output.stderr);
-            anyhow::bail!("Build failed: {}", stderr);
+    /// Persist a job's status and notify watchers of the transition
+    async fn save_job(&mut self, job: &BuildJob) {
+        if let Err(e) = self.storage.update_job(job).await {
+            error!("Failed to update job status: {}", e);
+            return;
         }
-
-        // Find the built ELF
-        let elf_path = methods_dir
-            .join("target/riscv-guest/riscv32im-risc0-zkvm-elf/release/guest");
-
-        if !elf_path.exists() {
-            anyhow::bail!("Guest ELF not found after build");
+        if let Err(e) = self.storage.publish_job_event(job).await {
+            warn!("Failed to publish status for job {}: {}", job.job_id, e);
         }
+    }
 
-        // Compute Image ID
-        info!("Computing Image ID for job: {}", job.job_id);
-        let elf_bytes = std::fs::read(&elf_path)
-            .context("Failed to read guest ELF")?;
-
-        // Use risc0 to compute image ID
-        // Note: In production, we'd use risc0_zkvm::compute_image_id
-        // For now, we'll compute a hash
-        let image_id = compute_image_id_hash(&elf_bytes);
-
-        info!("Image ID: {} for job: {}", image_id, job.job_id);
-
-        // Register with Image ID Registry
-        self.register_deployment(job, &image_id, &elf_path).await?;
+    /// Pop the next queued job, if there is one, and start generating its sources
+    ///
+    /// Generation runs on the blocking pool so it overlaps the current build.
+    /// Its outcome belongs to the prefetched job alone: a failure surfaces when
+    /// that job is processed, and never touches the job being built now.
+    async fn prefetch_next_job(&mut self) {
+        if self.prefetched.is_some() {
+            return;
+        }
 
-        // Mark job as completed
-        job.mark_completed(image_id, elf_path.to_string_lossy().to_string());
+        let job = match self.storage.pop_job(PREFETCH_WAIT_SECS).await {
+            Ok(Some(job)) => job,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Failed to prefetch next build job: {}", e);
+                return;
+            }
+        };
 
-        Ok(())
+        // A job that won't be built (see the mark_building check) isn't prepared
+        let prepared = job
+            .status
+            .can_transition_to(BuildStatus::Building)
+            .then(|| {
+                info!("Prefetching build job: {}", job.job_id);
+                spawn_prepare_job(self.config.build_dir.clone(), job.clone())
+            });
+        self.prefetched = Some(NextJob { job, prepared });
     }
 
-    /// Register deployment with Image ID Registry
-    async fn register_deployment(
+    /// Process a single build job
+    ///
+    /// `prepared` is the job's prefetched source generation, if any; otherwise
+    /// the sources are generated here.
+    async fn process_job(
         &self,
-        job: &BuildJob,
-        image_id: &str,
-        elf_path: &PathBuf,
+        job: &mut BuildJob,
+        prepared: Option<JoinHandle<Result<PreparedJob>>>,
     ) -> Result<()> {
-        let payload = serde_json::json!({
-            "customer_id": job.customer_id,
-            "image_id": image_id,
-            "guest_program_path": elf_path.to_string_lossy(),
-            "metadata": {
-                "job_id": job.job_id,
-                "use_case": job.dsl.get("use_case").and_then(|v| v.as_str()).unwrap_or("unknown"),
-                "description": job.dsl.get("description").and_then(|v| v.as_str()).unwrap_or(""),
-                "version": job.dsl.get("version").and_then(|v| v.as_str()).unwrap_or("1.0")
-            }
-        });
+        let prepared = match prepared {
+            Some(handle) => handle.await.context("Source generation task failed")??,
+            None => prepare_job(&self.config.build_dir, job)?,
+        };
 
-        let response = self.http_client
-            .post(format!("{}/api/deployments", self.config.registry_url))
-            .json(&payload)
-            .send()
-            .await
-            .context("Failed to connect to Image ID Registry")?;
+        let BuiltGuest {
+            elf_path,
+            elf_bytes,
+            image_id,
+        } = build_guest(&self.config, &job.job_id, &prepared)?;
+
+        // Store the ELF (the image ID is always computed over the raw bytes)
+        let (stored_path, elf_compression) = store_elf(
+            &self.config,
+            &job.job_id,
+            &prepared.job_dir,
+            &elf_path,
+            &elf_bytes,
+        )?;
 
-        if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-            anyhow::bail!("Failed to register deployment: {}", error_text);
+        // Mark job as completed
+        job.mark_completed(image_id.clone(), stored_path.to_string_lossy().to_string())?;
+
+        // Register with Image ID Registry. The ELF is already stored, so a registry
+        // outage leaves the job completed and retryable via POST /api/build/:job_id/register
+        if let Err(e) = register_deployment(
+            &self.http_client,
+            &self.config.registry_url,
+            job,
+            &image_id,
+            &stored_path,
+            elf_compression,
+        )
+        .await
+        {
+            error!("Registration failed for job {}: {}", job.job_id, e);
+            job.registration_error = Some(e.to_string());
         }
 
-        info!("Registered deployment for customer: {}", job.customer_id);
         Ok(())
     }
 
-    /// Send webhook notification
-    async fn send_webhook(&self, webhook_url: &str, job: &BuildJob) {
+    /// Send webhook notification, retrying per the configured policy
+    async fn send_webhook(&self, webhook_url: &str, job: &BuildJob) -> WebhookDelivery {
         let payload = WebhookPayload {
             job_id: job.job_id.clone(),
             customer_id: job.customer_id.clone(),
             status: job.status,
             image_id: job.image_id.clone(),
-            api_endpoint: job.image_id.as_ref().map(|_| {
-                format!("{}/api/prove", self.config.gateway_url)
-            }),
-            error: job.error.clone(),
+            api_endpoint: job
+                .image_id
+                .as_ref()
+                .filter(|_| job.registration_error.is_none())
+                .map(|_| format!("{}/api/prove", self.config.gateway_url)),
+            error: job.error.clone().or_else(|| job.registration_error.clone()),
+        };
+
+        // Sign the exact bytes we send
+        let body = match serde_json::to_vec(&payload) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Failed to serialize webhook for job {}: {}", job.job_id, e);
+                return WebhookDelivery {
+                    delivered: false,
+                    attempts: 0,
+                    last_error: Some(e.to_string()),
+                    finished_at: chrono::Utc::now(),
+                };
+            }
         };
+        let signature = self
+            .config
+            .webhook_secret
+            .as_ref()
+            .map(|secret| sign_webhook(secret, &body));
+
+        let delivery = deliver_webhook(
+            &self.http_client,
+            webhook_url,
+            &body,
+            signature.as_deref(),
+            &self.config.webhook_retry,
+        )
+        .await;
+
+        if delivery.delivered {
+            info!(
+                "Webhook sent successfully for job: {} ({} attempts)",
+                job.job_id, delivery.attempts
+            );
+        } else {
+            warn!(
+                "Giving up on webhook for job {} after {} attempts: {}",
+                job.job_id,
+                delivery.attempts,
+                delivery.last_error.as_deref().unwrap_or("unknown error")
+            );
+        }
+        delivery
+    }
+}
+
+/// Register a completed build with the Image ID Registry
+#[tracing::instrument(skip_all, fields(customer_id = %job.customer_id, image_id = %image_id))]
+pub async fn register_deployment(
+    client: &reqwest::Client,
+    registry_url: &str,
+    job: &BuildJob,
+    image_id: &str,
+    elf_path: &Path,
+    elf_compression: Option<&str>,
+) -> Result<()> {
+    let payload = serde_json::json!({
+        "customer_id": job.customer_id,
+        "image_id": image_id,
+        "guest_program_path": elf_path.to_string_lossy(),
+        "elf_compression": elf_compression,
+        "metadata": {
+            "job_id": job.job_id,
+            "use_case": job.dsl.get("use_case").and_then(|v| v.as_str()).unwrap_or("unknown"),
+            "description": job.dsl.get("description").and_then(|v| v.as_str()).unwrap_or(""),
+            "version": job.dsl.get("version").and_then(|v| v.as_str()).unwrap_or("1.0"),
+            "required_params": required_params(&job.dsl)?,
+            "schema_hash": schema_hash(&job.dsl)?
+        },
+        "dsl": job.dsl,
+        "guest_source": guest_source(&job.dsl)?
+    });
+
+    let response = client
+        .post(format!("{}/api/deployments", registry_url))
+        .headers(khafi_telemetry::trace_headers())
+        .json(&payload)
+        .send()
+        .await
+        .context("Failed to connect to Image ID Registry")?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        anyhow::bail!("Failed to register deployment: {}", error_text);
+    }
+
+    info!("Registered deployment for customer: {}", job.customer_id);
+    Ok(())
+}
+
+/// Public params a proof request must supply for this DSL (those without a default)
+fn required_params(dsl: &serde_json::Value) -> Result<Vec<serde_json::Value>> {
+    let dsl: BusinessRulesDSL = serde_json::from_value(dsl.clone())
+        .context("Failed to parse DSL for the required params manifest")?;
+
+    Ok(input_manifest(&dsl)
+        .into_iter()
+        .filter(|input| input.visibility == Visibility::Public && input.required)
+        .map(|input| serde_json::json!({ "name": input.name, "param_type": input.dsl_type }))
+        .collect())
+}
+
+/// SHA-256 (hex) of the DSL's canonicalized input and param schema
+///
+/// Covers each input's request path, type and whether it's required, sorted
+/// by path, so descriptions, rules and declaration order don't affect it.
+pub fn schema_hash(dsl: &serde_json::Value) -> Result<String> {
+    let dsl: BusinessRulesDSL =
+        serde_json::from_value(dsl.clone()).context("Failed to parse DSL for the schema hash")?;
+
+    let mut inputs: Vec<(String, String, bool)> = input_manifest(&dsl)
+        .into_iter()
+        .map(|input| (input.path, input.dsl_type, input.required))
+        .collect();
+    inputs.sort();
+
+    let canonical = serde_json::to_vec(&inputs).context("Failed to serialize input schema")?;
+    Ok(hex::encode(Sha256::digest(&canonical)))
+}
+
+/// Guest program source (`main.rs`) generated for the DSL
+///
+/// Code generation is deterministic, so this is the source the job built.
+pub fn guest_source(dsl: &serde_json::Value) -> Result<String> {
+    let dsl: BusinessRulesDSL =
+        serde_json::from_value(dsl.clone()).context("Failed to parse DSL for the guest source")?;
+
+    CodeGenerator::new(dsl)
+        .generate()
+        .context("Failed to generate guest source")
+}
+
+/// Compression of a stored ELF, as recorded by `store_elf`
+pub fn elf_compression(elf_path: &Path) -> Option<&'static str> {
+    elf_path
+        .extension()
+        .is_some_and(|ext| ext == "gz")
+        .then_some("gzip")
+}
+
+/// POST a webhook body, retrying with exponential backoff until a 2xx response
+/// or the policy's attempts run out
+async fn deliver_webhook(
+    client: &reqwest::Client,
+    webhook_url: &str,
+    body: &[u8],
+    signature: Option<&str>,
+    policy: &WebhookRetryPolicy,
+) -> WebhookDelivery {
+    let mut last_error = None;
+    let mut attempts = 0;
 
-        match self.http_client
+    while attempts < policy.max_attempts.max(1) {
+        attempts += 1;
+
+        let mut request = client
             .post(webhook_url)
-            .json(&payload)
-            .send()
-            .await
-        {
+            .timeout(policy.attempt_timeout)
+            .headers(khafi_telemetry::trace_headers())
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        if let Some(signature) = signature {
+            request = request.header(WEBHOOK_SIGNATURE_HEADER, signature);
+        }
+
+        match request.body(body.to_vec()).send().await {
+            Ok(response) if response.status().is_success() => {
+                return WebhookDelivery {
+                    delivered: true,
+                    attempts,
+                    last_error: None,
+                    finished_at: chrono::Utc::now(),
+                };
+            }
             Ok(response) => {
-                if response.status().is_success() {
-                    info!("Webhook sent successfully for job: {}", job.job_id);
-                } else {
-                    warn!(
-                        "Webhook returned error status {} for job: {}",
-                        response.status(),
-                        job.job_id
-                    );
-                }
+                last_error = Some(format!("Receiver returned status {}", response.status()));
             }
             Err(e) => {
-                warn!("Failed to send webhook for job {}: {}", job.job_id, e);
+                last_error = Some(e.to_string());
+            }
+        }
+
+        if attempts < policy.max_attempts {
+            let delay = policy.backoff(attempts);
+            warn!(
+                "Webhook attempt {} to {} failed ({}), retrying in {:?}",
+                attempts,
+                webhook_url,
+                last_error.as_deref().unwrap_or_default(),
+                delay
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    WebhookDelivery {
+        delivered: false,
+        attempts,
+        last_error,
+        finished_at: chrono::Utc::now(),
+    }
+}
+
+/// Compute the `X-Khafi-Signature` value for a webhook body
+///
+/// The signature is `sha256=` followed by the hex-encoded HMAC-SHA256 of the
+/// raw request body, keyed with the webhook secret.
+pub fn sign_webhook(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Generate a job's guest sources under `build_dir/<job_id>`
+pub(crate) fn prepare_job(build_dir: &Path, job: &BuildJob) -> Result<PreparedJob> {
+    // Parse DSL before creating the job directory so invalid DSL leaves nothing behind
+    let dsl_json = serde_json::to_string(&job.dsl).context("Failed to serialize DSL")?;
+
+    let parsed_dsl = DslParser::parse_str(&dsl_json).context("Failed to parse DSL")?;
+
+    // Create build directory
+    let job_dir = build_dir.join(&job.job_id);
+    std::fs::create_dir_all(&job_dir).context("Failed to create job directory")?;
+
+    // Generate SDK package
+    info!("Generating code for job: {}", job.job_id);
+    let generator = CodeGenerator::new(parsed_dsl);
+    generator
+        .generate_sdk_package(&job_dir)
+        .context("Failed to generate SDK package")?;
+
+    Ok(PreparedJob {
+        job_dir,
+        guest_name: generator.guest_package_name(),
+    })
+}
+
+/// Generate a job's guest sources on the blocking pool
+fn spawn_prepare_job(build_dir: PathBuf, job: BuildJob) -> JoinHandle<Result<PreparedJob>> {
+    tokio::task::spawn_blocking(move || prepare_job(&build_dir, &job))
+}
+
+/// Build a job's generated sources and compute the guest's image ID
+pub(crate) fn build_guest(
+    config: &WorkerConfig,
+    job_id: &str,
+    prepared: &PreparedJob,
+) -> Result<BuiltGuest> {
+    // Build guest program
+    info!("Building guest program for job: {}", job_id);
+    let methods_dir = prepared.job_dir.join("methods");
+
+    run_build(config, &methods_dir)?;
+
+    // Find the built ELF (its location depends on the risc0 version)
+    let elf_path = find_guest_elf(&methods_dir, &prepared.guest_name)?;
+    info!("Found guest ELF for job {}: {}", job_id, elf_path.display());
+
+    // Compute Image ID
+    info!("Computing Image ID for job: {}", job_id);
+    let elf_bytes = std::fs::read(&elf_path).context("Failed to read guest ELF")?;
+
+    // Use risc0 to compute image ID
+    // Note: In production, we'd use risc0_zkvm::compute_image_id
+    // For now, we'll compute a hash
+    let image_id = compute_image_id_hash(&elf_bytes);
+
+    info!("Image ID: {} for job: {}", image_id, job_id);
+
+    Ok(BuiltGuest {
+        elf_path,
+        elf_bytes,
+        image_id,
+    })
+}
+
+/// Run the configured build command in `methods_dir`
+fn run_build(config: &WorkerConfig, methods_dir: &Path) -> Result<()> {
+    let build_output = Command::new(&config.build_command)
+        .args(&config.build_args)
+        .current_dir(methods_dir)
+        .output()
+        .with_context(|| {
+            format!(
+                "Failed to execute {} {}",
+                config.build_command,
+                config.build_args.join(" ")
+            )
+        })?;
+
+    if !build_output.status.success() {
+        let stderr = String::from_utf8_lossy(&build_output.stderr);
+        anyhow::bail!("Build failed: {}", stderr);
+    }
+
+    Ok(())
+}
+
+/// Locate the ELF built for `guest_name` under `methods_dir/target/riscv-guest`
+///
+/// Older risc0 versions write `riscv32im-risc0-zkvm-elf/release/<guest>` directly
+/// under `riscv-guest`; newer ones nest it under the methods and guest crate
+/// names, and may add a `.bin` suffix. Every file named after the guest is a
+/// candidate, and the most recently modified one wins.
+fn find_guest_elf(methods_dir: &Path, guest_name: &str) -> Result<PathBuf> {
+    let guest_dir = methods_dir.join("target/riscv-guest");
+    let names = [guest_name.to_string(), format!("{}.bin", guest_name)];
+
+    let mut newest: Option<(std::time::SystemTime, PathBuf)> = None;
+    let mut pending = vec![guest_dir.clone()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                pending.push(path);
+            } else if names.iter().any(|name| entry.file_name() == name.as_str()) {
+                let modified = metadata.modified().unwrap_or(std::time::UNIX_EPOCH);
+                if newest.as_ref().is_none_or(|(time, _)| modified > *time) {
+                    newest = Some((modified, path));
+                }
             }
         }
     }
+
+    newest.map(|(_, path)| path).with_context(|| {
+        format!(
+            "Guest ELF not found after build: no {} under {}",
+            guest_name,
+            guest_dir.display()
+        )
+    })
+}
+
+/// Store a built guest ELF, returning its final path and compression
+///
+/// With `cleanup_after_build`, the ELF is written to `build_dir/artifacts/<job_id>.elf[.gz]`
+/// and the job directory (generated sources and cargo target dir) is removed.
+/// Otherwise it stays in the job directory, with a `.elf.gz` copy when compressing.
+fn store_elf(
+    config: &WorkerConfig,
+    job_id: &str,
+    job_dir: &Path,
+    elf_path: &Path,
+    elf_bytes: &[u8],
+) -> Result<(PathBuf, Option<&'static str>)> {
+    let (contents, elf_compression) = if config.compress_elf {
+        (compress_elf(elf_bytes)?, Some("gzip"))
+    } else {
+        (elf_bytes.to_vec(), None)
+    };
+
+    let stored_path = if config.cleanup_after_build {
+        let artifacts_dir = config.build_dir.join("artifacts");
+        std::fs::create_dir_all(&artifacts_dir)
+            .context("Failed to create artifacts directory")?;
+        let extension = if elf_compression.is_some() {
+            "elf.gz"
+        } else {
+            "elf"
+        };
+        artifacts_dir.join(format!("{}.{}", job_id, extension))
+    } else if elf_compression.is_some() {
+        elf_path.with_extension("elf.gz")
+    } else {
+        return Ok((elf_path.to_path_buf(), None));
+    };
+
+    std::fs::write(&stored_path, contents).context("Failed to write guest ELF")?;
+
+    if config.cleanup_after_build {
+        std::fs::remove_dir_all(job_dir).context("Failed to remove job directory")?;
+        info!("Removed build directory for job: {}", job_id);
+    }
+
+    Ok((stored_path, elf_compression))
+}
+
+/// Gzip-compress a guest ELF for storage
+fn compress_elf(elf_bytes: &[u8]) -> Result<Vec<u8>> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder
+        .write_all(elf_bytes)
+        .context("Failed to compress guest ELF")?;
+    encoder.finish().context("Failed to compress guest ELF")
 }
 
 /// Compute a simple hash-based image ID
 /// In production, use risc0_zkvm::compute_image_id
-fn compute_image_id_hash(elf_bytes: &[u8]) -> String {
+pub(crate) fn compute_image_id_hash(elf_bytes: &[u8]) -> String {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
 
@@ -244,11 +740,423 @@ fn compute_image_id_hash(elf_bytes: &[u8]) -> String {
 pub async fn start_workers(
     config: WorkerConfig,
     redis_url: &str,
+    redis_db: Option<i64>,
     shutdown_rx: mpsc::Receiver<()>,
 ) -> Result<()> {
-    let storage = Storage::new(redis_url).await?;
+    let storage = Storage::new(redis_url, redis_db).await?;
     let mut worker = Worker::new(config, storage);
 
     // Run worker (in production, spawn multiple)
     worker.run().await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config(
+        build_dir: &Path,
+        compress_elf: bool,
+        cleanup_after_build: bool,
+    ) -> WorkerConfig {
+        WorkerConfig {
+            build_dir: build_dir.to_path_buf(),
+            registry_url: "http://127.0.0.1:8083".to_string(),
+            gateway_url: "http://localhost:8080".to_string(),
+            num_workers: 1,
+            compress_elf,
+            cleanup_after_build,
+            webhook_secret: None,
+            webhook_retry: WebhookRetryPolicy::default(),
+            build_command: "cargo".to_string(),
+            build_args: vec!["risczero".to_string(), "build".to_string()],
+            prefetch_next_job: false,
+        }
+    }
+
+    /// Lay out a job directory as `cargo risczero build` would leave it
+    fn simulate_build(build_dir: &Path, job_id: &str) -> (PathBuf, PathBuf) {
+        let job_dir = build_dir.join(job_id);
+        let elf_path = job_dir
+            .join("methods/target/riscv-guest/riscv32im-risc0-zkvm-elf/release/guest");
+        std::fs::create_dir_all(elf_path.parent().unwrap()).unwrap();
+        std::fs::write(&elf_path, b"\x7fELF guest").unwrap();
+        (job_dir, elf_path)
+    }
+
+    #[test]
+    fn test_cleanup_after_build_removes_job_dir() {
+        let build_dir = tempfile::tempdir().unwrap();
+        let config = test_config(build_dir.path(), false, true);
+        let (job_dir, elf_path) = simulate_build(build_dir.path(), "job-1");
+
+        let (stored_path, compression) =
+            store_elf(&config, "job-1", &job_dir, &elf_path, b"\x7fELF guest").unwrap();
+
+        assert!(!job_dir.exists());
+        assert_eq!(compression, None);
+        assert_eq!(stored_path, build_dir.path().join("artifacts/job-1.elf"));
+        assert_eq!(std::fs::read(&stored_path).unwrap(), b"\x7fELF guest");
+    }
+
+    #[test]
+    fn test_run_build_uses_configured_command() {
+        let build_dir = tempfile::tempdir().unwrap();
+        let mut config = test_config(build_dir.path(), false, false);
+        config.build_command = "sh".to_string();
+        config.build_args = vec![
+            "-c".to_string(),
+            "echo $0 > built-by".to_string(),
+            "stub".to_string(),
+        ];
+
+        run_build(&config, build_dir.path()).unwrap();
+        let marker = std::fs::read_to_string(build_dir.path().join("built-by")).unwrap();
+        assert_eq!(marker.trim(), "stub");
+
+        // A failing build surfaces the command's stderr
+        config.build_args = vec![
+            "-c".to_string(),
+            "echo no toolchain >&2; exit 1".to_string(),
+        ];
+        let err = run_build(&config, build_dir.path()).unwrap_err();
+        assert_eq!(err.to_string().trim(), "Build failed: no toolchain");
+    }
+
+    /// Write a file under `root` with the given modification time (seconds after the epoch)
+    fn touch(root: &Path, relative: &str, modified_secs: u64) -> PathBuf {
+        let path = root.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, b"\x7fELF").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(std::time::UNIX_EPOCH + Duration::from_secs(modified_secs))
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_find_guest_elf_picks_newest_matching_binary() {
+        let methods_dir = tempfile::tempdir().unwrap();
+        let root = methods_dir.path();
+
+        // Old flat layout, a newer nested layout, and files that must be ignored
+        touch(
+            root,
+            "target/riscv-guest/riscv32im-risc0-zkvm-elf/release/age_check-guest",
+            1_000,
+        );
+        let newest = touch(
+            root,
+            "target/riscv-guest/age_check-methods/age_check-guest/riscv32im-risc0-zkvm-elf/release/age_check-guest.bin",
+            2_000,
+        );
+        touch(
+            root,
+            "target/riscv-guest/riscv32im-risc0-zkvm-elf/release/deps/age_check-guest-0123abcd",
+            3_000,
+        );
+        touch(
+            root,
+            "target/riscv-guest/riscv32im-risc0-zkvm-elf/release/other-guest",
+            4_000,
+        );
+        touch(root, "target/release/age_check-guest", 5_000);
+
+        assert_eq!(find_guest_elf(root, "age_check-guest").unwrap(), newest);
+
+        let err = find_guest_elf(root, "missing-guest").unwrap_err();
+        assert!(err.to_string().starts_with("Guest ELF not found after build"));
+    }
+
+    #[test]
+    fn test_sign_webhook_known_vector() {
+        let body = br#"{"job_id":"job-1","customer_id":"customer-123","status":"completed"}"#;
+        assert_eq!(
+            sign_webhook("whsec_test", body),
+            "sha256=19ed568e1bb143eb572760b33ebe4e15605e5c529c7434cfa852ed9b221f76d2"
+        );
+    }
+
+    fn fast_retry_policy(max_attempts: u32) -> WebhookRetryPolicy {
+        WebhookRetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(50),
+            attempt_timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Serve a webhook receiver that fails the first `failures` requests
+    async fn flaky_receiver(
+        failures: usize,
+    ) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let app = axum::Router::new().route(
+            "/hook",
+            axum::routing::post(move || {
+                let counter = counter.clone();
+                async move {
+                    if counter.fetch_add(1, Ordering::SeqCst) < failures {
+                        axum::http::StatusCode::SERVICE_UNAVAILABLE
+                    } else {
+                        axum::http::StatusCode::OK
+                    }
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        (format!("http://{}/hook", addr), hits)
+    }
+
+    #[tokio::test]
+    async fn test_webhook_retries_until_delivered() {
+        let (url, hits) = flaky_receiver(2).await;
+
+        let delivery = deliver_webhook(
+            &reqwest::Client::new(),
+            &url,
+            b"{}",
+            None,
+            &fast_retry_policy(5),
+        )
+        .await;
+
+        assert!(delivery.delivered);
+        assert_eq!(delivery.attempts, 3);
+        assert_eq!(hits.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_gives_up_after_max_attempts() {
+        let (url, hits) = flaky_receiver(usize::MAX).await;
+
+        let delivery = deliver_webhook(
+            &reqwest::Client::new(),
+            &url,
+            b"{}",
+            None,
+            &fast_retry_policy(3),
+        )
+        .await;
+
+        assert!(!delivery.delivered);
+        assert_eq!(delivery.attempts, 3);
+        assert_eq!(hits.load(std::sync::atomic::Ordering::SeqCst), 3);
+        assert!(delivery.last_error.unwrap().contains("503"));
+    }
+
+    #[test]
+    fn test_webhook_backoff_doubles_and_caps() {
+        let policy = WebhookRetryPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(10), Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn test_register_deployment_sends_required_params() {
+        use std::sync::{Arc, Mutex};
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let recorder = received.clone();
+        let app = axum::Router::new().route(
+            "/api/deployments",
+            axum::routing::post(move |axum::Json(body): axum::Json<serde_json::Value>| {
+                let recorder = recorder.clone();
+                async move {
+                    recorder.lock().unwrap().push(body);
+                    axum::http::StatusCode::CREATED
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        // Two public params; the defaulted one isn't required
+        let job = BuildJob::new(
+            "job-params".to_string(),
+            "customer-params".to_string(),
+            serde_json::json!({
+                "use_case": "age_check",
+                "private_inputs": { "type": "object", "fields": { "age": "u32" } },
+                "public_params": { "min_age": "u32", "region": "string", "max_age": "u32" },
+                "param_defaults": { "max_age": 150 },
+                "validation_rules": [
+                    {
+                        "type": "range_check",
+                        "field": "age",
+                        "min_param": "min_age",
+                        "max_param": "max_age"
+                    }
+                ]
+            }),
+        );
+        register_deployment(
+            &reqwest::Client::new(),
+            &format!("http://{}", addr),
+            &job,
+            &"ab".repeat(32),
+            Path::new("/artifacts/job-params.elf"),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let received = received.lock().unwrap();
+        assert_eq!(
+            received[0]["metadata"]["required_params"],
+            serde_json::json!([
+                { "name": "min_age", "param_type": "u32" },
+                { "name": "region", "param_type": "string" }
+            ])
+        );
+        assert_eq!(
+            received[0]["metadata"]["schema_hash"],
+            schema_hash(&job.dsl).unwrap()
+        );
+
+        // The source the guest was built from, as the code generator produces it
+        let dsl = DslParser::parse_str(&job.dsl.to_string()).unwrap();
+        let generated = CodeGenerator::new(dsl).generate().unwrap();
+        assert_eq!(received[0]["guest_source"], generated);
+
+        let build_dir = tempfile::tempdir().unwrap();
+        let prepared = prepare_job(build_dir.path(), &job).unwrap();
+        let main_rs =
+            std::fs::read_to_string(prepared.job_dir.join("methods/guest/src/main.rs")).unwrap();
+        assert_eq!(main_rs, generated);
+    }
+
+    #[test]
+    fn test_schema_hash_ignores_descriptions() {
+        let dsl = serde_json::json!({
+            "use_case": "age_check",
+            "description": "Check the customer is an adult",
+            "private_inputs": { "type": "object", "fields": { "age": "u32", "name": "string" } },
+            "public_params": { "min_age": "u32" },
+            "validation_rules": [
+                {
+                    "type": "range_check",
+                    "description": "Adult",
+                    "field": "age",
+                    "min_param": "min_age",
+                    "max": 150
+                }
+            ]
+        });
+        let hash = schema_hash(&dsl).unwrap();
+        assert_eq!(hash.len(), 64);
+
+        // Same schema, described differently and declared in another order
+        let mut redescribed = dsl.clone();
+        redescribed["description"] = "Age gate".into();
+        redescribed["validation_rules"][0]["description"] = "Of age".into();
+        redescribed["private_inputs"]["fields"] =
+            serde_json::json!({ "name": "string", "age": "u32" });
+        assert_eq!(schema_hash(&redescribed).unwrap(), hash);
+
+        // Changing what a request must supply changes the hash
+        let mut retyped = dsl.clone();
+        retyped["private_inputs"]["fields"]["age"] = "u64".into();
+        assert_ne!(schema_hash(&retyped).unwrap(), hash);
+
+        let mut defaulted = dsl.clone();
+        defaulted["param_defaults"] = serde_json::json!({ "min_age": 18 });
+        assert_ne!(schema_hash(&defaulted).unwrap(), hash);
+    }
+
+    #[test]
+    fn test_job_dir_kept_without_cleanup() {
+        let build_dir = tempfile::tempdir().unwrap();
+        let config = test_config(build_dir.path(), true, false);
+        let (job_dir, elf_path) = simulate_build(build_dir.path(), "job-2");
+
+        let (stored_path, compression) =
+            store_elf(&config, "job-2", &job_dir, &elf_path, b"\x7fELF guest").unwrap();
+
+        assert!(job_dir.exists());
+        assert_eq!(compression, Some("gzip"));
+        assert_eq!(stored_path, elf_path.with_extension("elf.gz"));
+        assert!(stored_path.exists());
+        assert_eq!(elf_compression(&stored_path), Some("gzip"));
+        assert_eq!(elf_compression(&elf_path), None);
+    }
+
+    fn age_check_job(job_id: &str) -> BuildJob {
+        BuildJob::new(
+            job_id.to_string(),
+            "customer-prefetch".to_string(),
+            serde_json::json!({
+                "use_case": "age_check",
+                "private_inputs": { "type": "object", "fields": { "age": "u32" } },
+                "public_params": { "min_age": "u32" },
+                "validation_rules": [
+                    { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 }
+                ]
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_generates_sources_during_build() {
+        let build_dir = tempfile::tempdir().unwrap();
+        let mut config = test_config(build_dir.path(), false, false);
+        config.build_command = "sh".to_string();
+        config.build_args = vec!["-c".to_string(), "sleep 1".to_string()];
+
+        let current = prepare_job(build_dir.path(), &age_check_job("job-current")).unwrap();
+
+        // The next job's sources are generated while the current one builds
+        let next = spawn_prepare_job(build_dir.path().to_path_buf(), age_check_job("job-next"));
+        run_build(&config, &current.job_dir.join("methods")).unwrap();
+        assert!(next.is_finished());
+
+        let next = next.await.unwrap().unwrap();
+        assert_eq!(next.job_dir, build_dir.path().join("job-next"));
+        assert_eq!(next.guest_name, current.guest_name);
+        assert!(next.job_dir.join("methods").is_dir());
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_failure_leaves_current_job_alone() {
+        let build_dir = tempfile::tempdir().unwrap();
+        let current = prepare_job(build_dir.path(), &age_check_job("job-current")).unwrap();
+        let sources_before = std::fs::read_dir(current.job_dir.join("methods"))
+            .unwrap()
+            .count();
+
+        // Invalid DSL fails only the prefetched job, and leaves no directory behind
+        let mut broken = age_check_job("job-broken");
+        broken.dsl["validation_rules"] = serde_json::json!("not a list");
+        let err = spawn_prepare_job(build_dir.path().to_path_buf(), broken)
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Failed to parse DSL");
+        assert!(!build_dir.path().join("job-broken").exists());
+
+        assert_eq!(
+            std::fs::read_dir(current.job_dir.join("methods"))
+                .unwrap()
+                .count(),
+            sources_before
+        );
+    }
+}
@@ -5,6 +5,9 @@ pub enum Error {
     #[error("Invalid proof: {0}")]
     InvalidProof(String),
 
+    #[error("Malformed journal: {0}")]
+    MalformedJournal(String),
+
     #[error("Nullifier replay detected")]
     NullifierReplay,
 
@@ -80,6 +80,10 @@ pub struct GuestInputs {
     pub business: BusinessInputs,
 }
 
+/// Metadata prefix a guest commits, followed by the rule's index, when the
+/// customer opted in to reporting which validation rule failed
+pub const FAILED_RULE_METADATA_PREFIX: &str = "failed_rule:";
+
 /// Output from RISC Zero guest program (written to journal)
 /// This is what the verifier can read without re-running the proof
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -130,6 +134,21 @@ impl GuestOutputs {
             metadata,
         }
     }
+
+    /// Index of the first failing validation rule, if the guest reported one
+    ///
+    /// Only guests generated with `report_failed_rule` commit this, and only
+    /// when validation fails.
+    pub fn failed_rule_index(&self) -> Option<usize> {
+        if self.compliance_result {
+            return None;
+        }
+        std::str::from_utf8(&self.metadata)
+            .ok()?
+            .strip_prefix(FAILED_RULE_METADATA_PREFIX)?
+            .parse()
+            .ok()
+    }
 }
 
 #[cfg(test)]
@@ -144,6 +163,26 @@ mod tests {
         assert_eq!(outputs.nullifier, nullifier);
     }
 
+    #[test]
+    fn test_failed_rule_index() {
+        let nullifier = Nullifier::new([1u8; 32]);
+
+        let outputs =
+            GuestOutputs::with_metadata(nullifier.clone(), false, b"failed_rule:2".to_vec());
+        assert_eq!(outputs.failed_rule_index(), Some(2));
+
+        // Not reported: opted out, malformed, or validation passed
+        assert_eq!(
+            GuestOutputs::failure(nullifier.clone()).failed_rule_index(),
+            None
+        );
+        let outputs =
+            GuestOutputs::with_metadata(nullifier.clone(), false, b"failed_rule:x".to_vec());
+        assert_eq!(outputs.failed_rule_index(), None);
+        let outputs = GuestOutputs::with_metadata(nullifier, true, b"failed_rule:2".to_vec());
+        assert_eq!(outputs.failed_rule_index(), None);
+    }
+
     #[test]
     fn test_serialization() {
         let nullifier = Nullifier::new([1u8; 32]);
@@ -2,8 +2,12 @@ pub mod error;
 pub mod inputs;
 pub mod nullifier;
 pub mod receipt;
+#[cfg(feature = "redis")]
+pub mod redis_url;
 
 pub use error::{Error, Result};
-pub use inputs::{BusinessInputs, GuestInputs, GuestOutputs, ZcashInputs};
+pub use inputs::{
+    BusinessInputs, GuestInputs, GuestOutputs, ZcashInputs, FAILED_RULE_METADATA_PREFIX,
+};
 pub use nullifier::Nullifier;
-pub use receipt::Receipt;
+pub use receipt::{decode_journal, decode_journal_strict, JournalEncoding, Receipt, ReceiptKind};
@@ -1,4 +1,167 @@
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// How `GuestOutputs` are encoded in a receipt's journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JournalEncoding {
+    /// Detect the encoding: JSON if the journal looks like a JSON object, else bincode
+    #[default]
+    Auto,
+    /// bincode (standard config)
+    Bincode,
+    /// JSON
+    Json,
+}
+
+impl FromStr for JournalEncoding {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "bincode" => Ok(Self::Bincode),
+            "json" => Ok(Self::Json),
+            other => Err(crate::Error::Other(anyhow::anyhow!(
+                "Unknown journal encoding: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Fields of a JSON-encoded `GuestOutputs` journal
+const JOURNAL_JSON_FIELDS: &[&str] = &["nullifier", "compliance_result", "metadata"];
+
+/// Decode `GuestOutputs` from journal bytes
+pub fn decode_journal(
+    journal_bytes: &[u8],
+    encoding: JournalEncoding,
+) -> crate::Result<crate::GuestOutputs> {
+    decode_journal_as(journal_bytes, encoding, false)
+}
+
+/// Decode `GuestOutputs` from journal bytes that must be exactly one encoded value
+///
+/// [`decode_journal`] ignores bytes after a bincode value and fields a JSON
+/// object has beyond `GuestOutputs`, so a differently-shaped journal can
+/// still decode. Strict decoding rejects both.
+pub fn decode_journal_strict(
+    journal_bytes: &[u8],
+    encoding: JournalEncoding,
+) -> crate::Result<crate::GuestOutputs> {
+    decode_journal_as(journal_bytes, encoding, true)
+}
+
+fn decode_journal_as(
+    journal_bytes: &[u8],
+    encoding: JournalEncoding,
+    strict: bool,
+) -> crate::Result<crate::GuestOutputs> {
+    match encoding {
+        JournalEncoding::Bincode => {
+            let (outputs, read): (crate::GuestOutputs, usize) =
+                bincode::serde::decode_from_slice(journal_bytes, bincode::config::standard())?;
+            if strict && read != journal_bytes.len() {
+                return Err(crate::Error::MalformedJournal(format!(
+                    "{} trailing bytes after GuestOutputs",
+                    journal_bytes.len() - read
+                )));
+            }
+            Ok(outputs)
+        }
+        JournalEncoding::Json if strict => {
+            let value: serde_json::Value = serde_json::from_slice(journal_bytes)?;
+            let fields = value.as_object().ok_or_else(|| {
+                crate::Error::MalformedJournal("JSON journal is not an object".to_string())
+            })?;
+            if let Some(extra) = fields
+                .keys()
+                .find(|key| !JOURNAL_JSON_FIELDS.contains(&key.as_str()))
+            {
+                return Err(crate::Error::MalformedJournal(format!(
+                    "unexpected field {:?}",
+                    extra
+                )));
+            }
+            Ok(serde_json::from_value(value)?)
+        }
+        JournalEncoding::Json => Ok(serde_json::from_slice(journal_bytes)?),
+        JournalEncoding::Auto => {
+            // A bincode journal starts with raw nullifier bytes, which may
+            // happen to be '{', so fall back to bincode if JSON fails
+            if journal_bytes.first() == Some(&b'{') {
+                if let Ok(outputs) = decode_journal_as(journal_bytes, JournalEncoding::Json, strict)
+                {
+                    return Ok(outputs);
+                }
+            }
+            decode_journal_as(journal_bytes, JournalEncoding::Bincode, strict)
+        }
+    }
+}
+
+/// Proof system behind a RISC Zero receipt
+///
+/// Every kind verifies against the image ID the same way; they differ in size
+/// and in where they can be verified (only Groth16 is cheap enough on-chain).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReceiptKind {
+    /// One STARK per segment (the prover's default output)
+    Composite,
+    /// Segments recursively compressed into a single STARK
+    Succinct,
+    /// A succinct receipt wrapped in a Groth16 SNARK
+    Groth16,
+    /// No proof at all (`RISC0_DEV_MODE`); fails verification outside dev mode
+    Fake,
+}
+
+impl ReceiptKind {
+    /// Kind of a RISC Zero inner receipt, or `None` for kinds this crate doesn't know
+    pub fn of(inner: &risc0_zkvm::InnerReceipt) -> Option<Self> {
+        match inner {
+            risc0_zkvm::InnerReceipt::Composite(_) => Some(Self::Composite),
+            risc0_zkvm::InnerReceipt::Succinct(_) => Some(Self::Succinct),
+            risc0_zkvm::InnerReceipt::Groth16(_) => Some(Self::Groth16),
+            risc0_zkvm::InnerReceipt::Fake(_) => Some(Self::Fake),
+            _ => None,
+        }
+    }
+
+    /// Lowercase name, as used in JSON and headers
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Composite => "composite",
+            Self::Succinct => "succinct",
+            Self::Groth16 => "groth16",
+            Self::Fake => "fake",
+        }
+    }
+}
+
+impl std::fmt::Display for ReceiptKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ReceiptKind {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "composite" => Ok(Self::Composite),
+            "succinct" => Ok(Self::Succinct),
+            "groth16" => Ok(Self::Groth16),
+            "fake" => Ok(Self::Fake),
+            other => Err(crate::Error::Other(anyhow::anyhow!(
+                "Unknown receipt kind: {}",
+                other
+            ))),
+        }
+    }
+}
 
 /// A wrapper around a RISC Zero receipt (proof)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +212,15 @@ impl Receipt {
         Ok(())
     }
 
+    /// Which proof system the wrapped receipt uses
+    pub fn kind(&self) -> crate::Result<ReceiptKind> {
+        let (risc0_receipt, _): (risc0_zkvm::Receipt, usize) =
+            bincode::serde::decode_from_slice(&self.inner, bincode::config::standard())?;
+
+        ReceiptKind::of(&risc0_receipt.inner)
+            .ok_or_else(|| crate::Error::InvalidProof("Unsupported receipt kind".to_string()))
+    }
+
     /// Get the journal (public outputs) from the receipt
     ///
     /// The journal contains the data that the guest program wrote via `env::commit()`.
@@ -65,6 +237,7 @@ impl Receipt {
     /// Verify the proof and decode the outputs in one step
     ///
     /// This is a convenience method that combines verification and journal extraction.
+    /// The journal encoding is detected automatically (see [`JournalEncoding::Auto`]).
     ///
     /// # Arguments
     /// * `expected_image_id` - The Image ID of the expected guest program
@@ -74,16 +247,35 @@ impl Receipt {
     pub fn verify_and_decode(
         &self,
         expected_image_id: &[u8; 32],
+    ) -> crate::Result<crate::GuestOutputs> {
+        self.verify_and_decode_with(expected_image_id, JournalEncoding::Auto)
+    }
+
+    /// Verify the proof and decode the outputs using a specific journal encoding
+    pub fn verify_and_decode_with(
+        &self,
+        expected_image_id: &[u8; 32],
+        encoding: JournalEncoding,
     ) -> crate::Result<crate::GuestOutputs> {
         // First verify the proof
         self.verify(expected_image_id)?;
 
         // Then extract and deserialize the outputs
         let journal_bytes = self.journal()?;
-        let (outputs, _): (crate::GuestOutputs, usize) =
-            bincode::serde::decode_from_slice(&journal_bytes, bincode::config::standard())?;
+        decode_journal(&journal_bytes, encoding)
+    }
+
+    /// Verify the proof and decode the outputs, rejecting journals that aren't
+    /// exactly one `GuestOutputs` (see [`decode_journal_strict`])
+    pub fn verify_and_decode_strict(
+        &self,
+        expected_image_id: &[u8; 32],
+        encoding: JournalEncoding,
+    ) -> crate::Result<crate::GuestOutputs> {
+        self.verify(expected_image_id)?;
 
-        Ok(outputs)
+        let journal_bytes = self.journal()?;
+        decode_journal_strict(&journal_bytes, encoding)
     }
 }
 
@@ -97,4 +289,132 @@ mod tests {
         assert_eq!(receipt.proof_size(), 3);
         assert_eq!(receipt.image_id[0], 42);
     }
+
+    #[test]
+    fn test_receipt_kind_names() {
+        for kind in [
+            ReceiptKind::Composite,
+            ReceiptKind::Succinct,
+            ReceiptKind::Groth16,
+            ReceiptKind::Fake,
+        ] {
+            assert_eq!(kind.as_str().parse::<ReceiptKind>().unwrap(), kind);
+            assert_eq!(
+                serde_json::to_value(kind).unwrap(),
+                serde_json::json!(kind.as_str())
+            );
+        }
+
+        assert_eq!(
+            "Groth16".parse::<ReceiptKind>().unwrap(),
+            ReceiptKind::Groth16
+        );
+        assert!("stark".parse::<ReceiptKind>().is_err());
+    }
+
+    #[test]
+    fn test_decode_journal_bincode_and_json() {
+        let outputs = crate::GuestOutputs::with_metadata(
+            crate::Nullifier::new([7u8; 32]),
+            true,
+            b"age_verified".to_vec(),
+        );
+
+        let bincode_bytes =
+            bincode::serde::encode_to_vec(&outputs, bincode::config::standard()).unwrap();
+        let json_bytes = serde_json::to_vec(&outputs).unwrap();
+
+        for (bytes, encoding) in [
+            (&bincode_bytes, JournalEncoding::Bincode),
+            (&bincode_bytes, JournalEncoding::Auto),
+            (&json_bytes, JournalEncoding::Json),
+            (&json_bytes, JournalEncoding::Auto),
+        ] {
+            let decoded = decode_journal(bytes, encoding).unwrap();
+            assert_eq!(decoded.nullifier, outputs.nullifier);
+            assert_eq!(decoded.compliance_result, outputs.compliance_result);
+            assert_eq!(decoded.metadata, outputs.metadata);
+        }
+
+        // An explicit encoding does not fall back to the other format
+        assert!(decode_journal(&json_bytes, JournalEncoding::Bincode).is_err());
+        assert!(decode_journal(&bincode_bytes, JournalEncoding::Json).is_err());
+    }
+
+    #[test]
+    fn test_decode_journal_bincode_starting_with_brace() {
+        // Nullifier bytes that look like the start of a JSON object
+        let outputs = crate::GuestOutputs::success(crate::Nullifier::new([b'{'; 32]));
+        let bytes = bincode::serde::encode_to_vec(&outputs, bincode::config::standard()).unwrap();
+
+        let decoded = decode_journal(&bytes, JournalEncoding::Auto).unwrap();
+        assert_eq!(decoded.nullifier, outputs.nullifier);
+    }
+
+    #[test]
+    fn test_strict_decode_rejects_wrong_shape() {
+        let outputs = crate::GuestOutputs::success(crate::Nullifier::new([7u8; 32]));
+
+        // A valid journal followed by extra committed data
+        let mut bincode_bytes =
+            bincode::serde::encode_to_vec(&outputs, bincode::config::standard()).unwrap();
+        let exact = bincode_bytes.clone();
+        bincode_bytes.extend_from_slice(b"extra");
+
+        // A JSON journal carrying fields GuestOutputs doesn't have
+        let mut json = serde_json::to_value(&outputs).unwrap();
+        json["approved_amount"] = serde_json::json!(1_000_000);
+        let json_bytes = serde_json::to_vec(&json).unwrap();
+
+        for (bytes, encoding, expected) in [
+            (&bincode_bytes, JournalEncoding::Bincode, "5 trailing bytes"),
+            (&bincode_bytes, JournalEncoding::Auto, "5 trailing bytes"),
+            (
+                &json_bytes,
+                JournalEncoding::Json,
+                "unexpected field \"approved_amount\"",
+            ),
+        ] {
+            // Lenient decoding accepts the journal...
+            assert!(decode_journal(bytes, encoding).is_ok());
+
+            // ...strict decoding does not
+            let err = decode_journal_strict(bytes, encoding).unwrap_err();
+            assert!(
+                matches!(&err, crate::Error::MalformedJournal(msg) if msg.contains(expected)),
+                "{:?}: {}",
+                encoding,
+                err
+            );
+        }
+
+        // Exactly encoded journals decode the same either way
+        let json_bytes = serde_json::to_vec(&outputs).unwrap();
+        for (bytes, encoding) in [
+            (&exact, JournalEncoding::Bincode),
+            (&exact, JournalEncoding::Auto),
+            (&json_bytes, JournalEncoding::Json),
+            (&json_bytes, JournalEncoding::Auto),
+        ] {
+            let decoded = decode_journal_strict(bytes, encoding).unwrap();
+            assert_eq!(decoded.nullifier, outputs.nullifier);
+        }
+    }
+
+    #[test]
+    fn test_journal_encoding_from_str() {
+        assert_eq!(
+            "JSON".parse::<JournalEncoding>().unwrap(),
+            JournalEncoding::Json
+        );
+        assert_eq!(
+            "bincode".parse::<JournalEncoding>().unwrap(),
+            JournalEncoding::Bincode
+        );
+        assert_eq!(
+            "auto".parse::<JournalEncoding>().unwrap(),
+            JournalEncoding::Auto
+        );
+        assert!("cbor".parse::<JournalEncoding>().is_err());
+    }
 }
@@ -0,0 +1,32 @@
+//! Redis connection settings shared by the services
+
+use redis::{ConnectionInfo, IntoConnectionInfo, RedisResult};
+
+/// Parse a Redis URL, overriding its database index when `redis_db` is set
+///
+/// Every service takes a `REDIS_URL` plus an optional `REDIS_DB`, so several
+/// can share one Redis instance without their keys colliding.
+pub fn connection_info(redis_url: &str, redis_db: Option<i64>) -> RedisResult<ConnectionInfo> {
+    let mut info = redis_url.into_connection_info()?;
+    if let Some(db) = redis_db {
+        info.redis.db = db;
+    }
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redis_db_override() {
+        let info = connection_info("redis://127.0.0.1:6379/2", Some(15)).unwrap();
+        assert_eq!(info.redis.db, 15);
+
+        // Without an override the URL's own index is kept
+        let info = connection_info("redis://127.0.0.1:6379/2", None).unwrap();
+        assert_eq!(info.redis.db, 2);
+
+        assert!(connection_info("not a url", None).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+//! Configuration for the health check probe
+//!
+//! Loads the services to probe from environment variables, defaulting to
+//! every service on its default local port.
+
+use anyhow::{Context, Result};
+use std::env;
+use std::time::Duration;
+
+/// Services probed when `HEALTHCHECK_SERVICES` is unset
+const DEFAULT_SERVICES: &[(&str, &str)] = &[
+    ("logic-compiler-api", "http://127.0.0.1:8082"),
+    ("zcash-backend", "http://127.0.0.1:8081"),
+    ("image-id-registry", "http://127.0.0.1:8083"),
+    ("proof-generation-service", "http://127.0.0.1:8084"),
+    ("build-service", "http://127.0.0.1:8085"),
+];
+
+/// Default per-service request timeout
+const DEFAULT_TIMEOUT_SECS: u64 = 5;
+
+/// A service whose `/health` endpoint is probed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceTarget {
+    /// Name reported in the combined status
+    pub name: String,
+
+    /// Base URL (the probe requests `{url}/health`)
+    pub url: String,
+}
+
+/// Probe configuration
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Services to probe, in report order
+    pub services: Vec<ServiceTarget>,
+
+    /// Timeout for each service's health request
+    pub timeout: Duration,
+}
+
+impl Config {
+    /// Load configuration from environment variables
+    pub fn from_env() -> Result<Self> {
+        let services = match env::var("HEALTHCHECK_SERVICES") {
+            Ok(list) => parse_services(&list)?,
+            Err(_) => DEFAULT_SERVICES
+                .iter()
+                .map(|(name, url)| ServiceTarget {
+                    name: name.to_string(),
+                    url: url.to_string(),
+                })
+                .collect(),
+        };
+
+        let timeout_secs = match env::var("HEALTHCHECK_TIMEOUT_SECS") {
+            Ok(v) => v.parse().context("Invalid HEALTHCHECK_TIMEOUT_SECS")?,
+            Err(_) => DEFAULT_TIMEOUT_SECS,
+        };
+
+        let config = Config {
+            services,
+            timeout: Duration::from_secs(timeout_secs),
+        };
+
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Validate configuration
+    fn validate(&self) -> Result<()> {
+        if self.services.is_empty() {
+            anyhow::bail!("HEALTHCHECK_SERVICES must list at least one service");
+        }
+
+        for (i, service) in self.services.iter().enumerate() {
+            if !service.url.starts_with("http://") && !service.url.starts_with("https://") {
+                anyhow::bail!(
+                    "URL for service {} must be http(s), got {}",
+                    service.name,
+                    service.url
+                );
+            }
+            if self.services[..i].iter().any(|s| s.name == service.name) {
+                anyhow::bail!("Service {} is listed more than once", service.name);
+            }
+        }
+
+        if self.timeout.is_zero() {
+            anyhow::bail!("HEALTHCHECK_TIMEOUT_SECS must be greater than 0");
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a comma-separated `name=url` list
+fn parse_services(list: &str) -> Result<Vec<ServiceTarget>> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|entry| {
+            let (name, url) = entry.split_once('=').with_context(|| {
+                format!("Expected name=url in HEALTHCHECK_SERVICES, got {}", entry)
+            })?;
+            let name = name.trim();
+            if name.is_empty() {
+                anyhow::bail!(
+                    "Missing service name in HEALTHCHECK_SERVICES entry {}",
+                    entry
+                );
+            }
+            Ok(ServiceTarget {
+                name: name.to_string(),
+                url: url.trim().trim_end_matches('/').to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // Tests share process environment variables
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_defaults_cover_every_service() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        env::remove_var("HEALTHCHECK_SERVICES");
+        env::remove_var("HEALTHCHECK_TIMEOUT_SECS");
+
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.services.len(), DEFAULT_SERVICES.len());
+        assert_eq!(config.services[2].name, "image-id-registry");
+        assert_eq!(config.services[2].url, "http://127.0.0.1:8083");
+        assert_eq!(config.timeout, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_services_from_env() {
+        let _guard = ENV_LOCK.lock().unwrap();
+
+        env::set_var(
+            "HEALTHCHECK_SERVICES",
+            " registry = http://registry:8083/ ,prover=http://prover:8084,",
+        );
+        let config = Config::from_env().unwrap();
+        assert_eq!(
+            config.services,
+            vec![
+                ServiceTarget {
+                    name: "registry".to_string(),
+                    url: "http://registry:8083".to_string(),
+                },
+                ServiceTarget {
+                    name: "prover".to_string(),
+                    url: "http://prover:8084".to_string(),
+                },
+            ]
+        );
+
+        for bad in [
+            "registry",
+            "=http://registry:8083",
+            "registry=registry:8083",
+            "a=http://a,a=http://b",
+            " , ",
+        ] {
+            env::set_var("HEALTHCHECK_SERVICES", bad);
+            assert!(Config::from_env().is_err(), "{}", bad);
+        }
+
+        env::remove_var("HEALTHCHECK_SERVICES");
+    }
+}
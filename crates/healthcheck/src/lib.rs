@@ -0,0 +1,193 @@
+//! Combined health check for a Khafi deployment
+//!
+//! Queries every service's `/health` endpoint concurrently and reports a
+//! single status with per-service detail: "healthy" when every service is
+//! healthy, "degraded" otherwise.
+
+pub mod config;
+
+use serde::Serialize;
+
+pub use config::{Config, ServiceTarget};
+
+/// Health of one service
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceHealth {
+    /// Service name, as configured
+    pub name: String,
+
+    /// Base URL that was probed
+    pub url: String,
+
+    /// "healthy" or "degraded" as the service reports it, or "unhealthy"
+    /// when it's unreachable or returns an error
+    pub status: String,
+
+    /// Why the service isn't healthy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Combined status of every probed service
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    /// "healthy" when every service is healthy, "degraded" otherwise
+    pub status: String,
+
+    /// Per-service results, in configured order
+    pub services: Vec<ServiceHealth>,
+}
+
+impl HealthReport {
+    /// Whether every service is healthy
+    pub fn is_healthy(&self) -> bool {
+        self.status == "healthy"
+    }
+}
+
+/// Probe every service and combine the results
+pub async fn check_all(client: &reqwest::Client, services: &[ServiceTarget]) -> HealthReport {
+    let services =
+        futures::future::join_all(services.iter().map(|target| check_service(client, target)))
+            .await;
+
+    let status = if services.iter().all(|service| service.status == "healthy") {
+        "healthy"
+    } else {
+        "degraded"
+    };
+
+    HealthReport {
+        status: status.to_string(),
+        services,
+    }
+}
+
+/// Probe one service's `/health`
+///
+/// Services wrap their health as `{ "data": { "status": ... } }`; a success
+/// response without a status counts as healthy.
+async fn check_service(client: &reqwest::Client, target: &ServiceTarget) -> ServiceHealth {
+    let (status, error) = match client.get(format!("{}/health", target.url)).send().await {
+        Ok(response) => {
+            let code = response.status();
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            if code.is_success() {
+                let status = body["data"]["status"].as_str().unwrap_or("healthy");
+                (status.to_string(), None)
+            } else {
+                let error = body["error"]
+                    .as_str()
+                    .map(|e| format!("{}: {}", code, e))
+                    .unwrap_or_else(|| format!("Returned {}", code));
+                ("unhealthy".to_string(), Some(error))
+            }
+        }
+        Err(e) => ("unhealthy".to_string(), Some(e.to_string())),
+    };
+
+    ServiceHealth {
+        name: target.name.clone(),
+        url: target.url.clone(),
+        status,
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::get, Json, Router};
+
+    /// Serve a stub `/health` that answers with `status` and `body`
+    async fn stub_service(status: StatusCode, body: serde_json::Value) -> String {
+        let app = Router::new().route(
+            "/health",
+            get(move || {
+                let body = body.clone();
+                async move { (status, Json(body)) }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        format!("http://{}", addr)
+    }
+
+    fn target(name: &str, url: String) -> ServiceTarget {
+        ServiceTarget {
+            name: name.to_string(),
+            url,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_all_services_healthy() {
+        let healthy = serde_json::json!({ "data": { "status": "healthy" } });
+        let services = vec![
+            target(
+                "registry",
+                stub_service(StatusCode::OK, healthy.clone()).await,
+            ),
+            target("prover", stub_service(StatusCode::OK, healthy).await),
+        ];
+
+        let report = check_all(&reqwest::Client::new(), &services).await;
+
+        assert!(report.is_healthy());
+        assert_eq!(report.services.len(), 2);
+        assert!(report.services.iter().all(|s| s.error.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_unhealthy_services_degrade_report() {
+        // Bind then drop a listener so nothing is listening on the port
+        let closed = {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+        let services = vec![
+            target(
+                "registry",
+                stub_service(
+                    StatusCode::OK,
+                    serde_json::json!({ "data": { "status": "healthy" } }),
+                )
+                .await,
+            ),
+            target(
+                "prover",
+                stub_service(
+                    StatusCode::OK,
+                    serde_json::json!({ "data": { "status": "degraded" } }),
+                )
+                .await,
+            ),
+            target(
+                "zcash-backend",
+                stub_service(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    serde_json::json!({ "error": "Redis connection failed" }),
+                )
+                .await,
+            ),
+            target("build-service", closed),
+        ];
+
+        let report = check_all(&reqwest::Client::new(), &services).await;
+
+        assert!(!report.is_healthy());
+        assert_eq!(report.status, "degraded");
+        let statuses: Vec<&str> = report.services.iter().map(|s| s.status.as_str()).collect();
+        assert_eq!(statuses, ["healthy", "degraded", "unhealthy", "unhealthy"]);
+        assert_eq!(
+            report.services[2].error.as_deref(),
+            Some("503 Service Unavailable: Redis connection failed")
+        );
+        assert!(report.services[3].error.is_some());
+    }
+}
@@ -0,0 +1,31 @@
+//! Khafi health check probe
+//!
+//! Prints the combined health of every configured service as JSON and exits
+//! non-zero unless all of them are healthy, for use as a container or
+//! orchestrator probe.
+
+use anyhow::{Context, Result};
+use khafi_healthcheck::{check_all, Config};
+use std::process::ExitCode;
+
+#[tokio::main]
+async fn main() -> Result<ExitCode> {
+    let config = Config::from_env()?;
+
+    let client = reqwest::Client::builder()
+        .timeout(config.timeout)
+        .build()
+        .context("Failed to build HTTP client")?;
+
+    let report = check_all(&client, &config.services).await;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).context("Failed to serialize report")?
+    );
+
+    Ok(if report.is_healthy() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
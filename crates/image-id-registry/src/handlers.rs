@@ -1,8 +1,11 @@
 //! API request handlers for Image ID Registry
+//!
+//! Successful responses are wrapped as `{ "data": ... }` and failures as
+//! `{ "error": "..." }`.
 
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -12,13 +15,18 @@ use tokio::sync::Mutex;
 use tracing::info;
 
 use crate::{
-    models::{CustomerDeployment, DeploymentMetadata},
-    storage::Storage,
+    models::{CustomerDeployment, DeploymentMetadata, RequiredParam},
+    storage::{DeploymentStore, Storage},
 };
 
+/// Maximum number of customer IDs accepted by the batch endpoint
+const MAX_BATCH_SIZE: usize = 500;
+
 /// Shared application state
-pub struct AppState {
-    pub storage: Mutex<Storage>,
+///
+/// Generic over the deployment store; the service itself uses Redis [`Storage`].
+pub struct AppState<S = Storage> {
+    pub storage: Mutex<S>,
 }
 
 /// API Error type
@@ -38,6 +46,25 @@ impl IntoResponse for ApiError {
     }
 }
 
+/// Successful response envelope
+#[derive(Debug, Serialize)]
+pub struct ApiResponse<T> {
+    pub data: T,
+}
+
+impl<T> ApiResponse<T> {
+    /// Wrap a response payload
+    pub fn new(data: T) -> Self {
+        Self { data }
+    }
+}
+
+impl<T: Serialize> IntoResponse for ApiResponse<T> {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
 impl From<anyhow::Error> for ApiError {
     fn from(err: anyhow::Error) -> Self {
         ApiError {
@@ -53,8 +80,14 @@ pub struct RegisterDeploymentRequest {
     pub customer_id: String,
     pub image_id: String,
     pub guest_program_path: String,
+    #[serde(default)]
+    pub elf_compression: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<DeploymentMetadata>,
+    #[serde(default)]
+    pub dsl: Option<serde_json::Value>,
+    #[serde(default)]
+    pub guest_source: Option<String>,
 }
 
 /// Response from registration
@@ -69,8 +102,14 @@ pub struct RegisterDeploymentResponse {
 pub struct UpdateDeploymentRequest {
     pub image_id: String,
     pub guest_program_path: String,
+    #[serde(default)]
+    pub elf_compression: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<DeploymentMetadata>,
+    #[serde(default)]
+    pub dsl: Option<serde_json::Value>,
+    #[serde(default)]
+    pub guest_source: Option<String>,
 }
 
 /// Deployment info response
@@ -79,6 +118,32 @@ pub struct DeploymentResponse {
     pub deployment: CustomerDeployment,
 }
 
+/// DSL stored with a deployment
+#[derive(Debug, Serialize)]
+pub struct DeploymentDslResponse {
+    pub customer_id: String,
+    pub image_id: String,
+    pub dsl: serde_json::Value,
+}
+
+/// Guest program source stored with a deployment
+#[derive(Debug, Serialize)]
+pub struct DeploymentSourceResponse {
+    pub customer_id: String,
+    pub image_id: String,
+    pub guest_source: String,
+}
+
+/// Public params a deployment's proof endpoint requires
+#[derive(Debug, Serialize)]
+pub struct RequiredParamsResponse {
+    pub customer_id: String,
+    pub image_id: String,
+    pub required_params: Vec<RequiredParam>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema_hash: Option<String>,
+}
+
 /// List of deployments
 #[derive(Debug, Serialize)]
 pub struct DeploymentsListResponse {
@@ -86,33 +151,52 @@ pub struct DeploymentsListResponse {
     pub total: usize,
 }
 
+/// Request to fetch several deployments at once
+#[derive(Debug, Deserialize)]
+pub struct BatchDeploymentsRequest {
+    pub customer_ids: Vec<String>,
+}
+
+/// Deployments found for a batch request, plus the IDs that were not found
+#[derive(Debug, Serialize)]
+pub struct BatchDeploymentsResponse {
+    pub deployments: Vec<CustomerDeployment>,
+    pub missing: Vec<String>,
+}
+
 /// Health check endpoint
 pub async fn health_handler() -> impl IntoResponse {
-    Json(serde_json::json!({
+    ApiResponse::new(serde_json::json!({
         "status": "healthy",
         "service": "image-id-registry"
     }))
 }
 
 /// Register a new customer deployment
-pub async fn register_deployment_handler(
-    State(state): State<Arc<AppState>>,
+#[tracing::instrument(skip_all, fields(customer_id = %payload.customer_id))]
+pub async fn register_deployment_handler<S: DeploymentStore>(
+    State(state): State<Arc<AppState<S>>>,
     Json(payload): Json<RegisterDeploymentRequest>,
-) -> Result<Json<RegisterDeploymentResponse>, ApiError> {
+) -> Result<ApiResponse<RegisterDeploymentResponse>, ApiError> {
     info!("Registering deployment for customer: {}", payload.customer_id);
 
+    validate_metadata(payload.metadata.as_ref())?;
+
     let deployment = CustomerDeployment::new(
         payload.customer_id.clone(),
         payload.image_id,
         payload.guest_program_path,
         payload.metadata,
-    );
+    )
+    .with_elf_compression(payload.elf_compression)
+    .with_dsl(payload.dsl)
+    .with_guest_source(payload.guest_source);
 
     let mut storage = state.storage.lock().await;
     let created = storage.register_deployment(&deployment).await?;
 
     if created {
-        Ok(Json(RegisterDeploymentResponse {
+        Ok(ApiResponse::new(RegisterDeploymentResponse {
             success: true,
             message: format!("Deployment registered for customer: {}", payload.customer_id),
         }))
@@ -127,26 +211,42 @@ pub async fn register_deployment_handler(
     }
 }
 
+/// Reject malformed deployment metadata with 400
+fn validate_metadata(metadata: Option<&DeploymentMetadata>) -> Result<(), ApiError> {
+    match metadata.map(DeploymentMetadata::validate) {
+        Some(Err(message)) => Err(ApiError {
+            status: StatusCode::BAD_REQUEST,
+            message: format!("Invalid metadata: {}", message),
+        }),
+        _ => Ok(()),
+    }
+}
+
 /// Update an existing customer deployment
-pub async fn update_deployment_handler(
-    State(state): State<Arc<AppState>>,
+pub async fn update_deployment_handler<S: DeploymentStore>(
+    State(state): State<Arc<AppState<S>>>,
     Path(customer_id): Path<String>,
     Json(payload): Json<UpdateDeploymentRequest>,
-) -> Result<Json<RegisterDeploymentResponse>, ApiError> {
+) -> Result<ApiResponse<RegisterDeploymentResponse>, ApiError> {
     info!("Updating deployment for customer: {}", customer_id);
 
+    validate_metadata(payload.metadata.as_ref())?;
+
     let deployment = CustomerDeployment::new(
         customer_id.clone(),
         payload.image_id,
         payload.guest_program_path,
         payload.metadata,
-    );
+    )
+    .with_elf_compression(payload.elf_compression)
+    .with_dsl(payload.dsl)
+    .with_guest_source(payload.guest_source);
 
     let mut storage = state.storage.lock().await;
     let updated = storage.update_deployment(&deployment).await?;
 
     if updated {
-        Ok(Json(RegisterDeploymentResponse {
+        Ok(ApiResponse::new(RegisterDeploymentResponse {
             success: true,
             message: format!("Deployment updated for customer: {}", customer_id),
         }))
@@ -159,17 +259,21 @@ pub async fn update_deployment_handler(
 }
 
 /// Get deployment by customer ID
-pub async fn get_deployment_handler(
-    State(state): State<Arc<AppState>>,
+///
+/// Supports conditional GETs: the response carries an `ETag`, and a request
+/// whose `If-None-Match` names the current one gets 304 Not Modified.
+pub async fn get_deployment_handler<S: DeploymentStore>(
+    State(state): State<Arc<AppState<S>>>,
     Path(customer_id): Path<String>,
-) -> Result<Json<DeploymentResponse>, ApiError> {
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
     info!("Getting deployment for customer: {}", customer_id);
 
     let mut storage = state.storage.lock().await;
     let deployment = storage.get_deployment(&customer_id).await?;
 
     match deployment {
-        Some(d) => Ok(Json(DeploymentResponse { deployment: d })),
+        Some(d) => Ok(deployment_response(&headers, d)),
         None => Err(ApiError {
             status: StatusCode::NOT_FOUND,
             message: format!("Deployment not found for customer: {}", customer_id),
@@ -177,18 +281,164 @@ pub async fn get_deployment_handler(
     }
 }
 
+/// Respond with a deployment and its `ETag`, or 304 Not Modified if the
+/// request's `If-None-Match` already names that tag
+fn deployment_response(headers: &HeaderMap, deployment: CustomerDeployment) -> Response {
+    let etag = deployment.etag();
+    if if_none_match(headers, &etag) {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+
+    (
+        [(header::ETAG, etag)],
+        ApiResponse::new(DeploymentResponse { deployment }),
+    )
+        .into_response()
+}
+
+/// Whether any `If-None-Match` entry matches `etag` (`*` matches anything,
+/// and weak tags compare by their opaque value)
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// Get deployments for a list of customer IDs
+pub async fn batch_get_deployments_handler<S: DeploymentStore>(
+    State(state): State<Arc<AppState<S>>>,
+    Json(payload): Json<BatchDeploymentsRequest>,
+) -> Result<ApiResponse<BatchDeploymentsResponse>, ApiError> {
+    info!("Getting {} deployments in batch", payload.customer_ids.len());
+
+    if payload.customer_ids.len() > MAX_BATCH_SIZE {
+        return Err(ApiError {
+            status: StatusCode::BAD_REQUEST,
+            message: format!(
+                "Batch of {} customer IDs exceeds the limit of {}",
+                payload.customer_ids.len(),
+                MAX_BATCH_SIZE
+            ),
+        });
+    }
+
+    let mut storage = state.storage.lock().await;
+    let (deployments, missing) = storage.get_deployments(&payload.customer_ids).await?;
+
+    Ok(ApiResponse::new(BatchDeploymentsResponse {
+        deployments,
+        missing,
+    }))
+}
+
+/// Get the DSL a customer's deployment was built from
+pub async fn get_deployment_dsl_handler<S: DeploymentStore>(
+    State(state): State<Arc<AppState<S>>>,
+    Path(customer_id): Path<String>,
+) -> Result<ApiResponse<DeploymentDslResponse>, ApiError> {
+    info!("Getting deployment DSL for customer: {}", customer_id);
+
+    let mut storage = state.storage.lock().await;
+    let deployment = storage
+        .get_deployment(&customer_id)
+        .await?
+        .ok_or_else(|| ApiError {
+            status: StatusCode::NOT_FOUND,
+            message: format!("Deployment not found for customer: {}", customer_id),
+        })?;
+
+    match deployment.dsl {
+        Some(dsl) => Ok(ApiResponse::new(DeploymentDslResponse {
+            customer_id: deployment.customer_id,
+            image_id: deployment.image_id,
+            dsl,
+        })),
+        None => Err(ApiError {
+            status: StatusCode::NOT_FOUND,
+            message: format!("No DSL stored for customer: {}", customer_id),
+        }),
+    }
+}
+
+/// Get the generated guest source a customer's image was compiled from
+pub async fn get_deployment_source_handler<S: DeploymentStore>(
+    State(state): State<Arc<AppState<S>>>,
+    Path(customer_id): Path<String>,
+) -> Result<ApiResponse<DeploymentSourceResponse>, ApiError> {
+    info!("Getting guest source for customer: {}", customer_id);
+
+    let mut storage = state.storage.lock().await;
+    let deployment = storage
+        .get_deployment(&customer_id)
+        .await?
+        .ok_or_else(|| ApiError {
+            status: StatusCode::NOT_FOUND,
+            message: format!("Deployment not found for customer: {}", customer_id),
+        })?;
+
+    match deployment.guest_source {
+        Some(guest_source) => Ok(ApiResponse::new(DeploymentSourceResponse {
+            customer_id: deployment.customer_id,
+            image_id: deployment.image_id,
+            guest_source,
+        })),
+        None => Err(ApiError {
+            status: StatusCode::NOT_FOUND,
+            message: format!("No guest source stored for customer: {}", customer_id),
+        }),
+    }
+}
+
+/// Get the public params a customer's proof requests must supply
+///
+/// Deployments registered without metadata report no required params.
+pub async fn get_required_params_handler<S: DeploymentStore>(
+    State(state): State<Arc<AppState<S>>>,
+    Path(customer_id): Path<String>,
+) -> Result<ApiResponse<RequiredParamsResponse>, ApiError> {
+    info!("Getting required params for customer: {}", customer_id);
+
+    let mut storage = state.storage.lock().await;
+    let deployment = storage
+        .get_deployment(&customer_id)
+        .await?
+        .ok_or_else(|| ApiError {
+            status: StatusCode::NOT_FOUND,
+            message: format!("Deployment not found for customer: {}", customer_id),
+        })?;
+
+    let (required_params, schema_hash) = deployment
+        .metadata
+        .map(|metadata| (metadata.required_params, metadata.schema_hash))
+        .unwrap_or_default();
+
+    Ok(ApiResponse::new(RequiredParamsResponse {
+        customer_id: deployment.customer_id,
+        image_id: deployment.image_id,
+        required_params,
+        schema_hash,
+    }))
+}
+
 /// Get deployment by Image ID
-pub async fn get_deployment_by_image_id_handler(
-    State(state): State<Arc<AppState>>,
+///
+/// Supports conditional GETs like [`get_deployment_handler`].
+pub async fn get_deployment_by_image_id_handler<S: DeploymentStore>(
+    State(state): State<Arc<AppState<S>>>,
     Path(image_id): Path<String>,
-) -> Result<Json<DeploymentResponse>, ApiError> {
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
     info!("Getting deployment for image_id: {}", image_id);
 
     let mut storage = state.storage.lock().await;
     let deployment = storage.get_deployment_by_image_id(&image_id).await?;
 
     match deployment {
-        Some(d) => Ok(Json(DeploymentResponse { deployment: d })),
+        Some(d) => Ok(deployment_response(&headers, d)),
         None => Err(ApiError {
             status: StatusCode::NOT_FOUND,
             message: format!("Deployment not found for image_id: {}", image_id),
@@ -197,17 +447,17 @@ pub async fn get_deployment_by_image_id_handler(
 }
 
 /// Delete a customer deployment
-pub async fn delete_deployment_handler(
-    State(state): State<Arc<AppState>>,
+pub async fn delete_deployment_handler<S: DeploymentStore>(
+    State(state): State<Arc<AppState<S>>>,
     Path(customer_id): Path<String>,
-) -> Result<Json<RegisterDeploymentResponse>, ApiError> {
+) -> Result<ApiResponse<RegisterDeploymentResponse>, ApiError> {
     info!("Deleting deployment for customer: {}", customer_id);
 
     let mut storage = state.storage.lock().await;
     let deleted = storage.delete_deployment(&customer_id).await?;
 
     if deleted {
-        Ok(Json(RegisterDeploymentResponse {
+        Ok(ApiResponse::new(RegisterDeploymentResponse {
             success: true,
             message: format!("Deployment deleted for customer: {}", customer_id),
         }))
@@ -220,9 +470,9 @@ pub async fn delete_deployment_handler(
 }
 
 /// List all deployments
-pub async fn list_deployments_handler(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<DeploymentsListResponse>, ApiError> {
+pub async fn list_deployments_handler<S: DeploymentStore>(
+    State(state): State<Arc<AppState<S>>>,
+) -> Result<ApiResponse<DeploymentsListResponse>, ApiError> {
     info!("Listing all deployments");
 
     let mut storage = state.storage.lock().await;
@@ -237,5 +487,484 @@ pub async fn list_deployments_handler(
 
     let total = deployments.len();
 
-    Ok(Json(DeploymentsListResponse { deployments, total }))
+    Ok(ApiResponse::new(DeploymentsListResponse {
+        deployments,
+        total,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// In-memory [`DeploymentStore`] for handler tests
+    #[derive(Default)]
+    struct MemoryStore {
+        deployments: HashMap<String, CustomerDeployment>,
+    }
+
+    #[async_trait::async_trait]
+    impl DeploymentStore for MemoryStore {
+        async fn register_deployment(
+            &mut self,
+            deployment: &CustomerDeployment,
+        ) -> anyhow::Result<bool> {
+            if self.deployments.contains_key(&deployment.customer_id) {
+                return Ok(false);
+            }
+            self.deployments
+                .insert(deployment.customer_id.clone(), deployment.clone());
+            Ok(true)
+        }
+
+        async fn update_deployment(
+            &mut self,
+            deployment: &CustomerDeployment,
+        ) -> anyhow::Result<bool> {
+            match self.deployments.get_mut(&deployment.customer_id) {
+                Some(existing) => {
+                    *existing = deployment.clone();
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        }
+
+        async fn get_deployment(
+            &mut self,
+            customer_id: &str,
+        ) -> anyhow::Result<Option<CustomerDeployment>> {
+            Ok(self.deployments.get(customer_id).cloned())
+        }
+
+        async fn get_deployment_by_image_id(
+            &mut self,
+            image_id: &str,
+        ) -> anyhow::Result<Option<CustomerDeployment>> {
+            Ok(self
+                .deployments
+                .values()
+                .find(|d| d.image_id == image_id)
+                .cloned())
+        }
+
+        async fn get_deployments(
+            &mut self,
+            customer_ids: &[String],
+        ) -> anyhow::Result<(Vec<CustomerDeployment>, Vec<String>)> {
+            let mut found = Vec::new();
+            let mut missing = Vec::new();
+            for customer_id in customer_ids {
+                match self.deployments.get(customer_id) {
+                    Some(deployment) => found.push(deployment.clone()),
+                    None => missing.push(customer_id.clone()),
+                }
+            }
+            Ok((found, missing))
+        }
+
+        async fn delete_deployment(&mut self, customer_id: &str) -> anyhow::Result<bool> {
+            Ok(self.deployments.remove(customer_id).is_some())
+        }
+
+        async fn list_customers(&mut self) -> anyhow::Result<Vec<String>> {
+            Ok(self.deployments.keys().cloned().collect())
+        }
+
+        async fn count_deployments(&mut self) -> anyhow::Result<usize> {
+            Ok(self.deployments.len())
+        }
+    }
+
+    fn memory_state() -> Arc<AppState<MemoryStore>> {
+        Arc::new(AppState {
+            storage: Mutex::new(MemoryStore::default()),
+        })
+    }
+
+    fn register_request(customer_id: &str, image_id: &str) -> RegisterDeploymentRequest {
+        RegisterDeploymentRequest {
+            customer_id: customer_id.to_string(),
+            image_id: image_id.to_string(),
+            guest_program_path: "/path/to/guest.elf".to_string(),
+            elf_compression: None,
+            metadata: None,
+            dsl: None,
+            guest_source: None,
+        }
+    }
+
+    async fn register(state: &Arc<AppState<MemoryStore>>, customer_id: &str, image_id: &str) {
+        let ApiResponse { data: response } = register_deployment_handler(
+            State(state.clone()),
+            Json(register_request(customer_id, image_id)),
+        )
+        .await
+        .unwrap();
+        assert!(response.success);
+    }
+
+    #[tokio::test]
+    async fn test_register_then_get() {
+        let state = memory_state();
+
+        register(&state, "customer-123", "image-abc").await;
+
+        let response = get_deployment_handler(
+            State(state.clone()),
+            Path("customer-123".to_string()),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        let body = body_json(response).await;
+        assert_eq!(body["data"]["deployment"]["image_id"], "image-abc");
+
+        let response = get_deployment_by_image_id_handler(
+            State(state.clone()),
+            Path("image-abc".to_string()),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        let body = body_json(response).await;
+        assert_eq!(body["data"]["deployment"]["customer_id"], "customer-123");
+
+        // A second registration for the same customer conflicts
+        let err = register_deployment_handler(
+            State(state),
+            Json(register_request("customer-123", "image-other")),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::CONFLICT);
+    }
+
+    fn metadata(use_case: &str, description: &str, version: &str) -> DeploymentMetadata {
+        DeploymentMetadata {
+            use_case: use_case.to_string(),
+            description: description.to_string(),
+            version: version.to_string(),
+            required_params: Vec::new(),
+            schema_hash: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_valid_metadata_accepted() {
+        let state = memory_state();
+
+        for (customer_id, version) in [("c1", "1.0"), ("c2", "2.1.3"), ("c3", "v1.2.0-rc.1")] {
+            let mut request = register_request(customer_id, "image-abc");
+            request.metadata = Some(metadata("age_check", "Checks age", version));
+            let ApiResponse { data: response } =
+                register_deployment_handler(State(state.clone()), Json(request))
+                    .await
+                    .unwrap();
+            assert!(response.success, "version {} rejected", version);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_malformed_metadata_rejected() {
+        let state = memory_state();
+        let long_description = "x".repeat(DeploymentMetadata::MAX_DESCRIPTION_LEN + 1);
+
+        for (bad, expected) in [
+            (metadata("  ", "", "1.0"), "use_case must not be empty"),
+            (
+                metadata("age_check", "", "latest"),
+                "version must look like",
+            ),
+            (metadata("age_check", "", "1"), "version must look like"),
+            (metadata("age_check", "", "1.0-"), "version must look like"),
+            (
+                metadata("age_check", &long_description, "1.0"),
+                "description is 1025 characters",
+            ),
+        ] {
+            let mut request = register_request("customer-123", "image-abc");
+            request.metadata = Some(bad);
+            let err = register_deployment_handler(State(state.clone()), Json(request))
+                .await
+                .unwrap_err();
+            assert_eq!(err.status, StatusCode::BAD_REQUEST);
+            assert!(
+                err.message
+                    .starts_with(&format!("Invalid metadata: {}", expected)),
+                "{}",
+                err.message
+            );
+        }
+
+        // Nothing was stored, and updates are validated too
+        register(&state, "customer-123", "image-abc").await;
+        let err = update_deployment_handler(
+            State(state),
+            Path("customer-123".to_string()),
+            Json(UpdateDeploymentRequest {
+                image_id: "image-new".to_string(),
+                guest_program_path: "/path/to/new.elf".to_string(),
+                elf_compression: None,
+                metadata: Some(metadata("", "", "1.0")),
+                dsl: None,
+                guest_source: None,
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_update_deployment() {
+        let state = memory_state();
+
+        let update = || UpdateDeploymentRequest {
+            image_id: "image-new".to_string(),
+            guest_program_path: "/path/to/new.elf".to_string(),
+            elf_compression: Some("gzip".to_string()),
+            metadata: None,
+            dsl: None,
+            guest_source: None,
+        };
+
+        // Nothing to update yet
+        let err = update_deployment_handler(
+            State(state.clone()),
+            Path("customer-123".to_string()),
+            Json(update()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+
+        register(&state, "customer-123", "image-old").await;
+        let ApiResponse { data: response } = update_deployment_handler(
+            State(state.clone()),
+            Path("customer-123".to_string()),
+            Json(update()),
+        )
+        .await
+        .unwrap();
+        assert!(response.success);
+
+        let response = get_deployment_handler(
+            State(state),
+            Path("customer-123".to_string()),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        let deployment = &body_json(response).await["data"]["deployment"];
+        assert_eq!(deployment["image_id"], "image-new");
+        assert_eq!(deployment["guest_program_path"], "/path/to/new.elf");
+        assert_eq!(deployment["elf_compression"], "gzip");
+    }
+
+    #[tokio::test]
+    async fn test_delete_deployment() {
+        let state = memory_state();
+
+        register(&state, "customer-123", "image-abc").await;
+        let ApiResponse { data: response } =
+            delete_deployment_handler(State(state.clone()), Path("customer-123".to_string()))
+                .await
+                .unwrap();
+        assert!(response.success);
+
+        let err = get_deployment_handler(
+            State(state.clone()),
+            Path("customer-123".to_string()),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+
+        let err = delete_deployment_handler(State(state.clone()), Path("customer-123".to_string()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+
+        let ApiResponse { data: list } = list_deployments_handler(State(state)).await.unwrap();
+        assert_eq!(list.total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_required_params_stored_with_metadata() {
+        let state = memory_state();
+
+        // Metadata as the build service sends it
+        let request: RegisterDeploymentRequest = serde_json::from_value(serde_json::json!({
+            "customer_id": "customer-123",
+            "image_id": "image-abc",
+            "guest_program_path": "/path/to/guest.elf",
+            "metadata": {
+                "use_case": "age_check",
+                "description": "",
+                "version": "1.0",
+                "required_params": [
+                    { "name": "min_age", "param_type": "u32" },
+                    { "name": "region", "param_type": "string" }
+                ],
+                "schema_hash": "5e1f".repeat(16)
+            }
+        }))
+        .unwrap();
+        register_deployment_handler(State(state.clone()), Json(request))
+            .await
+            .unwrap();
+
+        let ApiResponse { data: response } =
+            get_required_params_handler(State(state.clone()), Path("customer-123".to_string()))
+                .await
+                .unwrap();
+        assert_eq!(response.image_id, "image-abc");
+        let names: Vec<&str> = response
+            .required_params
+            .iter()
+            .map(|param| param.name.as_str())
+            .collect();
+        assert_eq!(names, ["min_age", "region"]);
+        assert_eq!(response.schema_hash, Some("5e1f".repeat(16)));
+
+        // Deployments without metadata require nothing; unknown customers are 404
+        register(&state, "customer-456", "image-def").await;
+        let ApiResponse { data: response } =
+            get_required_params_handler(State(state.clone()), Path("customer-456".to_string()))
+                .await
+                .unwrap();
+        assert!(response.required_params.is_empty());
+        assert!(response.schema_hash.is_none());
+
+        let err = get_required_params_handler(State(state), Path("missing".to_string()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_guest_source_served() {
+        let state = memory_state();
+
+        let guest_source = "fn main() {\n    // generated\n}\n";
+        let mut request = register_request("customer-123", "image-abc");
+        request.guest_source = Some(guest_source.to_string());
+        register_deployment_handler(State(state.clone()), Json(request))
+            .await
+            .unwrap();
+
+        let ApiResponse { data: response } =
+            get_deployment_source_handler(State(state.clone()), Path("customer-123".to_string()))
+                .await
+                .unwrap();
+        assert_eq!(response.image_id, "image-abc");
+        assert_eq!(response.guest_source, guest_source);
+
+        // Deployments registered without source, and unknown customers, are 404
+        register(&state, "customer-456", "image-def").await;
+        for customer_id in ["customer-456", "missing"] {
+            let err =
+                get_deployment_source_handler(State(state.clone()), Path(customer_id.to_string()))
+                    .await
+                    .unwrap_err();
+            assert_eq!(err.status, StatusCode::NOT_FOUND);
+        }
+    }
+
+    async fn body_json(response: Response) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_deployment_not_modified() {
+        let state = memory_state();
+        register(&state, "customer-123", "image-abc").await;
+
+        let get = |if_none_match: Option<String>| {
+            let state = state.clone();
+            async move {
+                let mut headers = HeaderMap::new();
+                if let Some(etag) = if_none_match {
+                    headers.insert(header::IF_NONE_MATCH, etag.parse().unwrap());
+                }
+                get_deployment_handler(State(state), Path("customer-123".to_string()), headers)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let response = get(None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(etag.starts_with("\"image-abc-"));
+
+        // Same deployment: 304 with no body
+        let response = get(Some(etag.clone())).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], etag.as_str());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+
+        // A stale tag gets the full deployment again
+        let response = get(Some("\"image-old-1\"".to_string())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Updating the deployment changes its tag
+        update_deployment_handler(
+            State(state.clone()),
+            Path("customer-123".to_string()),
+            Json(UpdateDeploymentRequest {
+                image_id: "image-new".to_string(),
+                guest_program_path: "/path/to/new.elf".to_string(),
+                elf_compression: None,
+                metadata: None,
+                dsl: None,
+                guest_source: None,
+            }),
+        )
+        .await
+        .unwrap();
+        let response = get(Some(etag)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            body_json(response).await["data"]["deployment"]["image_id"],
+            "image-new"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_health_uses_data_envelope() {
+        let response = health_handler().await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = body_json(response).await;
+        assert_eq!(body["data"]["status"], "healthy");
+        assert_eq!(body["data"]["service"], "image-id-registry");
+    }
+
+    #[tokio::test]
+    async fn test_errors_use_error_envelope() {
+        let state = memory_state();
+
+        let response =
+            get_deployment_handler(State(state), Path("missing".to_string()), HeaderMap::new())
+                .await
+                .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let body = body_json(response).await;
+        assert_eq!(body["error"], "Deployment not found for customer: missing");
+        assert!(body.get("data").is_none());
+    }
 }
@@ -17,40 +17,59 @@ use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 
 pub use handlers::AppState;
-pub use models::{CustomerDeployment, DeploymentMetadata};
-pub use storage::Storage;
+pub use models::{CustomerDeployment, DeploymentMetadata, RequiredParam};
+pub use storage::{DeploymentStore, Storage};
 
 /// Create the application router
-pub fn create_router(state: AppState) -> Router {
+pub fn create_router<S: DeploymentStore>(state: AppState<S>) -> Router {
     let shared_state = Arc::new(state);
 
     Router::new()
         .route("/health", get(handlers::health_handler))
         .route(
             "/api/deployments",
-            post(handlers::register_deployment_handler),
+            post(handlers::register_deployment_handler::<S>),
         )
         .route(
             "/api/deployments",
-            get(handlers::list_deployments_handler),
+            get(handlers::list_deployments_handler::<S>),
+        )
+        .route(
+            "/api/deployments/batch",
+            post(handlers::batch_get_deployments_handler::<S>),
         )
         .route(
             "/api/deployments/:customer_id",
-            get(handlers::get_deployment_handler),
+            get(handlers::get_deployment_handler::<S>),
         )
         .route(
             "/api/deployments/:customer_id",
-            put(handlers::update_deployment_handler),
+            put(handlers::update_deployment_handler::<S>),
         )
         .route(
             "/api/deployments/:customer_id",
-            delete(handlers::delete_deployment_handler),
+            delete(handlers::delete_deployment_handler::<S>),
+        )
+        .route(
+            "/api/deployments/:customer_id/dsl",
+            get(handlers::get_deployment_dsl_handler::<S>),
+        )
+        .route(
+            "/api/deployments/:customer_id/source",
+            get(handlers::get_deployment_source_handler::<S>),
+        )
+        .route(
+            "/api/deployments/:customer_id/params",
+            get(handlers::get_required_params_handler::<S>),
         )
         .route(
             "/api/deployments/by-image-id/:image_id",
-            get(handlers::get_deployment_by_image_id_handler),
+            get(handlers::get_deployment_by_image_id_handler::<S>),
         )
         .with_state(shared_state)
         .layer(CorsLayer::permissive())
-        .layer(TraceLayer::new_for_http())
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(khafi_telemetry::http_request_span::<axum::body::Body>),
+        )
 }
@@ -7,24 +7,22 @@ use image_id_registry::{create_router, AppState, Storage};
 use std::env;
 use tokio::sync::Mutex;
 use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "image_id_registry=debug,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
     // Load environment variables
     dotenvy::dotenv().ok();
 
+    // Initialize tracing (exports to OTLP when OTEL_EXPORTER_OTLP_ENDPOINT is set)
+    let _telemetry = khafi_telemetry::init_tracing("image-id-registry", "image_id_registry=debug,tower_http=debug")?;
+
     // Configuration
     let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+    let redis_db: Option<i64> = env::var("REDIS_DB")
+        .ok()
+        .map(|v| v.parse())
+        .transpose()
+        .context("Invalid REDIS_DB")?;
     let host = env::var("REGISTRY_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
     let port = env::var("REGISTRY_PORT").unwrap_or_else(|_| "8083".to_string());
 
@@ -33,7 +31,7 @@ async fn main() -> Result<()> {
     info!("Listening on {}:{}", host, port);
 
     // Initialize storage
-    let storage = Storage::new(&redis_url)
+    let storage = Storage::new(&redis_url, redis_db)
         .await
         .context("Failed to initialize storage")?;
 
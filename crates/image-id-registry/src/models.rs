@@ -15,12 +15,24 @@ pub struct CustomerDeployment {
     /// Path to the built guest program ELF file
     pub guest_program_path: String,
 
+    /// Compression applied to the stored ELF (e.g. "gzip"), if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elf_compression: Option<String>,
+
     /// When this deployment was created
     pub created_at: DateTime<Utc>,
 
     /// Optional metadata about the deployment
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<DeploymentMetadata>,
+
+    /// Original DSL (JSON) the guest program was built from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dsl: Option<serde_json::Value>,
+
+    /// Generated guest program source (`main.rs`) the image was compiled from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guest_source: Option<String>,
 }
 
 /// Optional metadata for a deployment
@@ -34,6 +46,80 @@ pub struct DeploymentMetadata {
 
     /// DSL version
     pub version: String,
+
+    /// Public params every proof request must supply (those without a default)
+    #[serde(default)]
+    pub required_params: Vec<RequiredParam>,
+
+    /// Hash of the DSL's input and param schema, computed by the build service
+    ///
+    /// Changes whenever the inputs a proof request must supply change, so
+    /// clients can detect that they were built against an older schema.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_hash: Option<String>,
+}
+
+/// A public param the proof endpoint requires
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequiredParam {
+    /// Param name as declared in the DSL
+    pub name: String,
+
+    /// DSL type (e.g. "u32", "string")
+    pub param_type: String,
+}
+
+impl DeploymentMetadata {
+    /// Maximum description length, in characters
+    pub const MAX_DESCRIPTION_LEN: usize = 1024;
+
+    /// Check the metadata is well-formed, returning a message describing the first problem
+    pub fn validate(&self) -> Result<(), String> {
+        if self.use_case.trim().is_empty() {
+            return Err("use_case must not be empty".to_string());
+        }
+
+        if !is_semver_like(&self.version) {
+            return Err(format!(
+                "version must look like MAJOR.MINOR[.PATCH], got {:?}",
+                self.version
+            ));
+        }
+
+        let description_len = self.description.chars().count();
+        if description_len > Self::MAX_DESCRIPTION_LEN {
+            return Err(format!(
+                "description is {} characters (max {})",
+                description_len,
+                Self::MAX_DESCRIPTION_LEN
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether `version` is two or three numeric components, optionally prefixed
+/// with `v` and followed by a `-pre` or `+build` suffix (e.g. "1.0", "v2.1.3-rc.1")
+fn is_semver_like(version: &str) -> bool {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let (core, suffix) = match version.find(['-', '+']) {
+        Some(at) => version.split_at(at),
+        None => (version, ""),
+    };
+
+    let parts: Vec<&str> = core.split('.').collect();
+    let core_ok = (2..=3).contains(&parts.len())
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+    let suffix_ok = suffix.is_empty()
+        || (suffix.len() > 1
+            && suffix[1..]
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+')));
+
+    core_ok && suffix_ok
 }
 
 impl CustomerDeployment {
@@ -48,8 +134,41 @@ impl CustomerDeployment {
             customer_id,
             image_id,
             guest_program_path,
+            elf_compression: None,
             created_at: Utc::now(),
             metadata,
+            dsl: None,
+            guest_source: None,
         }
     }
+
+    /// Record the compression applied to the stored ELF
+    pub fn with_elf_compression(mut self, elf_compression: Option<String>) -> Self {
+        self.elf_compression = elf_compression;
+        self
+    }
+
+    /// Attach the DSL the guest program was built from
+    pub fn with_dsl(mut self, dsl: Option<serde_json::Value>) -> Self {
+        self.dsl = dsl;
+        self
+    }
+
+    /// Attach the generated guest program source
+    pub fn with_guest_source(mut self, guest_source: Option<String>) -> Self {
+        self.guest_source = guest_source;
+        self
+    }
+
+    /// Entity tag for conditional GETs
+    ///
+    /// Registering and updating both stamp a new `created_at`, so the tag
+    /// changes whenever the stored deployment does.
+    pub fn etag(&self) -> String {
+        format!(
+            "\"{}-{}\"",
+            self.image_id,
+            self.created_at.timestamp_micros()
+        )
+    }
 }
@@ -2,10 +2,54 @@
 
 use crate::models::CustomerDeployment;
 use anyhow::{Context, Result};
+use async_trait::async_trait;
+use khafi_common::redis_url::connection_info;
 use redis::aio::ConnectionManager;
 use redis::AsyncCommands;
 use tracing::{debug, info};
 
+/// Deployment persistence used by the API handlers
+///
+/// [`Storage`] is the Redis implementation; handlers are generic over this
+/// trait so they can be tested against an in-memory store.
+#[async_trait]
+pub trait DeploymentStore: Send + 'static {
+    /// Register a new customer deployment
+    /// Returns Ok(true) if created, Ok(false) if customer already has a deployment
+    async fn register_deployment(&mut self, deployment: &CustomerDeployment) -> Result<bool>;
+
+    /// Update an existing customer deployment
+    /// Returns Ok(false) if the customer has no deployment
+    async fn update_deployment(&mut self, deployment: &CustomerDeployment) -> Result<bool>;
+
+    /// Get deployment by customer ID
+    async fn get_deployment(&mut self, customer_id: &str) -> Result<Option<CustomerDeployment>>;
+
+    /// Get deployment by Image ID
+    async fn get_deployment_by_image_id(
+        &mut self,
+        image_id: &str,
+    ) -> Result<Option<CustomerDeployment>>;
+
+    /// Get deployments for several customers
+    ///
+    /// Returns the deployments found (in request order) and the customer IDs
+    /// that have no deployment.
+    async fn get_deployments(
+        &mut self,
+        customer_ids: &[String],
+    ) -> Result<(Vec<CustomerDeployment>, Vec<String>)>;
+
+    /// Delete a customer deployment
+    async fn delete_deployment(&mut self, customer_id: &str) -> Result<bool>;
+
+    /// List all customer IDs with deployments
+    async fn list_customers(&mut self) -> Result<Vec<String>>;
+
+    /// Get total count of deployments
+    async fn count_deployments(&mut self) -> Result<usize>;
+}
+
 /// Storage backend for customer deployments
 pub struct Storage {
     conn: ConnectionManager,
@@ -13,22 +57,28 @@ pub struct Storage {
 
 impl Storage {
     /// Create a new storage instance
-    pub async fn new(redis_url: &str) -> Result<Self> {
-        let client = redis::Client::open(redis_url)
+    ///
+    /// `redis_db` selects the database index, overriding any `/<db>` in the URL.
+    pub async fn new(redis_url: &str, redis_db: Option<i64>) -> Result<Self> {
+        let info = connection_info(redis_url, redis_db).context("Invalid Redis URL")?;
+        let db = info.redis.db;
+
+        let client = redis::Client::open(info)
             .context("Failed to create Redis client")?;
 
         let conn = ConnectionManager::new(client)
             .await
             .context("Failed to connect to Redis")?;
 
-        info!("Connected to Redis at {}", redis_url);
+        info!("Connected to Redis at {} (db {})", redis_url, db);
 
         Ok(Self { conn })
     }
+}
 
-    /// Register a new customer deployment
-    /// Returns Ok(true) if created, Ok(false) if customer already has a deployment
-    pub async fn register_deployment(&mut self, deployment: &CustomerDeployment) -> Result<bool> {
+#[async_trait]
+impl DeploymentStore for Storage {
+    async fn register_deployment(&mut self, deployment: &CustomerDeployment) -> Result<bool> {
         let key = format!("deployment:{}", deployment.customer_id);
 
         // Check if deployment already exists
@@ -58,8 +108,7 @@ impl Storage {
         Ok(true)
     }
 
-    /// Update an existing customer deployment
-    pub async fn update_deployment(&mut self, deployment: &CustomerDeployment) -> Result<bool> {
+    async fn update_deployment(&mut self, deployment: &CustomerDeployment) -> Result<bool> {
         let key = format!("deployment:{}", deployment.customer_id);
 
         // Check if deployment exists
@@ -91,8 +140,7 @@ impl Storage {
         Ok(true)
     }
 
-    /// Get deployment by customer ID
-    pub async fn get_deployment(&mut self, customer_id: &str) -> Result<Option<CustomerDeployment>> {
+    async fn get_deployment(&mut self, customer_id: &str) -> Result<Option<CustomerDeployment>> {
         let key = format!("deployment:{}", customer_id);
 
         let json: Option<String> = self.conn.get(&key).await?;
@@ -107,8 +155,7 @@ impl Storage {
         }
     }
 
-    /// Get deployment by Image ID
-    pub async fn get_deployment_by_image_id(&mut self, image_id: &str) -> Result<Option<CustomerDeployment>> {
+    async fn get_deployment_by_image_id(&mut self, image_id: &str) -> Result<Option<CustomerDeployment>> {
         let image_key = format!("image_id:{}", image_id);
 
         // Get customer_id from image_id lookup
@@ -120,8 +167,42 @@ impl Storage {
         }
     }
 
-    /// Delete a customer deployment
-    pub async fn delete_deployment(&mut self, customer_id: &str) -> Result<bool> {
+    /// Fetches every deployment with a single `MGET`
+    async fn get_deployments(
+        &mut self,
+        customer_ids: &[String],
+    ) -> Result<(Vec<CustomerDeployment>, Vec<String>)> {
+        if customer_ids.is_empty() {
+            return Ok((Vec::new(), Vec::new()));
+        }
+
+        let keys: Vec<String> = customer_ids
+            .iter()
+            .map(|id| format!("deployment:{}", id))
+            .collect();
+
+        let values: Vec<Option<String>> = redis::cmd("MGET")
+            .arg(&keys)
+            .query_async(&mut self.conn)
+            .await?;
+
+        let mut found = Vec::new();
+        let mut missing = Vec::new();
+        for (customer_id, value) in customer_ids.iter().zip(values) {
+            match value {
+                Some(data) => {
+                    let deployment: CustomerDeployment = serde_json::from_str(&data)
+                        .context("Failed to deserialize deployment")?;
+                    found.push(deployment);
+                }
+                None => missing.push(customer_id.clone()),
+            }
+        }
+
+        Ok((found, missing))
+    }
+
+    async fn delete_deployment(&mut self, customer_id: &str) -> Result<bool> {
         let key = format!("deployment:{}", customer_id);
 
         // Get deployment to clean up image_id mapping
@@ -145,14 +226,12 @@ impl Storage {
         Ok(deleted)
     }
 
-    /// List all customer IDs with deployments
-    pub async fn list_customers(&mut self) -> Result<Vec<String>> {
+    async fn list_customers(&mut self) -> Result<Vec<String>> {
         let customers: Vec<String> = self.conn.smembers("deployments:all").await?;
         Ok(customers)
     }
 
-    /// Get total count of deployments
-    pub async fn count_deployments(&mut self) -> Result<usize> {
+    async fn count_deployments(&mut self) -> Result<usize> {
         let count: usize = self.conn.scard("deployments:all").await?;
         Ok(count)
     }
@@ -164,7 +243,7 @@ mod tests {
     use crate::models::DeploymentMetadata;
 
     async fn get_test_storage() -> Storage {
-        Storage::new("redis://127.0.0.1:6379/15")
+        Storage::new("redis://127.0.0.1:6379", Some(15))
             .await
             .expect("Failed to connect to test Redis")
     }
@@ -181,6 +260,8 @@ mod tests {
                 use_case: "age_verification".to_string(),
                 description: "Verify user age".to_string(),
                 version: "1.0".to_string(),
+                required_params: Vec::new(),
+                schema_hash: None,
             }),
         );
 
@@ -281,4 +362,70 @@ mod tests {
         // Clean up
         storage.delete_deployment("customer-789").await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_get_deployments_batch() {
+        let mut storage = get_test_storage().await;
+
+        let seeded = [("customer-batch-1", "image-b1"), ("customer-batch-2", "image-b2")];
+        for (customer_id, image_id) in seeded {
+            let deployment = CustomerDeployment::new(
+                customer_id.to_string(),
+                image_id.to_string(),
+                "/path/to/guest.elf".to_string(),
+                None,
+            );
+            storage.register_deployment(&deployment).await.unwrap();
+        }
+
+        let ids = vec![
+            "customer-batch-2".to_string(),
+            "customer-batch-missing".to_string(),
+            "customer-batch-1".to_string(),
+        ];
+        let (found, missing) = storage.get_deployments(&ids).await.unwrap();
+
+        let found_ids: Vec<&str> = found.iter().map(|d| d.customer_id.as_str()).collect();
+        assert_eq!(found_ids, vec!["customer-batch-2", "customer-batch-1"]);
+        assert_eq!(missing, vec!["customer-batch-missing".to_string()]);
+
+        // Clean up
+        storage.delete_deployment("customer-batch-1").await.unwrap();
+        storage.delete_deployment("customer-batch-2").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dsl_round_trip() {
+        let mut storage = get_test_storage().await;
+
+        let dsl = serde_json::json!({
+            "use_case": "age_verification",
+            "private_inputs": { "type": "object", "fields": { "age": "u32" } },
+            "public_params": { "min_age": "u32" },
+            "validation_rules": [
+                { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 }
+            ]
+        });
+
+        let deployment = CustomerDeployment::new(
+            "customer-dsl".to_string(),
+            "image-dsl".to_string(),
+            "/path/to/guest.elf".to_string(),
+            None,
+        )
+        .with_dsl(Some(dsl.clone()));
+
+        storage.register_deployment(&deployment).await.unwrap();
+
+        let retrieved = storage
+            .get_deployment("customer-dsl")
+            .await
+            .unwrap()
+            .expect("Deployment not found");
+
+        assert_eq!(retrieved.dsl, Some(dsl));
+
+        // Clean up
+        storage.delete_deployment("customer-dsl").await.unwrap();
+    }
 }
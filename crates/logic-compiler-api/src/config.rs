@@ -5,6 +5,7 @@
 use anyhow::{Context, Result};
 use std::env;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// Application configuration
 #[derive(Debug, Clone)]
@@ -20,6 +21,15 @@ pub struct Config {
 
     /// Directory containing template files
     pub templates_dir: PathBuf,
+
+    /// Build Service base URL
+    pub build_service_url: String,
+
+    /// Timeout for requests to the Build Service, in seconds
+    pub build_service_timeout_secs: u64,
+
+    /// Maximum size of a Build Service response body
+    pub build_service_max_response_bytes: usize,
 }
 
 impl Config {
@@ -43,6 +53,19 @@ impl Config {
             templates_dir: env::var("TEMPLATES_DIR")
                 .unwrap_or_else(|_| "./docs/examples".to_string())
                 .into(),
+
+            build_service_url: env::var("BUILD_SERVICE_URL")
+                .unwrap_or_else(|_| "http://127.0.0.1:8085".to_string()),
+
+            build_service_timeout_secs: env::var("BUILD_SERVICE_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .context("Invalid BUILD_SERVICE_TIMEOUT_SECS")?,
+
+            build_service_max_response_bytes: env::var("BUILD_SERVICE_MAX_RESPONSE_BYTES")
+                .unwrap_or_else(|_| "1048576".to_string())
+                .parse()
+                .context("Invalid BUILD_SERVICE_MAX_RESPONSE_BYTES")?,
         };
 
         // Validate configuration
@@ -57,6 +80,14 @@ impl Config {
             anyhow::bail!("API_PORT must be greater than 0");
         }
 
+        if self.build_service_timeout_secs == 0 {
+            anyhow::bail!("BUILD_SERVICE_TIMEOUT_SECS must be greater than 0");
+        }
+
+        if self.build_service_max_response_bytes == 0 {
+            anyhow::bail!("BUILD_SERVICE_MAX_RESPONSE_BYTES must be greater than 0");
+        }
+
         Ok(())
     }
 
@@ -65,6 +96,11 @@ impl Config {
         format!("{}:{}", self.api_host, self.api_port)
     }
 
+    /// Get the Build Service request timeout
+    pub fn build_service_timeout(&self) -> Duration {
+        Duration::from_secs(self.build_service_timeout_secs)
+    }
+
     /// Ensure output directories exist
     pub fn ensure_directories(&self) -> Result<()> {
         std::fs::create_dir_all(&self.sdk_output_dir).with_context(|| {
@@ -97,6 +133,9 @@ mod tests {
         env::remove_var("API_PORT");
         env::remove_var("SDK_OUTPUT_DIR");
         env::remove_var("TEMPLATES_DIR");
+        env::remove_var("BUILD_SERVICE_URL");
+        env::remove_var("BUILD_SERVICE_TIMEOUT_SECS");
+        env::remove_var("BUILD_SERVICE_MAX_RESPONSE_BYTES");
 
         let config = Config::from_env().expect("Failed to load config");
 
@@ -104,6 +143,9 @@ mod tests {
         assert_eq!(config.api_port, 8082);
         assert_eq!(config.sdk_output_dir, PathBuf::from("./output/sdks"));
         assert_eq!(config.templates_dir, PathBuf::from("./docs/examples"));
+        assert_eq!(config.build_service_url, "http://127.0.0.1:8085");
+        assert_eq!(config.build_service_timeout(), Duration::from_secs(30));
+        assert_eq!(config.build_service_max_response_bytes, 1024 * 1024);
     }
 
     #[test]
@@ -114,6 +156,9 @@ mod tests {
             api_port: 9000,
             sdk_output_dir: PathBuf::from("./output"),
             templates_dir: PathBuf::from("./templates"),
+            build_service_url: "http://127.0.0.1:8085".to_string(),
+            build_service_timeout_secs: 30,
+            build_service_max_response_bytes: 1024 * 1024,
         };
 
         assert_eq!(config.api_address(), "127.0.0.1:9000");
@@ -126,6 +171,9 @@ mod tests {
             api_port: 0,
             sdk_output_dir: PathBuf::from("./output"),
             templates_dir: PathBuf::from("./templates"),
+            build_service_url: "http://127.0.0.1:8085".to_string(),
+            build_service_timeout_secs: 30,
+            build_service_max_response_bytes: 1024 * 1024,
         };
 
         let result = config.validate();
@@ -135,4 +183,24 @@ mod tests {
             .to_string()
             .contains("API_PORT must be greater than 0"));
     }
+
+    #[test]
+    fn test_validate_zero_build_service_timeout() {
+        let config = Config {
+            api_host: "0.0.0.0".to_string(),
+            api_port: 8082,
+            sdk_output_dir: PathBuf::from("./output"),
+            templates_dir: PathBuf::from("./templates"),
+            build_service_url: "http://127.0.0.1:8085".to_string(),
+            build_service_timeout_secs: 0,
+            build_service_max_response_bytes: 1024 * 1024,
+        };
+
+        let result = config.validate();
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("BUILD_SERVICE_TIMEOUT_SECS"));
+    }
 }
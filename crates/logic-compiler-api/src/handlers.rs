@@ -1,4 +1,7 @@
 //! API request handlers for logic compiler operations
+//!
+//! Successful JSON responses are wrapped as `{ "data": ... }` and failures
+//! as `{ "error": "..." }`. The Build Service uses the same envelope.
 
 use axum::{
     extract::{Path, State},
@@ -6,13 +9,15 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
-use logic_compiler::{BusinessRulesDSL, CodeGenerator, DslParser};
+use logic_compiler::codegen::InputField;
+use logic_compiler::{BusinessRulesDSL, CodeGenerator, DslDiff, DslParser, DslValidationError};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::{error, info};
 use uuid::Uuid;
 
-use crate::AppState;
+use crate::{sdk_verify, AppState};
 
 /// Request to validate DSL
 #[derive(Debug, Deserialize)]
@@ -31,6 +36,10 @@ pub struct ValidateResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 
+    /// Every validation problem, with the rule and property it refers to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<DslValidationError>>,
+
     /// Parsed DSL structure if valid
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parsed_dsl: Option<BusinessRulesDSL>,
@@ -53,11 +62,25 @@ pub struct CompileResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub code: Option<String>,
 
+    /// Every input the SDK expects: type, private/public, and the rules that read it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_manifest: Option<Vec<InputField>>,
+
     /// Error message if compilation failed
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
 
+/// Request to compare two versions of a DSL
+#[derive(Debug, Deserialize)]
+pub struct DiffRequest {
+    /// Currently deployed DSL
+    pub old: serde_json::Value,
+
+    /// Proposed replacement
+    pub new: serde_json::Value,
+}
+
 /// Request to generate SDK package
 #[derive(Debug, Deserialize)]
 pub struct GenerateSdkRequest {
@@ -139,6 +162,87 @@ pub struct TemplatesResponse {
     pub templates: Vec<TemplateInfo>,
 }
 
+/// Request to check a locally built guest ELF against its DSL
+#[derive(Debug, Deserialize)]
+pub struct VerifySdkRequest {
+    /// JSON DSL specification the guest was built from
+    pub dsl: serde_json::Value,
+
+    /// Hex-encoded guest ELF
+    pub elf_hex: String,
+
+    /// Image ID the customer expects (e.g. reported by their local build)
+    #[serde(default)]
+    pub expected_image_id: Option<String>,
+
+    /// Inputs for an executor dry run; outputs aren't checked without them
+    #[serde(default)]
+    pub sample_inputs: Option<SampleInputs>,
+}
+
+/// Sample inputs for a guest dry run
+#[derive(Debug, Deserialize)]
+pub struct SampleInputs {
+    pub private_inputs: serde_json::Value,
+    pub public_params: serde_json::Value,
+}
+
+/// Response from ELF/DSL verification
+#[derive(Debug, Serialize)]
+pub struct VerifySdkResponse {
+    /// Whether every check passed
+    pub valid: bool,
+
+    /// Image ID computed from the ELF
+    pub image_id: String,
+
+    /// Whether the computed image ID equals `expected_image_id`, if given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_id_matches: Option<bool>,
+
+    /// Whether the guest was run to check its committed outputs
+    pub outputs_checked: bool,
+
+    /// Every mismatch found
+    pub errors: Vec<String>,
+}
+
+/// Health of a downstream service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyHealth {
+    /// "healthy", "degraded", "unhealthy" or "unknown"
+    pub status: String,
+
+    /// Why the service isn't healthy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl DependencyHealth {
+    fn new(status: &str, error: Option<String>) -> Self {
+        Self {
+            status: status.to_string(),
+            error,
+        }
+    }
+}
+
+/// Composite status of this API and the services `deploy` depends on
+#[derive(Debug, Serialize)]
+pub struct StatusResponse {
+    /// "healthy" when every dependency is healthy, "degraded" otherwise
+    pub status: String,
+
+    /// Always "logic-compiler-api"
+    pub service: String,
+
+    /// Build Service health
+    pub build_service: DependencyHealth,
+
+    /// Image ID Registry health, as reported by the Build Service
+    pub registry: DependencyHealth,
+}
+
 /// API Error type
 #[derive(Debug)]
 pub struct ApiError {
@@ -156,6 +260,25 @@ impl IntoResponse for ApiError {
     }
 }
 
+/// Successful response envelope
+#[derive(Debug, Serialize)]
+pub struct ApiResponse<T> {
+    pub data: T,
+}
+
+impl<T> ApiResponse<T> {
+    /// Wrap a response payload
+    pub fn new(data: T) -> Self {
+        Self { data }
+    }
+}
+
+impl<T: Serialize> IntoResponse for ApiResponse<T> {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
 impl From<anyhow::Error> for ApiError {
     fn from(err: anyhow::Error) -> Self {
         ApiError {
@@ -167,16 +290,89 @@ impl From<anyhow::Error> for ApiError {
 
 /// Health check endpoint
 pub async fn health_handler() -> impl IntoResponse {
-    Json(serde_json::json!({
+    ApiResponse::new(serde_json::json!({
         "status": "healthy",
         "service": "logic-compiler-api"
     }))
 }
 
+/// How long `/api/status` waits on the Build Service health check
+const STATUS_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Composite health of this API, the Build Service and (through it) the registry
+///
+/// Always returns 200; the body's `status` is "degraded" when any dependency
+/// is unhealthy or unreachable, since deployments can't complete until it recovers.
+pub async fn status_handler(State(state): State<Arc<AppState>>) -> ApiResponse<StatusResponse> {
+    let (build_service, registry) = probe_build_service(&state).await;
+    let status = if build_service.status == "healthy" && registry.status == "healthy" {
+        "healthy"
+    } else {
+        "degraded"
+    };
+
+    ApiResponse::new(StatusResponse {
+        status: status.to_string(),
+        service: "logic-compiler-api".to_string(),
+        build_service,
+        registry,
+    })
+}
+
+/// Health of the Build Service and, as it reports it, the registry
+async fn probe_build_service(state: &AppState) -> (DependencyHealth, DependencyHealth) {
+    match fetch_build_service_health(state).await {
+        Ok(health) => {
+            let build_service =
+                DependencyHealth::new(health["status"].as_str().unwrap_or("unknown"), None);
+            let registry = serde_json::from_value(health["dependencies"]["registry"].clone())
+                .unwrap_or_else(|_| {
+                    DependencyHealth::new(
+                        "unknown",
+                        Some("Build Service did not report registry health".to_string()),
+                    )
+                });
+            (build_service, registry)
+        }
+        Err(e) => (
+            DependencyHealth::new("unhealthy", Some(e.message)),
+            DependencyHealth::new(
+                "unknown",
+                Some("Build Service unavailable to report registry health".to_string()),
+            ),
+        ),
+    }
+}
+
+/// Fetch the `data` of the Build Service's `/health` body
+async fn fetch_build_service_health(state: &AppState) -> Result<serde_json::Value, ApiError> {
+    let response = state
+        .build_client
+        .get(format!("{}/health", state.build_service_url))
+        .timeout(STATUS_PROBE_TIMEOUT)
+        .send()
+        .await
+        .map_err(build_service_error)?;
+
+    if !response.status().is_success() {
+        return Err(ApiError {
+            status: StatusCode::BAD_GATEWAY,
+            message: format!("Build Service returned {}", response.status()),
+        });
+    }
+
+    let body = read_build_response(response, state.build_service_max_response_bytes).await?;
+    let mut health: serde_json::Value = serde_json::from_slice(&body).map_err(|e| ApiError {
+        status: StatusCode::BAD_GATEWAY,
+        message: format!("Invalid Build Service health response: {}", e),
+    })?;
+    Ok(health["data"].take())
+}
+
 /// Validate DSL without compiling
 pub async fn validate_handler(
     Json(payload): Json<ValidateRequest>,
-) -> Result<Json<ValidateResponse>, ApiError> {
+) -> Result<ApiResponse<ValidateResponse>, ApiError> {
     info!("Validating DSL");
 
     // Convert Value to JSON string
@@ -189,17 +385,25 @@ pub async fn validate_handler(
     match DslParser::parse_str(&dsl_json) {
         Ok(parsed_dsl) => {
             info!("DSL validation successful");
-            Ok(Json(ValidateResponse {
+            Ok(ApiResponse::new(ValidateResponse {
                 valid: true,
                 error: None,
+                errors: None,
                 parsed_dsl: Some(parsed_dsl),
             }))
         }
         Err(e) => {
             info!("DSL validation failed: {}", e);
-            Ok(Json(ValidateResponse {
+
+            // Structured errors are only available once the JSON matches the schema
+            let errors = serde_json::from_value::<BusinessRulesDSL>(payload.dsl)
+                .ok()
+                .and_then(|dsl| DslParser::validate_structured(&dsl).err());
+
+            Ok(ApiResponse::new(ValidateResponse {
                 valid: false,
                 error: Some(e.to_string()),
+                errors,
                 parsed_dsl: None,
             }))
         }
@@ -209,7 +413,7 @@ pub async fn validate_handler(
 /// Compile DSL to guest program code
 pub async fn compile_handler(
     Json(payload): Json<CompileRequest>,
-) -> Result<Json<CompileResponse>, ApiError> {
+) -> Result<ApiResponse<CompileResponse>, ApiError> {
     info!("Compiling DSL");
 
     // Convert Value to JSON string
@@ -223,9 +427,10 @@ pub async fn compile_handler(
         Ok(dsl) => dsl,
         Err(e) => {
             error!("Failed to parse DSL: {}", e);
-            return Ok(Json(CompileResponse {
+            return Ok(ApiResponse::new(CompileResponse {
                 success: false,
                 code: None,
+                input_manifest: None,
                 error: Some(format!("DSL validation failed: {}", e)),
             }));
         }
@@ -236,28 +441,116 @@ pub async fn compile_handler(
     match generator.generate() {
         Ok(code) => {
             info!("Code generation successful");
-            Ok(Json(CompileResponse {
+            Ok(ApiResponse::new(CompileResponse {
                 success: true,
                 code: Some(code),
+                input_manifest: Some(generator.input_manifest()),
                 error: None,
             }))
         }
         Err(e) => {
             error!("Code generation failed: {}", e);
-            Ok(Json(CompileResponse {
+            Ok(ApiResponse::new(CompileResponse {
                 success: false,
                 code: None,
+                input_manifest: None,
                 error: Some(format!("Code generation failed: {}", e)),
             }))
         }
     }
 }
 
+/// Report how a DSL update changes the inputs clients send
+pub async fn diff_handler(
+    Json(payload): Json<DiffRequest>,
+) -> Result<ApiResponse<DslDiff>, ApiError> {
+    info!("Diffing DSL versions");
+
+    let old = parse_dsl_value(&payload.old, "old")?;
+    let new = parse_dsl_value(&payload.new, "new")?;
+
+    Ok(ApiResponse::new(logic_compiler::diff(&old, &new)))
+}
+
+/// Parse and validate one DSL of a request, naming it in the error
+fn parse_dsl_value(dsl: &serde_json::Value, name: &str) -> Result<BusinessRulesDSL, ApiError> {
+    DslParser::parse_str(&dsl.to_string()).map_err(|e| ApiError {
+        status: StatusCode::BAD_REQUEST,
+        message: format!("Invalid {} DSL: {}", name, e),
+    })
+}
+
+/// Check a locally built guest ELF against the DSL it was built from
+pub async fn verify_sdk_handler(
+    Json(payload): Json<VerifySdkRequest>,
+) -> Result<ApiResponse<VerifySdkResponse>, ApiError> {
+    info!("Verifying uploaded guest ELF");
+
+    let dsl_json = serde_json::to_string(&payload.dsl).map_err(|e| ApiError {
+        status: StatusCode::BAD_REQUEST,
+        message: format!("Invalid JSON: {}", e),
+    })?;
+    let dsl = DslParser::parse_str(&dsl_json).map_err(|e| ApiError {
+        status: StatusCode::BAD_REQUEST,
+        message: format!("DSL validation failed: {}", e),
+    })?;
+
+    let elf = hex::decode(payload.elf_hex.trim_start_matches("0x")).map_err(|e| ApiError {
+        status: StatusCode::BAD_REQUEST,
+        message: format!("Invalid elf_hex: {}", e),
+    })?;
+
+    let image_id = sdk_verify::image_id(&elf).map_err(|e| ApiError {
+        status: StatusCode::BAD_REQUEST,
+        message: format!("Invalid guest ELF: {:#}", e),
+    })?;
+
+    let mut errors = Vec::new();
+
+    let image_id_matches = payload.expected_image_id.as_deref().map(|expected| {
+        let matches = sdk_verify::image_ids_match(&image_id, expected);
+        if !matches {
+            errors.push(format!(
+                "Image ID mismatch: ELF has {}, expected {}",
+                image_id, expected
+            ));
+        }
+        matches
+    });
+
+    let outputs_checked = payload.sample_inputs.is_some();
+    if let Some(inputs) = payload.sample_inputs {
+        // Executing the guest is CPU-bound
+        let journal = tokio::task::spawn_blocking(move || {
+            sdk_verify::dry_run(&elf, &inputs.private_inputs, &inputs.public_params)
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Dry run task failed: {}", e))?;
+
+        match journal {
+            Ok(journal) => errors.extend(sdk_verify::check_output_shape(&dsl, &journal)),
+            Err(e) => errors.push(format!("Dry run failed: {:#}", e)),
+        }
+    }
+
+    if !errors.is_empty() {
+        info!("Guest ELF does not match DSL: {:?}", errors);
+    }
+
+    Ok(ApiResponse::new(VerifySdkResponse {
+        valid: errors.is_empty(),
+        image_id,
+        image_id_matches,
+        outputs_checked,
+        errors,
+    }))
+}
+
 /// Generate complete SDK package
 pub async fn generate_sdk_handler(
     State(state): State<Arc<AppState>>,
     Json(payload): Json<GenerateSdkRequest>,
-) -> Result<Json<GenerateSdkResponse>, ApiError> {
+) -> Result<ApiResponse<GenerateSdkResponse>, ApiError> {
     info!("Generating SDK package");
 
     // Convert Value to JSON string
@@ -271,7 +564,7 @@ pub async fn generate_sdk_handler(
         Ok(dsl) => dsl,
         Err(e) => {
             error!("Failed to parse DSL: {}", e);
-            return Ok(Json(GenerateSdkResponse {
+            return Ok(ApiResponse::new(GenerateSdkResponse {
                 success: false,
                 sdk_id: None,
                 error: Some(format!("DSL validation failed: {}", e)),
@@ -292,7 +585,7 @@ pub async fn generate_sdk_handler(
             let _ = std::fs::write(sdk_dir.join("use_case.txt"), &use_case);
 
             info!("SDK package generated: {}", sdk_id);
-            Ok(Json(GenerateSdkResponse {
+            Ok(ApiResponse::new(GenerateSdkResponse {
                 success: true,
                 sdk_id: Some(sdk_id),
                 error: None,
@@ -302,7 +595,7 @@ pub async fn generate_sdk_handler(
             error!("SDK generation failed: {}", e);
             // Clean up failed directory
             let _ = std::fs::remove_dir_all(&sdk_dir);
-            Ok(Json(GenerateSdkResponse {
+            Ok(ApiResponse::new(GenerateSdkResponse {
                 success: false,
                 sdk_id: None,
                 error: Some(format!("SDK generation failed: {}", e)),
@@ -365,7 +658,7 @@ pub async fn download_sdk_handler(
 /// List available templates
 pub async fn list_templates_handler(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<TemplatesResponse>, ApiError> {
+) -> Result<ApiResponse<TemplatesResponse>, ApiError> {
     info!("Listing templates");
 
     let templates_dir = &state.templates_dir;
@@ -400,14 +693,58 @@ pub async fn list_templates_handler(
         }
     }
 
-    Ok(Json(TemplatesResponse { templates }))
+    Ok(ApiResponse::new(TemplatesResponse { templates }))
+}
+
+/// Map a failed Build Service request to an API error
+fn build_service_error(err: reqwest::Error) -> ApiError {
+    if err.is_timeout() {
+        ApiError {
+            status: StatusCode::GATEWAY_TIMEOUT,
+            message: format!("Build Service timed out: {}", err),
+        }
+    } else {
+        ApiError {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            message: format!("Build Service unavailable: {}", err),
+        }
+    }
+}
+
+/// Read a Build Service response body, refusing more than `limit` bytes
+async fn read_build_response(
+    mut response: reqwest::Response,
+    limit: usize,
+) -> Result<Vec<u8>, ApiError> {
+    let too_large = || ApiError {
+        status: StatusCode::BAD_GATEWAY,
+        message: format!("Build Service response exceeds {} bytes", limit),
+    };
+
+    if response
+        .content_length()
+        .is_some_and(|len| len > limit as u64)
+    {
+        return Err(too_large());
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(build_service_error)? {
+        if body.len() + chunk.len() > limit {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(body)
 }
 
 /// Deploy DSL to gateway - queues build job with Build Service (async)
+#[tracing::instrument(skip_all, fields(customer_id = %payload.customer_id))]
 pub async fn deploy_handler(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Json(payload): Json<DeployRequest>,
-) -> Result<Json<DeployResponse>, ApiError> {
+) -> Result<ApiResponse<DeployResponse>, ApiError> {
     info!("Queueing deployment for customer: {}", payload.customer_id);
 
     // Validate DSL first
@@ -418,7 +755,7 @@ pub async fn deploy_handler(
 
     if let Err(e) = DslParser::parse_str(&dsl_json) {
         error!("Failed to parse DSL: {}", e);
-        return Ok(Json(DeployResponse {
+        return Ok(ApiResponse::new(DeployResponse {
             success: false,
             customer_id: None,
             image_id: None,
@@ -429,9 +766,6 @@ pub async fn deploy_handler(
     }
 
     // Queue build job with Build Service
-    let build_service_url = std::env::var("BUILD_SERVICE_URL")
-        .unwrap_or_else(|_| "http://127.0.0.1:8085".to_string());
-
     let gateway_url = std::env::var("GATEWAY_URL")
         .unwrap_or_else(|_| "http://localhost:8080".to_string());
 
@@ -442,21 +776,22 @@ pub async fn deploy_handler(
         // "webhook_url": format!("{}/api/deploy/webhook", gateway_url)
     });
 
-    let client = reqwest::Client::new();
-    let build_response = client
-        .post(format!("{}/api/build", build_service_url))
+    let build_response = state
+        .build_client
+        .post(format!("{}/api/build", state.build_service_url))
+        .headers(khafi_telemetry::trace_headers())
         .json(&build_payload)
         .send()
         .await
-        .map_err(|e| ApiError {
-            status: StatusCode::SERVICE_UNAVAILABLE,
-            message: format!("Build Service unavailable: {}", e),
-        })?;
+        .map_err(build_service_error)?;
+
+    let build_status = build_response.status();
+    let body = read_build_response(build_response, state.build_service_max_response_bytes).await?;
 
-    if !build_response.status().is_success() {
-        let error_text = build_response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+    if !build_status.is_success() {
+        let error_text = String::from_utf8_lossy(&body);
         error!("Failed to queue build: {}", error_text);
-        return Ok(Json(DeployResponse {
+        return Ok(ApiResponse::new(DeployResponse {
             success: false,
             customer_id: None,
             image_id: None,
@@ -467,18 +802,19 @@ pub async fn deploy_handler(
     }
 
     // Parse response to get job_id
-    let build_result: serde_json::Value = build_response.json().await.map_err(|e| ApiError {
+    let build_result: serde_json::Value = serde_json::from_slice(&body).map_err(|e| ApiError {
         status: StatusCode::INTERNAL_SERVER_ERROR,
         message: format!("Failed to parse build response: {}", e),
     })?;
 
-    let job_id = build_result.get("job_id")
+    let job_id = build_result["data"]
+        .get("job_id")
         .and_then(|v| v.as_str())
         .map(|s| s.to_string());
 
     info!("Build queued for customer: {} with job_id: {:?}", payload.customer_id, job_id);
 
-    Ok(Json(DeployResponse {
+    Ok(ApiResponse::new(DeployResponse {
         success: true,
         customer_id: Some(payload.customer_id.clone()),
         image_id: None, // Will be available after build completes
@@ -490,22 +826,17 @@ pub async fn deploy_handler(
 
 /// Check deployment/build status
 pub async fn deploy_status_handler(
+    State(state): State<Arc<AppState>>,
     Path(job_id): Path<String>,
-) -> Result<Json<serde_json::Value>, ApiError> {
+) -> Result<ApiResponse<serde_json::Value>, ApiError> {
     info!("Checking deployment status for job: {}", job_id);
 
-    let build_service_url = std::env::var("BUILD_SERVICE_URL")
-        .unwrap_or_else(|_| "http://127.0.0.1:8085".to_string());
-
-    let client = reqwest::Client::new();
-    let response = client
-        .get(format!("{}/api/build/{}", build_service_url, job_id))
+    let response = state
+        .build_client
+        .get(format!("{}/api/build/{}", state.build_service_url, job_id))
         .send()
         .await
-        .map_err(|e| ApiError {
-            status: StatusCode::SERVICE_UNAVAILABLE,
-            message: format!("Build Service unavailable: {}", e),
-        })?;
+        .map_err(build_service_error)?;
 
     if response.status() == StatusCode::NOT_FOUND {
         return Err(ApiError {
@@ -514,19 +845,28 @@ pub async fn deploy_status_handler(
         });
     }
 
-    let job_status: serde_json::Value = response.json().await.map_err(|e| ApiError {
-        status: StatusCode::INTERNAL_SERVER_ERROR,
-        message: format!("Failed to parse status response: {}", e),
-    })?;
+    if !response.status().is_success() {
+        return Err(ApiError {
+            status: StatusCode::BAD_GATEWAY,
+            message: format!("Build Service returned {}", response.status()),
+        });
+    }
+
+    let body = read_build_response(response, state.build_service_max_response_bytes).await?;
+    let mut job_status: serde_json::Value =
+        serde_json::from_slice(&body).map_err(|e| ApiError {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: format!("Failed to parse status response: {}", e),
+        })?;
 
-    Ok(Json(job_status))
+    Ok(ApiResponse::new(job_status["data"].take()))
 }
 
 /// Get a specific template by name
 pub async fn get_template_handler(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
-) -> Result<Json<serde_json::Value>, ApiError> {
+) -> Result<ApiResponse<serde_json::Value>, ApiError> {
     info!("Getting template: {}", name);
 
     let template_path = state.templates_dir.join(format!("{}.json", name));
@@ -548,7 +888,7 @@ pub async fn get_template_handler(
         message: format!("Failed to parse template: {}", e),
     })?;
 
-    Ok(Json(json))
+    Ok(ApiResponse::new(json))
 }
 
 /// Helper: Create tarball from directory
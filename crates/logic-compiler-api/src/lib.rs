@@ -16,23 +16,37 @@
 //!
 //! - `POST /api/validate` - Validate DSL without compiling
 //! - `POST /api/compile` - Compile DSL to guest program code
+//! - `POST /api/verify-sdk` - Check a locally built guest ELF against its DSL
 //! - `POST /api/sdk/generate` - Generate complete SDK package
 //! - `GET /api/sdk/download/:id` - Download SDK package as tarball
 //! - `GET /api/templates` - List available templates
 //! - `GET /api/templates/:name` - Get specific template
 //! - `GET /health` - Health check
+//! - `GET /api/status` - Composite health of this API, the Build Service and the registry
 
 pub mod config;
 pub mod handlers;
+pub mod sdk_verify;
 
 use axum::{
+    extract::DefaultBodyLimit,
     routing::{get, post},
     Router,
 };
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
 
+/// Request body limit for `/api/verify-sdk`, which carries a hex-encoded ELF
+const VERIFY_SDK_BODY_LIMIT: usize = 32 * 1024 * 1024;
+
+/// Default timeout for requests to the Build Service
+pub const DEFAULT_BUILD_SERVICE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default limit on Build Service response bodies
+pub const DEFAULT_BUILD_SERVICE_MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+
 /// Application state shared across handlers
 #[derive(Clone)]
 pub struct AppState {
@@ -41,16 +55,51 @@ pub struct AppState {
 
     /// Directory containing template files
     pub templates_dir: PathBuf,
+
+    /// Build Service base URL
+    pub build_service_url: String,
+
+    /// HTTP client for Build Service requests (carries the request timeout)
+    pub build_client: reqwest::Client,
+
+    /// Maximum size of a Build Service response body
+    pub build_service_max_response_bytes: usize,
 }
 
 impl AppState {
     /// Create new application state
     pub fn new(sdk_output_dir: PathBuf, templates_dir: PathBuf) -> Self {
+        let build_service_url = std::env::var("BUILD_SERVICE_URL")
+            .unwrap_or_else(|_| "http://127.0.0.1:8085".to_string());
+
         Self {
             sdk_output_dir,
             templates_dir,
+            build_service_url,
+            build_client: build_client(DEFAULT_BUILD_SERVICE_TIMEOUT),
+            build_service_max_response_bytes: DEFAULT_BUILD_SERVICE_MAX_RESPONSE_BYTES,
         }
     }
+
+    /// Set the Build Service URL, request timeout and response size limit
+    pub fn with_build_service(
+        mut self,
+        url: impl Into<String>,
+        timeout: Duration,
+        max_response_bytes: usize,
+    ) -> Self {
+        self.build_service_url = url.into();
+        self.build_client = build_client(timeout);
+        self.build_service_max_response_bytes = max_response_bytes;
+        self
+    }
+}
+
+fn build_client(timeout: Duration) -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .expect("Failed to build HTTP client")
 }
 
 /// Create the API router
@@ -60,9 +109,15 @@ pub fn create_router(state: AppState) -> Router {
     Router::new()
         // Health check
         .route("/health", get(handlers::health_handler))
+        .route("/api/status", get(handlers::status_handler))
         // DSL validation and compilation
         .route("/api/validate", post(handlers::validate_handler))
         .route("/api/compile", post(handlers::compile_handler))
+        .route("/api/dsl/diff", post(handlers::diff_handler))
+        .route(
+            "/api/verify-sdk",
+            post(handlers::verify_sdk_handler).layer(DefaultBodyLimit::max(VERIFY_SDK_BODY_LIMIT)),
+        )
         // Deployment (async via Build Service)
         .route("/api/deploy", post(handlers::deploy_handler))
         .route("/api/deploy/status/{job_id}", get(handlers::deploy_status_handler))
@@ -79,6 +134,9 @@ pub fn create_router(state: AppState) -> Router {
         .layer(
             CorsLayer::permissive(), // Allow all origins for development
         )
-        .layer(TraceLayer::new_for_http())
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(khafi_telemetry::http_request_span::<axum::body::Body>),
+        )
         .with_state(state)
 }
@@ -12,11 +12,8 @@ use tracing::info;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_target(false)
-        .compact()
-        .init();
+    // Initialize tracing (exports to OTLP when OTEL_EXPORTER_OTLP_ENDPOINT is set)
+    let _telemetry = khafi_telemetry::init_tracing("logic-compiler-api", "info")?;
 
     info!("Starting Logic Compiler API Service");
 
@@ -38,7 +35,16 @@ async fn main() -> Result<()> {
     info!("Templates directory: {}", config.templates_dir.display());
 
     // Create application state
-    let state = AppState::new(config.sdk_output_dir.clone(), config.templates_dir.clone());
+    let state = AppState::new(config.sdk_output_dir.clone(), config.templates_dir.clone())
+        .with_build_service(
+            config.build_service_url.clone(),
+            config.build_service_timeout(),
+            config.build_service_max_response_bytes,
+        );
+    info!(
+        "Build Service: {} (timeout {}s)",
+        config.build_service_url, config.build_service_timeout_secs
+    );
 
     // Create router
     let app = create_router(state);
@@ -0,0 +1,160 @@
+//! Checks for guest ELFs built locally from a DSL
+//!
+//! Customers who build their guest program themselves can submit the ELF with
+//! the DSL it was generated from. The ELF's image ID is computed and, when
+//! sample inputs are given, the guest is run in the executor (no proving) to
+//! check that its journal has the layout generated guests commit (JSON that
+//! decodes as `GuestOutputs`).
+
+use anyhow::{Context, Result};
+use logic_compiler::BusinessRulesDSL;
+use risc0_zkvm::{default_executor, ExecutorEnv};
+use serde::{Deserialize, Serialize};
+
+/// Journal layout committed by generated guests (`Outputs` in the guest template)
+#[derive(Debug, Serialize, Deserialize)]
+struct CommittedOutputs {
+    nullifier: [u8; 32],
+    compliance_result: bool,
+    metadata: Vec<u8>,
+}
+
+/// Compute a guest ELF's image ID as hex
+pub fn image_id(elf: &[u8]) -> Result<String> {
+    let digest = risc0_zkvm::compute_image_id(elf).context("Failed to compute image ID")?;
+    Ok(digest.to_string())
+}
+
+/// Whether two hex image IDs are equal, ignoring case and a `0x` prefix
+pub fn image_ids_match(a: &str, b: &str) -> bool {
+    let normalize = |id: &str| id.trim_start_matches("0x").to_ascii_lowercase();
+    normalize(a) == normalize(b)
+}
+
+/// Run the guest in the executor with sample inputs and return its journal
+///
+/// Inputs are written the same way the proof generation service writes them,
+/// followed by an all-zero nullifier.
+pub fn dry_run(
+    elf: &[u8],
+    private_inputs: &serde_json::Value,
+    public_params: &serde_json::Value,
+) -> Result<Vec<u8>> {
+    let env = ExecutorEnv::builder()
+        .write(&serde_json::to_string(private_inputs)?)?
+        .write(&serde_json::to_string(public_params)?)?
+        .write(&[0u8; 32])?
+        .build()
+        .context("Failed to build executor environment")?;
+
+    let session = default_executor()
+        .execute(env, elf)
+        .context("Failed to execute guest program")?;
+
+    Ok(session.journal.bytes)
+}
+
+/// Check a journal against the outputs the DSL declares
+///
+/// Returns one message per mismatch; an empty list means the shapes agree.
+pub fn check_output_shape(dsl: &BusinessRulesDSL, journal: &[u8]) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    // An omitted `outputs` section leaves the type empty, which means bool
+    let declared = dsl.outputs.compliance_result.as_str();
+    if !declared.is_empty() && declared != "bool" {
+        errors.push(format!(
+            "DSL declares compliance_result as {}, but guests commit a bool",
+            dsl.outputs.compliance_result
+        ));
+    }
+
+    // Generated guests only commit nullifier, compliance_result and metadata
+    let mut extra: Vec<&String> = dsl.outputs.additional.keys().collect();
+    extra.sort();
+    for name in extra {
+        errors.push(format!(
+            "DSL declares output `{}`, which generated guests do not commit",
+            name
+        ));
+    }
+
+    // Trailing bytes after the JSON object are rejected by the parser itself
+    if let Err(e) = serde_json::from_slice::<CommittedOutputs>(journal) {
+        errors.push(format!(
+            "Journal does not match the declared outputs (nullifier, compliance_result, metadata): {}",
+            e
+        ));
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logic_compiler::DslParser;
+
+    const DSL: &str = r#"{
+        "use_case": "age_check",
+        "private_inputs": { "type": "object", "fields": { "age": "u32" } },
+        "public_params": { "min_age": "u32" },
+        "validation_rules": [
+            { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 }
+        ]
+    }"#;
+
+    fn journal<T: Serialize>(value: &T) -> Vec<u8> {
+        serde_json::to_vec(value).unwrap()
+    }
+
+    #[test]
+    fn test_matching_journal_accepted() {
+        let dsl = DslParser::parse_str(DSL).unwrap();
+        let outputs = CommittedOutputs {
+            nullifier: [7u8; 32],
+            compliance_result: true,
+            metadata: b"range_check:age".to_vec(),
+        };
+
+        assert!(check_output_shape(&dsl, &journal(&outputs)).is_empty());
+
+        // The same journal is what the verifier decodes
+        let decoded =
+            khafi_common::decode_journal(&journal(&outputs), khafi_common::JournalEncoding::Auto)
+                .unwrap();
+        assert_eq!(decoded.nullifier, khafi_common::Nullifier::new([7u8; 32]));
+        assert!(decoded.compliance_result);
+        assert_eq!(decoded.metadata, b"range_check:age");
+    }
+
+    #[test]
+    fn test_mismatched_journal_flagged() {
+        let dsl = DslParser::parse_str(DSL).unwrap();
+
+        // A guest built from different code that commits something else entirely
+        let errors = check_output_shape(&dsl, &journal(&("approved", 42u32)));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("does not match the declared outputs"));
+
+        // A DSL declaring outputs the guest never commits
+        let mut dsl = dsl;
+        dsl.outputs
+            .additional
+            .insert("risk_score".to_string(), "u32".to_string());
+        let outputs = CommittedOutputs {
+            nullifier: [7u8; 32],
+            compliance_result: true,
+            metadata: Vec::new(),
+        };
+        let errors = check_output_shape(&dsl, &journal(&outputs));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("risk_score"));
+    }
+
+    #[test]
+    fn test_image_ids_match() {
+        assert!(image_ids_match("0xABCD", "abcd"));
+        assert!(!image_ids_match("abcd", "abce"));
+    }
+}
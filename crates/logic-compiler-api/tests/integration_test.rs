@@ -6,6 +6,7 @@ use axum::{
 };
 use logic_compiler_api::{create_router, AppState};
 use serde_json::json;
+use std::time::{Duration, Instant};
 use tower::ServiceExt; // for `oneshot`
 
 /// Helper to create test app with temporary directories
@@ -43,6 +44,7 @@ async fn test_health_check() {
         .await
         .unwrap();
     let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let json = &json["data"];
 
     assert_eq!(json["status"], "healthy");
     assert_eq!(json["service"], "logic-compiler-api");
@@ -95,6 +97,7 @@ async fn test_validate_valid_dsl() {
         .await
         .unwrap();
     let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let json = &json["data"];
 
     assert_eq!(json["valid"], true);
     assert!(json["error"].is_null());
@@ -131,6 +134,7 @@ async fn test_validate_invalid_dsl() {
         .await
         .unwrap();
     let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let json = &json["data"];
 
     assert_eq!(json["valid"], false);
     assert!(json["error"].is_string());
@@ -138,6 +142,11 @@ async fn test_validate_invalid_dsl() {
         .as_str()
         .unwrap()
         .contains("At least one validation rule"));
+
+    // Structured errors point at the offending property
+    assert_eq!(json["errors"][0]["field"], "validation_rules");
+    assert_eq!(json["errors"][0]["code"], "required");
+    assert!(json["errors"][0]["rule_index"].is_null());
 }
 
 #[tokio::test]
@@ -187,6 +196,7 @@ async fn test_compile_valid_dsl() {
         .await
         .unwrap();
     let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let json = &json["data"];
 
     assert_eq!(json["success"], true);
     assert!(json["code"].is_string());
@@ -196,6 +206,20 @@ async fn test_compile_valid_dsl() {
     let code = json["code"].as_str().unwrap();
     assert!(code.contains("risc0_zkvm"));
     assert!(code.contains("main()"));
+    // The manifest tells the customer what their SDK needs
+    let manifest = json["input_manifest"].as_array().unwrap();
+    assert_eq!(manifest.len(), 2);
+    assert_eq!(
+        manifest[0]["path"],
+        "private_inputs.user_data.date_of_birth"
+    );
+    assert_eq!(manifest[0]["visibility"], "private");
+    assert_eq!(
+        manifest[0]["referenced_by"][0]["rule_type"],
+        "age_verification"
+    );
+    assert_eq!(manifest[1]["path"], "public_params.min_age");
+    assert_eq!(manifest[1]["rust_type"], "u32");
 }
 
 #[tokio::test]
@@ -228,12 +252,83 @@ async fn test_compile_invalid_dsl() {
         .await
         .unwrap();
     let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let json = &json["data"];
 
     assert_eq!(json["success"], false);
     assert!(json["code"].is_null());
     assert!(json["error"].is_string());
 }
 
+async fn post_diff(
+    old: serde_json::Value,
+    new: serde_json::Value,
+) -> (StatusCode, serde_json::Value) {
+    let (app, _sdk_dir, _templates_dir) = create_test_app();
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/dsl/diff")
+                .method("POST")
+                .header("content-type", "application/json")
+                .body(Body::from(json!({ "old": old, "new": new }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_dsl_diff() {
+    let old = json!({
+        "use_case": "age_check",
+        "private_inputs": {
+            "type": "object",
+            "fields": { "age": "u32", "country": "string" }
+        },
+        "public_params": { "min_age": "u32" },
+        "validation_rules": [
+            { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 }
+        ]
+    });
+
+    // Dropping a required private input breaks existing clients
+    let mut new = old.clone();
+    new["private_inputs"]["fields"]
+        .as_object_mut()
+        .unwrap()
+        .remove("country");
+    let (status, json) = post_diff(old.clone(), new).await;
+    assert_eq!(status, StatusCode::OK);
+    let diff = &json["data"];
+    assert_eq!(diff["breaking"], true);
+    assert_eq!(diff["removed_fields"][0]["path"], "private_inputs.country");
+    assert_eq!(diff["removed_fields"][0]["breaking"], true);
+
+    // A new public param with a default doesn't
+    let mut new = old.clone();
+    new["public_params"]["region"] = json!("string");
+    new["param_defaults"] = json!({ "region": "EU" });
+    let (_, json) = post_diff(old.clone(), new).await;
+    let diff = &json["data"];
+    assert_eq!(diff["breaking"], false);
+    assert_eq!(diff["added_fields"][0]["path"], "public_params.region");
+    assert_eq!(diff["added_fields"][0]["required"], false);
+
+    let (status, json) = post_diff(old, json!({ "use_case": "broken" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(json["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("Invalid new DSL"));
+}
+
 #[tokio::test]
 async fn test_generate_sdk() {
     let (app, sdk_dir, _templates_dir) = create_test_app();
@@ -281,6 +376,7 @@ async fn test_generate_sdk() {
         .await
         .unwrap();
     let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let json = &json["data"];
 
     assert_eq!(json["success"], true);
     assert!(json["sdk_id"].is_string());
@@ -318,6 +414,7 @@ async fn test_list_templates_empty() {
         .await
         .unwrap();
     let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let json = &json["data"];
 
     assert!(json["templates"].is_array());
     assert_eq!(json["templates"].as_array().unwrap().len(), 0);
@@ -338,4 +435,195 @@ async fn test_get_template_not_found() {
         .unwrap();
 
     assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Errors carry only a message, never a `data` payload
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(json["error"].as_str().unwrap().contains("nonexistent"));
+    assert!(json.get("data").is_none());
+}
+
+#[tokio::test]
+async fn test_verify_sdk_rejects_invalid_elf() {
+    let (app, _sdk_dir, _templates_dir) = create_test_app();
+
+    let request_body = json!({
+        "dsl": {
+            "use_case": "age_check",
+            "private_inputs": { "type": "object", "fields": { "age": "u32" } },
+            "public_params": { "min_age": "u32" },
+            "validation_rules": [
+                { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 }
+            ]
+        },
+        "elf_hex": "zz-not-hex"
+    });
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/verify-sdk")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&request_body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    assert!(json["error"].as_str().unwrap().contains("elf_hex"));
+}
+
+#[tokio::test]
+async fn test_deploy_times_out_when_build_service_hangs() {
+    // A "build service" that accepts connections but never responds
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            connections.push(socket);
+        }
+    });
+
+    let sdk_output_dir = tempfile::tempdir().unwrap();
+    let templates_dir = tempfile::tempdir().unwrap();
+    let state = AppState::new(
+        sdk_output_dir.path().to_path_buf(),
+        templates_dir.path().to_path_buf(),
+    )
+    .with_build_service(format!("http://{}", addr), Duration::from_millis(200), 1024);
+    let app = create_router(state);
+
+    let request_body = json!({
+        "customer_id": "customer-123",
+        "dsl": {
+            "use_case": "age_check",
+            "private_inputs": { "type": "object", "fields": { "age": "u32" } },
+            "public_params": { "min_age": "u32" },
+            "validation_rules": [
+                { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 }
+            ]
+        }
+    });
+
+    let started = Instant::now();
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/deploy")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&request_body).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    assert!(json["error"].as_str().unwrap().contains("timed out"));
+}
+
+/// Serve a "build service" whose `/health` always returns `health`
+async fn stub_build_service(health: serde_json::Value) -> String {
+    let app = axum::Router::new().route(
+        "/health",
+        axum::routing::get(move || {
+            let health = health.clone();
+            async move { axum::Json(json!({ "data": health })) }
+        }),
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    format!("http://{}", addr)
+}
+
+async fn get_status(build_service_url: String) -> serde_json::Value {
+    let sdk_output_dir = tempfile::tempdir().unwrap();
+    let templates_dir = tempfile::tempdir().unwrap();
+    let state = AppState::new(
+        sdk_output_dir.path().to_path_buf(),
+        templates_dir.path().to_path_buf(),
+    )
+    .with_build_service(build_service_url, Duration::from_secs(5), 1024);
+
+    let response = create_router(state)
+        .oneshot(
+            Request::builder()
+                .uri("/api/status")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    body["data"].clone()
+}
+
+#[tokio::test]
+async fn test_status_aggregates_dependency_health() {
+    // Build service and registry both up
+    let url = stub_build_service(json!({
+        "status": "healthy",
+        "service": "build-service",
+        "dependencies": { "registry": { "status": "healthy" } }
+    }))
+    .await;
+    let status = get_status(url).await;
+    assert_eq!(status["status"], "healthy");
+    assert_eq!(status["build_service"]["status"], "healthy");
+    assert_eq!(status["registry"]["status"], "healthy");
+
+    // Registry down behind a reachable build service
+    let url = stub_build_service(json!({
+        "status": "degraded",
+        "service": "build-service",
+        "dependencies": {
+            "registry": { "status": "unhealthy", "error": "Registry unreachable: connection refused" }
+        }
+    }))
+    .await;
+    let status = get_status(url).await;
+    assert_eq!(status["status"], "degraded");
+    assert_eq!(status["build_service"]["status"], "degraded");
+    assert_eq!(status["registry"]["status"], "unhealthy");
+    assert!(status["registry"]["error"]
+        .as_str()
+        .unwrap()
+        .contains("connection refused"));
+
+    // Build service itself unreachable
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    let status = get_status(url).await;
+    assert_eq!(status["status"], "degraded");
+    assert_eq!(status["build_service"]["status"], "unhealthy");
+    assert_eq!(status["registry"]["status"], "unknown");
 }
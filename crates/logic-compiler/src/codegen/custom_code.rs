@@ -0,0 +1,158 @@
+//! Allowlist for raw-Rust `custom` rule code
+//!
+//! Custom code is embedded verbatim into the guest and built by the build
+//! service, so it is screened token by token before either happens. The
+//! check is deliberately conservative: code may read inputs, call methods
+//! and use operators, but cannot name crates, declare items or run macros.
+//! New rules should use `expression` instead.
+
+use anyhow::{anyhow, bail, Result};
+use proc_macro2::{Spacing, TokenStream, TokenTree};
+
+/// Keywords that declare items, escape the guest's scope or opt out of safety
+const FORBIDDEN_KEYWORDS: &[&str] = &[
+    "unsafe",
+    "extern",
+    "use",
+    "mod",
+    "crate",
+    "super",
+    "static",
+    "fn",
+    "impl",
+    "trait",
+    "macro_rules",
+];
+
+/// Types whose associated items custom code may reach through a path (e.g. `u64::MAX`)
+const PERMITTED_PATH_ROOTS: &[&str] = &[
+    "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64",
+    "i128", "isize", "String", "Vec", "Option", "Result",
+];
+
+/// Check custom code against the allowlist, returning its tokens if it passes
+pub fn check_custom_code(code: &str) -> Result<TokenStream> {
+    let tokens: TokenStream = code
+        .parse()
+        .map_err(|e| anyhow!("code is not valid Rust: {}", e))?;
+    check_tokens(tokens.clone())?;
+    Ok(tokens)
+}
+
+fn check_tokens(tokens: TokenStream) -> Result<()> {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Group(group) => check_tokens(group.stream())?,
+            TokenTree::Ident(ident) => {
+                let name = ident.to_string();
+                let name = name.strip_prefix("r#").unwrap_or(&name);
+
+                if FORBIDDEN_KEYWORDS.contains(&name) {
+                    bail!("'{}' is not allowed in custom code", name);
+                }
+                if punct_at(&tokens, i + 1) == Some(('!', Spacing::Alone)) {
+                    bail!("macros are not allowed in custom code ('{}!')", name);
+                }
+
+                // Only the first segment of a path needs checking; a method
+                // turbofish (`.parse::<u32>()`) isn't a path
+                let starts_path = is_path_separator(&tokens, i + 1)
+                    && !(i >= 2 && is_path_separator(&tokens, i - 2))
+                    && !(i >= 1 && matches!(punct_at(&tokens, i - 1), Some(('.', _))));
+                if starts_path && !PERMITTED_PATH_ROOTS.contains(&name) {
+                    bail!("path '{}::' is outside the permitted set", name);
+                }
+            }
+            TokenTree::Punct(punct) => {
+                if punct.as_char() == '#' {
+                    bail!("attributes are not allowed in custom code");
+                }
+
+                // `::` continues a path after a segment or generic arguments;
+                // anywhere else it starts an absolute path like `::std::fs`
+                let continues_path = i >= 1
+                    && match &tokens[i - 1] {
+                        TokenTree::Ident(_) => true,
+                        TokenTree::Punct(p) => p.as_char() == '>',
+                        _ => false,
+                    };
+                let after_separator = i >= 1 && is_path_separator(&tokens, i - 1);
+                if is_path_separator(&tokens, i) && !continues_path && !after_separator {
+                    bail!("absolute paths are not allowed in custom code");
+                }
+            }
+            TokenTree::Literal(_) => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Character and spacing of `tokens[i]`, if it is punctuation
+fn punct_at(tokens: &[TokenTree], i: usize) -> Option<(char, Spacing)> {
+    match tokens.get(i) {
+        Some(TokenTree::Punct(p)) => Some((p.as_char(), p.spacing())),
+        _ => None,
+    }
+}
+
+/// Whether `tokens[i..]` starts with `::`
+fn is_path_separator(tokens: &[TokenTree], i: usize) -> bool {
+    punct_at(tokens, i) == Some((':', Spacing::Joint))
+        && matches!(punct_at(tokens, i + 1), Some((':', _)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simple_code_accepted() {
+        for code in [
+            "private_inputs.manual_override",
+            r#"private_inputs.age >= 18 && private_inputs.country != "XX""#,
+            "!private_inputs.blocked || private_inputs.score > u64::MAX / 2",
+            "private_inputs.items.iter().all(|item| item.len() < 64)",
+            "private_inputs.code.parse::<u32>().is_ok()",
+            "{ let total = private_inputs.a + private_inputs.b; total <= public_params.limit }",
+        ] {
+            assert!(check_custom_code(code).is_ok(), "{}", code);
+        }
+    }
+
+    #[test]
+    fn test_dangerous_code_rejected() {
+        for (code, expected) in [
+            (
+                r#"std::process::Command::new("sh").status().is_ok()"#,
+                "path 'std::'",
+            ),
+            ("unsafe { *(0 as *const bool) }", "'unsafe' is not allowed"),
+            (
+                r#"extern "C" { fn abort(); } true"#,
+                "'extern' is not allowed",
+            ),
+            (
+                r#"include_str!("/etc/passwd").is_empty()"#,
+                "macros are not allowed",
+            ),
+            (
+                "true && ::std::fs::remove_dir_all(\"/\").is_ok()",
+                "absolute paths",
+            ),
+            ("core::mem::size_of::<u8>() == 1", "path 'core::'"),
+            (
+                "{ use std::env; env::args().count() > 0 }",
+                "'use' is not allowed",
+            ),
+            ("#[allow(unused)] true", "attributes are not allowed"),
+            ("r#unsafe { true }", "'unsafe' is not allowed"),
+            ("\"unterminated", "not valid Rust"),
+        ] {
+            let err = check_custom_code(code).unwrap_err().to_string();
+            assert!(err.contains(expected), "{}: {}", code, err);
+        }
+    }
+}
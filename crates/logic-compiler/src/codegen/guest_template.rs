@@ -12,6 +12,12 @@ pub fn create_guest_program(
     let use_case = &dsl.use_case;
     let description = &dsl.description;
     let helper_functions = super::validation_gen::generate_helper_functions();
+    // Guests that disclose facts commit them alongside the rule tags
+    let success_metadata = if dsl.disclose.is_empty() {
+        "passed_rules_metadata(&public_params)"
+    } else {
+        "disclosure_metadata(&private_inputs, &public_params)"
+    };
 
     let program = format!(
         r#"//! Guest program for: {use_case}
@@ -25,6 +31,8 @@ pub fn create_guest_program(
 
 use risc0_zkvm::guest::env;
 
+risc0_zkvm::guest::entry!(main);
+
 {types_code}
 
 {helper_functions}
@@ -33,23 +41,41 @@ use risc0_zkvm::guest::env;
 
 /// Main entry point for the guest program
 fn main() {{
-    // Read private inputs
-    let private_inputs: PrivateInputs = env::read();
+    // Read private inputs and public parameters (written by the host as JSON strings)
+    let private_json: String = env::read();
+    let private_inputs: PrivateInputs =
+        serde_json::from_str(&private_json).expect("Invalid private inputs");
+
+    let public_json: String = env::read();
+    let public_params: PublicParams =
+        serde_json::from_str(&public_json).expect("Invalid public parameters");
 
-    // Read public parameters
-    let public_params: PublicParams = env::read();
+    // Read the nullifier (passed through to the journal for replay protection)
+    let nullifier: [u8; 32] = env::read();
 
     // Perform all validation checks
-    let compliance_result = validate_all(&private_inputs, &public_params);
+    let validation = validate_all(&private_inputs, &public_params);
+    let compliance_result = validation.is_ok();
+
+    // Record which rules were checked (or, if the DSL opts in, which one
+    // failed), revealing no private data beyond the facts the DSL discloses
+    let metadata = match validation {{
+        Ok(()) => {success_metadata},
+        Err(rule_index) => failed_rule_metadata(rule_index),
+    }};
 
     // Create output
     let outputs = Outputs {{
+        nullifier,
         compliance_result,
+        metadata,
         // TODO: Add any additional output fields from DSL
     }};
 
-    // Commit output to the journal (this becomes the public output of the proof)
-    env::commit(&outputs);
+    // Commit JSON outputs to the journal (this becomes the public output of the
+    // proof and decodes as khafi_common::GuestOutputs)
+    let journal = serde_json::to_vec(&outputs).expect("Failed to serialize outputs");
+    env::commit_slice(&journal);
 }}
 "#,
         use_case = use_case,
@@ -57,6 +83,7 @@ fn main() {{
         types_code = types_code,
         helper_functions = helper_functions,
         validation_code = validation_code,
+        success_metadata = success_metadata,
     );
 
     Ok(program)
@@ -87,6 +114,10 @@ This SDK enforces the following validation rules:
 
 {}
 
+## Parameter Defaults
+
+{}
+
 ## Usage
 
 ### 1. Build the guest program
@@ -101,22 +132,26 @@ cargo build --release
 ```rust
 use risc0_zkvm::{{default_prover, ExecutorEnv}};
 
-// Prepare inputs
+// Prepare inputs (public params with a default may be left out of the JSON;
+// the guest fills them in)
 let private_inputs = PrivateInputs {{ /* ... */ }};
-let public_params = PublicParams {{ /* ... */ }};
+let public_params = serde_json::json!({{ /* ... */ }});
+let nullifier: [u8; 32] = /* ... */;
 
-// Build executor environment
+// Build executor environment (inputs and params as JSON strings, then the
+// nullifier, as the proof generation service writes them)
 let env = ExecutorEnv::builder()
-    .write(&private_inputs)?
-    .write(&public_params)?
+    .write(&serde_json::to_string(&private_inputs)?)?
+    .write(&public_params.to_string())?
+    .write(&nullifier)?
     .build()?;
 
 // Generate proof
 let prover = default_prover();
-let receipt = prover.prove(env, GUEST_ELF)?;
+let receipt = prover.prove(env, {}_GUEST_ELF)?;
 
-// Extract outputs
-let outputs: Outputs = receipt.journal.decode()?;
+// Extract outputs (the journal is JSON)
+let outputs: Outputs = serde_json::from_slice(&receipt.journal.bytes)?;
 println!("Compliance result: {{}}", outputs.compliance_result);
 ```
 
@@ -133,7 +168,9 @@ that validate business logic without revealing private data.
         dsl.use_case,
         dsl.description,
         dsl.version,
-        format_validation_rules(dsl)
+        format_validation_rules(dsl),
+        format_param_defaults(dsl),
+        dsl.crate_name().to_uppercase()
     )
 }
 
@@ -153,6 +190,22 @@ fn format_validation_rules(dsl: &BusinessRulesDSL) -> String {
         .join("\n")
 }
 
+fn format_param_defaults(dsl: &BusinessRulesDSL) -> String {
+    if dsl.param_defaults.is_empty() {
+        return "Every public parameter is required.".to_string();
+    }
+
+    let defaults: Vec<String> = dsl
+        .param_defaults
+        .iter()
+        .map(|(name, value)| format!("- `{}`: `{}`", name, value))
+        .collect();
+    format!(
+        "Public parameters the guest fills in when they're omitted:\n\n{}",
+        defaults.join("\n")
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -164,17 +217,22 @@ mod tests {
             .expect("Failed to parse DSL");
 
         let types = "struct PrivateInputs {}\nstruct PublicParams {}\nstruct Outputs {}";
-        let validation = "fn validate_all() -> bool { true }";
+        let validation = "fn validate_all() -> Result<(), usize> { Ok(()) }";
 
         let program =
             create_guest_program(&dsl, types, validation).expect("Failed to create guest program");
 
         // Verify program structure
         assert!(program.contains("#![no_main]"));
+        assert!(program.contains("risc0_zkvm::guest::entry!(main)"));
         assert!(program.contains("use risc0_zkvm::guest::env"));
         assert!(program.contains("fn main()"));
         assert!(program.contains("env::read()"));
         assert!(program.contains("env::commit"));
+        // Inputs arrive as JSON strings, the way the proof generation service writes them
+        assert!(program.contains("let private_json: String = env::read()"));
+        assert!(program.contains("let public_json: String = env::read()"));
+        assert!(program.contains("let nullifier: [u8; 32] = env::read()"));
         assert!(program.contains(&dsl.use_case));
     }
 
@@ -189,5 +247,19 @@ mod tests {
         assert!(readme.contains("Overview"));
         assert!(readme.contains("Usage"));
         assert!(readme.contains("Validation Rules"));
+        assert!(readme.contains("Every public parameter is required."));
+    }
+
+    #[test]
+    fn test_sdk_readme_lists_param_defaults() {
+        let mut dsl = DslParser::parse_file("../../docs/examples/age-verification-simple.json")
+            .expect("Failed to parse DSL");
+        dsl.param_defaults
+            .insert("min_age".to_string(), serde_json::json!(18));
+
+        let readme = create_sdk_readme(&dsl);
+
+        assert!(readme.contains("- `min_age`: `18`"));
+        assert!(readme.contains(".write(&public_params.to_string())?"));
     }
 }
@@ -0,0 +1,186 @@
+//! Input manifest - what a generated SDK needs from the caller
+//!
+//! Lists every private input and public parameter with its Rust type and the
+//! rules that read it, so customers can see what to supply before building.
+
+use super::type_gen::{rust_type_name, to_snake_case};
+use crate::dsl::{BusinessRulesDSL, InputSchema, ValidationRule};
+use serde::{Deserialize, Serialize};
+
+/// Whether an input stays hidden in the proof or is visible to the verifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    /// Private input (hidden in the proof)
+    Private,
+    /// Public parameter (visible to the verifier)
+    Public,
+}
+
+/// A rule that reads an input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleReference {
+    /// Index of the top-level rule in `validation_rules`
+    pub index: usize,
+
+    /// Type of the top-level rule (e.g. "range_check")
+    pub rule_type: String,
+
+    /// Description of the top-level rule
+    pub description: String,
+}
+
+/// One input the generated SDK expects
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputField {
+    /// Field name as declared in the DSL
+    pub name: String,
+
+    /// Location in the request JSON (`private_inputs.prescription.quantity`,
+    /// `public_params.max_quantity`)
+    pub path: String,
+
+    /// DSL type (e.g. "u32", "bytes")
+    pub dsl_type: String,
+
+    /// Rust type in the generated structs (e.g. "u32", "Vec<u8>")
+    pub rust_type: String,
+
+    /// Private input or public parameter
+    pub visibility: Visibility,
+
+    /// Whether a proof request must supply it (public params with a default are optional)
+    pub required: bool,
+
+    /// Rules that read this input, in DSL order
+    pub referenced_by: Vec<RuleReference>,
+}
+
+/// Build the input manifest for a DSL
+///
+/// Private inputs come first, then public params, each in DSL declaration order.
+/// `Custom` rules count as referencing any input named in their code.
+pub fn input_manifest(dsl: &BusinessRulesDSL) -> Vec<InputField> {
+    let mut inputs: Vec<InputField> = match &dsl.private_inputs {
+        InputSchema::Object(object) => object
+            .fields
+            .iter()
+            .map(|(name, ty)| (format!("private_inputs.{}", to_snake_case(name)), name, ty))
+            .collect::<Vec<_>>(),
+        InputSchema::Map(objects) => objects
+            .iter()
+            .flat_map(|(object, schema)| {
+                schema.fields.iter().map(move |(name, ty)| {
+                    let path = format!(
+                        "private_inputs.{}.{}",
+                        to_snake_case(object),
+                        to_snake_case(name)
+                    );
+                    (path, name, ty)
+                })
+            })
+            .collect(),
+    }
+    .into_iter()
+    .map(|(path, name, ty)| InputField {
+        referenced_by: references(dsl, name, ValidationRule::referenced_fields),
+        name: name.clone(),
+        path,
+        dsl_type: ty.clone(),
+        rust_type: rust_type_name(ty).to_string(),
+        visibility: Visibility::Private,
+        required: true,
+    })
+    .collect();
+
+    let public = dsl
+        .public_params
+        .fields()
+        .iter()
+        .map(|(name, ty)| InputField {
+            name: name.clone(),
+            path: format!("public_params.{}", to_snake_case(name)),
+            dsl_type: ty.clone(),
+            rust_type: rust_type_name(ty).to_string(),
+            visibility: Visibility::Public,
+            required: !dsl.param_defaults.contains_key(name),
+            referenced_by: references(dsl, name, ValidationRule::referenced_params),
+        });
+
+    inputs.extend(public);
+    inputs
+}
+
+/// Top-level rules that reference `name`, directly or through nested rules
+fn references(
+    dsl: &BusinessRulesDSL,
+    name: &str,
+    referenced: fn(&ValidationRule) -> Vec<&str>,
+) -> Vec<RuleReference> {
+    dsl.validation_rules
+        .iter()
+        .enumerate()
+        .filter(|(_, rule)| referenced(rule).contains(&name) || custom_code_mentions(rule, name))
+        .map(|(index, rule)| RuleReference {
+            index,
+            rule_type: rule.rule_type().to_string(),
+            description: rule.description().to_string(),
+        })
+        .collect()
+}
+
+/// Whether any `Custom` rule in `rule` uses `name` as an identifier in its code
+fn custom_code_mentions(rule: &ValidationRule, name: &str) -> bool {
+    match rule {
+        ValidationRule::Custom { code, .. } => code
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .any(|word| word == name),
+        ValidationRule::Not { rule, .. } => custom_code_mentions(rule, name),
+        ValidationRule::AnyOf { rules, .. } => {
+            rules.iter().any(|rule| custom_code_mentions(rule, name))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DslParser;
+
+    #[test]
+    fn test_public_param_with_default_not_required() {
+        let dsl = DslParser::parse_str(
+            r#"{
+                "use_case": "age_check",
+                "private_inputs": { "type": "object", "fields": { "age": "u32" } },
+                "public_params": { "min_age": "u32", "max_age": "u32" },
+                "param_defaults": { "max_age": 150 },
+                "validation_rules": [
+                    { "type": "range_check", "field": "age", "min_param": "min_age", "max_param": "max_age" },
+                    { "type": "custom", "code": "private_inputs.age % 2 == 0" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let manifest = input_manifest(&dsl);
+        let paths: Vec<&str> = manifest.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "private_inputs.age",
+                "public_params.min_age",
+                "public_params.max_age"
+            ]
+        );
+
+        let indexes = |field: &InputField| -> Vec<usize> {
+            field.referenced_by.iter().map(|r| r.index).collect()
+        };
+        assert_eq!(indexes(&manifest[0]), vec![0, 1]);
+        assert!(manifest[1].required);
+        assert_eq!(indexes(&manifest[1]), vec![0]);
+        assert!(!manifest[2].required);
+    }
+}
@@ -2,7 +2,10 @@
 //!
 //! This module transforms BusinessRulesDSL into Rust code that runs in the zkVM.
 
+pub mod custom_code;
 pub mod guest_template;
+pub mod iso_countries;
+pub mod manifest;
 pub mod type_gen;
 pub mod validation_gen;
 
@@ -10,6 +13,8 @@ use crate::dsl::BusinessRulesDSL;
 use anyhow::{Context, Result};
 use std::path::Path;
 
+pub use manifest::{InputField, RuleReference, Visibility};
+
 /// Main code generator that orchestrates guest program creation
 pub struct CodeGenerator {
     dsl: BusinessRulesDSL,
@@ -37,6 +42,26 @@ impl CodeGenerator {
         Ok(guest_code)
     }
 
+    /// List every input the generated SDK expects, with its type and referencing rules
+    pub fn input_manifest(&self) -> Vec<InputField> {
+        manifest::input_manifest(&self.dsl)
+    }
+
+    /// Cargo package (and binary) name of the generated guest program
+    pub fn guest_package_name(&self) -> String {
+        format!("{}-guest", self.dsl.crate_name())
+    }
+
+    /// Cargo package name of the generated methods crate
+    pub fn methods_package_name(&self) -> String {
+        format!("{}-methods", self.dsl.crate_name())
+    }
+
+    /// Name of the ELF constant `risc0_build` generates for the guest
+    pub fn guest_elf_constant(&self) -> String {
+        format!("{}_GUEST_ELF", self.dsl.crate_name().to_uppercase())
+    }
+
     /// Generate and write guest program to a file
     pub fn generate_to_file<P: AsRef<Path>>(&self, output_path: P) -> Result<()> {
         let code = self.generate()?;
@@ -78,7 +103,7 @@ impl CodeGenerator {
     fn generate_guest_cargo_toml(&self) -> Result<String> {
         Ok(format!(
             r#"[package]
-name = "{}-guest"
+name = "{}"
 version = "0.1.0"
 edition = "2021"
 
@@ -87,12 +112,13 @@ edition = "2021"
 [dependencies]
 risc0-zkvm = {{ version = "1.0", default-features = false, features = ["std"] }}
 serde = {{ version = "1.0", default-features = false, features = ["derive"] }}
+serde_json = "1.0"
 
 [patch.crates-io]
 # Optimization for zkVM
 sha2 = {{ git = "https://github.com/risc0/RustCrypto-hashes", tag = "sha2-v0.10.6-risczero.0" }}
 "#,
-            self.dsl.use_case
+            self.guest_package_name()
         ))
     }
 
@@ -107,7 +133,7 @@ sha2 = {{ git = "https://github.com/risc0/RustCrypto-hashes", tag = "sha2-v0.10.
     fn generate_methods_cargo_toml(&self) -> Result<String> {
         Ok(format!(
             r#"[package]
-name = "{}-methods"
+name = "{}"
 version = "0.1.0"
 edition = "2021"
 
@@ -120,7 +146,7 @@ risc0-zkvm = "1.0"
 [package.metadata.risc0]
 methods = ["guest"]
 "#,
-            self.dsl.use_case
+            self.methods_package_name()
         ))
     }
 }
@@ -1,14 +1,15 @@
 //! Type generation - converts DSL schemas to Rust struct definitions
 
-use crate::dsl::{BusinessRulesDSL, InputSchema, ParamSchema};
-use anyhow::Result;
+use crate::dsl::{BusinessRulesDSL, InputSchema};
+use anyhow::{bail, Result};
+use indexmap::IndexMap;
 use proc_macro2::TokenStream;
 use quote::quote;
 
 /// Generate Rust type definitions from DSL schemas
 pub fn generate_types(dsl: &BusinessRulesDSL) -> Result<String> {
     let private_inputs = generate_private_inputs(&dsl.private_inputs)?;
-    let public_params = generate_public_params(&dsl.public_params)?;
+    let public_params = generate_public_params(dsl)?;
     let outputs = generate_outputs(dsl)?;
 
     let combined = quote! {
@@ -77,37 +78,79 @@ fn generate_private_inputs(schema: &InputSchema) -> Result<TokenStream> {
 }
 
 /// Generate public params struct
-fn generate_public_params(schema: &ParamSchema) -> Result<TokenStream> {
-    match schema {
-        ParamSchema::Map(map) => {
-            let fields: Vec<TokenStream> = map
-                .iter()
-                .map(|(name, type_str)| {
-                    let field_name = format_ident(&to_snake_case(name));
-                    let field_type = map_type_string(type_str);
-                    quote! { pub #field_name: #field_type }
-                })
-                .collect();
+///
+/// Params with a DSL default get a `#[serde(default = ...)]` function
+/// returning it, so the guest fills them in when the host omits them.
+fn generate_public_params(dsl: &BusinessRulesDSL) -> Result<TokenStream> {
+    let mut fields = Vec::new();
+    let mut default_fns = Vec::new();
 
-            Ok(quote! {
-                /// Public parameters (visible to verifier)
-                #[derive(Debug, Clone, Serialize, Deserialize)]
-                pub struct PublicParams {
-                    #(#fields),*
-                }
-            })
+    for (name, type_str) in dsl.public_params.fields() {
+        let field_name = format_ident(&to_snake_case(name));
+        let field_type = map_type_string(type_str);
+
+        match dsl.param_defaults.get(name) {
+            Some(value) => {
+                let default_fn = format!("default_{}", to_snake_case(name));
+                let default_ident = format_ident(&default_fn);
+                let default_value = default_value_tokens(name, type_str, value)?;
+
+                fields.push(quote! {
+                    #[serde(default = #default_fn)]
+                    pub #field_name: #field_type
+                });
+                default_fns.push(quote! {
+                    fn #default_ident() -> #field_type {
+                        #default_value
+                    }
+                });
+            }
+            None => fields.push(quote! { pub #field_name: #field_type }),
         }
-        ParamSchema::Object(obj) => {
-            let fields = generate_fields(&obj.fields);
-            Ok(quote! {
-                /// Public parameters (visible to verifier)
-                #[derive(Debug, Clone, Serialize, Deserialize)]
-                pub struct PublicParams {
-                    #(#fields),*
-                }
-            })
+    }
+
+    Ok(quote! {
+        /// Public parameters (visible to verifier)
+        #[derive(Debug, Clone, Serialize, Deserialize)]
+        pub struct PublicParams {
+            #(#fields),*
         }
+
+        #(#default_fns)*
+    })
+}
+
+/// Rust expression for a public param's DSL default
+fn default_value_tokens(
+    name: &str,
+    type_str: &str,
+    value: &serde_json::Value,
+) -> Result<TokenStream> {
+    if !value_matches_type(type_str, value) {
+        bail!(
+            "Default for public param '{}' is not a valid {}",
+            name,
+            type_str
+        );
     }
+
+    let literal = |value: &serde_json::Value| -> TokenStream {
+        match value.as_str() {
+            Some(s) => quote! { #s.to_string() },
+            None => value
+                .to_string()
+                .parse()
+                .expect("JSON numbers and bools are valid tokens"),
+        }
+    };
+
+    Ok(match value.as_array() {
+        Some(items) => {
+            let items = items.iter().map(literal);
+            quote! { vec![#(#items),*] }
+        }
+        None => literal(value),
+    })
 }
 
 /// Generate outputs struct
@@ -127,15 +170,19 @@ fn generate_outputs(dsl: &BusinessRulesDSL) -> Result<TokenStream> {
         /// Outputs from the verification (public)
         #[derive(Debug, Clone, Serialize, Deserialize)]
         pub struct Outputs {
+            /// Nullifier supplied by the host, for replay protection
+            pub nullifier: [u8; 32],
             /// Whether validation passed
             pub compliance_result: bool,
+            /// Comma-separated tags of the rules that passed (empty on failure)
+            pub metadata: Vec<u8>,
             #(#additional_fields),*
         }
     })
 }
 
-/// Generate field definitions from a HashMap
-fn generate_fields(fields: &std::collections::HashMap<String, String>) -> Vec<TokenStream> {
+/// Generate field definitions in DSL declaration order
+fn generate_fields(fields: &IndexMap<String, String>) -> Vec<TokenStream> {
     fields
         .iter()
         .map(|(name, type_str)| {
@@ -148,22 +195,56 @@ fn generate_fields(fields: &std::collections::HashMap<String, String>) -> Vec<To
 
 /// Map DSL type strings to Rust types
 fn map_type_string(type_str: &str) -> TokenStream {
-    match type_str {
-        "string" => quote! { String },
-        "u32" => quote! { u32 },
-        "u64" => quote! { u64 },
-        "i32" => quote! { i32 },
-        "i64" => quote! { i64 },
-        "bool" => quote! { bool },
-        "bytes" => quote! { Vec<u8> },
-        "array<string>" | "array[string]" => quote! { Vec<String> },
-        "array<u32>" | "array[u32]" => quote! { Vec<u32> },
-        "array<u64>" | "array[u64]" => quote! { Vec<u64> },
-        _ => {
-            // Default to String for unknown types
-            eprintln!("Warning: Unknown type '{}', defaulting to String", type_str);
-            quote! { String }
-        }
+    let rust_type = known_rust_type(type_str).unwrap_or_else(|| {
+        // Default to String for unknown types
+        eprintln!("Warning: Unknown type '{}', defaulting to String", type_str);
+        "String"
+    });
+    rust_type.parse().expect("Rust type names are valid tokens")
+}
+
+/// Rust type a DSL type string maps to in the generated structs (e.g. `Vec<u8>`)
+///
+/// Unknown types map to `String`, as they do in the generated code.
+pub fn rust_type_name(type_str: &str) -> &'static str {
+    known_rust_type(type_str).unwrap_or("String")
+}
+
+fn known_rust_type(type_str: &str) -> Option<&'static str> {
+    Some(match type_str {
+        "string" => "String",
+        "u32" => "u32",
+        "u64" => "u64",
+        "i32" => "i32",
+        "i64" => "i64",
+        "bool" => "bool",
+        "bytes" => "Vec<u8>",
+        "array<string>" | "array[string]" => "Vec<String>",
+        "array<u32>" | "array[u32]" => "Vec<u32>",
+        "array<u64>" | "array[u64]" => "Vec<u64>",
+        _ => return None,
+    })
+}
+
+/// Whether a JSON value deserializes into the Rust type a DSL type maps to
+pub fn value_matches_type(type_str: &str, value: &serde_json::Value) -> bool {
+    let is_array_of = |pred: fn(&serde_json::Value) -> bool| {
+        value.as_array().is_some_and(|items| items.iter().all(pred))
+    };
+
+    match rust_type_name(type_str) {
+        "u32" => value.as_u64().is_some_and(|n| n <= u32::MAX as u64),
+        "u64" => value.as_u64().is_some(),
+        "i32" => value
+            .as_i64()
+            .is_some_and(|n| n >= i32::MIN as i64 && n <= i32::MAX as i64),
+        "i64" => value.as_i64().is_some(),
+        "bool" => value.is_boolean(),
+        "Vec<u8>" => is_array_of(|v| v.as_u64().is_some_and(|n| n <= u8::MAX as u64)),
+        "Vec<String>" => is_array_of(serde_json::Value::is_string),
+        "Vec<u32>" => is_array_of(|v| v.as_u64().is_some_and(|n| n <= u32::MAX as u64)),
+        "Vec<u64>" => is_array_of(|v| v.as_u64().is_some()),
+        _ => value.is_string(),
     }
 }
 
@@ -180,9 +261,9 @@ fn to_pascal_case(s: &str) -> String {
         .collect()
 }
 
-/// Convert string to snake_case
-fn to_snake_case(s: &str) -> String {
-    s.to_lowercase().replace('-', "_").replace(' ', "_")
+/// Convert string to snake_case, as field names appear in the generated structs
+pub fn to_snake_case(s: &str) -> String {
+    s.to_lowercase().replace(['-', ' '], "_")
 }
 
 /// Helper to create ident from string
@@ -205,7 +286,6 @@ fn format_code(tokens: &TokenStream) -> String {
 mod tests {
     use super::*;
     use crate::dsl::ObjectSchema;
-    use std::collections::HashMap;
 
     #[test]
     fn test_map_type_string() {
@@ -217,6 +297,7 @@ mod tests {
 
         let tokens = map_type_string("bytes");
         assert_eq!(tokens.to_string(), "Vec < u8 >");
+        assert_eq!(rust_type_name("bytes"), "Vec<u8>");
     }
 
     #[test]
@@ -235,7 +316,7 @@ mod tests {
 
     #[test]
     fn test_generate_simple_types() {
-        let mut fields = HashMap::new();
+        let mut fields = IndexMap::new();
         fields.insert("date_of_birth".to_string(), "string".to_string());
         fields.insert("user_id".to_string(), "string".to_string());
 
@@ -252,4 +333,29 @@ mod tests {
         assert!(code.contains("date_of_birth"));
         assert!(code.contains("user_id"));
     }
+
+    #[test]
+    fn test_generate_param_defaults() {
+        let mut dsl: BusinessRulesDSL = serde_json::from_value(serde_json::json!({
+            "use_case": "age_check",
+            "private_inputs": { "type": "object", "fields": { "age": "u32" } },
+            "public_params": { "min_age": "u32", "regions": "array<string>", "max_age": "u32" },
+            "param_defaults": { "min_age": 18, "regions": ["EU", "UK"] },
+            "validation_rules": []
+        }))
+        .unwrap();
+
+        let code = generate_types(&dsl).unwrap();
+        assert!(code.contains("#[serde(default = \"default_min_age\")]\n    pub min_age: u32"));
+        assert!(code.contains("fn default_min_age() -> u32 {\n    18\n}"));
+        assert!(code.contains("vec![\"EU\".to_string(), \"UK\".to_string()]"));
+        // Params without a default stay required
+        assert!(code.contains("    pub max_age: u32"));
+        assert!(!code.contains("default_max_age"));
+
+        dsl.param_defaults
+            .insert("max_age".to_string(), serde_json::json!("old"));
+        let err = generate_types(&dsl).unwrap_err();
+        assert!(err.to_string().contains("'max_age' is not a valid u32"));
+    }
 }
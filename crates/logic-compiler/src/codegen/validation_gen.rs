@@ -1,37 +1,269 @@
 //! Validation logic generation - converts DSL validation rules to Rust code
 
+use super::custom_code::check_custom_code;
+use super::type_gen::to_snake_case;
+use crate::dsl::expr::{BinaryOp, Expr, ExprScope, ExprType};
 use crate::dsl::{BusinessRulesDSL, ValidationRule};
 use anyhow::Result;
 use proc_macro2::TokenStream;
 use quote::quote;
 
+/// Maximum size of the rule-tag metadata committed by generated guests
+pub const MAX_METADATA_LEN: usize = 256;
+
 /// Generate validation logic from DSL rules
+///
+/// Ordering contract: `validate_all` contains exactly one guard per top-level
+/// rule, in DSL order, and returns the index of the first failing guard. Nested rules
+/// (`not`, `any_of`) are checked in their declared order too. Customers rely
+/// on this to put cheap or most-likely-to-fail rules first, so every rule type
+/// must be emitted in place rather than grouped or reordered.
+///
+/// Rule sets are dispatched after the top-level rules. With `n` top-level
+/// rules, index `n` means the selector matched no set and rule `i` of the
+/// selected set is index `n + 1 + i`.
 pub fn generate_validations(dsl: &BusinessRulesDSL) -> Result<String> {
+    let scope = ExprScope::new(dsl);
     let validation_checks: Vec<TokenStream> = dsl
         .validation_rules
         .iter()
         .enumerate()
-        .map(|(idx, rule)| generate_validation_rule(rule, idx))
+        .map(|(idx, rule)| generate_validation_rule(rule, idx, &scope))
         .collect();
+    let rule_set_checks = generate_rule_set_dispatch(dsl, &scope);
+
+    // Only embed the country table in guests that need it
+    let uses_country_table = uses_iso_country_check(&dsl.validation_rules)
+        || dsl
+            .rule_sets
+            .iter()
+            .flat_map(|rule_sets| rule_sets.sets.values())
+            .any(|rules| uses_iso_country_check(rules));
+    let country_table = if uses_country_table {
+        super::iso_countries::generate_country_table()
+    } else {
+        TokenStream::new()
+    };
+
+    let rule_tags: Vec<String> = dsl.validation_rules.iter().map(|r| r.tag()).collect();
+    let rule_tags_fn = generate_rule_tags_fn(dsl);
+    let max_metadata_len = proc_macro2::Literal::usize_unsuffixed(MAX_METADATA_LEN);
+
+    // Reporting the failing rule is opt-in, since the index says something
+    // about the private inputs
+    let failed_rule_metadata = if dsl.report_failed_rule {
+        let prefix = khafi_common::FAILED_RULE_METADATA_PREFIX;
+        quote! {
+            /// Metadata recording the index of the first failing rule
+            fn failed_rule_metadata(rule_index: usize) -> Vec<u8> {
+                format!("{}{}", #prefix, rule_index).into_bytes()
+            }
+        }
+    } else {
+        quote! {
+            /// Metadata committed when validation fails (nothing is reported)
+            fn failed_rule_metadata(_rule_index: usize) -> Vec<u8> {
+                Vec::new()
+            }
+        }
+    };
+
+    let disclosure_metadata = generate_disclosure(dsl, &scope);
 
     let combined = quote! {
-        /// Perform all validation checks
+        /// Perform all validation checks, returning the index of the first
+        /// failing rule
         fn validate_all(
             private_inputs: &PrivateInputs,
             public_params: &PublicParams,
-        ) -> bool {
+        ) -> Result<(), usize> {
             #(#validation_checks)*
-            true
+            #rule_set_checks
+            Ok(())
         }
+
+        /// Tags of the top-level rules enforced by `validate_all`, in DSL order
+        const RULE_TAGS: &[&str] = &[#(#rule_tags),*];
+
+        #rule_tags_fn
+
+        /// Upper bound on the committed metadata size (bytes)
+        const MAX_METADATA_LEN: usize = #max_metadata_len;
+
+        /// Comma-separated list of passed rule tags, truncated at a tag boundary
+        fn passed_rules_metadata(public_params: &PublicParams) -> Vec<u8> {
+            let mut metadata = Vec::new();
+            for tag in rule_tags(public_params) {
+                let separator = if metadata.is_empty() { 0 } else { 1 };
+                if metadata.len() + separator + tag.len() > MAX_METADATA_LEN {
+                    break;
+                }
+                if separator == 1 {
+                    metadata.push(b',');
+                }
+                metadata.extend_from_slice(tag.as_bytes());
+            }
+            metadata
+        }
+
+        #failed_rule_metadata
+
+        #disclosure_metadata
+
+        #country_table
     };
 
     // Format the generated code
     format_rust_code(&combined.to_string())
 }
 
+/// Generate `disclosure_metadata`, committed instead of the rule tags when the
+/// DSL discloses facts
+///
+/// The metadata is a JSON object, `{"rules": [<tags>], "disclosed": {<name>: <value>}}`,
+/// with each fact's value computed from its expression. Integers that don't
+/// fit a JSON number (or overflow) are `null`.
+fn generate_disclosure(dsl: &BusinessRulesDSL, scope: &ExprScope) -> TokenStream {
+    if dsl.disclose.is_empty() {
+        return TokenStream::new();
+    }
+
+    let facts = dsl.disclose.iter().map(|(name, expr)| {
+        let value = match Expr::parse(expr).and_then(|parsed| {
+            let ty = parsed.check(scope)?;
+            Ok((generate_expression(&parsed, scope), ty))
+        }) {
+            Ok((value, ExprType::Int)) => quote! {
+                match #value {
+                    Some(v) => i64::try_from(v)
+                        .map(serde_json::Value::from)
+                        .or_else(|_| u64::try_from(v).map(serde_json::Value::from))
+                        .unwrap_or(serde_json::Value::Null),
+                    None => serde_json::Value::Null,
+                }
+            },
+            Ok((value, _)) => quote! { serde_json::Value::from(#value) },
+            Err(e) => {
+                let message = format!("invalid disclosed fact '{}': {}", name, e);
+                quote! { compile_error!(#message) }
+            }
+        };
+
+        quote! {
+            disclosed.insert(#name.to_string(), #value);
+        }
+    });
+
+    quote! {
+        /// Passed rule tags and the facts the DSL discloses, as a JSON object
+        fn disclosure_metadata(
+            private_inputs: &PrivateInputs,
+            public_params: &PublicParams,
+        ) -> Vec<u8> {
+            let mut disclosed = serde_json::Map::new();
+            #(#facts)*
+
+            let metadata = serde_json::json!({
+                "rules": rule_tags(public_params),
+                "disclosed": disclosed,
+            });
+            serde_json::to_vec(&metadata).expect("Failed to serialize disclosed facts")
+        }
+    }
+}
+
+/// Generate the `match` in `validate_all` that checks the selected rule set
+fn generate_rule_set_dispatch(dsl: &BusinessRulesDSL, scope: &ExprScope) -> TokenStream {
+    let Some(rule_sets) = &dsl.rule_sets else {
+        return TokenStream::new();
+    };
+
+    let selector = format_ident(&to_snake_case(&rule_sets.selector));
+    let no_match = proc_macro2::Literal::usize_unsuffixed(dsl.validation_rules.len());
+    let arms = rule_sets.sets.iter().map(|(value, rules)| {
+        let checks = rules.iter().enumerate().map(|(idx, rule)| {
+            generate_validation_rule(rule, dsl.validation_rules.len() + 1 + idx, scope)
+        });
+        quote! {
+            #value => {
+                #(#checks)*
+            }
+        }
+    });
+
+    quote! {
+        match public_params.#selector.as_str() {
+            #(#arms)*
+            _ => return Err(#no_match),
+        }
+    }
+}
+
+/// Generate `rule_tags`, the tags of every rule checked for a proof's params
+fn generate_rule_tags_fn(dsl: &BusinessRulesDSL) -> TokenStream {
+    let Some(rule_sets) = &dsl.rule_sets else {
+        return quote! {
+            /// Tags of the rules checked for a proof, in order
+            fn rule_tags(_public_params: &PublicParams) -> Vec<&'static str> {
+                RULE_TAGS.to_vec()
+            }
+        };
+    };
+
+    let selector = format_ident(&to_snake_case(&rule_sets.selector));
+    let arms = rule_sets.sets.iter().map(|(value, rules)| {
+        let tags = rules.iter().map(|rule| rule.tag());
+        quote! {
+            #value => tags.extend_from_slice(&[#(#tags),*]),
+        }
+    });
+
+    quote! {
+        /// Tags of the rules checked for a proof: `RULE_TAGS`, then the
+        /// selected rule set's, in order
+        fn rule_tags(public_params: &PublicParams) -> Vec<&'static str> {
+            let mut tags = RULE_TAGS.to_vec();
+            match public_params.#selector.as_str() {
+                #(#arms)*
+                _ => {}
+            }
+            tags
+        }
+    }
+}
+
+/// Whether any rule, including nested ones, is an `iso_country_check`
+fn uses_iso_country_check(rules: &[ValidationRule]) -> bool {
+    rules.iter().any(|rule| match rule {
+        ValidationRule::IsoCountryCheck { .. } => true,
+        ValidationRule::Not { rule, .. } => {
+            uses_iso_country_check(std::slice::from_ref(rule.as_ref()))
+        }
+        ValidationRule::AnyOf { rules, .. } => uses_iso_country_check(rules),
+        _ => false,
+    })
+}
+
 /// Generate code for a single validation rule
-fn generate_validation_rule(rule: &ValidationRule, _idx: usize) -> TokenStream {
-    let check = match rule {
+///
+/// Each rule becomes a guard inside `validate_all` that returns its index on failure.
+fn generate_validation_rule(rule: &ValidationRule, idx: usize, scope: &ExprScope) -> TokenStream {
+    let check = generate_rule_check(rule, scope);
+    let idx = proc_macro2::Literal::usize_unsuffixed(idx);
+
+    quote! {
+        if !(#check) {
+            return Err(#idx);
+        }
+    }
+}
+
+/// Generate a block expression that evaluates to `true` when the rule passes
+///
+/// Composite rules (e.g. `not`) nest the checks of their inner rules.
+/// `scope` types the inputs referenced by `expression` rules.
+fn generate_rule_check(rule: &ValidationRule, scope: &ExprScope) -> TokenStream {
+    match rule {
         ValidationRule::SignatureCheck {
             description,
             field,
@@ -51,7 +283,6 @@ fn generate_validation_rule(rule: &ValidationRule, _idx: usize) -> TokenStream {
                 .collect();
 
             quote! {
-                // Validation #idx: #desc
                 {
                     // TODO: Implement #algo signature verification
                     // For now, this is a placeholder that assumes signature is valid
@@ -70,16 +301,12 @@ fn generate_validation_rule(rule: &ValidationRule, _idx: usize) -> TokenStream {
                     let public_key = &public_params.#pubkey_ident;
 
                     // Placeholder - replace with actual verification
-                    let signature_valid = verify_signature_placeholder(
+                    verify_signature_placeholder(
                         &message,
                         signature,
                         public_key,
                         #algo
-                    );
-
-                    if !signature_valid {
-                        return false;
-                    }
+                    )
                 }
             }
         }
@@ -114,15 +341,12 @@ fn generate_validation_rule(rule: &ValidationRule, _idx: usize) -> TokenStream {
             };
 
             quote! {
-                // Validation #idx: #desc
                 {
                     #min_check
                     #max_check
 
                     let value = private_inputs.#field_ident;
-                    if value < min_value || value > max_value {
-                        return false;
-                    }
+                    !(value < min_value || value > max_value)
                 }
             }
         }
@@ -146,16 +370,13 @@ fn generate_validation_rule(rule: &ValidationRule, _idx: usize) -> TokenStream {
             };
 
             quote! {
-                // Validation #idx: #desc
                 {
                     #min_age_code
 
                     let dob = &private_inputs.#dob_ident;
                     let age = calculate_age(dob);
 
-                    if age < min_age {
-                        return false;
-                    }
+                    age >= min_age
                 }
             }
         }
@@ -170,14 +391,11 @@ fn generate_validation_rule(rule: &ValidationRule, _idx: usize) -> TokenStream {
             let blacklist_ident = format_ident(&to_snake_case(blacklist_param));
 
             quote! {
-                // Validation #idx: #desc
                 {
                     let value = &private_inputs.#field_ident;
                     let blacklist = &public_params.#blacklist_ident;
 
-                    if blacklist.contains(value) {
-                        return false;
-                    }
+                    !blacklist.contains(value)
                 }
             }
         }
@@ -193,7 +411,6 @@ fn generate_validation_rule(rule: &ValidationRule, _idx: usize) -> TokenStream {
             let prohibited_ident = format_ident(&to_snake_case(prohibited_param));
 
             quote! {
-                // Validation #idx: #desc
                 {
                     let items = &private_inputs.#field_ident;
                     let prohibited = &public_params.#prohibited_ident;
@@ -201,34 +418,258 @@ fn generate_validation_rule(rule: &ValidationRule, _idx: usize) -> TokenStream {
                     let has_intersection = items.iter()
                         .any(|item| prohibited.contains(item));
 
-                    if #must_be_empty && has_intersection {
-                        return false;
+                    !(#must_be_empty && has_intersection)
+                }
+            }
+        }
+
+        ValidationRule::ChecksumCheck {
+            description,
+            field,
+            algorithm,
+        } => {
+            let _desc = description;
+            let field_ident = format_ident(&to_snake_case(field));
+
+            let check = match algorithm.as_str() {
+                "mod97" => quote! { mod97_valid(&value) },
+                _ => quote! { luhn_valid(&value) },
+            };
+
+            quote! {
+                {
+                    // Works for both numeric and string fields
+                    let value = private_inputs.#field_ident.to_string();
+                    #check
+                }
+            }
+        }
+
+        ValidationRule::IsoCountryCheck {
+            description,
+            field,
+            set_param,
+            exclude,
+        } => {
+            let _desc = description;
+            let field_ident = format_ident(&to_snake_case(field));
+
+            let set_check = if let Some(param) = set_param {
+                let param_ident = format_ident(&to_snake_case(param));
+                let result = if *exclude {
+                    quote! { !in_set }
+                } else {
+                    quote! { in_set }
+                };
+                quote! {
+                    let in_set = public_params.#param_ident
+                        .iter()
+                        .any(|c| iso_country_alpha2(c) == Some(code));
+                    #result
+                }
+            } else {
+                quote! { true }
+            };
+
+            quote! {
+                {
+                    let value = &private_inputs.#field_ident;
+                    match iso_country_alpha2(value) {
+                        Some(code) => { #set_check }
+                        None => false,
+                    }
+                }
+            }
+        }
+
+        ValidationRule::DateRangeCheck {
+            description,
+            date_field,
+            reference_date_param,
+            after,
+            after_param,
+            before,
+            before_param,
+            within_days,
+            within_days_param,
+        } => {
+            let _desc = description;
+            let date_ident = format_ident(&to_snake_case(date_field));
+            let reference_ident = format_ident(&to_snake_case(reference_date_param));
+
+            // Each bound compares day numbers; unparseable bounds fail the check
+            let mut bounds = Vec::new();
+            if let Some(after) = after {
+                bounds.push(quote! {
+                    parse_iso_date_days(#after).is_some_and(|after| date >= after)
+                });
+            } else if let Some(param) = after_param {
+                let param_ident = format_ident(&to_snake_case(param));
+                bounds.push(quote! {
+                    parse_iso_date_days(&public_params.#param_ident).is_some_and(|after| date >= after)
+                });
+            }
+            if let Some(before) = before {
+                bounds.push(quote! {
+                    parse_iso_date_days(#before).is_some_and(|before| date <= before)
+                });
+            } else if let Some(param) = before_param {
+                let param_ident = format_ident(&to_snake_case(param));
+                bounds.push(quote! {
+                    parse_iso_date_days(&public_params.#param_ident).is_some_and(|before| date <= before)
+                });
+            }
+            let within = if let Some(days) = within_days {
+                let days = i64::from(*days);
+                Some(quote! { #days })
+            } else {
+                within_days_param.as_ref().map(|param| {
+                    let param_ident = format_ident(&to_snake_case(param));
+                    quote! { (public_params.#param_ident as i64) }
+                })
+            };
+            // The reference date is always required to parse, but only `within_days` uses it
+            let reference_pat = if let Some(within) = within {
+                bounds.push(quote! {
+                    date <= reference && reference - date <= #within
+                });
+                quote! { reference }
+            } else {
+                quote! { _ }
+            };
+
+            quote! {
+                {
+                    let date = parse_iso_date_days(&private_inputs.#date_ident);
+                    let reference = parse_iso_date_days(&public_params.#reference_ident);
+
+                    match (date, reference) {
+                        (Some(date), Some(#reference_pat)) => #(#bounds)&&*,
+                        _ => false,
                     }
                 }
             }
         }
 
+        ValidationRule::Expression { description, expr } => {
+            let _desc = description;
+            // The parser rejects invalid expressions; if one gets here anyway,
+            // fail the guest build rather than emit a check that always passes
+            match Expr::parse(expr).and_then(|parsed| {
+                parsed.check(scope)?;
+                Ok(generate_expression(&parsed, scope))
+            }) {
+                Ok(check) => check,
+                Err(e) => {
+                    let message = format!("invalid expression rule: {}", e);
+                    quote! { { compile_error!(#message) } }
+                }
+            }
+        }
+
         ValidationRule::Custom { description, code } => {
             let _desc = description;
-            // Parse the custom code as a TokenStream
-            let custom_code: TokenStream = code.parse().unwrap_or_else(|_| {
-                eprintln!("Warning: Failed to parse custom code, using placeholder");
-                quote! { true }
+            // Code outside the allowlist never reaches the guest
+            let custom_code = check_custom_code(code).unwrap_or_else(|e| {
+                let message = format!("disallowed custom code: {}", e);
+                quote! { compile_error!(#message) }
             });
 
             quote! {
-                // Validation #idx: #desc (custom)
+                { #custom_code }
+            }
+        }
+
+        ValidationRule::Not { description, rule } => {
+            let _desc = description;
+            let inner = generate_rule_check(rule, scope);
+
+            quote! {
                 {
-                    let result = #custom_code;
-                    if !result {
-                        return false;
-                    }
+                    let inner_passed = #inner;
+                    !inner_passed
                 }
             }
         }
-    };
 
-    check
+        ValidationRule::AnyOf { description, rules } => {
+            let _desc = description;
+            let checks = rules.iter().map(|rule| generate_rule_check(rule, scope));
+
+            // `||` short-circuits, so later alternatives only run if earlier ones fail
+            quote! {
+                (#(#checks)||*)
+            }
+        }
+    }
+}
+
+/// Generate Rust for a type-checked expression
+///
+/// Integer subexpressions evaluate to `Option<i128>`, with `None` for
+/// overflow or division by zero; any comparison involving `None` is false,
+/// so such inputs fail the rule instead of panicking in the guest. String
+/// subexpressions evaluate to `&str`, bool ones to `bool`.
+fn generate_expression(expr: &Expr, scope: &ExprScope) -> TokenStream {
+    match expr {
+        Expr::Bool(b) => quote! { #b },
+        Expr::Int(n) => {
+            let n = proc_macro2::Literal::i128_suffixed(i128::from(*n));
+            quote! { Some(#n) }
+        }
+        Expr::Str(s) => quote! { #s },
+        Expr::Field(name) | Expr::Param(name) => {
+            let ident = format_ident(&to_snake_case(name));
+            let value = if matches!(expr, Expr::Field(_)) {
+                quote! { private_inputs.#ident }
+            } else {
+                quote! { public_params.#ident }
+            };
+            match expr.check(scope) {
+                Ok(ExprType::Int) => quote! { Some(i128::from(#value)) },
+                Ok(ExprType::Str) => quote! { #value.as_str() },
+                _ => value,
+            }
+        }
+        Expr::Not(operand) => {
+            let operand = generate_expression(operand, scope);
+            quote! { !(#operand) }
+        }
+        Expr::Binary(op, lhs, rhs) => {
+            let is_int = matches!(lhs.check(scope), Ok(ExprType::Int));
+            let lhs = generate_expression(lhs, scope);
+            let rhs = generate_expression(rhs, scope);
+
+            let checked = match op {
+                BinaryOp::Add => Some(quote! { checked_add }),
+                BinaryOp::Sub => Some(quote! { checked_sub }),
+                BinaryOp::Mul => Some(quote! { checked_mul }),
+                BinaryOp::Div => Some(quote! { checked_div }),
+                BinaryOp::Rem => Some(quote! { checked_rem }),
+                _ => None,
+            };
+            if let Some(checked) = checked {
+                return quote! {
+                    match (#lhs, #rhs) {
+                        (Some(lhs), Some(rhs)) => lhs.#checked(rhs),
+                        _ => None,
+                    }
+                };
+            }
+
+            let op_tokens: TokenStream = op.symbol().parse().expect("operator tokens");
+            if is_int {
+                quote! {
+                    match (#lhs, #rhs) {
+                        (Some(lhs), Some(rhs)) => lhs #op_tokens rhs,
+                        _ => false,
+                    }
+                }
+            } else {
+                quote! { ((#lhs) #op_tokens (#rhs)) }
+            }
+        }
+    }
 }
 
 /// Generate helper functions needed for validation
@@ -263,6 +704,95 @@ pub fn generate_helper_functions() -> String {
             age
         }
 
+        /// Luhn (mod 10) check digit validation, e.g. for card numbers
+        fn luhn_valid(value: &str) -> bool {
+            let digits: Vec<u32> = value
+                .chars()
+                .filter(|c| !c.is_whitespace() && *c != '-')
+                .map(|c| c.to_digit(10))
+                .collect::<Option<Vec<u32>>>()
+                .unwrap_or_default();
+            if digits.len() < 2 {
+                return false;
+            }
+
+            let sum: u32 = digits
+                .iter()
+                .rev()
+                .enumerate()
+                .map(|(i, &d)| {
+                    if i % 2 == 1 {
+                        let doubled = d * 2;
+                        if doubled > 9 { doubled - 9 } else { doubled }
+                    } else {
+                        d
+                    }
+                })
+                .sum();
+
+            sum % 10 == 0
+        }
+
+        /// ISO 7064 MOD 97-10 validation (IBAN-style, letters count as 10-35)
+        ///
+        /// For IBANs the country code and check digits must already be moved
+        /// to the end; plain numeric identifiers are checked as-is.
+        fn mod97_valid(value: &str) -> bool {
+            let mut remainder: u32 = 0;
+            let mut seen = false;
+            for c in value.chars().filter(|c| !c.is_whitespace()) {
+                let n = match c.to_digit(36) {
+                    Some(n) => n,
+                    None => return false,
+                };
+                remainder = if n < 10 {
+                    (remainder * 10 + n) % 97
+                } else {
+                    (remainder * 100 + n) % 97
+                };
+                seen = true;
+            }
+
+            seen && remainder == 1
+        }
+
+        /// Parse an ISO 8601 date (YYYY-MM-DD, optional time suffix) into days since 1970-01-01
+        ///
+        /// Uses integer arithmetic only, so comparisons are exact inside the zkVM.
+        fn parse_iso_date_days(date: &str) -> Option<i64> {
+            let date = date.get(..10)?;
+            let parts: Vec<&str> = date.split('-').collect();
+            if parts.len() != 3 {
+                return None;
+            }
+
+            let year: i64 = parts[0].parse().ok()?;
+            let month: i64 = parts[1].parse().ok()?;
+            let day: i64 = parts[2].parse().ok()?;
+
+            let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+            let days_in_month = match month {
+                1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+                4 | 6 | 9 | 11 => 30,
+                2 if leap => 29,
+                2 => 28,
+                _ => return None,
+            };
+            if day < 1 || day > days_in_month {
+                return None;
+            }
+
+            // Days from civil date (proleptic Gregorian calendar)
+            let y = if month <= 2 { year - 1 } else { year };
+            let era = if y >= 0 { y } else { y - 399 } / 400;
+            let yoe = y - era * 400;
+            let mp = (month + 9) % 12;
+            let doy = (153 * mp + 2) / 5 + day - 1;
+            let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+            Some(era * 146097 + doe - 719468)
+        }
+
         /// Placeholder for signature verification
         /// TODO: Replace with actual cryptographic verification
         fn verify_signature_placeholder(
@@ -294,11 +824,6 @@ fn format_rust_code(code: &str) -> Result<String> {
     Ok(prettyplease::unparse(&parsed))
 }
 
-/// Convert string to snake_case
-fn to_snake_case(s: &str) -> String {
-    s.to_lowercase().replace('-', "_").replace(' ', "_")
-}
-
 /// Helper to create ident from string
 fn format_ident(s: &str) -> proc_macro2::Ident {
     syn::parse_str(s)
@@ -320,7 +845,7 @@ mod tests {
             max_param: None,
         };
 
-        let code = generate_validation_rule(&rule, 0);
+        let code = generate_validation_rule(&rule, 0, &ExprScope::default());
         let code_str = code.to_string();
 
         assert!(code_str.contains("quantity"));
@@ -337,7 +862,7 @@ mod tests {
             min_age_param: None,
         };
 
-        let code = generate_validation_rule(&rule, 0);
+        let code = generate_validation_rule(&rule, 0, &ExprScope::default());
         let code_str = code.to_string();
 
         assert!(code_str.contains("date_of_birth"));
@@ -350,5 +875,177 @@ mod tests {
         let helpers = generate_helper_functions();
         assert!(helpers.contains("calculate_age"));
         assert!(helpers.contains("verify_signature_placeholder"));
+        assert!(helpers.contains("fn luhn_valid"));
+        assert!(helpers.contains("fn mod97_valid"));
+        assert!(helpers.contains("fn parse_iso_date_days"));
+    }
+
+    #[test]
+    fn test_generate_luhn_checksum() {
+        let rule = ValidationRule::ChecksumCheck {
+            description: "Card number check digit".to_string(),
+            field: "card_number".to_string(),
+            algorithm: "luhn".to_string(),
+        };
+
+        let code = generate_validation_rule(&rule, 0, &ExprScope::default());
+        let code_str = code.to_string();
+
+        assert!(code_str.contains("card_number"));
+        assert!(code_str.contains("luhn_valid"));
+        assert!(!code_str.contains("mod97_valid"));
+    }
+
+    #[test]
+    fn test_generate_expression() {
+        let dsl: BusinessRulesDSL = serde_json::from_value(serde_json::json!({
+            "use_case": "expr",
+            "private_inputs": {
+                "type": "object",
+                "fields": { "quantity": "u32", "country": "string", "suspended": "bool" }
+            },
+            "public_params": { "max_quantity": "u64" },
+            "validation_rules": [{
+                "type": "expression",
+                "expr": "input.quantity * 2 <= param.max_quantity && (input.country == \"GB\" || !input.suspended)"
+            }]
+        }))
+        .unwrap();
+
+        let code = generate_validations(&dsl).unwrap();
+        assert!(code.contains("Some(i128::from(private_inputs.quantity))"));
+        assert!(code.contains("lhs.checked_mul(rhs)"));
+        assert!(code.contains("Some(i128::from(public_params.max_quantity))"));
+        assert!(code.contains("private_inputs.country.as_str()"));
+        assert!(code.contains("!(private_inputs.suspended)"));
+        assert!(!code.contains("compile_error"));
+    }
+
+    #[test]
+    fn test_invalid_expression_fails_guest_build() {
+        let rule = ValidationRule::Expression {
+            description: String::new(),
+            expr: "std::process::exit(0)".to_string(),
+        };
+
+        let code = generate_validation_rule(&rule, 0, &ExprScope::default()).to_string();
+        assert!(code.contains("compile_error"));
+        assert!(!code.contains("exit"));
+    }
+
+    #[test]
+    fn test_disallowed_custom_code_fails_guest_build() {
+        let rule = ValidationRule::Custom {
+            description: String::new(),
+            code: "std::process::Command::new(\"sh\").status().is_ok()".to_string(),
+        };
+
+        let code = generate_validation_rule(&rule, 0, &ExprScope::default()).to_string();
+        assert!(code.contains("compile_error"));
+        assert!(!code.contains("Command :: new"));
+    }
+
+    /// One rule of every type, each reading a field no other rule reads
+    fn one_of_every_rule() -> Vec<(ValidationRule, &'static str)> {
+        let rules = serde_json::json!([
+            { "type": "signature_check", "field": "alpha", "algorithm": "ed25519",
+              "public_key_param": "pubkey", "message_fields": [] },
+            { "type": "range_check", "field": "bravo", "min": 1, "max": 10 },
+            { "type": "age_verification", "dob_field": "charlie", "min_age": 18 },
+            { "type": "blacklist_check", "field": "delta", "blacklist_param": "banned" },
+            { "type": "array_intersection_check", "field": "echo",
+              "prohibited_param": "prohibited", "must_be_empty": true },
+            { "type": "checksum_check", "field": "foxtrot", "algorithm": "luhn" },
+            { "type": "iso_country_check", "field": "golf" },
+            { "type": "date_range_check", "date_field": "hotel",
+              "reference_date_param": "today", "within_days": 30 },
+            { "type": "expression", "expr": "input.india > 0" },
+            { "type": "custom", "code": "private_inputs.juliet > 0" },
+            { "type": "not", "rule": { "type": "range_check", "field": "kilo", "max": 5 } },
+            { "type": "any_of", "rules": [
+                { "type": "range_check", "field": "lima", "min": 1 },
+                { "type": "range_check", "field": "mike", "min": 1 }
+            ] }
+        ]);
+        let rules: Vec<ValidationRule> = serde_json::from_value(rules).unwrap();
+        let markers = [
+            "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel", "india",
+            "juliet", "kilo", "lima",
+        ];
+
+        // No wildcard arm: a new rule type must be added above before this compiles
+        for rule in &rules {
+            match rule {
+                ValidationRule::SignatureCheck { .. }
+                | ValidationRule::RangeCheck { .. }
+                | ValidationRule::AgeVerification { .. }
+                | ValidationRule::BlacklistCheck { .. }
+                | ValidationRule::ArrayIntersectionCheck { .. }
+                | ValidationRule::ChecksumCheck { .. }
+                | ValidationRule::IsoCountryCheck { .. }
+                | ValidationRule::DateRangeCheck { .. }
+                | ValidationRule::Expression { .. }
+                | ValidationRule::Custom { .. }
+                | ValidationRule::Not { .. }
+                | ValidationRule::AnyOf { .. } => {}
+            }
+        }
+
+        rules.into_iter().zip(markers).collect()
+    }
+
+    /// Positions of each marker within the generated `validate_all`
+    fn marker_positions(rules: &[(ValidationRule, &str)]) -> Vec<usize> {
+        // Expressions are type checked, so declare every marker field
+        let fields: serde_json::Map<String, serde_json::Value> = rules
+            .iter()
+            .map(|(_, marker)| (marker.to_string(), "u64".into()))
+            .collect();
+        let dsl: BusinessRulesDSL = serde_json::from_value(serde_json::json!({
+            "use_case": "ordering",
+            "private_inputs": { "type": "object", "fields": fields },
+            "public_params": {},
+            "validation_rules": rules.iter().map(|(rule, _)| rule).collect::<Vec<_>>(),
+        }))
+        .unwrap();
+
+        let code = generate_validations(&dsl).unwrap();
+        let start = code.find("fn validate_all").unwrap();
+        let end = code.find("const RULE_TAGS").unwrap();
+        let validate_all = &code[start..end];
+
+        rules
+            .iter()
+            .map(|(rule, marker)| {
+                validate_all
+                    .find(&format!("private_inputs.{}", marker))
+                    .unwrap_or_else(|| panic!("{} check missing", rule.rule_type()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_every_rule_type_emitted_in_dsl_order() {
+        let mut rules = one_of_every_rule();
+        for (idx, (rule, _)) in rules.iter_mut().enumerate() {
+            rule.set_description(format!("rule {}", idx));
+        }
+
+        // Every rotation puts a different rule type first and last
+        for shift in 0..rules.len() {
+            let mut rotated = rules.clone();
+            rotated.rotate_left(shift);
+
+            let positions = marker_positions(&rotated);
+            for (pair, window) in rotated.windows(2).zip(positions.windows(2)) {
+                assert!(
+                    window[0] < window[1],
+                    "{} emitted after {} (rotation {})",
+                    pair[0].0.rule_type(),
+                    pair[1].0.rule_type(),
+                    shift
+                );
+            }
+        }
     }
 }
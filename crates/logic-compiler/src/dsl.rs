@@ -3,8 +3,14 @@
 //! This module defines the structure of the JSON DSL that customers use
 //! to define their business logic validation rules.
 
+use crate::codegen::type_gen::to_snake_case;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+
+mod diff;
+pub mod expr;
+
+pub use diff::{diff, DslDiff, FieldChange};
 
 /// Top-level DSL specification for a customer's business logic
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +18,10 @@ pub struct BusinessRulesDSL {
     /// Use case identifier (e.g., "prescription_validation", "manifest_compliance")
     pub use_case: String,
 
+    /// Base name for the generated guest and methods crates (defaults to `use_case`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guest_name: Option<String>,
+
     /// Human-readable description of the use case
     #[serde(default)]
     pub description: String,
@@ -26,26 +36,93 @@ pub struct BusinessRulesDSL {
     /// Public parameter schema (validation parameters visible to verifier)
     pub public_params: ParamSchema,
 
+    /// Default values for public parameters a proof request may omit
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub param_defaults: IndexMap<String, serde_json::Value>,
+
     /// Validation rules to enforce
     pub validation_rules: Vec<ValidationRule>,
 
+    /// Additional rules chosen per proof by a public param (e.g. one set per product line)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule_sets: Option<RuleSets>,
+
     /// Output schema (what the proof reveals)
     #[serde(default)]
     pub outputs: OutputSchema,
+
+    /// Derived facts to reveal in the proof's metadata, by name
+    ///
+    /// Each value is an expression (see [`expr`]) over the inputs, e.g.
+    /// `"ships_to_eu": "input.region == \"EU\""`. Only what's listed is
+    /// disclosed, and only when validation passes.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub disclose: IndexMap<String, String>,
+
+    /// Commit the index of the first failing rule when validation fails
+    ///
+    /// Off by default: the index reveals which check the private inputs
+    /// failed, so only opt in when that's acceptable for the use case.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub report_failed_rule: bool,
 }
 
 fn default_version() -> String {
     "1.0".to_string()
 }
 
+impl BusinessRulesDSL {
+    /// Base name for generated crates, safe to use as a Cargo package name
+    ///
+    /// Taken from `guest_name`, falling back to `use_case`. Lowercased, with
+    /// each run of characters other than ASCII letters and digits replaced by
+    /// `_`; prefixed with `guest_` if it would start with a digit.
+    pub fn crate_name(&self) -> String {
+        let source = self.guest_name.as_deref().unwrap_or(&self.use_case);
+
+        let mut name = String::new();
+        for c in source.chars() {
+            if c.is_ascii_alphanumeric() {
+                name.push(c.to_ascii_lowercase());
+            } else if !name.is_empty() && !name.ends_with('_') {
+                name.push('_');
+            }
+        }
+        let name = name.trim_end_matches('_');
+
+        match name.chars().next() {
+            None => "guest".to_string(),
+            Some(c) if c.is_ascii_digit() => format!("guest_{}", name),
+            Some(_) => name.to_string(),
+        }
+    }
+
+    /// Fill in public parameters omitted from a request with their DSL defaults
+    ///
+    /// Parameters the request supplies are left untouched. Does nothing if
+    /// `public_params` is not a JSON object.
+    pub fn apply_param_defaults(&self, public_params: &mut serde_json::Value) {
+        let Some(params) = public_params.as_object_mut() else {
+            return;
+        };
+
+        for (name, value) in &self.param_defaults {
+            // Requests use the field names of the generated `PublicParams` struct
+            params
+                .entry(to_snake_case(name))
+                .or_insert_with(|| value.clone());
+        }
+    }
+}
+
 /// Schema for private inputs
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum InputSchema {
     /// Single object with fields
     Object(ObjectSchema),
-    /// Map of named inputs
-    Map(HashMap<String, ObjectSchema>),
+    /// Map of named inputs, in declaration order
+    Map(IndexMap<String, ObjectSchema>),
 }
 
 /// Schema for a structured object
@@ -55,20 +132,30 @@ pub struct ObjectSchema {
     #[serde(rename = "type")]
     pub type_name: String,
 
-    /// Field definitions
-    pub fields: HashMap<String, String>,
+    /// Field definitions, in declaration order
+    pub fields: IndexMap<String, String>,
 }
 
 /// Schema for public parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ParamSchema {
-    /// Map of parameter names to types
-    Map(HashMap<String, String>),
+    /// Map of parameter names to types, in declaration order
+    Map(IndexMap<String, String>),
     /// Object with explicit schema
     Object(ObjectSchema),
 }
 
+impl ParamSchema {
+    /// Declared parameter names and their types
+    pub fn fields(&self) -> &IndexMap<String, String> {
+        match self {
+            ParamSchema::Map(fields) => fields,
+            ParamSchema::Object(obj) => &obj.fields,
+        }
+    }
+}
+
 /// Schema for outputs
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct OutputSchema {
@@ -76,15 +163,28 @@ pub struct OutputSchema {
     #[serde(default = "default_bool_type")]
     pub compliance_result: String,
 
-    /// Additional output fields (optional)
+    /// Additional output fields (optional), in declaration order
     #[serde(flatten)]
-    pub additional: HashMap<String, String>,
+    pub additional: IndexMap<String, String>,
 }
 
 fn default_bool_type() -> String {
     "bool".to_string()
 }
 
+/// Rule sets keyed by the value of a selector public param
+///
+/// `validation_rules` always apply; the set named by the selector's value is
+/// checked after them. A proof whose selector matches no set fails.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleSets {
+    /// Public param (of type `string`) whose value picks the rule set
+    pub selector: String,
+
+    /// Rules for each selector value, in declaration order
+    pub sets: IndexMap<String, Vec<ValidationRule>>,
+}
+
 /// A validation rule in the DSL
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -182,7 +282,88 @@ pub enum ValidationRule {
         must_be_empty: bool,
     },
 
+    /// Check a numeric identifier's check digits (card numbers, IBANs, ...)
+    ChecksumCheck {
+        /// Human-readable description
+        #[serde(default)]
+        description: String,
+
+        /// Field containing the identifier
+        field: String,
+
+        /// Checksum algorithm ("luhn" or "mod97")
+        algorithm: String,
+    },
+
+    /// Check that a value is an ISO 3166-1 alpha-2 or alpha-3 country code
+    IsoCountryCheck {
+        /// Human-readable description
+        #[serde(default)]
+        description: String,
+
+        /// Field containing the country code
+        field: String,
+
+        /// Parameter name containing a set of country codes to check against (optional)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        set_param: Option<String>,
+
+        /// If true, the code must NOT be in `set_param`; otherwise it must be
+        #[serde(default)]
+        exclude: bool,
+    },
+
+    /// Check that a date falls within bounds, relative to a reference date
+    DateRangeCheck {
+        /// Human-readable description
+        #[serde(default)]
+        description: String,
+
+        /// Field containing the date (ISO 8601 format)
+        date_field: String,
+
+        /// Parameter name for the reference ("current") date
+        reference_date_param: String,
+
+        /// Earliest allowed date, inclusive (optional)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        after: Option<String>,
+
+        /// Parameter name for the earliest allowed date
+        #[serde(skip_serializing_if = "Option::is_none")]
+        after_param: Option<String>,
+
+        /// Latest allowed date, inclusive (optional)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        before: Option<String>,
+
+        /// Parameter name for the latest allowed date
+        #[serde(skip_serializing_if = "Option::is_none")]
+        before_param: Option<String>,
+
+        /// Date must be at most this many days before the reference date (optional)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        within_days: Option<u32>,
+
+        /// Parameter name for the within-days limit
+        #[serde(skip_serializing_if = "Option::is_none")]
+        within_days_param: Option<String>,
+    },
+
+    /// Boolean expression over inputs and params (see [`expr`])
+    Expression {
+        /// Human-readable description
+        #[serde(default)]
+        description: String,
+
+        /// Expression source, e.g. `input.quantity <= param.max_quantity`
+        expr: String,
+    },
+
     /// Custom validation code (advanced)
+    ///
+    /// Deprecated: the code is embedded verbatim into the guest, so only
+    /// trusted DSL authors should use it. Prefer `expression`.
     Custom {
         /// Human-readable description
         #[serde(default)]
@@ -191,6 +372,26 @@ pub enum ValidationRule {
         /// Custom Rust code snippet
         code: String,
     },
+
+    /// Invert a rule: passes only when the nested rule fails
+    Not {
+        /// Human-readable description
+        #[serde(default)]
+        description: String,
+
+        /// Rule that must NOT be satisfied
+        rule: Box<ValidationRule>,
+    },
+
+    /// Group of rules where passing any one satisfies the group
+    AnyOf {
+        /// Human-readable description
+        #[serde(default)]
+        description: String,
+
+        /// Alternatives, checked in order until one passes
+        rules: Vec<ValidationRule>,
+    },
 }
 
 impl ValidationRule {
@@ -202,10 +403,39 @@ impl ValidationRule {
             ValidationRule::AgeVerification { description, .. } => description,
             ValidationRule::BlacklistCheck { description, .. } => description,
             ValidationRule::ArrayIntersectionCheck { description, .. } => description,
+            ValidationRule::ChecksumCheck { description, .. } => description,
+            ValidationRule::IsoCountryCheck { description, .. } => description,
+            ValidationRule::DateRangeCheck { description, .. } => description,
+            ValidationRule::Expression { description, .. } => description,
             ValidationRule::Custom { description, .. } => description,
+            ValidationRule::Not { description, .. } => description,
+            ValidationRule::AnyOf { description, .. } => description,
         }
     }
 
+    /// Get mutable access to the human-readable description of this rule
+    pub fn description_mut(&mut self) -> &mut String {
+        match self {
+            ValidationRule::SignatureCheck { description, .. }
+            | ValidationRule::RangeCheck { description, .. }
+            | ValidationRule::AgeVerification { description, .. }
+            | ValidationRule::BlacklistCheck { description, .. }
+            | ValidationRule::ArrayIntersectionCheck { description, .. }
+            | ValidationRule::ChecksumCheck { description, .. }
+            | ValidationRule::IsoCountryCheck { description, .. }
+            | ValidationRule::DateRangeCheck { description, .. }
+            | ValidationRule::Expression { description, .. }
+            | ValidationRule::Custom { description, .. }
+            | ValidationRule::Not { description, .. }
+            | ValidationRule::AnyOf { description, .. } => description,
+        }
+    }
+
+    /// Set the human-readable description of this rule, whatever its type
+    pub fn set_description(&mut self, description: impl Into<String>) {
+        *self.description_mut() = description.into();
+    }
+
     /// Get a short name for this rule type
     pub fn rule_type(&self) -> &str {
         match self {
@@ -214,7 +444,132 @@ impl ValidationRule {
             ValidationRule::AgeVerification { .. } => "age_verification",
             ValidationRule::BlacklistCheck { .. } => "blacklist_check",
             ValidationRule::ArrayIntersectionCheck { .. } => "array_intersection_check",
+            ValidationRule::ChecksumCheck { .. } => "checksum_check",
+            ValidationRule::IsoCountryCheck { .. } => "iso_country_check",
+            ValidationRule::DateRangeCheck { .. } => "date_range_check",
+            ValidationRule::Expression { .. } => "expression",
             ValidationRule::Custom { .. } => "custom",
+            ValidationRule::Not { .. } => "not",
+            ValidationRule::AnyOf { .. } => "any_of",
+        }
+    }
+
+    /// Private input fields this rule reads, including those of nested rules
+    ///
+    /// `Custom` rules are opaque code, so they report none. `Expression` rules
+    /// that fail to parse report none either.
+    pub fn referenced_fields(&self) -> Vec<&str> {
+        match self {
+            ValidationRule::SignatureCheck {
+                field,
+                message_fields,
+                ..
+            } => std::iter::once(field)
+                .chain(message_fields)
+                .map(String::as_str)
+                .collect(),
+            ValidationRule::RangeCheck { field, .. }
+            | ValidationRule::BlacklistCheck { field, .. }
+            | ValidationRule::ArrayIntersectionCheck { field, .. }
+            | ValidationRule::ChecksumCheck { field, .. }
+            | ValidationRule::IsoCountryCheck { field, .. } => vec![field],
+            ValidationRule::AgeVerification { dob_field, .. } => vec![dob_field],
+            ValidationRule::DateRangeCheck { date_field, .. } => vec![date_field],
+            ValidationRule::Expression { expr: source, .. } => expr::Expr::parse(source)
+                .map(|expr| expr.fields())
+                .unwrap_or_default(),
+            ValidationRule::Custom { .. } => vec![],
+            ValidationRule::Not { rule, .. } => rule.referenced_fields(),
+            ValidationRule::AnyOf { rules, .. } => {
+                rules.iter().flat_map(|r| r.referenced_fields()).collect()
+            }
+        }
+    }
+
+    /// Public params this rule reads, including those of nested rules
+    ///
+    /// `Custom` rules are opaque code, so they report none. `Expression` rules
+    /// that fail to parse report none either.
+    pub fn referenced_params(&self) -> Vec<&str> {
+        match self {
+            ValidationRule::SignatureCheck {
+                public_key_param, ..
+            } => vec![public_key_param],
+            ValidationRule::BlacklistCheck {
+                blacklist_param, ..
+            } => vec![blacklist_param],
+            ValidationRule::ArrayIntersectionCheck {
+                prohibited_param, ..
+            } => vec![prohibited_param],
+            ValidationRule::RangeCheck {
+                min_param,
+                max_param,
+                ..
+            } => [min_param, max_param]
+                .into_iter()
+                .flatten()
+                .map(String::as_str)
+                .collect(),
+            ValidationRule::AgeVerification { min_age_param, .. } => {
+                min_age_param.as_deref().into_iter().collect()
+            }
+            ValidationRule::IsoCountryCheck { set_param, .. } => {
+                set_param.as_deref().into_iter().collect()
+            }
+            ValidationRule::DateRangeCheck {
+                reference_date_param,
+                after_param,
+                before_param,
+                within_days_param,
+                ..
+            } => std::iter::once(reference_date_param.as_str())
+                .chain(
+                    [after_param, before_param, within_days_param]
+                        .into_iter()
+                        .flatten()
+                        .map(String::as_str),
+                )
+                .collect(),
+            ValidationRule::Expression { expr: source, .. } => expr::Expr::parse(source)
+                .map(|expr| expr.params())
+                .unwrap_or_default(),
+            ValidationRule::ChecksumCheck { .. } | ValidationRule::Custom { .. } => vec![],
+            ValidationRule::Not { rule, .. } => rule.referenced_params(),
+            ValidationRule::AnyOf { rules, .. } => {
+                rules.iter().flat_map(|r| r.referenced_params()).collect()
+            }
+        }
+    }
+
+    /// Get a compact, non-sensitive tag identifying this rule
+    ///
+    /// Combines the rule type with the field it checks (e.g. `range_check:quantity`).
+    /// Tags only reference schema names, never input values.
+    pub fn tag(&self) -> String {
+        match self {
+            ValidationRule::SignatureCheck { field, .. }
+            | ValidationRule::RangeCheck { field, .. }
+            | ValidationRule::BlacklistCheck { field, .. }
+            | ValidationRule::ArrayIntersectionCheck { field, .. }
+            | ValidationRule::ChecksumCheck { field, .. }
+            | ValidationRule::IsoCountryCheck { field, .. } => {
+                format!("{}:{}", self.rule_type(), field)
+            }
+            ValidationRule::AgeVerification { dob_field, .. } => {
+                format!("{}:{}", self.rule_type(), dob_field)
+            }
+            ValidationRule::DateRangeCheck { date_field, .. } => {
+                format!("{}:{}", self.rule_type(), date_field)
+            }
+            ValidationRule::Expression { .. } | ValidationRule::Custom { .. } => {
+                self.rule_type().to_string()
+            }
+            ValidationRule::Not { rule, .. } => format!("{}:{}", self.rule_type(), rule.tag()),
+            // `|` rather than `,` so the group stays one entry in the metadata list
+            ValidationRule::AnyOf { rules, .. } => {
+                let tags: Vec<String> = rules.iter().map(ValidationRule::tag).collect();
+                format!("{}:{}", self.rule_type(), tags.join("|"))
+            }
         }
     }
 }
@@ -235,6 +590,131 @@ mod tests {
         assert_eq!(rule.rule_type(), "age_verification");
     }
 
+    #[test]
+    fn test_crate_name_sanitized() {
+        let mut dsl: BusinessRulesDSL = serde_json::from_value(serde_json::json!({
+            "use_case": "Age Verification!",
+            "private_inputs": { "type": "object", "fields": {} },
+            "public_params": {},
+            "validation_rules": []
+        }))
+        .unwrap();
+        assert_eq!(dsl.crate_name(), "age_verification");
+
+        dsl.use_case = "2FA -- check".to_string();
+        assert_eq!(dsl.crate_name(), "guest_2fa_check");
+
+        dsl.use_case = "!!!".to_string();
+        assert_eq!(dsl.crate_name(), "guest");
+
+        // An explicit guest name wins over the use case
+        dsl.guest_name = Some("KYC-Guest".to_string());
+        assert_eq!(dsl.crate_name(), "kyc_guest");
+    }
+
+    #[test]
+    fn test_apply_param_defaults() {
+        let dsl: BusinessRulesDSL = serde_json::from_value(serde_json::json!({
+            "use_case": "age_check",
+            "private_inputs": { "type": "object", "fields": { "age": "u32" } },
+            "public_params": { "min_age": "u32", "max_age": "u32" },
+            "param_defaults": { "min_age": 18, "max_age": 150 },
+            "validation_rules": []
+        }))
+        .unwrap();
+
+        let mut params = serde_json::json!({ "max_age": 99 });
+        dsl.apply_param_defaults(&mut params);
+
+        assert_eq!(params, serde_json::json!({ "min_age": 18, "max_age": 99 }));
+    }
+
+    #[test]
+    fn test_set_description() {
+        let mut rule = ValidationRule::Not {
+            description: String::new(),
+            rule: Box::new(ValidationRule::Custom {
+                description: "inner".to_string(),
+                code: "true".to_string(),
+            }),
+        };
+        rule.set_description("Must not pass");
+
+        assert_eq!(rule.description(), "Must not pass");
+        match &rule {
+            ValidationRule::Not { rule, .. } => assert_eq!(rule.description(), "inner"),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_validation_rule_tag() {
+        let rule = ValidationRule::RangeCheck {
+            description: String::new(),
+            field: "quantity".to_string(),
+            min: Some(1),
+            max: None,
+            max_param: Some("max_quantity".to_string()),
+            min_param: None,
+        };
+        assert_eq!(rule.tag(), "range_check:quantity");
+
+        let rule = ValidationRule::Custom {
+            description: String::new(),
+            code: "true".to_string(),
+        };
+        assert_eq!(rule.tag(), "custom");
+
+        let rule = ValidationRule::AnyOf {
+            description: String::new(),
+            rules: vec![
+                ValidationRule::Custom {
+                    description: String::new(),
+                    code: "true".to_string(),
+                },
+                ValidationRule::AgeVerification {
+                    description: String::new(),
+                    dob_field: "dob".to_string(),
+                    min_age: Some(18),
+                    min_age_param: None,
+                },
+            ],
+        };
+        assert_eq!(rule.tag(), "any_of:custom|age_verification:dob");
+    }
+
+    #[test]
+    fn test_referenced_fields_and_params() {
+        let rule: ValidationRule = serde_json::from_value(serde_json::json!({
+            "type": "any_of",
+            "rules": [
+                {
+                    "type": "signature_check",
+                    "field": "sig",
+                    "algorithm": "ed25519",
+                    "public_key_param": "pubkey",
+                    "message_fields": ["drug_name", "quantity"]
+                },
+                {
+                    "type": "not",
+                    "rule": { "type": "range_check", "field": "quantity", "max_param": "max_quantity" }
+                },
+                { "type": "custom", "code": "private_inputs.age > 0" },
+                { "type": "expression", "expr": "input.age >= param.min_age" }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            rule.referenced_fields(),
+            vec!["sig", "drug_name", "quantity", "quantity", "age"]
+        );
+        assert_eq!(
+            rule.referenced_params(),
+            vec!["pubkey", "max_quantity", "min_age"]
+        );
+    }
+
     #[test]
     fn test_default_version() {
         assert_eq!(default_version(), "1.0");
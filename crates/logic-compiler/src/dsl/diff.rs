@@ -0,0 +1,258 @@
+//! Compatibility diff between two versions of a DSL
+//!
+//! Compares the inputs a generated SDK expects (see the input manifest) and
+//! the validation rules, flagging changes that break existing clients.
+
+use crate::codegen::manifest::{input_manifest, InputField, Visibility};
+use crate::dsl::BusinessRulesDSL;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// An input added, removed or retyped between two DSL versions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+    /// Location in the request JSON (e.g. `public_params.min_age`)
+    pub path: String,
+
+    /// Private input or public parameter
+    pub visibility: Visibility,
+
+    /// DSL type in the old version (`None` if added)
+    pub old_type: Option<String>,
+
+    /// DSL type in the new version (`None` if removed)
+    pub new_type: Option<String>,
+
+    /// Whether a proof request must supply it, in the newer version that has it
+    pub required: bool,
+
+    /// Whether existing clients break
+    pub breaking: bool,
+}
+
+/// Differences between two DSL versions
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DslDiff {
+    /// Inputs only in the new version
+    pub added_fields: Vec<FieldChange>,
+
+    /// Inputs only in the old version
+    pub removed_fields: Vec<FieldChange>,
+
+    /// Inputs whose type or requiredness changed
+    pub retyped_fields: Vec<FieldChange>,
+
+    /// Tags of rules only in the new version
+    pub added_rules: Vec<String>,
+
+    /// Tags of rules only in the old version
+    pub removed_rules: Vec<String>,
+
+    /// Whether any change breaks existing clients
+    pub breaking: bool,
+}
+
+/// Compare two DSL versions
+///
+/// Inputs are matched by request path. A change is breaking if a client
+/// written against `old` could send a request `new` rejects: a required input
+/// was added or removed, an input changed type, or an optional input became
+/// required. Rule changes affect which inputs pass, not their shape, so they
+/// are reported but never breaking. Rules are matched by [`ValidationRule::tag`].
+///
+/// [`ValidationRule::tag`]: crate::dsl::ValidationRule::tag
+pub fn diff(old: &BusinessRulesDSL, new: &BusinessRulesDSL) -> DslDiff {
+    let old_inputs = input_manifest(old);
+    let new_inputs = input_manifest(new);
+    let old_by_path: HashMap<&str, &InputField> =
+        old_inputs.iter().map(|f| (f.path.as_str(), f)).collect();
+    let new_by_path: HashMap<&str, &InputField> =
+        new_inputs.iter().map(|f| (f.path.as_str(), f)).collect();
+
+    let mut result = DslDiff::default();
+
+    for field in &new_inputs {
+        match old_by_path.get(field.path.as_str()) {
+            None => result.added_fields.push(FieldChange {
+                path: field.path.clone(),
+                visibility: field.visibility,
+                old_type: None,
+                new_type: Some(field.dsl_type.clone()),
+                required: field.required,
+                breaking: field.required,
+            }),
+            Some(old_field) => {
+                let retyped = old_field.dsl_type != field.dsl_type;
+                let now_required = field.required && !old_field.required;
+                if retyped || old_field.required != field.required {
+                    result.retyped_fields.push(FieldChange {
+                        path: field.path.clone(),
+                        visibility: field.visibility,
+                        old_type: Some(old_field.dsl_type.clone()),
+                        new_type: Some(field.dsl_type.clone()),
+                        required: field.required,
+                        breaking: retyped || now_required,
+                    });
+                }
+            }
+        }
+    }
+
+    for field in &old_inputs {
+        if !new_by_path.contains_key(field.path.as_str()) {
+            result.removed_fields.push(FieldChange {
+                path: field.path.clone(),
+                visibility: field.visibility,
+                old_type: Some(field.dsl_type.clone()),
+                new_type: None,
+                required: field.required,
+                breaking: field.required,
+            });
+        }
+    }
+
+    let old_tags: Vec<String> = old.validation_rules.iter().map(|r| r.tag()).collect();
+    let new_tags: Vec<String> = new.validation_rules.iter().map(|r| r.tag()).collect();
+    result.added_rules = unmatched(&new_tags, &old_tags);
+    result.removed_rules = unmatched(&old_tags, &new_tags);
+
+    result.breaking = result
+        .added_fields
+        .iter()
+        .chain(&result.removed_fields)
+        .chain(&result.retyped_fields)
+        .any(|change| change.breaking);
+
+    result
+}
+
+/// Tags in `tags` with no counterpart in `others`, counting duplicates
+fn unmatched(tags: &[String], others: &[String]) -> Vec<String> {
+    let mut remaining: HashMap<&str, usize> = HashMap::new();
+    for tag in others {
+        *remaining.entry(tag).or_default() += 1;
+    }
+
+    tags.iter()
+        .filter(|tag| match remaining.get_mut(tag.as_str()) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DslParser;
+
+    fn parse(json: serde_json::Value) -> BusinessRulesDSL {
+        DslParser::parse_str(&json.to_string()).unwrap()
+    }
+
+    fn age_check(
+        private_fields: serde_json::Value,
+        params: serde_json::Value,
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "use_case": "age_check",
+            "private_inputs": { "type": "object", "fields": private_fields },
+            "public_params": params,
+            "validation_rules": [
+                { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_identical_dsls_have_no_changes() {
+        let dsl = parse(age_check(
+            serde_json::json!({ "age": "u32" }),
+            serde_json::json!({ "min_age": "u32" }),
+        ));
+
+        assert_eq!(diff(&dsl, &dsl), DslDiff::default());
+    }
+
+    #[test]
+    fn test_removed_required_field_is_breaking() {
+        let old = parse(age_check(
+            serde_json::json!({ "age": "u32", "country": "string" }),
+            serde_json::json!({ "min_age": "u32" }),
+        ));
+        let new = parse(age_check(
+            serde_json::json!({ "age": "u32" }),
+            serde_json::json!({ "min_age": "u32" }),
+        ));
+
+        let result = diff(&old, &new);
+        assert!(result.breaking);
+        assert_eq!(result.removed_fields.len(), 1);
+        assert_eq!(result.removed_fields[0].path, "private_inputs.country");
+        assert_eq!(result.removed_fields[0].old_type.as_deref(), Some("string"));
+        assert!(result.removed_fields[0].breaking);
+        assert!(result.added_fields.is_empty());
+    }
+
+    #[test]
+    fn test_added_optional_field_is_not_breaking() {
+        let old = parse(age_check(
+            serde_json::json!({ "age": "u32" }),
+            serde_json::json!({ "min_age": "u32" }),
+        ));
+        let mut new_json = age_check(
+            serde_json::json!({ "age": "u32" }),
+            serde_json::json!({ "min_age": "u32", "max_age": "u32" }),
+        );
+        new_json["param_defaults"] = serde_json::json!({ "max_age": 150 });
+        new_json["validation_rules"].as_array_mut().unwrap().push(
+            serde_json::json!({ "type": "range_check", "field": "age", "min": 0, "max_param": "max_age" }),
+        );
+        let new = parse(new_json);
+
+        let result = diff(&old, &new);
+        assert!(!result.breaking);
+        assert_eq!(result.added_fields.len(), 1);
+        assert_eq!(result.added_fields[0].path, "public_params.max_age");
+        assert!(!result.added_fields[0].required);
+        assert_eq!(result.added_rules, ["range_check:age"]);
+        assert!(result.removed_rules.is_empty());
+    }
+
+    #[test]
+    fn test_retyped_and_newly_required_fields_are_breaking() {
+        let old = parse(age_check(
+            serde_json::json!({ "age": "u32" }),
+            serde_json::json!({ "min_age": "u32" }),
+        ));
+        let new = parse(age_check(
+            serde_json::json!({ "age": "u64" }),
+            serde_json::json!({ "min_age": "u32" }),
+        ));
+
+        let result = diff(&old, &new);
+        assert!(result.breaking);
+        assert_eq!(result.retyped_fields.len(), 1);
+        assert_eq!(result.retyped_fields[0].old_type.as_deref(), Some("u32"));
+        assert_eq!(result.retyped_fields[0].new_type.as_deref(), Some("u64"));
+
+        // Dropping a default makes the param required
+        let mut with_default = age_check(
+            serde_json::json!({ "age": "u32" }),
+            serde_json::json!({ "min_age": "u32" }),
+        );
+        with_default["param_defaults"] = serde_json::json!({ "min_age": 18 });
+        let result = diff(&parse(with_default.clone()), &old);
+        assert!(result.breaking);
+        assert!(result.retyped_fields[0].required);
+
+        // ...and adding one is harmless
+        let result = diff(&old, &parse(with_default));
+        assert!(!result.breaking);
+        assert_eq!(result.retyped_fields.len(), 1);
+    }
+}
@@ -0,0 +1,646 @@
+//! Expression mini-language for `expression` rules
+//!
+//! A restricted alternative to raw-Rust `custom` rules. Expressions can only
+//! read declared inputs and combine them with literals, arithmetic,
+//! comparisons and boolean operators, so they cannot reach paths, macros,
+//! method calls or anything else outside the generated guest's inputs:
+//!
+//! ```text
+//! input.quantity * 2 <= param.max_quantity && !input.suspended
+//! input.country == "GB" || input.age >= 21
+//! ```
+//!
+//! - `input.<field>` reads a private input field, `param.<name>` a public param
+//! - Literals: unsigned integers, `"strings"` (with `\"` and `\\` escapes), `true`, `false`
+//! - Operators, loosest first: `||`, `&&`, `== != < <= > >=`, `+ -`, `* / %`, unary `!`
+//!
+//! Expressions are type checked against the DSL schema. Integer inputs
+//! (`u32`, `u64`, `i32`, `i64`) support arithmetic and ordering, strings and
+//! bools support `==`/`!=`, and the whole expression must be a bool.
+
+use super::{BusinessRulesDSL, InputSchema};
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+
+/// Longest expression accepted, in bytes
+pub const MAX_EXPR_LEN: usize = 2048;
+
+/// Deepest nesting of parentheses and unary operators accepted
+const MAX_DEPTH: usize = 32;
+
+/// Parsed expression, borrowing names from its source text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr<'a> {
+    /// Boolean literal
+    Bool(bool),
+    /// Integer literal
+    Int(u64),
+    /// String literal
+    Str(String),
+    /// Private input field (`input.<field>`)
+    Field(&'a str),
+    /// Public parameter (`param.<name>`)
+    Param(&'a str),
+    /// Logical negation (`!`)
+    Not(Box<Expr<'a>>),
+    /// Binary operation
+    Binary(BinaryOp, Box<Expr<'a>>, Box<Expr<'a>>),
+}
+
+/// Binary operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinaryOp {
+    /// Operator as written in expressions
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Or => "||",
+            BinaryOp::And => "&&",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+        }
+    }
+}
+
+/// Type of an expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExprType {
+    Bool,
+    Int,
+    Str,
+}
+
+impl ExprType {
+    /// Expression type of a DSL field type, if expressions can use it
+    pub fn of_dsl_type(type_str: &str) -> Option<Self> {
+        match type_str {
+            "bool" => Some(ExprType::Bool),
+            "u32" | "u64" | "i32" | "i64" => Some(ExprType::Int),
+            "string" => Some(ExprType::Str),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ExprType::Bool => "bool",
+            ExprType::Int => "integer",
+            ExprType::Str => "string",
+        }
+    }
+}
+
+/// Declared inputs an expression may reference, with their DSL types
+#[derive(Debug, Clone, Default)]
+pub struct ExprScope<'a> {
+    fields: HashMap<&'a str, &'a str>,
+    params: HashMap<&'a str, &'a str>,
+}
+
+impl<'a> ExprScope<'a> {
+    /// Scope holding every private input field and public param of `dsl`
+    pub fn new(dsl: &'a BusinessRulesDSL) -> Self {
+        let objects: Vec<_> = match &dsl.private_inputs {
+            InputSchema::Object(object) => vec![object],
+            InputSchema::Map(objects) => objects.values().collect(),
+        };
+
+        Self {
+            fields: objects
+                .into_iter()
+                .flat_map(|object| object.fields.iter())
+                .map(|(name, ty)| (name.as_str(), ty.as_str()))
+                .collect(),
+            params: dsl
+                .public_params
+                .fields()
+                .iter()
+                .map(|(name, ty)| (name.as_str(), ty.as_str()))
+                .collect(),
+        }
+    }
+
+    /// DSL type of a declared public param
+    pub fn param_type(&self, name: &str) -> Option<&'a str> {
+        self.params.get(name).copied()
+    }
+}
+
+impl<'a> Expr<'a> {
+    /// Parse an expression
+    pub fn parse(source: &'a str) -> Result<Self> {
+        if source.len() > MAX_EXPR_LEN {
+            bail!("expression is longer than {} bytes", MAX_EXPR_LEN);
+        }
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            depth: 0,
+        };
+
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            bail!("unexpected {} after end of expression", token.describe());
+        }
+        Ok(expr)
+    }
+
+    /// Private input fields read by the expression, in order of appearance
+    pub fn fields(&self) -> Vec<&'a str> {
+        let mut names = Vec::new();
+        self.visit(&mut |expr| {
+            if let Expr::Field(name) = expr {
+                names.push(*name);
+            }
+        });
+        names
+    }
+
+    /// Public params read by the expression, in order of appearance
+    pub fn params(&self) -> Vec<&'a str> {
+        let mut names = Vec::new();
+        self.visit(&mut |expr| {
+            if let Expr::Param(name) = expr {
+                names.push(*name);
+            }
+        });
+        names
+    }
+
+    /// Type check the expression against the declared inputs
+    pub fn check(&self, scope: &ExprScope) -> Result<ExprType> {
+        match self {
+            Expr::Bool(_) => Ok(ExprType::Bool),
+            Expr::Int(_) => Ok(ExprType::Int),
+            Expr::Str(_) => Ok(ExprType::Str),
+            Expr::Field(name) => reference_type("private input", name, &scope.fields),
+            Expr::Param(name) => reference_type("public param", name, &scope.params),
+            Expr::Not(operand) => match operand.check(scope)? {
+                ExprType::Bool => Ok(ExprType::Bool),
+                ty => bail!("'!' needs a bool operand, got {}", ty.name()),
+            },
+            Expr::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.check(scope)?, rhs.check(scope)?);
+                let expected = match op {
+                    BinaryOp::Or | BinaryOp::And => Some(ExprType::Bool),
+                    BinaryOp::Eq | BinaryOp::Ne => None,
+                    _ => Some(ExprType::Int),
+                };
+                let operands_ok = match expected {
+                    Some(ty) => lhs == ty && rhs == ty,
+                    None => lhs == rhs,
+                };
+                if !operands_ok {
+                    bail!(
+                        "'{}' cannot combine {} and {}",
+                        op.symbol(),
+                        lhs.name(),
+                        rhs.name()
+                    );
+                }
+
+                match op {
+                    BinaryOp::Add
+                    | BinaryOp::Sub
+                    | BinaryOp::Mul
+                    | BinaryOp::Div
+                    | BinaryOp::Rem => Ok(ExprType::Int),
+                    _ => Ok(ExprType::Bool),
+                }
+            }
+        }
+    }
+
+    fn visit<'s>(&'s self, f: &mut impl FnMut(&'s Expr<'a>)) {
+        f(self);
+        match self {
+            Expr::Not(operand) => operand.visit(f),
+            Expr::Binary(_, lhs, rhs) => {
+                lhs.visit(f);
+                rhs.visit(f);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn reference_type(kind: &str, name: &str, declared: &HashMap<&str, &str>) -> Result<ExprType> {
+    let Some(&type_str) = declared.get(name) else {
+        bail!("'{}' is not a declared {}", name, kind);
+    };
+    match ExprType::of_dsl_type(type_str) {
+        Some(ty) => Ok(ty),
+        None => bail!(
+            "{} '{}' has type '{}', which expressions cannot use",
+            kind,
+            name,
+            type_str
+        ),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token<'a> {
+    Ident(&'a str),
+    Int(u64),
+    Str(String),
+    Dot,
+    LParen,
+    RParen,
+    Bang,
+    Op(BinaryOp),
+}
+
+impl Token<'_> {
+    fn describe(&self) -> String {
+        match self {
+            Token::Ident(name) => format!("'{}'", name),
+            Token::Int(n) => format!("'{}'", n),
+            Token::Str(_) => "string literal".to_string(),
+            Token::Dot => "'.'".to_string(),
+            Token::LParen => "'('".to_string(),
+            Token::RParen => "')'".to_string(),
+            Token::Bang => "'!'".to_string(),
+            Token::Op(op) => format!("'{}'", op.symbol()),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token<'_>>> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        let next = bytes.get(pos + 1).copied();
+
+        if c.is_ascii_whitespace() {
+            pos += 1;
+            continue;
+        }
+
+        if c.is_ascii_alphabetic() || c == b'_' {
+            let start = pos;
+            while pos < bytes.len() && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_') {
+                pos += 1;
+            }
+            tokens.push(Token::Ident(&source[start..pos]));
+            continue;
+        }
+
+        if c.is_ascii_digit() {
+            let start = pos;
+            while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                pos += 1;
+            }
+            let value = source[start..pos]
+                .parse()
+                .map_err(|_| anyhow::anyhow!("integer '{}' is too large", &source[start..pos]))?;
+            tokens.push(Token::Int(value));
+            continue;
+        }
+
+        if c == b'"' {
+            let mut value = String::new();
+            let mut chars = source[pos + 1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    None => bail!("unterminated string literal at position {}", pos),
+                    Some((i, '"')) => break pos + 1 + i + 1,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, escaped @ ('"' | '\\'))) => value.push(escaped),
+                        _ => bail!("unsupported escape in string literal at position {}", pos),
+                    },
+                    Some((_, ch)) => value.push(ch),
+                }
+            };
+            tokens.push(Token::Str(value));
+            pos = end;
+            continue;
+        }
+
+        let (token, len) = match (c, next) {
+            (b'|', Some(b'|')) => (Token::Op(BinaryOp::Or), 2),
+            (b'&', Some(b'&')) => (Token::Op(BinaryOp::And), 2),
+            (b'=', Some(b'=')) => (Token::Op(BinaryOp::Eq), 2),
+            (b'!', Some(b'=')) => (Token::Op(BinaryOp::Ne), 2),
+            (b'<', Some(b'=')) => (Token::Op(BinaryOp::Le), 2),
+            (b'>', Some(b'=')) => (Token::Op(BinaryOp::Ge), 2),
+            (b'<', _) => (Token::Op(BinaryOp::Lt), 1),
+            (b'>', _) => (Token::Op(BinaryOp::Gt), 1),
+            (b'+', _) => (Token::Op(BinaryOp::Add), 1),
+            (b'-', _) => (Token::Op(BinaryOp::Sub), 1),
+            (b'*', _) => (Token::Op(BinaryOp::Mul), 1),
+            (b'/', _) => (Token::Op(BinaryOp::Div), 1),
+            (b'%', _) => (Token::Op(BinaryOp::Rem), 1),
+            (b'!', _) => (Token::Bang, 1),
+            (b'.', _) => (Token::Dot, 1),
+            (b'(', _) => (Token::LParen, 1),
+            (b')', _) => (Token::RParen, 1),
+            _ => {
+                let ch = source[pos..].chars().next().unwrap_or_default();
+                bail!("unexpected character '{}' at position {}", ch, pos);
+            }
+        };
+        tokens.push(token);
+        pos += len;
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser, one method per precedence level
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Consume the next token if it is one of `ops`
+    fn eat_op(&mut self, ops: &[BinaryOp]) -> Option<BinaryOp> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse a left-associative chain of `ops` between operands parsed by `operand`
+    fn parse_chain(
+        &mut self,
+        ops: &[BinaryOp],
+        operand: fn(&mut Self) -> Result<Expr<'a>>,
+    ) -> Result<Expr<'a>> {
+        let mut lhs = operand(self)?;
+        while let Some(op) = self.eat_op(ops) {
+            let rhs = operand(self)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr<'a>> {
+        self.parse_chain(&[BinaryOp::Or], Self::parse_and)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr<'a>> {
+        self.parse_chain(&[BinaryOp::And], Self::parse_comparison)
+    }
+
+    /// Comparisons don't chain: `a < b < c` is rejected
+    fn parse_comparison(&mut self) -> Result<Expr<'a>> {
+        const COMPARISONS: &[BinaryOp] = &[
+            BinaryOp::Eq,
+            BinaryOp::Ne,
+            BinaryOp::Lt,
+            BinaryOp::Le,
+            BinaryOp::Gt,
+            BinaryOp::Ge,
+        ];
+
+        let lhs = self.parse_sum()?;
+        let Some(op) = self.eat_op(COMPARISONS) else {
+            return Ok(lhs);
+        };
+        let rhs = self.parse_sum()?;
+        if let Some(Token::Op(next)) = self.peek() {
+            if COMPARISONS.contains(next) {
+                bail!("comparisons cannot be chained; use '&&' to combine them");
+            }
+        }
+        Ok(Expr::Binary(op, Box::new(lhs), Box::new(rhs)))
+    }
+
+    fn parse_sum(&mut self) -> Result<Expr<'a>> {
+        self.parse_chain(&[BinaryOp::Add, BinaryOp::Sub], Self::parse_product)
+    }
+
+    fn parse_product(&mut self) -> Result<Expr<'a>> {
+        self.parse_chain(
+            &[BinaryOp::Mul, BinaryOp::Div, BinaryOp::Rem],
+            Self::parse_unary,
+        )
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr<'a>> {
+        if self.peek() == Some(&Token::Bang) {
+            self.pos += 1;
+            let operand = self.nested(Self::parse_unary)?;
+            return Ok(Expr::Not(Box::new(operand)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr<'a>> {
+        match self.next() {
+            Some(Token::Int(n)) => Ok(Expr::Int(n)),
+            Some(Token::Str(s)) => Ok(Expr::Str(s)),
+            Some(Token::LParen) => {
+                let expr = self.nested(Self::parse_or)?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    Some(token) => bail!("expected ')', found {}", token.describe()),
+                    None => bail!("expected ')' before end of expression"),
+                }
+            }
+            Some(Token::Ident("true")) => Ok(Expr::Bool(true)),
+            Some(Token::Ident("false")) => Ok(Expr::Bool(false)),
+            Some(Token::Ident(root @ ("input" | "param"))) => {
+                let name = match (self.next(), self.next()) {
+                    (Some(Token::Dot), Some(Token::Ident(name))) => name,
+                    _ => bail!("expected '{}.<name>'", root),
+                };
+                if matches!(self.peek(), Some(Token::Dot | Token::LParen)) {
+                    bail!(
+                        "'{}.{}' cannot be followed by a member access or call",
+                        root,
+                        name
+                    );
+                }
+                Ok(if root == "input" {
+                    Expr::Field(name)
+                } else {
+                    Expr::Param(name)
+                })
+            }
+            Some(Token::Ident(name)) => bail!(
+                "unknown name '{}': reference private inputs as 'input.<field>' and public params as 'param.<name>'",
+                name
+            ),
+            Some(token) => bail!("unexpected {}", token.describe()),
+            None => bail!("unexpected end of expression"),
+        }
+    }
+
+    /// Run `parse` one nesting level deeper, bounding recursion
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Expr<'a>>) -> Result<Expr<'a>> {
+        if self.depth >= MAX_DEPTH {
+            bail!("expression is nested more than {} levels deep", MAX_DEPTH);
+        }
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope_dsl() -> BusinessRulesDSL {
+        serde_json::from_value(serde_json::json!({
+            "use_case": "expr",
+            "private_inputs": {
+                "type": "object",
+                "fields": {
+                    "quantity": "u32",
+                    "country": "string",
+                    "suspended": "bool",
+                    "signature": "bytes"
+                }
+            },
+            "public_params": { "max_quantity": "u64" },
+            "validation_rules": []
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_respects_precedence() {
+        let expr =
+            Expr::parse("input.quantity * 2 <= param.max_quantity && !input.suspended").unwrap();
+
+        assert_eq!(
+            expr,
+            Expr::Binary(
+                BinaryOp::And,
+                Box::new(Expr::Binary(
+                    BinaryOp::Le,
+                    Box::new(Expr::Binary(
+                        BinaryOp::Mul,
+                        Box::new(Expr::Field("quantity")),
+                        Box::new(Expr::Int(2)),
+                    )),
+                    Box::new(Expr::Param("max_quantity")),
+                )),
+                Box::new(Expr::Not(Box::new(Expr::Field("suspended")))),
+            )
+        );
+        assert_eq!(expr.fields(), vec!["quantity", "suspended"]);
+        assert_eq!(expr.params(), vec!["max_quantity"]);
+    }
+
+    #[test]
+    fn test_type_check() {
+        let dsl = scope_dsl();
+        let scope = ExprScope::new(&dsl);
+        let check = |source: &str| Expr::parse(source).unwrap().check(&scope);
+
+        assert_eq!(
+            check(r#"input.country == "GB" || (input.quantity + 1) % 2 == 0"#).unwrap(),
+            ExprType::Bool
+        );
+        assert_eq!(
+            check("input.quantity - param.max_quantity").unwrap(),
+            ExprType::Int
+        );
+
+        for (source, expected) in [
+            ("input.country < 3", "'<' cannot combine string and integer"),
+            ("!input.quantity", "'!' needs a bool operand"),
+            ("input.missing", "'missing' is not a declared private input"),
+            (
+                "param.min_age > 1",
+                "'min_age' is not a declared public param",
+            ),
+            ("input.signature == input.signature", "type 'bytes'"),
+        ] {
+            let err = check(source).unwrap_err().to_string();
+            assert!(err.contains(expected), "{}: {}", source, err);
+        }
+    }
+
+    #[test]
+    fn test_dangerous_constructs_rejected() {
+        for (source, expected) in [
+            (
+                r#"std::process::Command::new("sh").status().is_ok()"#,
+                "unexpected character ':'",
+            ),
+            (
+                "input.quantity > 0 && std.process.exit(1)",
+                "unknown name 'std'",
+            ),
+            ("unsafe { true }", "unexpected character '{'"),
+            (
+                r#"include_str!("/etc/passwd") == "x""#,
+                "unknown name 'include_str'",
+            ),
+            ("input.country.len() > 2", "member access or call"),
+            ("input.quantity; true", "unexpected character ';'"),
+            ("{ true }", "unexpected character '{'"),
+            ("input.quantity as u8 == 1", "unexpected 'as'"),
+            ("1 < 2 < 3", "cannot be chained"),
+        ] {
+            let err = Expr::parse(source).unwrap_err().to_string();
+            assert!(err.contains(expected), "{}: {}", source, err);
+        }
+
+        let deep = format!("{}true{}", "(".repeat(100), ")".repeat(100));
+        assert!(Expr::parse(&deep)
+            .unwrap_err()
+            .to_string()
+            .contains("nested more than"));
+    }
+
+    #[test]
+    fn test_string_literals() {
+        assert_eq!(
+            Expr::parse(r#""say \"hi\" \\ bye""#).unwrap(),
+            Expr::Str(r#"say "hi" \ bye"#.to_string())
+        );
+        assert!(Expr::parse(r#""unterminated"#).is_err());
+        assert!(Expr::parse(r#""\n""#).is_err());
+    }
+}
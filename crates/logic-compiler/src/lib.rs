@@ -9,4 +9,4 @@ pub mod parser;
 
 pub use codegen::CodeGenerator;
 pub use dsl::*;
-pub use parser::DslParser;
+pub use parser::{DslParser, DslValidationError};
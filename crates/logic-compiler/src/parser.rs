@@ -2,8 +2,13 @@
 //!
 //! This module handles parsing JSON DSL files and validating them.
 
+use crate::codegen::custom_code::check_custom_code;
+use crate::codegen::type_gen::value_matches_type;
+use crate::dsl::expr::{Expr, ExprScope, ExprType};
 use crate::dsl::*;
 use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
 use std::path::Path;
 
 /// Parser for Business Rules DSL
@@ -47,28 +52,250 @@ impl DslParser {
     /// - At least one validation rule
     /// - Valid field references
     /// - Valid parameter references
+    /// - Public param defaults that are declared and match their types
+    ///
+    /// All problems are reported together, one per line.
     fn validate(dsl: &BusinessRulesDSL) -> Result<()> {
+        match Self::validate_structured(dsl) {
+            Ok(()) => Ok(()),
+            Err(errors) if errors.len() == 1 => anyhow::bail!("{}", errors[0]),
+            Err(errors) => {
+                let lines: Vec<String> = errors.iter().map(|e| format!("  - {}", e)).collect();
+                anyhow::bail!(
+                    "DSL has {} validation errors:\n{}",
+                    errors.len(),
+                    lines.join("\n")
+                )
+            }
+        }
+    }
+
+    /// Validate a parsed DSL, collecting every problem instead of stopping at the first
+    ///
+    /// Each error identifies the offending rule and property so a UI can
+    /// highlight the matching part of the form.
+    pub fn validate_structured(dsl: &BusinessRulesDSL) -> Result<(), Vec<DslValidationError>> {
+        let mut errors = Vec::new();
+
         // Check use_case is not empty
         if dsl.use_case.is_empty() {
-            anyhow::bail!("use_case cannot be empty");
+            errors.push(DslValidationError {
+                rule_index: None,
+                field: Some("use_case".to_string()),
+                message: "use_case cannot be empty".to_string(),
+                code: "required".to_string(),
+            });
         }
 
-        // Check we have at least one validation rule
-        if dsl.validation_rules.is_empty() {
-            anyhow::bail!("At least one validation rule is required");
+        // Check we have at least one validation rule (rule sets check their own)
+        if dsl.validation_rules.is_empty() && dsl.rule_sets.is_none() {
+            errors.push(DslValidationError {
+                rule_index: None,
+                field: Some("validation_rules".to_string()),
+                message: "At least one validation rule is required".to_string(),
+                code: "required".to_string(),
+            });
+        }
+
+        // Defaults must belong to a declared public param and fit its type
+        let param_types = dsl.public_params.fields();
+        for (name, value) in &dsl.param_defaults {
+            match param_types.get(name) {
+                None => errors.push(DslValidationError {
+                    rule_index: None,
+                    field: Some(format!("param_defaults.{}", name)),
+                    message: format!("Default given for undeclared public param '{}'", name),
+                    code: "unknown_param".to_string(),
+                }),
+                Some(ty) if !value_matches_type(ty, value) => errors.push(DslValidationError {
+                    rule_index: None,
+                    field: Some(format!("param_defaults.{}", name)),
+                    message: format!("Default for public param '{}' is not a valid {}", name, ty),
+                    code: "invalid_default".to_string(),
+                }),
+                Some(_) => {}
+            }
         }
 
         // Validate each rule
+        let private_fields = private_field_types(&dsl.private_inputs);
+        let scope = ExprScope::new(dsl);
         for (idx, rule) in dsl.validation_rules.iter().enumerate() {
-            Self::validate_rule(rule, dsl)
-                .with_context(|| format!("Validation rule {} is invalid", idx))?;
+            let mut issues = Vec::new();
+            Self::validate_rule(rule, "", &private_fields, &scope, &mut issues);
+
+            errors.extend(issues.into_iter().map(|issue| DslValidationError {
+                rule_index: Some(idx),
+                field: Some(issue.field),
+                message: issue.message,
+                code: issue.code.to_string(),
+            }));
+        }
+
+        if let Some(rule_sets) = &dsl.rule_sets {
+            Self::validate_rule_sets(dsl, rule_sets, &private_fields, &scope, &mut errors);
+        }
+
+        // Disclosed facts must be named, well-typed expressions
+        for (name, expr) in &dsl.disclose {
+            let field = Some(format!("disclose.{}", name));
+            let problem = if name.trim().is_empty() {
+                Some(("required", "Disclosed facts need a name".to_string()))
+            } else if expr.trim().is_empty() {
+                Some((
+                    "required",
+                    format!("Disclosed fact '{}' has no expression", name),
+                ))
+            } else {
+                Expr::parse(expr)
+                    .and_then(|parsed| parsed.check(&scope))
+                    .err()
+                    .map(|e| {
+                        (
+                            "invalid_expression",
+                            format!("Disclosed fact '{}': {}", name, e),
+                        )
+                    })
+            };
+
+            if let Some((code, message)) = problem {
+                errors.push(DslValidationError {
+                    rule_index: None,
+                    field,
+                    message,
+                    code: code.to_string(),
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate the selector and every rule set, each independently of the others
+    ///
+    /// Problems are reported against `rule_sets.sets.<value>[<index>]` rather
+    /// than a `rule_index`, which refers to the top-level rules.
+    fn validate_rule_sets(
+        dsl: &BusinessRulesDSL,
+        rule_sets: &RuleSets,
+        private_fields: &HashMap<&str, &str>,
+        scope: &ExprScope,
+        errors: &mut Vec<DslValidationError>,
+    ) {
+        let mut error = |field: String, code: &str, message: String| {
+            errors.push(DslValidationError {
+                rule_index: None,
+                field: Some(field),
+                message,
+                code: code.to_string(),
+            });
+        };
+
+        let selector = &rule_sets.selector;
+        match dsl.public_params.fields().get(selector) {
+            _ if selector.is_empty() => error(
+                "rule_sets.selector".to_string(),
+                "required",
+                "rule_sets: selector cannot be empty".to_string(),
+            ),
+            None => error(
+                "rule_sets.selector".to_string(),
+                "unknown_param",
+                format!(
+                    "rule_sets: selector '{}' is not a declared public param",
+                    selector
+                ),
+            ),
+            Some(ty) if ty != "string" => error(
+                "rule_sets.selector".to_string(),
+                "invalid_selector_type",
+                format!(
+                    "rule_sets: selector '{}' must be a string param, got '{}'",
+                    selector, ty
+                ),
+            ),
+            Some(_) => {}
+        }
+
+        if rule_sets.sets.is_empty() {
+            error(
+                "rule_sets.sets".to_string(),
+                "required",
+                "rule_sets: at least one rule set is required".to_string(),
+            );
         }
 
-        Ok(())
+        for (value, rules) in &rule_sets.sets {
+            let path = format!("rule_sets.sets.{}", value);
+
+            // Every selection must enforce at least one rule
+            if rules.is_empty() && dsl.validation_rules.is_empty() {
+                error(
+                    path.clone(),
+                    "required",
+                    format!(
+                        "Rule set '{}' needs at least one rule when validation_rules is empty",
+                        value
+                    ),
+                );
+            }
+
+            for (idx, rule) in rules.iter().enumerate() {
+                let mut issues = Vec::new();
+                let rule_path = format!("{}[{}]", path, idx);
+                Self::validate_rule(rule, &rule_path, private_fields, scope, &mut issues);
+
+                for issue in issues {
+                    error(issue.field, issue.code, issue.message);
+                }
+            }
+        }
     }
 
-    /// Validate a single validation rule
-    fn validate_rule(rule: &ValidationRule, _dsl: &BusinessRulesDSL) -> Result<()> {
+    /// Validate a single validation rule, recording every problem found
+    ///
+    /// `path` prefixes reported property names for nested rules (e.g. `rules[1]`).
+    /// `private_fields` maps declared private input fields to their types;
+    /// `scope` holds the inputs and params expressions may reference.
+    fn validate_rule(
+        rule: &ValidationRule,
+        path: &str,
+        private_fields: &HashMap<&str, &str>,
+        scope: &ExprScope,
+        issues: &mut Vec<RuleIssue>,
+    ) {
+        let mut issue = |field: &str, code: &'static str, message: String| {
+            issues.push(RuleIssue {
+                field: join_path(path, field),
+                code,
+                message,
+            });
+        };
+
+        // Params of the wrong kind generate code that only fails at cargo build.
+        // Undeclared params are left to the build, as before.
+        for (property, param, kind) in param_references(rule) {
+            match scope.param_type(param) {
+                Some(ty) if !kind.accepts(ty) => issue(
+                    property,
+                    "param_type_mismatch",
+                    format!(
+                        "{}: {} '{}' must be {} param, got '{}'",
+                        rule.rule_type(),
+                        property,
+                        param,
+                        kind.describe(),
+                        ty
+                    ),
+                ),
+                _ => {}
+            }
+        }
+
         match rule {
             ValidationRule::SignatureCheck {
                 field,
@@ -78,21 +305,65 @@ impl DslParser {
                 ..
             } => {
                 if field.is_empty() {
-                    anyhow::bail!("signature_check: field cannot be empty");
+                    issue(
+                        "field",
+                        "required",
+                        "signature_check: field cannot be empty".into(),
+                    );
                 }
                 if algorithm.is_empty() {
-                    anyhow::bail!("signature_check: algorithm cannot be empty");
+                    issue(
+                        "algorithm",
+                        "required",
+                        "signature_check: algorithm cannot be empty".into(),
+                    );
+                } else if !matches!(algorithm.as_str(), "ed25519" | "ecdsa" | "rsa") {
+                    issue(
+                        "algorithm",
+                        "unsupported_algorithm",
+                        format!("signature_check: unsupported algorithm '{}'", algorithm),
+                    );
                 }
                 if public_key_param.is_empty() {
-                    anyhow::bail!("signature_check: public_key_param cannot be empty");
+                    issue(
+                        "public_key_param",
+                        "required",
+                        "signature_check: public_key_param cannot be empty".into(),
+                    );
                 }
                 if message_fields.is_empty() {
-                    anyhow::bail!("signature_check: message_fields cannot be empty");
+                    issue(
+                        "message_fields",
+                        "required",
+                        "signature_check: message_fields cannot be empty".into(),
+                    );
                 }
-                // Validate supported algorithms
-                match algorithm.as_str() {
-                    "ed25519" | "ecdsa" | "rsa" => {}
-                    _ => anyhow::bail!("signature_check: unsupported algorithm '{}'", algorithm),
+                // The signed message is built from each field's bytes, so only
+                // string/bytes fields can be part of it. Nothing to check against
+                // when no private inputs are declared.
+                if !private_fields.is_empty() {
+                    for (idx, name) in message_fields.iter().enumerate() {
+                        let property = format!("message_fields[{}]", idx);
+                        match private_fields.get(name.as_str()) {
+                            None => issue(
+                                &property,
+                                "unknown_field",
+                                format!(
+                                    "signature_check: message field '{}' is not a declared private input",
+                                    name
+                                ),
+                            ),
+                            Some(&ty) if !matches!(ty, "string" | "bytes") => issue(
+                                &property,
+                                "invalid_field_type",
+                                format!(
+                                    "signature_check: message field '{}' must be string or bytes, got '{}'",
+                                    name, ty
+                                ),
+                            ),
+                            Some(_) => {}
+                        }
+                    }
                 }
             }
 
@@ -105,14 +376,26 @@ impl DslParser {
                 ..
             } => {
                 if field.is_empty() {
-                    anyhow::bail!("range_check: field cannot be empty");
+                    issue(
+                        "field",
+                        "required",
+                        "range_check: field cannot be empty".into(),
+                    );
                 }
                 // Must have either min/max or min_param/max_param
                 if min.is_none() && min_param.is_none() {
-                    anyhow::bail!("range_check: must specify either 'min' or 'min_param'");
+                    issue(
+                        "min",
+                        "required",
+                        "range_check: must specify either 'min' or 'min_param'".into(),
+                    );
                 }
                 if max.is_none() && max_param.is_none() {
-                    anyhow::bail!("range_check: must specify either 'max' or 'max_param'");
+                    issue(
+                        "max",
+                        "required",
+                        "range_check: must specify either 'max' or 'max_param'".into(),
+                    );
                 }
             }
 
@@ -123,12 +406,18 @@ impl DslParser {
                 ..
             } => {
                 if dob_field.is_empty() {
-                    anyhow::bail!("age_verification: dob_field cannot be empty");
+                    issue(
+                        "dob_field",
+                        "required",
+                        "age_verification: dob_field cannot be empty".into(),
+                    );
                 }
                 // Must have either min_age or min_age_param
                 if min_age.is_none() && min_age_param.is_none() {
-                    anyhow::bail!(
-                        "age_verification: must specify either 'min_age' or 'min_age_param'"
+                    issue(
+                        "min_age",
+                        "required",
+                        "age_verification: must specify either 'min_age' or 'min_age_param'".into(),
                     );
                 }
             }
@@ -139,10 +428,18 @@ impl DslParser {
                 ..
             } => {
                 if field.is_empty() {
-                    anyhow::bail!("blacklist_check: field cannot be empty");
+                    issue(
+                        "field",
+                        "required",
+                        "blacklist_check: field cannot be empty".into(),
+                    );
                 }
                 if blacklist_param.is_empty() {
-                    anyhow::bail!("blacklist_check: blacklist_param cannot be empty");
+                    issue(
+                        "blacklist_param",
+                        "required",
+                        "blacklist_check: blacklist_param cannot be empty".into(),
+                    );
                 }
             }
 
@@ -152,25 +449,408 @@ impl DslParser {
                 ..
             } => {
                 if field.is_empty() {
-                    anyhow::bail!("array_intersection_check: field cannot be empty");
+                    issue(
+                        "field",
+                        "required",
+                        "array_intersection_check: field cannot be empty".into(),
+                    );
                 }
                 if prohibited_param.is_empty() {
-                    anyhow::bail!("array_intersection_check: prohibited_param cannot be empty");
+                    issue(
+                        "prohibited_param",
+                        "required",
+                        "array_intersection_check: prohibited_param cannot be empty".into(),
+                    );
+                }
+            }
+
+            ValidationRule::ChecksumCheck {
+                field, algorithm, ..
+            } => {
+                if field.is_empty() {
+                    issue(
+                        "field",
+                        "required",
+                        "checksum_check: field cannot be empty".into(),
+                    );
+                }
+                if !matches!(algorithm.as_str(), "luhn" | "mod97") {
+                    issue(
+                        "algorithm",
+                        "unsupported_algorithm",
+                        format!("checksum_check: unsupported algorithm '{}'", algorithm),
+                    );
+                }
+                // The check runs on the field's `to_string()`, which is only
+                // meaningful for strings and integers
+                match private_fields.get(field.as_str()) {
+                    Some(&ty) if !matches!(ty, "string" | "u32" | "u64" | "i32" | "i64") => issue(
+                        "field",
+                        "invalid_field_type",
+                        format!(
+                            "checksum_check: field '{}' must be string or integer, got '{}'",
+                            field, ty
+                        ),
+                    ),
+                    _ => {}
+                }
+            }
+
+            ValidationRule::IsoCountryCheck {
+                field,
+                set_param,
+                exclude,
+                ..
+            } => {
+                if field.is_empty() {
+                    issue(
+                        "field",
+                        "required",
+                        "iso_country_check: field cannot be empty".into(),
+                    );
+                }
+                match set_param {
+                    Some(param) if param.is_empty() => issue(
+                        "set_param",
+                        "required",
+                        "iso_country_check: set_param cannot be empty".into(),
+                    ),
+                    None if *exclude => issue(
+                        "exclude",
+                        "invalid_combination",
+                        "iso_country_check: 'exclude' requires 'set_param'".into(),
+                    ),
+                    _ => {}
+                }
+            }
+
+            ValidationRule::DateRangeCheck {
+                date_field,
+                reference_date_param,
+                after,
+                after_param,
+                before,
+                before_param,
+                within_days,
+                within_days_param,
+                ..
+            } => {
+                if date_field.is_empty() {
+                    issue(
+                        "date_field",
+                        "required",
+                        "date_range_check: date_field cannot be empty".into(),
+                    );
+                }
+                if reference_date_param.is_empty() {
+                    issue(
+                        "reference_date_param",
+                        "required",
+                        "date_range_check: reference_date_param cannot be empty".into(),
+                    );
+                }
+                if after.is_none()
+                    && after_param.is_none()
+                    && before.is_none()
+                    && before_param.is_none()
+                    && within_days.is_none()
+                    && within_days_param.is_none()
+                {
+                    issue(
+                        "after",
+                        "required",
+                        "date_range_check: must specify at least one of 'after', 'before' or 'within_days' (or their params)".into(),
+                    );
+                }
+                for (name, date) in [("after", after), ("before", before)] {
+                    if let Some(date) = date {
+                        if !is_iso_date(date) {
+                            issue(
+                                name,
+                                "invalid_date",
+                                format!(
+                                    "date_range_check: '{}' must be an ISO 8601 date (YYYY-MM-DD), got '{}'",
+                                    name, date
+                                ),
+                            );
+                        }
+                    }
+                }
+            }
+
+            ValidationRule::Expression { expr, .. } => {
+                if expr.trim().is_empty() {
+                    issue(
+                        "expr",
+                        "required",
+                        "expression: expr cannot be empty".into(),
+                    );
+                } else {
+                    match Expr::parse(expr).and_then(|parsed| parsed.check(scope)) {
+                        Ok(ExprType::Bool) => {}
+                        Ok(_) => issue(
+                            "expr",
+                            "invalid_expression",
+                            "expression: expr must evaluate to a bool".into(),
+                        ),
+                        Err(e) => issue("expr", "invalid_expression", format!("expression: {}", e)),
+                    }
                 }
             }
 
             ValidationRule::Custom { code, .. } => {
                 if code.is_empty() {
-                    anyhow::bail!("custom: code cannot be empty");
+                    issue("code", "required", "custom: code cannot be empty".into());
+                } else if let Err(e) = check_custom_code(code) {
+                    issue("code", "disallowed_code", format!("custom: {}", e));
+                }
+            }
+
+            ValidationRule::Not { rule, .. } => {
+                let mut nested = Vec::new();
+                Self::validate_rule(
+                    rule,
+                    &join_path(path, "rule"),
+                    private_fields,
+                    scope,
+                    &mut nested,
+                );
+                issues.extend(
+                    nested
+                        .into_iter()
+                        .map(|n| n.nested_in("not: nested rule is invalid")),
+                );
+            }
+
+            ValidationRule::AnyOf { rules, .. } => {
+                if rules.is_empty() {
+                    issue(
+                        "rules",
+                        "empty_group",
+                        "any_of: must contain at least one rule".into(),
+                    );
+                }
+                for (idx, rule) in rules.iter().enumerate() {
+                    let mut nested = Vec::new();
+                    Self::validate_rule(
+                        rule,
+                        &join_path(path, &format!("rules[{}]", idx)),
+                        private_fields,
+                        scope,
+                        &mut nested,
+                    );
+                    let context = format!("any_of: nested rule {} is invalid", idx);
+                    issues.extend(nested.into_iter().map(|n| n.nested_in(&context)));
                 }
-                // TODO: Could add basic Rust syntax validation here
             }
         }
+    }
+}
+
+/// A single DSL validation problem, structured for highlighting in a UI
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DslValidationError {
+    /// Index of the offending entry in `validation_rules` (`None` for DSL-level problems)
+    pub rule_index: Option<usize>,
 
-        Ok(())
+    /// Offending property, e.g. `blacklist_param` or `rules[1].code` for nested rules
+    pub field: Option<String>,
+
+    /// Human-readable description of the problem
+    pub message: String,
+
+    /// Stable machine-readable code (`required`, `unsupported_algorithm`, ...)
+    pub code: String,
+}
+
+impl std::fmt::Display for DslValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.rule_index {
+            Some(idx) => write!(f, "Validation rule {} is invalid: {}", idx, self.message),
+            None => write!(f, "{}", self.message),
+        }
     }
 }
 
+impl std::error::Error for DslValidationError {}
+
+/// A problem found inside one rule, before it is tied to a rule index
+struct RuleIssue {
+    field: String,
+    code: &'static str,
+    message: String,
+}
+
+impl RuleIssue {
+    /// Prefix the message with the context of the enclosing composite rule
+    fn nested_in(self, context: &str) -> Self {
+        Self {
+            message: format!("{}: {}", context, self.message),
+            ..self
+        }
+    }
+}
+
+/// Kind of public param a rule property must reference
+#[derive(Debug, Clone, Copy)]
+enum ParamKind {
+    /// Compared against numbers (`u32`, `u64`, `i32`, `i64`)
+    Numeric,
+    /// Searched for the checked value (any `array<...>`)
+    Array,
+    /// A set of country codes (`array<string>`)
+    StringArray,
+    /// An ISO 8601 date (`string`)
+    Date,
+    /// Raw key material (`bytes`)
+    Bytes,
+}
+
+impl ParamKind {
+    /// Whether a param of DSL type `ty` can be used where this kind is expected
+    fn accepts(self, ty: &str) -> bool {
+        match self {
+            ParamKind::Numeric => matches!(ty, "u32" | "u64" | "i32" | "i64"),
+            ParamKind::Array => ty.starts_with("array<") || ty.starts_with("array["),
+            ParamKind::StringArray => matches!(ty, "array<string>" | "array[string]"),
+            ParamKind::Date => ty == "string",
+            ParamKind::Bytes => ty == "bytes",
+        }
+    }
+
+    /// Description for error messages, e.g. "a numeric"
+    fn describe(self) -> &'static str {
+        match self {
+            ParamKind::Numeric => "a numeric (u32, u64, i32 or i64)",
+            ParamKind::Array => "an array",
+            ParamKind::StringArray => "an array<string>",
+            ParamKind::Date => "a string (ISO 8601 date)",
+            ParamKind::Bytes => "a bytes",
+        }
+    }
+}
+
+/// Public params `rule` reads directly, with the property naming each and the
+/// kind it must be
+///
+/// Nested rules are checked when they're validated, and `expression` rules
+/// are type checked as a whole.
+fn param_references(rule: &ValidationRule) -> Vec<(&'static str, &str, ParamKind)> {
+    fn optional<'a>(
+        property: &'static str,
+        param: &'a Option<String>,
+        kind: ParamKind,
+    ) -> Option<(&'static str, &'a str, ParamKind)> {
+        param.as_deref().map(|param| (property, param, kind))
+    }
+
+    match rule {
+        ValidationRule::SignatureCheck {
+            public_key_param, ..
+        } => vec![("public_key_param", public_key_param, ParamKind::Bytes)],
+        ValidationRule::RangeCheck {
+            min_param,
+            max_param,
+            ..
+        } => [
+            optional("min_param", min_param, ParamKind::Numeric),
+            optional("max_param", max_param, ParamKind::Numeric),
+        ]
+        .into_iter()
+        .flatten()
+        .collect(),
+        ValidationRule::AgeVerification { min_age_param, .. } => {
+            optional("min_age_param", min_age_param, ParamKind::Numeric)
+                .into_iter()
+                .collect()
+        }
+        ValidationRule::BlacklistCheck {
+            blacklist_param, ..
+        } => vec![("blacklist_param", blacklist_param, ParamKind::Array)],
+        ValidationRule::ArrayIntersectionCheck {
+            prohibited_param, ..
+        } => vec![("prohibited_param", prohibited_param, ParamKind::Array)],
+        ValidationRule::IsoCountryCheck { set_param, .. } => {
+            optional("set_param", set_param, ParamKind::StringArray)
+                .into_iter()
+                .collect()
+        }
+        ValidationRule::DateRangeCheck {
+            reference_date_param,
+            after_param,
+            before_param,
+            within_days_param,
+            ..
+        } => std::iter::once(Some((
+            "reference_date_param",
+            reference_date_param.as_str(),
+            ParamKind::Date,
+        )))
+        .chain([
+            optional("after_param", after_param, ParamKind::Date),
+            optional("before_param", before_param, ParamKind::Date),
+            optional("within_days_param", within_days_param, ParamKind::Numeric),
+        ])
+        .flatten()
+        .collect(),
+        ValidationRule::ChecksumCheck { .. }
+        | ValidationRule::Expression { .. }
+        | ValidationRule::Custom { .. }
+        | ValidationRule::Not { .. }
+        | ValidationRule::AnyOf { .. } => vec![],
+    }
+}
+
+/// Declared private input fields and their types, across all input objects
+fn private_field_types(schema: &InputSchema) -> HashMap<&str, &str> {
+    let objects: Vec<&ObjectSchema> = match schema {
+        InputSchema::Object(object) => vec![object],
+        InputSchema::Map(objects) => objects.values().collect(),
+    };
+    objects
+        .into_iter()
+        .flat_map(|object| object.fields.iter())
+        .map(|(name, ty)| (name.as_str(), ty.as_str()))
+        .collect()
+}
+
+/// Join a nested rule path and a property name (`rules[1]` + `code` → `rules[1].code`)
+fn join_path(path: &str, field: &str) -> String {
+    if path.is_empty() {
+        field.to_string()
+    } else {
+        format!("{}.{}", path, field)
+    }
+}
+
+/// Whether `s` is a calendar date in `YYYY-MM-DD` form
+fn is_iso_date(s: &str) -> bool {
+    let parts: Vec<&str> = s.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        return false;
+    };
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return false;
+    }
+
+    let (Ok(year), Ok(month), Ok(day)) = (
+        year.parse::<u32>(),
+        month.parse::<u32>(),
+        day.parse::<u32>(),
+    ) else {
+        return false;
+    };
+
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days_in_month).contains(&day)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -271,4 +951,589 @@ mod tests {
             err_msg
         );
     }
+
+    #[test]
+    fn test_validate_not_nested_rule() {
+        let json = r#"{
+            "use_case": "test",
+            "private_inputs": {},
+            "public_params": {},
+            "validation_rules": [
+                {
+                    "type": "not",
+                    "rule": {
+                        "type": "blacklist_check",
+                        "field": "country",
+                        "blacklist_param": ""
+                    }
+                }
+            ]
+        }"#;
+
+        let result = DslParser::parse_str(json);
+        let err_msg = format!("{:?}", result.unwrap_err());
+        assert!(
+            err_msg.contains("not: nested rule is invalid")
+                && err_msg.contains("blacklist_param cannot be empty"),
+            "Error chain didn't contain expected error: {}",
+            err_msg
+        );
+    }
+
+    #[test]
+    fn test_validate_any_of_rules() {
+        let json = r#"{
+            "use_case": "test",
+            "private_inputs": {},
+            "public_params": {},
+            "validation_rules": [
+                { "type": "any_of", "rules": [] }
+            ]
+        }"#;
+
+        let result = DslParser::parse_str(json);
+        let err_msg = format!("{:?}", result.unwrap_err());
+        assert!(
+            err_msg.contains("any_of: must contain at least one rule"),
+            "Error chain didn't contain expected error: {}",
+            err_msg
+        );
+
+        let json = r#"{
+            "use_case": "test",
+            "private_inputs": {},
+            "public_params": {},
+            "validation_rules": [
+                {
+                    "type": "any_of",
+                    "rules": [
+                        { "type": "custom", "code": "true" },
+                        { "type": "custom", "code": "" }
+                    ]
+                }
+            ]
+        }"#;
+
+        let result = DslParser::parse_str(json);
+        let err_msg = format!("{:?}", result.unwrap_err());
+        assert!(
+            err_msg.contains("any_of: nested rule 1 is invalid")
+                && err_msg.contains("custom: code cannot be empty"),
+            "Error chain didn't contain expected error: {}",
+            err_msg
+        );
+    }
+
+    #[test]
+    fn test_validate_checksum_algorithm() {
+        let json = r#"{
+            "use_case": "test",
+            "private_inputs": {},
+            "public_params": {},
+            "validation_rules": [
+                { "type": "checksum_check", "field": "card_number", "algorithm": "crc32" }
+            ]
+        }"#;
+
+        let result = DslParser::parse_str(json);
+        let err_msg = format!("{:?}", result.unwrap_err());
+        assert!(
+            err_msg.contains("checksum_check: unsupported algorithm 'crc32'"),
+            "Error chain didn't contain expected error: {}",
+            err_msg
+        );
+    }
+
+    #[test]
+    fn test_checksum_field_must_be_string_or_integer() {
+        let json = r#"{
+            "use_case": "test",
+            "private_inputs": {
+                "type": "object",
+                "fields": { "card_number": "u64", "iban": "string", "scan": "bytes" }
+            },
+            "public_params": {},
+            "validation_rules": [
+                { "type": "checksum_check", "field": "card_number", "algorithm": "luhn" },
+                { "type": "checksum_check", "field": "iban", "algorithm": "mod97" },
+                { "type": "checksum_check", "field": "scan", "algorithm": "luhn" }
+            ]
+        }"#;
+
+        let dsl: BusinessRulesDSL = serde_json::from_str(json).unwrap();
+        let errors = DslParser::validate_structured(&dsl).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].rule_index, Some(2));
+        assert_eq!(errors[0].field.as_deref(), Some("field"));
+        assert_eq!(errors[0].code, "invalid_field_type");
+        assert!(errors[0]
+            .message
+            .contains("field 'scan' must be string or integer, got 'bytes'"));
+    }
+
+    #[test]
+    fn test_validate_date_range_check() {
+        let json = r#"{
+            "use_case": "test",
+            "private_inputs": {},
+            "public_params": {},
+            "validation_rules": [
+                {
+                    "type": "date_range_check",
+                    "date_field": "issued_on",
+                    "reference_date_param": "today"
+                }
+            ]
+        }"#;
+
+        let result = DslParser::parse_str(json);
+        let err_msg = format!("{:?}", result.unwrap_err());
+        assert!(
+            err_msg.contains("must specify at least one of"),
+            "Error chain didn't contain expected error: {}",
+            err_msg
+        );
+
+        let json = r#"{
+            "use_case": "test",
+            "private_inputs": {},
+            "public_params": {},
+            "validation_rules": [
+                {
+                    "type": "date_range_check",
+                    "date_field": "issued_on",
+                    "reference_date_param": "today",
+                    "after": "2024-02-30"
+                }
+            ]
+        }"#;
+
+        let result = DslParser::parse_str(json);
+        let err_msg = format!("{:?}", result.unwrap_err());
+        assert!(
+            err_msg.contains("'after' must be an ISO 8601 date"),
+            "Error chain didn't contain expected error: {}",
+            err_msg
+        );
+    }
+
+    #[test]
+    fn test_validate_structured_collects_errors() {
+        let json = r#"{
+            "use_case": "test",
+            "private_inputs": {},
+            "public_params": {},
+            "validation_rules": [
+                {
+                    "type": "age_verification",
+                    "dob_field": "dob",
+                    "min_age": 18
+                },
+                {
+                    "type": "signature_check",
+                    "field": "sig",
+                    "algorithm": "md5",
+                    "public_key_param": "pk",
+                    "message_fields": ["data"]
+                },
+                {
+                    "type": "any_of",
+                    "rules": [
+                        { "type": "custom", "code": "true" },
+                        { "type": "blacklist_check", "field": "country", "blacklist_param": "" }
+                    ]
+                }
+            ]
+        }"#;
+
+        let dsl: BusinessRulesDSL = serde_json::from_str(json).unwrap();
+        let errors = DslParser::validate_structured(&dsl).unwrap_err();
+
+        assert_eq!(errors.len(), 2);
+
+        assert_eq!(errors[0].rule_index, Some(1));
+        assert_eq!(errors[0].field.as_deref(), Some("algorithm"));
+        assert_eq!(errors[0].code, "unsupported_algorithm");
+        assert!(errors[0].message.contains("'md5'"));
+
+        assert_eq!(errors[1].rule_index, Some(2));
+        assert_eq!(errors[1].field.as_deref(), Some("rules[1].blacklist_param"));
+        assert_eq!(errors[1].code, "required");
+        assert!(errors[1]
+            .message
+            .contains("blacklist_param cannot be empty"));
+    }
+
+    #[test]
+    fn test_signature_message_field_must_be_string_or_bytes() {
+        let json = r#"{
+            "use_case": "test",
+            "private_inputs": {
+                "type": "object",
+                "fields": { "order_id": "string", "amount": "u64", "sig": "bytes" }
+            },
+            "public_params": { "pk": "bytes" },
+            "validation_rules": [
+                {
+                    "type": "signature_check",
+                    "field": "sig",
+                    "algorithm": "ed25519",
+                    "public_key_param": "pk",
+                    "message_fields": ["order_id", "amount"]
+                }
+            ]
+        }"#;
+
+        let dsl: BusinessRulesDSL = serde_json::from_str(json).unwrap();
+        let errors = DslParser::validate_structured(&dsl).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field.as_deref(), Some("message_fields[1]"));
+        assert_eq!(errors[0].code, "invalid_field_type");
+
+        let err_msg = DslParser::parse_str(json).unwrap_err().to_string();
+        assert!(
+            err_msg.contains("message field 'amount' must be string or bytes, got 'u64'"),
+            "{}",
+            err_msg
+        );
+
+        // Undeclared fields are rejected too
+        let undeclared = json.replace(r#"["order_id", "amount"]"#, r#"["order_ref"]"#);
+        let dsl: BusinessRulesDSL = serde_json::from_str(&undeclared).unwrap();
+        let errors = DslParser::validate_structured(&dsl).unwrap_err();
+        assert_eq!(errors[0].code, "unknown_field");
+    }
+
+    #[test]
+    fn test_parse_reports_all_rule_errors() {
+        let json = r#"{
+            "use_case": "test",
+            "private_inputs": {},
+            "public_params": {},
+            "validation_rules": [
+                { "type": "range_check", "field": "quantity", "max": 10 },
+                { "type": "custom", "code": "" },
+                { "type": "checksum_check", "field": "iban", "algorithm": "crc32" }
+            ]
+        }"#;
+
+        let err_msg = DslParser::parse_str(json).unwrap_err().to_string();
+        assert!(
+            err_msg.starts_with("DSL has 3 validation errors:"),
+            "{}",
+            err_msg
+        );
+        assert!(err_msg.contains(
+            "Validation rule 0 is invalid: range_check: must specify either 'min' or 'min_param'"
+        ));
+        assert!(err_msg.contains("Validation rule 1 is invalid: custom: code cannot be empty"));
+        assert!(err_msg.contains(
+            "Validation rule 2 is invalid: checksum_check: unsupported algorithm 'crc32'"
+        ));
+    }
+
+    #[test]
+    fn test_validate_expression_rule() {
+        let json = r#"{
+            "use_case": "test",
+            "private_inputs": { "type": "object", "fields": { "age": "u32", "country": "string" } },
+            "public_params": { "min_age": "u32" },
+            "validation_rules": [
+                { "type": "expression", "expr": "input.age >= param.min_age && input.country != \"XX\"" }
+            ]
+        }"#;
+        assert!(DslParser::parse_str(json).is_ok());
+
+        let invalid = json.replace(
+            r#"input.age >= param.min_age && input.country != \"XX\""#,
+            r#"std::process::Command::new(\"sh\").status().is_ok()"#,
+        );
+        let dsl: BusinessRulesDSL = serde_json::from_str(&invalid).unwrap();
+        let errors = DslParser::validate_structured(&dsl).unwrap_err();
+        assert_eq!(errors[0].field.as_deref(), Some("expr"));
+        assert_eq!(errors[0].code, "invalid_expression");
+
+        // Expressions must be conditions, not values
+        let not_bool = json.replace(
+            r#"input.age >= param.min_age && input.country != \"XX\""#,
+            "input.age + param.min_age",
+        );
+        let err_msg = DslParser::parse_str(&not_bool).unwrap_err().to_string();
+        assert!(
+            err_msg.contains("expr must evaluate to a bool"),
+            "{}",
+            err_msg
+        );
+    }
+
+    #[test]
+    fn test_custom_code_allowlist() {
+        let json = r#"{
+            "use_case": "test",
+            "private_inputs": { "type": "object", "fields": { "age": "u32" } },
+            "public_params": {},
+            "validation_rules": [
+                { "type": "custom", "code": "private_inputs.age > 17 && private_inputs.age < 130" }
+            ]
+        }"#;
+        assert!(DslParser::parse_str(json).is_ok());
+
+        let unsafe_code = json.replace(
+            "private_inputs.age > 17 && private_inputs.age < 130",
+            "unsafe { std::process::Command::new(\\\"sh\\\").status().is_ok() }",
+        );
+        let dsl: BusinessRulesDSL = serde_json::from_str(&unsafe_code).unwrap();
+        let errors = DslParser::validate_structured(&dsl).unwrap_err();
+        assert_eq!(errors[0].field.as_deref(), Some("code"));
+        assert_eq!(errors[0].code, "disallowed_code");
+        assert!(errors[0].message.contains("'unsafe' is not allowed"));
+    }
+
+    #[test]
+    fn test_validate_disclose() {
+        let json = r#"{
+            "use_case": "test",
+            "private_inputs": { "type": "object", "fields": { "age": "u32", "region": "string" } },
+            "public_params": { "min_age": "u32" },
+            "validation_rules": [
+                { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 }
+            ],
+            "disclose": { "region": "input.region", "adult": "input.age >= 18" }
+        }"#;
+        assert!(DslParser::parse_str(json).is_ok());
+
+        let json = json.replace(
+            r#""adult": "input.age >= 18""#,
+            r#""adult": "input.dob >= 18", "blank": " ""#,
+        );
+        let dsl: BusinessRulesDSL = serde_json::from_str(&json).unwrap();
+        let errors = DslParser::validate_structured(&dsl).unwrap_err();
+
+        let codes: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|e| (e.field.as_deref().unwrap(), e.code.as_str()))
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                ("disclose.adult", "invalid_expression"),
+                ("disclose.blank", "required"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_rule_sets() {
+        let json = r#"{
+            "use_case": "test",
+            "private_inputs": { "type": "object", "fields": { "age": "u32", "quantity": "u32" } },
+            "public_params": { "product_line": "string", "min_age": "u32" },
+            "validation_rules": [],
+            "rule_sets": {
+                "selector": "product_line",
+                "sets": {
+                    "pharma": [
+                        { "type": "range_check", "field": "quantity", "min": 1, "max": 30 }
+                    ],
+                    "alcohol": [
+                        { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 }
+                    ]
+                }
+            }
+        }"#;
+        assert!(DslParser::parse_str(json).is_ok());
+
+        // Each set is checked on its own, and the selector must be a string param
+        let json = json
+            .replace(r#""selector": "product_line""#, r#""selector": "min_age""#)
+            .replace(r#""min": 1, "max": 30"#, r#""min": 1"#)
+            .replace(r#""field": "age""#, r#""field": """#)
+            .replace(r#""pharma": ["#, r#""empty": [], "pharma": ["#);
+        let dsl: BusinessRulesDSL = serde_json::from_str(&json).unwrap();
+        let errors = DslParser::validate_structured(&dsl).unwrap_err();
+
+        let codes: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|e| (e.field.as_deref().unwrap(), e.code.as_str()))
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                ("rule_sets.selector", "invalid_selector_type"),
+                ("rule_sets.sets.empty", "required"),
+                ("rule_sets.sets.pharma[0].max", "required"),
+                ("rule_sets.sets.alcohol[0].field", "required"),
+            ]
+        );
+        assert!(errors.iter().all(|e| e.rule_index.is_none()));
+    }
+
+    #[test]
+    fn test_param_kind_mismatch_rejected() {
+        let dsl_with = |rule: serde_json::Value| -> BusinessRulesDSL {
+            serde_json::from_value(serde_json::json!({
+                "use_case": "test",
+                "private_inputs": {
+                    "type": "object",
+                    "fields": {
+                        "age": "u32",
+                        "country": "string",
+                        "tags": "array<string>",
+                        "signature": "bytes",
+                        "issued": "string"
+                    }
+                },
+                "public_params": {
+                    "limit": "u32",
+                    "region": "string",
+                    "codes": "array<string>",
+                    "ids": "array<u32>",
+                    "key": "bytes"
+                },
+                "validation_rules": [rule]
+            }))
+            .unwrap()
+        };
+
+        let mismatches = [
+            (
+                serde_json::json!({ "type": "range_check", "field": "age", "min": 0, "max_param": "region" }),
+                "max_param",
+            ),
+            (
+                serde_json::json!({ "type": "range_check", "field": "age", "min_param": "codes", "max": 9 }),
+                "min_param",
+            ),
+            (
+                serde_json::json!({ "type": "age_verification", "dob_field": "issued", "min_age_param": "key" }),
+                "min_age_param",
+            ),
+            (
+                serde_json::json!({ "type": "blacklist_check", "field": "country", "blacklist_param": "region" }),
+                "blacklist_param",
+            ),
+            (
+                serde_json::json!({ "type": "array_intersection_check", "field": "tags", "prohibited_param": "limit" }),
+                "prohibited_param",
+            ),
+            (
+                serde_json::json!({ "type": "iso_country_check", "field": "country", "set_param": "ids" }),
+                "set_param",
+            ),
+            (
+                serde_json::json!({
+                    "type": "date_range_check",
+                    "date_field": "issued",
+                    "reference_date_param": "limit",
+                    "within_days": 30
+                }),
+                "reference_date_param",
+            ),
+            (
+                serde_json::json!({
+                    "type": "date_range_check",
+                    "date_field": "issued",
+                    "reference_date_param": "region",
+                    "before_param": "key",
+                    "within_days_param": "region"
+                }),
+                "before_param",
+            ),
+            (
+                serde_json::json!({
+                    "type": "signature_check",
+                    "field": "signature",
+                    "algorithm": "ed25519",
+                    "public_key_param": "region",
+                    "message_fields": ["country"]
+                }),
+                "public_key_param",
+            ),
+        ];
+
+        for (rule, property) in mismatches {
+            let errors = DslParser::validate_structured(&dsl_with(rule.clone())).unwrap_err();
+            let error = &errors[0];
+            assert_eq!(error.rule_index, Some(0), "{}", rule);
+            assert_eq!(error.field.as_deref(), Some(property), "{}", rule);
+            assert_eq!(error.code, "param_type_mismatch", "{}", rule);
+        }
+
+        // The message names the rule, param and both kinds
+        let errors = DslParser::validate_structured(&dsl_with(serde_json::json!({
+            "type": "range_check", "field": "age", "min": 0, "max_param": "region"
+        })))
+        .unwrap_err();
+        assert_eq!(
+            errors[0].message,
+            "range_check: max_param 'region' must be a numeric (u32, u64, i32 or i64) param, got 'string'"
+        );
+
+        // Nested rules are checked too, and every mismatch is reported
+        let errors = DslParser::validate_structured(&dsl_with(serde_json::json!({
+            "type": "not",
+            "rule": {
+                "type": "date_range_check",
+                "date_field": "issued",
+                "reference_date_param": "region",
+                "before_param": "key",
+                "within_days_param": "region"
+            }
+        })))
+        .unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_deref().unwrap()).collect();
+        assert_eq!(fields, ["rule.before_param", "rule.within_days_param"]);
+
+        // Params of the right kind are accepted
+        for rule in [
+            serde_json::json!({ "type": "range_check", "field": "age", "min": 0, "max_param": "limit" }),
+            serde_json::json!({ "type": "blacklist_check", "field": "age", "blacklist_param": "ids" }),
+            serde_json::json!({ "type": "iso_country_check", "field": "country", "set_param": "codes" }),
+            serde_json::json!({
+                "type": "date_range_check",
+                "date_field": "issued",
+                "reference_date_param": "region",
+                "within_days_param": "limit"
+            }),
+        ] {
+            assert!(
+                DslParser::validate_structured(&dsl_with(rule.clone())).is_ok(),
+                "{}",
+                rule
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_param_defaults() {
+        let json = r#"{
+            "use_case": "test",
+            "private_inputs": { "type": "object", "fields": { "age": "u32" } },
+            "public_params": { "min_age": "u32", "region": "string" },
+            "param_defaults": { "min_age": 18, "region": "EU" },
+            "validation_rules": [
+                { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 }
+            ]
+        }"#;
+        assert!(DslParser::parse_str(json).is_ok());
+
+        let json = json
+            .replace(r#""min_age": 18"#, r#""min_age": "eighteen""#)
+            .replace(r#""region": "EU""#, r#""country": "DE""#);
+        let dsl: BusinessRulesDSL = serde_json::from_str(&json).unwrap();
+        let errors = DslParser::validate_structured(&dsl).unwrap_err();
+
+        // Reported in declaration order
+        let codes: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|e| (e.field.as_deref().unwrap(), e.code.as_str()))
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                ("param_defaults.min_age", "invalid_default"),
+                ("param_defaults.country", "unknown_param"),
+            ]
+        );
+    }
 }
@@ -202,6 +202,42 @@ fn test_generate_sdk_package() {
     );
 }
 
+#[test]
+fn test_use_case_sanitized_into_crate_names() {
+    let dsl = DslParser::parse_str(
+        r#"{
+            "use_case": "Age Verification!",
+            "private_inputs": { "type": "object", "fields": { "age": "u32" } },
+            "public_params": { "min_age": "u32" },
+            "validation_rules": [
+                { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 }
+            ]
+        }"#,
+    )
+    .unwrap();
+    let generator = CodeGenerator::new(dsl);
+
+    assert_eq!(generator.guest_package_name(), "age_verification-guest");
+    assert_eq!(generator.methods_package_name(), "age_verification-methods");
+    assert_eq!(generator.guest_elf_constant(), "AGE_VERIFICATION_GUEST_ELF");
+
+    // The generated manifests use the same names
+    let temp_dir = TempDir::new().unwrap();
+    generator.generate_sdk_package(temp_dir.path()).unwrap();
+    let guest_cargo = fs::read_to_string(temp_dir.path().join("methods/guest/Cargo.toml")).unwrap();
+    assert!(guest_cargo.contains("name = \"age_verification-guest\""));
+    let methods_cargo = fs::read_to_string(temp_dir.path().join("methods/Cargo.toml")).unwrap();
+    assert!(methods_cargo.contains("name = \"age_verification-methods\""));
+
+    // risc0_build derives the ELF constant from the guest package name
+    let constant = generator
+        .guest_package_name()
+        .to_uppercase()
+        .replace('-', "_")
+        + "_ELF";
+    assert_eq!(constant, generator.guest_elf_constant());
+}
+
 #[test]
 fn test_type_generation_with_multiple_fields() {
     let dsl = DslParser::parse_file("../../docs/examples/pharma-rules.json")
@@ -303,3 +339,598 @@ fn test_code_compiles_syntax() {
 
     println!("Generated code passes syntax validation");
 }
+
+#[test]
+fn test_guest_commits_rule_tags_metadata() {
+    let dsl = DslParser::parse_file("../../docs/examples/pharma-rules.json")
+        .expect("Failed to parse DSL");
+
+    let generator = CodeGenerator::new(dsl);
+    let code = generator.generate().expect("Failed to generate code");
+
+    // Rule tags are emitted in DSL order
+    assert!(
+        code.contains(
+            r#"const RULE_TAGS: &[&str] = &[
+    "signature_check:prescriber_signature",
+    "range_check:quantity",
+    "age_verification:patient_dob",
+    "custom",
+];"#
+        ),
+        "Missing or misordered RULE_TAGS"
+    );
+    assert!(
+        code.contains("const MAX_METADATA_LEN: usize = 256;"),
+        "Missing metadata size bound"
+    );
+
+    // Outputs carry the metadata, populated only when validation passes
+    assert!(
+        code.contains("pub metadata: Vec<u8>"),
+        "Missing metadata output"
+    );
+    assert!(
+        code.contains("passed_rules_metadata(&public_params)"),
+        "Guest does not commit rule tags"
+    );
+    let main_start = code.find("fn main()").expect("main not found");
+    assert!(
+        code[main_start..].contains("metadata,"),
+        "Outputs not populated with metadata"
+    );
+}
+
+#[test]
+fn test_guest_records_failing_rule_index() {
+    let json = r#"{
+        "use_case": "age_check",
+        "report_failed_rule": true,
+        "private_inputs": { "type": "object", "fields": { "age": "u32", "country": "string" } },
+        "public_params": { "min_age": "u32", "blocked": "array<string>" },
+        "validation_rules": [
+            { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 },
+            { "type": "blacklist_check", "field": "country", "blacklist_param": "blocked" }
+        ]
+    }"#;
+    let dsl = DslParser::parse_str(json).expect("Failed to parse DSL");
+
+    let generator = CodeGenerator::new(dsl);
+    let code = generator.generate().expect("Failed to generate code");
+
+    // Each guard reports its own rule index, in DSL order
+    let validate_start = code
+        .find("fn validate_all")
+        .expect("validate_all not found");
+    let validate_section = &code[validate_start..];
+    let first = validate_section
+        .find("return Err(0);")
+        .expect("Missing index of first rule");
+    let second = validate_section
+        .find("return Err(1);")
+        .expect("Missing index of second rule");
+    assert!(first < second);
+
+    // The failing index is committed as metadata
+    assert!(
+        code.contains(r#"format!("{}{}", "failed_rule:", rule_index)"#),
+        "Guest does not record the failing rule index"
+    );
+    let main_start = code.find("fn main()").expect("main not found");
+    assert!(
+        code[main_start..].contains("Err(rule_index) => failed_rule_metadata(rule_index)"),
+        "Guest does not commit failing rule metadata"
+    );
+    assert!(syn::parse_file(&code).is_ok());
+
+    // Without the opt-in, a failing proof reveals nothing about which rule failed
+    let dsl = DslParser::parse_str(&json.replace(r#""report_failed_rule": true,"#, ""))
+        .expect("Failed to parse DSL");
+    let code = CodeGenerator::new(dsl)
+        .generate()
+        .expect("Failed to generate code");
+    assert!(!code.contains("failed_rule:"));
+}
+
+#[test]
+fn test_guest_selects_rule_set_by_selector() {
+    let dsl = DslParser::parse_str(
+        r#"{
+            "use_case": "product_compliance",
+            "private_inputs": {
+                "type": "object",
+                "fields": { "age": "u32", "quantity": "u32", "country": "string" }
+            },
+            "public_params": { "product_line": "string", "min_age": "u32", "blocked": "array<string>" },
+            "validation_rules": [
+                { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 }
+            ],
+            "rule_sets": {
+                "selector": "product_line",
+                "sets": {
+                    "pharma": [
+                        { "type": "range_check", "field": "quantity", "min": 1, "max": 30 }
+                    ],
+                    "food": [
+                        { "type": "blacklist_check", "field": "country", "blacklist_param": "blocked" }
+                    ]
+                }
+            }
+        }"#,
+    )
+    .expect("Failed to parse DSL");
+
+    let generator = CodeGenerator::new(dsl);
+    let code = generator.generate().expect("Failed to generate code");
+
+    let validate_start = code
+        .find("fn validate_all")
+        .expect("validate_all not found");
+    let validate_end = code.find("const RULE_TAGS").expect("RULE_TAGS not found");
+    let validate_all = &code[validate_start..validate_end];
+
+    // Top-level rules run first, then the set matching the selector's value
+    let common = validate_all
+        .find("private_inputs.age")
+        .expect("Missing top-level rule");
+    let dispatch = validate_all
+        .find("match public_params.product_line.as_str()")
+        .expect("Missing dispatch on the selector");
+    assert!(common < dispatch);
+
+    let pharma = validate_all
+        .find(r#""pharma" =>"#)
+        .expect("Missing pharma rule set");
+    let food = validate_all
+        .find(r#""food" =>"#)
+        .expect("Missing food rule set");
+    assert!(pharma < food);
+    assert!(validate_all[pharma..food].contains("private_inputs.quantity"));
+    assert!(!validate_all[pharma..food].contains("public_params.blocked"));
+    assert!(validate_all[food..].contains("public_params.blocked"));
+    assert!(!validate_all[food..].contains("private_inputs.quantity"));
+
+    // An unknown selector value fails, and set rules are indexed after it
+    assert!(validate_all.contains("_ => return Err(1),"));
+    assert!(validate_all[pharma..food].contains("return Err(2);"));
+
+    // Passing proofs commit the tags of the rules actually checked
+    assert!(code.contains(r#""pharma" => tags.extend_from_slice(&["range_check:quantity"]),"#));
+    assert!(code.contains(r#""food" => tags.extend_from_slice(&["blacklist_check:country"]),"#));
+    assert!(syn::parse_file(&code).is_ok());
+}
+
+#[test]
+fn test_guest_fills_in_param_defaults() {
+    let dsl = DslParser::parse_str(
+        r#"{
+            "use_case": "age_check",
+            "private_inputs": { "type": "object", "fields": { "age": "u32" } },
+            "public_params": { "min_age": "u32", "max_age": "u32" },
+            "param_defaults": { "min_age": 18 },
+            "validation_rules": [
+                { "type": "range_check", "field": "age", "min_param": "min_age", "max_param": "max_age" }
+            ]
+        }"#,
+    )
+    .expect("Failed to parse DSL");
+
+    let generator = CodeGenerator::new(dsl);
+    let code = generator.generate().expect("Failed to generate code");
+
+    // The omitted param is filled in while the guest parses its public params,
+    // before validate_all runs
+    assert!(code.contains("#[serde(default = \"default_min_age\")]\n    pub min_age: u32"));
+    assert!(code.contains("fn default_min_age() -> u32 {\n    18\n}"));
+    assert!(!code.contains("default_max_age"));
+
+    let main = &code[code.find("fn main()").expect("main not found")..];
+    let parse = main
+        .find("serde_json::from_str(&public_json)")
+        .expect("Missing public params parse");
+    let validate = main
+        .find("validate_all(&private_inputs, &public_params)")
+        .expect("Missing validate_all call");
+    assert!(parse < validate);
+    assert!(syn::parse_file(&code).is_ok());
+}
+
+#[test]
+fn test_guest_commits_disclosed_facts() {
+    let dsl = DslParser::parse_str(
+        r#"{
+            "use_case": "shipping",
+            "private_inputs": {
+                "type": "object",
+                "fields": { "address": "string", "region": "string", "weight_kg": "u32" }
+            },
+            "public_params": { "max_weight_kg": "u32" },
+            "validation_rules": [
+                { "type": "range_check", "field": "weight_kg", "min": 1, "max_param": "max_weight_kg" }
+            ],
+            "disclose": { "destination_region": "input.region", "heavy": "input.weight_kg > 20" }
+        }"#,
+    )
+    .expect("Failed to parse DSL");
+
+    let generator = CodeGenerator::new(dsl);
+    let code = generator.generate().expect("Failed to generate code");
+
+    // Each declared fact is computed from its expression, in declaration order
+    let disclosure_start = code
+        .find("fn disclosure_metadata")
+        .expect("disclosure_metadata not found");
+    let disclosure = &code[disclosure_start..];
+    let region = disclosure
+        .find(r#""destination_region".to_string()"#)
+        .expect("Missing destination_region fact");
+    let heavy = disclosure
+        .find(r#""heavy".to_string()"#)
+        .expect("Missing heavy fact");
+    assert!(region < heavy);
+    assert!(disclosure.contains("private_inputs.region.as_str()"));
+    assert!(disclosure.contains("i128::from(private_inputs.weight_kg)"));
+    assert!(disclosure.contains(r#""disclosed" : disclosed"#));
+
+    // Only the declared facts are revealed, not the rest of the inputs
+    assert!(!disclosure[..disclosure.find("\n}\n").unwrap()].contains("address"));
+
+    // Passing proofs commit the disclosure instead of the plain rule tags
+    let main_start = code.find("fn main()").expect("main not found");
+    assert!(code[main_start..]
+        .contains("Ok(()) => disclosure_metadata(&private_inputs, &public_params)"));
+    assert!(syn::parse_file(&code).is_ok());
+}
+
+#[test]
+fn test_not_rule_inverts_nested_check() {
+    // Not(blacklist_check) means the value MUST be in the list
+    let dsl = DslParser::parse_str(
+        r#"{
+            "use_case": "allowlist",
+            "private_inputs": { "type": "object", "fields": { "country": "string" } },
+            "public_params": { "allowed_countries": "array<string>" },
+            "validation_rules": [
+                {
+                    "type": "not",
+                    "description": "Country must be allowed",
+                    "rule": {
+                        "type": "blacklist_check",
+                        "field": "country",
+                        "blacklist_param": "allowed_countries"
+                    }
+                }
+            ]
+        }"#,
+    )
+    .expect("Failed to parse DSL");
+
+    let generator = CodeGenerator::new(dsl);
+    let code = generator.generate().expect("Failed to generate code");
+
+    let validate_start = code
+        .find("fn validate_all")
+        .expect("validate_all not found");
+    let validate_section = &code[validate_start..];
+
+    // The nested blacklist check passes when the value is NOT listed...
+    let inner_pos = validate_section
+        .find("!blacklist.contains(value)")
+        .expect("Missing nested blacklist check");
+    // ...and the wrapper fails validation exactly when it passes
+    let invert_pos = validate_section
+        .find("!inner_passed")
+        .expect("Missing inversion of nested rule");
+    assert!(inner_pos < invert_pos);
+
+    assert!(
+        code.contains("\"not:blacklist_check:country\""),
+        "Missing rule tag for not rule"
+    );
+}
+
+#[test]
+fn test_any_of_passes_when_second_rule_passes() {
+    // Signature OR manual override: an invalid signature alone must not fail validation
+    let dsl = DslParser::parse_str(
+        r#"{
+            "use_case": "override",
+            "private_inputs": {
+                "type": "object",
+                "fields": {
+                    "request_id": "string",
+                    "signature": "bytes",
+                    "manual_override": "bool"
+                }
+            },
+            "public_params": { "pubkey": "bytes" },
+            "validation_rules": [
+                {
+                    "type": "any_of",
+                    "description": "Signature valid or manual override present",
+                    "rules": [
+                        {
+                            "type": "signature_check",
+                            "field": "signature",
+                            "algorithm": "ed25519",
+                            "public_key_param": "pubkey",
+                            "message_fields": ["request_id"]
+                        },
+                        { "type": "custom", "code": "private_inputs.manual_override" }
+                    ]
+                }
+            ]
+        }"#,
+    )
+    .expect("Failed to parse DSL");
+
+    let generator = CodeGenerator::new(dsl);
+    let code = generator.generate().expect("Failed to generate code");
+
+    let validate_start = code
+        .find("fn validate_all")
+        .expect("validate_all not found");
+    let validate_section = &code[validate_start..];
+
+    // Both alternatives are combined into a single condition...
+    let sig_pos = validate_section
+        .find("verify_signature_placeholder")
+        .expect("Missing first alternative");
+    let or_pos = validate_section
+        .find("||")
+        .expect("Missing short-circuit OR");
+    let override_pos = validate_section
+        .find("private_inputs.manual_override")
+        .expect("Missing second alternative");
+    assert!(sig_pos < or_pos && or_pos < override_pos);
+
+    // ...so a failing signature check doesn't return before the override is tried
+    assert!(
+        !validate_section[..override_pos].contains("return Err"),
+        "First alternative fails validation on its own"
+    );
+
+    assert!(
+        code.contains("\"any_of:signature_check:signature|custom\""),
+        "Missing rule tag for any_of rule"
+    );
+}
+
+#[test]
+fn test_iso_country_check_embeds_country_table() {
+    let dsl = DslParser::parse_str(
+        r#"{
+            "use_case": "sanctions",
+            "private_inputs": { "type": "object", "fields": { "country": "string" } },
+            "public_params": { "sanctioned_countries": "array<string>" },
+            "validation_rules": [
+                {
+                    "type": "iso_country_check",
+                    "field": "country",
+                    "set_param": "sanctioned_countries",
+                    "exclude": true
+                }
+            ]
+        }"#,
+    )
+    .expect("Failed to parse DSL");
+
+    let code = CodeGenerator::new(dsl)
+        .generate()
+        .expect("Failed to generate code");
+
+    // Valid codes are listed in both forms, unknown codes are not
+    assert!(code.contains("(\"DE\", \"DEU\")"), "Missing country table");
+    assert!(!code.contains("\"XX\""), "Unassigned code in country table");
+
+    // Invalid codes fail, valid ones are then checked against the sanctioned set
+    let validate_start = code
+        .find("fn validate_all")
+        .expect("validate_all not found");
+    let validate_section = &code[validate_start..];
+    assert!(validate_section.contains("match iso_country_alpha2(value)"));
+    assert!(validate_section.contains("None => false"));
+    assert!(validate_section.contains("!in_set"));
+
+    // Guests without the rule don't carry the table
+    let dsl = DslParser::parse_file("../../docs/examples/shipping-rules.json")
+        .expect("Failed to parse DSL");
+    let code = CodeGenerator::new(dsl)
+        .generate()
+        .expect("Failed to generate code");
+    assert!(!code.contains("ISO_3166_COUNTRIES"));
+}
+
+#[test]
+fn test_date_range_check_generates_day_comparison() {
+    // "Document issued within the last 30 days, and not before 2020"
+    let dsl = DslParser::parse_str(
+        r#"{
+            "use_case": "document_freshness",
+            "private_inputs": { "type": "object", "fields": { "issued_on": "string" } },
+            "public_params": { "today": "string" },
+            "validation_rules": [
+                {
+                    "type": "date_range_check",
+                    "date_field": "issued_on",
+                    "reference_date_param": "today",
+                    "after": "2020-01-01",
+                    "within_days": 30
+                }
+            ]
+        }"#,
+    )
+    .expect("Failed to parse DSL");
+
+    let code = CodeGenerator::new(dsl)
+        .generate()
+        .expect("Failed to generate code");
+
+    let validate_start = code
+        .find("fn validate_all")
+        .expect("validate_all not found");
+    let validate_section = &code[validate_start..];
+
+    // Both the field and the reference date are parsed into day numbers
+    assert!(validate_section.contains("parse_iso_date_days(&private_inputs.issued_on)"));
+    assert!(validate_section.contains("parse_iso_date_days(&public_params.today)"));
+
+    // Bounds are integer comparisons on day numbers
+    assert!(validate_section.contains("parse_iso_date_days(\"2020-01-01\")"));
+    assert!(validate_section.contains("date >= after"));
+    assert!(validate_section.contains("reference - date <= 30i64"));
+    assert!(
+        !validate_section.contains("f64"),
+        "Date comparison uses floats"
+    );
+
+    assert!(
+        code.contains("fn parse_iso_date_days"),
+        "Missing date helper"
+    );
+}
+
+#[test]
+fn test_pharma_input_manifest() {
+    use logic_compiler::codegen::Visibility;
+
+    let dsl = DslParser::parse_file("../../docs/examples/pharma-rules.json")
+        .expect("Failed to parse pharma DSL");
+    let manifest = CodeGenerator::new(dsl).input_manifest();
+
+    let private: Vec<(&str, &str, Vec<&str>)> = manifest
+        .iter()
+        .filter(|field| field.visibility == Visibility::Private)
+        .map(|field| {
+            let rules = field
+                .referenced_by
+                .iter()
+                .map(|rule| rule.rule_type.as_str())
+                .collect();
+            (field.name.as_str(), field.rust_type.as_str(), rules)
+        })
+        .collect();
+
+    assert_eq!(
+        private,
+        vec![
+            ("drug_name", "String", vec!["signature_check"]),
+            ("quantity", "u32", vec!["range_check"]),
+            (
+                "patient_dob",
+                "String",
+                vec!["signature_check", "age_verification"]
+            ),
+            ("prescriber_id", "String", vec!["signature_check", "custom"]),
+            ("prescriber_signature", "Vec<u8>", vec!["signature_check"]),
+        ]
+    );
+    assert!(manifest
+        .iter()
+        .filter(|field| field.visibility == Visibility::Private)
+        .all(|field| field.required && field.path.starts_with("private_inputs.prescription.")));
+
+    let public: Vec<&str> = manifest
+        .iter()
+        .filter(|field| field.visibility == Visibility::Public)
+        .map(|field| field.name.as_str())
+        .collect();
+    assert_eq!(public, vec!["max_quantity", "min_age", "prescriber_pubkey"]);
+}
+
+#[test]
+fn test_generation_is_deterministic() {
+    let dsl_json = r#"{
+        "use_case": "many_fields",
+        "private_inputs": {
+            "type": "object",
+            "fields": {
+                "zeta": "u32", "alpha": "string", "mu": "u64", "beta": "bytes",
+                "omega": "bool", "gamma": "i32", "kappa": "string", "delta": "u32"
+            }
+        },
+        "public_params": {
+            "max_zeta": "u32", "min_mu": "u64", "banned": "array<string>", "flag": "bool"
+        },
+        "validation_rules": [
+            { "type": "range_check", "field": "zeta", "min": 0, "max_param": "max_zeta" }
+        ],
+        "outputs": { "compliance_result": "bool", "zz_hash": "bytes", "aa_count": "u32" }
+    }"#;
+
+    let generate = || {
+        CodeGenerator::new(DslParser::parse_str(dsl_json).unwrap())
+            .generate()
+            .unwrap()
+    };
+    let first = generate();
+    for _ in 0..5 {
+        assert_eq!(generate(), first);
+    }
+
+    // Fields are emitted in the order the DSL declares them
+    let positions: Vec<usize> = [
+        "zeta", "alpha", "mu", "beta", "omega", "gamma", "kappa", "delta",
+    ]
+    .iter()
+    .map(|field| first.find(&format!("pub {}:", field)).unwrap())
+    .collect();
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn test_declaration_order_preserved() {
+    let dsl_json = r#"{
+        "use_case": "shipment",
+        "private_inputs": {
+            "shipper": { "type": "object", "fields": { "name": "string", "id": "u32" } },
+            "cargo": { "type": "object", "fields": { "weight": "u64", "contents": "string" } }
+        },
+        "public_params": { "max_weight": "u64", "allowed_shipper": "u32" },
+        "validation_rules": [
+            { "type": "range_check", "field": "weight", "min": 0, "max_param": "max_weight" }
+        ],
+        "outputs": { "compliance_result": "bool", "shipment_hash": "bytes", "checked_at": "u64" }
+    }"#;
+
+    // API handlers receive the DSL as a serde_json::Value before parsing it
+    let value: serde_json::Value = serde_json::from_str(dsl_json).unwrap();
+    let dsl = DslParser::parse_str(&value.to_string()).unwrap();
+
+    // Serializing keeps the declared order too
+    let reserialized = serde_json::to_string(&dsl).unwrap();
+    assert!(reserialized.find("\"shipper\"").unwrap() < reserialized.find("\"cargo\"").unwrap());
+
+    let code = CodeGenerator::new(dsl.clone()).generate().unwrap();
+    let in_order = |items: &[&str]| {
+        let positions: Vec<usize> = items.iter().map(|item| code.find(item).unwrap()).collect();
+        positions.windows(2).all(|pair| pair[0] < pair[1])
+    };
+    assert!(in_order(&["struct Shipper", "struct Cargo"]));
+    assert!(in_order(&["pub shipper: Shipper", "pub cargo: Cargo"]));
+    assert!(in_order(&[
+        "pub name:",
+        "pub id:",
+        "pub weight:",
+        "pub contents:"
+    ]));
+    assert!(in_order(&["pub max_weight:", "pub allowed_shipper:"]));
+    assert!(in_order(&["pub shipment_hash:", "pub checked_at:"]));
+
+    let paths: Vec<String> = CodeGenerator::new(dsl)
+        .input_manifest()
+        .into_iter()
+        .map(|field| field.path)
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            "private_inputs.shipper.name",
+            "private_inputs.shipper.id",
+            "private_inputs.cargo.weight",
+            "private_inputs.cargo.contents",
+            "public_params.max_weight",
+            "public_params.allowed_shipper",
+        ]
+    );
+}
@@ -0,0 +1,210 @@
+//! Configuration management for Proof Generation Service
+//!
+//! Loads configuration from environment variables with sensible defaults.
+
+use anyhow::{Context, Result};
+use std::env;
+
+use crate::prover::{ExecutionLimits, PROVER_BACKENDS};
+
+/// Application configuration
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Image ID Registry URL
+    pub registry_url: String,
+
+    /// API server host
+    pub host: String,
+
+    /// API server port
+    pub port: u16,
+
+    /// Requested RISC Zero prover backend (`RISC0_PROVER`, e.g. "local" or
+    /// "bonsai"); `None` lets `default_prover()` choose
+    pub prover_backend: Option<String>,
+
+    /// Maximum number of proofs generated at once
+    pub max_concurrent_proofs: usize,
+
+    /// Guest execution limits
+    pub limits: ExecutionLimits,
+
+    /// Customers whose guest programs are loaded at startup
+    pub preload_customers: Vec<String>,
+
+    /// Seconds between attempts to load preloaded programs while the registry is down
+    pub registry_retry_secs: u64,
+}
+
+impl Config {
+    /// Load configuration from environment variables
+    pub fn from_env() -> Result<Self> {
+        let config = Config {
+            registry_url: env::var("REGISTRY_URL")
+                .unwrap_or_else(|_| "http://127.0.0.1:8083".to_string()),
+
+            host: env::var("PROVER_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
+
+            port: env::var("PROVER_PORT")
+                .unwrap_or_else(|_| "8084".to_string())
+                .parse()
+                .context("Invalid PROVER_PORT")?,
+
+            prover_backend: env::var("RISC0_PROVER")
+                .ok()
+                .map(|v| v.to_lowercase())
+                .filter(|v| !v.is_empty()),
+
+            max_concurrent_proofs: env::var("MAX_CONCURRENT_PROOFS")
+                .unwrap_or_else(|_| "1".to_string())
+                .parse()
+                .context("Invalid MAX_CONCURRENT_PROOFS")?,
+
+            limits: ExecutionLimits::from_env(),
+
+            preload_customers: env::var("PRELOAD_CUSTOMERS")
+                .map(|v| {
+                    v.split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default(),
+
+            registry_retry_secs: env::var("REGISTRY_RETRY_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .context("Invalid REGISTRY_RETRY_SECS")?,
+        };
+
+        // Validate configuration
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Validate configuration
+    fn validate(&self) -> Result<()> {
+        if self.port == 0 {
+            anyhow::bail!("PROVER_PORT must be greater than 0");
+        }
+
+        let url = reqwest::Url::parse(&self.registry_url)
+            .with_context(|| format!("Invalid REGISTRY_URL: {}", self.registry_url))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            anyhow::bail!(
+                "REGISTRY_URL must be an http(s) URL, got {}",
+                self.registry_url
+            );
+        }
+
+        if let Some(backend) = &self.prover_backend {
+            if !PROVER_BACKENDS.contains(&backend.as_str()) {
+                anyhow::bail!(
+                    "RISC0_PROVER must be one of {}, got {}",
+                    PROVER_BACKENDS.join(", "),
+                    backend
+                );
+            }
+        }
+
+        if self.registry_retry_secs == 0 {
+            anyhow::bail!("REGISTRY_RETRY_SECS must be greater than 0");
+        }
+
+        if self.max_concurrent_proofs == 0 {
+            anyhow::bail!("MAX_CONCURRENT_PROOFS must be greater than 0");
+        }
+
+        Ok(())
+    }
+
+    /// Get the API server address
+    pub fn api_address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // Tests share process environment variables
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    const VARS: &[&str] = &[
+        "REGISTRY_URL",
+        "PROVER_HOST",
+        "PROVER_PORT",
+        "RISC0_PROVER",
+        "MAX_CONCURRENT_PROOFS",
+        "PROVER_SESSION_LIMIT",
+        "PROVER_SEGMENT_LIMIT_PO2",
+        "PROVER_MAX_INPUT_BYTES",
+        "PRELOAD_CUSTOMERS",
+        "REGISTRY_RETRY_SECS",
+    ];
+
+    #[test]
+    fn test_config_defaults() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        for var in VARS {
+            env::remove_var(var);
+        }
+
+        let config = Config::from_env().expect("Failed to load config");
+
+        assert_eq!(config.registry_url, "http://127.0.0.1:8083");
+        assert_eq!(config.api_address(), "0.0.0.0:8084");
+        assert_eq!(config.prover_backend, None);
+        assert_eq!(config.max_concurrent_proofs, 1);
+        assert_eq!(config.limits.session_limit, None);
+        assert_eq!(
+            config.limits.max_input_bytes,
+            Some(crate::prover::DEFAULT_MAX_INPUT_BYTES)
+        );
+        assert!(config.preload_customers.is_empty());
+        assert_eq!(config.registry_retry_secs, 30);
+    }
+
+    #[test]
+    fn test_malformed_registry_url_rejected() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        for var in VARS {
+            env::remove_var(var);
+        }
+
+        env::set_var("REGISTRY_URL", "not a url");
+        let err = Config::from_env().unwrap_err();
+        assert!(err.to_string().contains("REGISTRY_URL"));
+
+        env::set_var("REGISTRY_URL", "ftp://registry:8083");
+        assert!(Config::from_env().is_err());
+
+        env::remove_var("REGISTRY_URL");
+        env::set_var("PROVER_PORT", "http");
+        assert!(Config::from_env().is_err());
+
+        env::remove_var("PROVER_PORT");
+    }
+
+    #[test]
+    fn test_prover_backend_validated() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        for var in VARS {
+            env::remove_var(var);
+        }
+
+        env::set_var("RISC0_PROVER", "Bonsai");
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.prover_backend.as_deref(), Some("bonsai"));
+
+        env::set_var("RISC0_PROVER", "gpu");
+        let err = Config::from_env().unwrap_err();
+        assert!(err.to_string().contains("RISC0_PROVER"));
+
+        env::remove_var("RISC0_PROVER");
+    }
+}
@@ -1,4 +1,7 @@
 //! API handlers for Proof Generation Service
+//!
+//! Successful responses are wrapped as `{ "data": ... }` and failures as
+//! `{ "error": "..." }`.
 
 use axum::{
     extract::State,
@@ -6,20 +9,39 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use serde::Serialize;
+use std::collections::BTreeSet;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{error, info};
+use std::time::Instant;
+use tokio::sync::{RwLock, Semaphore};
+use khafi_common::Nullifier;
+use logic_compiler::DslParser;
+use tracing::{error, info, warn};
 
 use crate::{
-    models::{GenerateProofRequest, GenerateProofResponse, GuestProgram},
-    prover::Prover,
-    registry_client::RegistryClient,
+    input_validation::validate_inputs,
+    metrics::ProofMetrics,
+    models::{
+        EstimateResponse, GenerateProofRequest, GenerateProofResponse, GuestProgram, LoadedProgram,
+        ProgramsResponse,
+    },
+    prover::{ProverBackend, ProverError, ProvingBackend},
+    registry_client::{DeploymentInfo, RegistryClient},
 };
 
 /// Shared application state
 pub struct AppState {
-    pub prover: RwLock<Prover>,
+    /// Proving backend (the RISC Zero [`Prover`](crate::Prover) outside tests)
+    pub prover: RwLock<Box<dyn ProvingBackend>>,
     pub registry_client: RegistryClient,
+    /// Customers whose programs should be preloaded but couldn't be fetched yet
+    pub pending_programs: RwLock<BTreeSet<String>>,
+    /// Prover backend detected at startup
+    pub prover_backend: ProverBackend,
+    /// Proof latency and cycle metrics served at `/metrics`
+    pub metrics: ProofMetrics,
+    /// One permit per proof that may run at once (`MAX_CONCURRENT_PROOFS`)
+    pub proof_slots: Semaphore,
 }
 
 /// API Error type
@@ -39,6 +61,25 @@ impl IntoResponse for ApiError {
     }
 }
 
+/// Successful response envelope
+#[derive(Debug, Serialize)]
+pub struct ApiResponse<T> {
+    pub data: T,
+}
+
+impl<T> ApiResponse<T> {
+    /// Wrap a response payload
+    pub fn new(data: T) -> Self {
+        Self { data }
+    }
+}
+
+impl<T: Serialize> IntoResponse for ApiResponse<T> {
+    fn into_response(self) -> Response {
+        Json(self).into_response()
+    }
+}
+
 impl From<anyhow::Error> for ApiError {
     fn from(err: anyhow::Error) -> Self {
         ApiError {
@@ -49,68 +90,76 @@ impl From<anyhow::Error> for ApiError {
 }
 
 /// Health check endpoint
-pub async fn health_handler() -> impl IntoResponse {
-    Json(serde_json::json!({
-        "status": "healthy",
-        "service": "proof-generation-service"
+///
+/// Reports "degraded" while preloaded programs are still waiting on the registry.
+pub async fn health_handler(
+    State(state): State<Arc<AppState>>,
+) -> ApiResponse<serde_json::Value> {
+    let pending: Vec<String> = state
+        .pending_programs
+        .read()
+        .await
+        .iter()
+        .cloned()
+        .collect();
+    let status = if pending.is_empty() {
+        "healthy"
+    } else {
+        "degraded"
+    };
+
+    ApiResponse::new(serde_json::json!({
+        "status": status,
+        "service": "proof-generation-service",
+        "pending_programs": pending
     }))
 }
 
 /// Generate a proof for customer inputs
+#[tracing::instrument(skip_all, fields(customer_id = %payload.customer_id))]
 pub async fn generate_proof_handler(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<GenerateProofRequest>,
-) -> Result<Json<GenerateProofResponse>, ApiError> {
+    Json(mut payload): Json<GenerateProofRequest>,
+) -> Result<ApiResponse<GenerateProofResponse>, ApiError> {
     info!("Generating proof for customer: {}", payload.customer_id);
 
-    // Check if we have the guest program loaded
+    let nullifier = parse_nullifier(&payload.nullifier)?;
+    ensure_program_loaded(&state, &payload.customer_id).await?;
+
+    // Queue for a proving slot before taking the prover lock, so waiting
+    // requests don't hold up program loads
+    let _slot = state
+        .proof_slots
+        .acquire()
+        .await
+        .map_err(anyhow::Error::from)?;
     let prover = state.prover.read().await;
-    let has_program = prover.has_program(&payload.customer_id);
-    drop(prover);
-
-    // If not loaded, fetch from registry and load it
-    if !has_program {
-        info!("Guest program not loaded, fetching from registry");
-
-        let deployment = state
-            .registry_client
-            .get_deployment(&payload.customer_id)
-            .await?
-            .ok_or_else(|| ApiError {
-                status: StatusCode::NOT_FOUND,
-                message: format!(
-                    "No deployment found for customer: {}",
-                    payload.customer_id
-                ),
-            })?;
-
-        // Load the guest program
-        let guest_program = GuestProgram::load(
-            deployment.customer_id.clone(),
-            deployment.image_id.clone(),
-            deployment.guest_program_path.clone(),
-        )
-        .map_err(|e| ApiError {
-            status: StatusCode::INTERNAL_SERVER_ERROR,
-            message: format!("Failed to load guest program: {}", e),
-        })?;
 
-        let mut prover = state.prover.write().await;
-        prover.load_program(guest_program)?;
-    }
+    // Fill in param defaults, then reject inputs that don't match the
+    // deployment's schema before proving
+    prepare_inputs(&**prover, &mut payload)?;
 
     // Generate proof
-    let prover = state.prover.read().await;
+    let started = Instant::now();
     match prover.generate_proof(
         &payload.customer_id,
         &payload.private_inputs,
         &payload.public_params,
+        &nullifier,
     ) {
         Ok(result) => {
             info!("Proof generated successfully for customer: {}", payload.customer_id);
-            Ok(Json(GenerateProofResponse {
+            state.metrics.record_proof(
+                &payload.customer_id,
+                &result.image_id,
+                started.elapsed(),
+                result.cycles.total_cycles,
+            );
+            Ok(ApiResponse::new(GenerateProofResponse {
                 success: true,
-                proof: Some(result.proof),
+                proof: Some(payload.proof_encoding.encode(&result.proof)),
+                proof_encoding: Some(payload.proof_encoding),
+                receipt_kind: Some(result.receipt_kind),
                 image_id: Some(result.image_id),
                 outputs: Some(result.outputs),
                 error: None,
@@ -118,9 +167,17 @@ pub async fn generate_proof_handler(
         }
         Err(e) => {
             error!("Proof generation failed for customer {}: {:#}", payload.customer_id, e);
-            Ok(Json(GenerateProofResponse {
+            if let Some(ProverError::InputTooLarge { .. }) = e.downcast_ref::<ProverError>() {
+                return Err(ApiError {
+                    status: StatusCode::BAD_REQUEST,
+                    message: e.to_string(),
+                });
+            }
+            Ok(ApiResponse::new(GenerateProofResponse {
                 success: false,
                 proof: None,
+                proof_encoding: None,
+                receipt_kind: None,
                 image_id: None,
                 outputs: None,
                 error: Some(format!("Proof generation failed: {}", e)),
@@ -129,11 +186,110 @@ pub async fn generate_proof_handler(
     }
 }
 
+/// Estimate the cycle count for customer inputs without generating a proof
+///
+/// Takes the same body as `/api/generate-proof`.
+#[tracing::instrument(skip_all, fields(customer_id = %payload.customer_id))]
+pub async fn estimate_handler(
+    State(state): State<Arc<AppState>>,
+    Json(mut payload): Json<GenerateProofRequest>,
+) -> Result<ApiResponse<EstimateResponse>, ApiError> {
+    let nullifier = parse_nullifier(&payload.nullifier)?;
+    ensure_program_loaded(&state, &payload.customer_id).await?;
+
+    let prover = state.prover.read().await;
+    prepare_inputs(&**prover, &mut payload)?;
+
+    let estimate = prover
+        .estimate(
+            &payload.customer_id,
+            &payload.private_inputs,
+            &payload.public_params,
+            &nullifier,
+        )
+        .map_err(|e| {
+            error!("Estimation failed for customer {}: {:#}", payload.customer_id, e);
+            let status = match e.downcast_ref::<ProverError>() {
+                Some(ProverError::InputTooLarge { .. }) => StatusCode::BAD_REQUEST,
+                Some(ProverError::CycleLimitExceeded { .. }) => StatusCode::UNPROCESSABLE_ENTITY,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            ApiError {
+                status,
+                message: format!("Estimation failed: {}", e),
+            }
+        })?;
+
+    Ok(ApiResponse::new(EstimateResponse {
+        customer_id: payload.customer_id,
+        total_cycles: estimate.total_cycles,
+        user_cycles: estimate.user_cycles,
+        segments: estimate.segments,
+    }))
+}
+
+/// Fetch a customer's guest program from the registry unless it's already loaded
+async fn ensure_program_loaded(state: &AppState, customer_id: &str) -> Result<(), ApiError> {
+    if state.prover.read().await.has_program(customer_id) {
+        return Ok(());
+    }
+
+    info!("Guest program not loaded, fetching from registry");
+
+    let deployment = state
+        .registry_client
+        .get_deployment(customer_id)
+        .await?
+        .ok_or_else(|| ApiError {
+            status: StatusCode::NOT_FOUND,
+            message: format!("No deployment found for customer: {}", customer_id),
+        })?;
+
+    // Load the guest program
+    let guest_program = load_guest_program(&deployment).map_err(|e| ApiError {
+        status: StatusCode::INTERNAL_SERVER_ERROR,
+        message: format!("Failed to load guest program: {}", e),
+    })?;
+
+    let mut prover = state.prover.write().await;
+    prover.load_program(guest_program)?;
+    Ok(())
+}
+
+/// Parse the request's hex nullifier
+fn parse_nullifier(nullifier: &str) -> Result<Nullifier, ApiError> {
+    Nullifier::from_hex(nullifier).map_err(|e| ApiError {
+        status: StatusCode::BAD_REQUEST,
+        message: format!("Invalid nullifier: {}", e),
+    })
+}
+
+/// Apply the deployment's public param defaults and validate request inputs
+/// against its DSL schema, if known
+fn prepare_inputs(
+    prover: &dyn ProvingBackend,
+    payload: &mut GenerateProofRequest,
+) -> Result<(), ApiError> {
+    if let Some(dsl) = prover
+        .get_program(&payload.customer_id)
+        .and_then(|program| program.dsl.as_ref())
+    {
+        dsl.apply_param_defaults(&mut payload.public_params);
+        validate_inputs(dsl, &payload.private_inputs, &payload.public_params).map_err(|e| {
+            ApiError {
+                status: StatusCode::BAD_REQUEST,
+                message: e.to_string(),
+            }
+        })?;
+    }
+    Ok(())
+}
+
 /// Load a guest program for a customer
 pub async fn load_program_handler(
     State(state): State<Arc<AppState>>,
     Json(customer_id): Json<String>,
-) -> Result<Json<serde_json::Value>, ApiError> {
+) -> Result<ApiResponse<serde_json::Value>, ApiError> {
     info!("Loading guest program for customer: {}", customer_id);
 
     // Fetch deployment from registry
@@ -147,37 +303,592 @@ pub async fn load_program_handler(
         })?;
 
     // Load the guest program
-    let guest_program = GuestProgram::load(
-        deployment.customer_id.clone(),
-        deployment.image_id.clone(),
-        deployment.guest_program_path.clone(),
-    )?;
+    let guest_program = load_guest_program(&deployment)?;
 
     let mut prover = state.prover.write().await;
     prover.load_program(guest_program)?;
 
     info!("Guest program loaded successfully for customer: {}", customer_id);
 
-    Ok(Json(serde_json::json!({
+    Ok(ApiResponse::new(serde_json::json!({
         "success": true,
         "customer_id": customer_id,
         "image_id": deployment.image_id
     })))
 }
 
+/// Load a deployment's guest program along with the DSL it was compiled from
+pub(crate) fn load_guest_program(deployment: &DeploymentInfo) -> anyhow::Result<GuestProgram> {
+    if let Some(compression) = &deployment.elf_compression {
+        if compression != "gzip" {
+            anyhow::bail!("Unsupported guest ELF compression: {}", compression);
+        }
+    }
+
+    let dsl = match &deployment.dsl {
+        Some(json) => match DslParser::parse_str(&json.to_string()) {
+            Ok(dsl) => Some(dsl),
+            Err(e) => {
+                warn!(
+                    "Ignoring invalid DSL for customer {}, inputs will not be validated: {:#}",
+                    deployment.customer_id, e
+                );
+                None
+            }
+        },
+        None => None,
+    };
+
+    Ok(GuestProgram::load(
+        deployment.customer_id.clone(),
+        deployment.image_id.clone(),
+        deployment.guest_program_path.clone(),
+    )?
+    .with_dsl(dsl))
+}
+
+/// List the guest programs currently loaded, with their image IDs
+pub async fn list_programs_handler(
+    State(state): State<Arc<AppState>>,
+) -> ApiResponse<ProgramsResponse> {
+    let prover = state.prover.read().await;
+    let mut programs: Vec<LoadedProgram> = prover
+        .programs()
+        .into_iter()
+        .map(LoadedProgram::from)
+        .collect();
+    programs.sort_by(|a, b| a.customer_id.cmp(&b.customer_id));
+
+    ApiResponse::new(ProgramsResponse { programs })
+}
+
+/// Proving metrics in the Prometheus text format
+pub async fn metrics_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        state.metrics.render(),
+    )
+}
+
 /// Get service status
 pub async fn status_handler(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<serde_json::Value>, ApiError> {
+) -> Result<ApiResponse<serde_json::Value>, ApiError> {
     let prover = state.prover.read().await;
     let program_count = prover.program_count();
 
     // Check registry health
     let registry_healthy = state.registry_client.health_check().await.unwrap_or(false);
 
-    Ok(Json(serde_json::json!({
+    Ok(ApiResponse::new(serde_json::json!({
         "service": "proof-generation-service",
         "loaded_programs": program_count,
-        "registry_healthy": registry_healthy
+        "registry_healthy": registry_healthy,
+        "prover_backend": state.prover_backend
     })))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ProofEncoding;
+    use crate::prover::{CycleEstimate, ExecutionLimits, ProofResult, Prover};
+    use khafi_common::ReceiptKind;
+    use std::collections::HashMap;
+
+    /// Backend that answers from canned results instead of running a guest
+    #[derive(Default)]
+    struct MockBackend {
+        programs: HashMap<String, GuestProgram>,
+        /// Error to return from proving and estimation, if any
+        fail_with: Option<String>,
+    }
+
+    impl MockBackend {
+        fn with_program(customer_id: &str, image_id: &str) -> Self {
+            let mut backend = Self::default();
+            backend
+                .load_program(GuestProgram {
+                    customer_id: customer_id.to_string(),
+                    image_id: image_id.to_string(),
+                    elf_path: "/path/to/guest.elf".to_string(),
+                    elf_binary: vec![],
+                    dsl: None,
+                })
+                .unwrap();
+            backend
+        }
+
+        fn program(&self, customer_id: &str) -> anyhow::Result<&GuestProgram> {
+            if let Some(error) = &self.fail_with {
+                anyhow::bail!("{}", error);
+            }
+            self.programs
+                .get(customer_id)
+                .ok_or_else(|| anyhow::anyhow!("Guest program not found"))
+        }
+    }
+
+    impl ProvingBackend for MockBackend {
+        fn load_program(&mut self, program: GuestProgram) -> anyhow::Result<()> {
+            self.programs.insert(program.customer_id.clone(), program);
+            Ok(())
+        }
+
+        fn has_program(&self, customer_id: &str) -> bool {
+            self.programs.contains_key(customer_id)
+        }
+
+        fn get_program(&self, customer_id: &str) -> Option<&GuestProgram> {
+            self.programs.get(customer_id)
+        }
+
+        fn program_count(&self) -> usize {
+            self.programs.len()
+        }
+
+        fn programs(&self) -> Vec<&GuestProgram> {
+            self.programs.values().collect()
+        }
+
+        fn generate_proof(
+            &self,
+            customer_id: &str,
+            _private_inputs: &serde_json::Value,
+            public_params: &serde_json::Value,
+            nullifier: &Nullifier,
+        ) -> anyhow::Result<ProofResult> {
+            let program = self.program(customer_id)?;
+            Ok(ProofResult {
+                proof: vec![0xca, 0xfe, 0xba, 0xbe],
+                receipt_kind: ReceiptKind::Succinct,
+                image_id: program.image_id.clone(),
+                outputs: serde_json::json!({
+                    "params": public_params,
+                    "nullifier": nullifier.to_hex(),
+                }),
+                cycles: CycleEstimate {
+                    total_cycles: 1 << 16,
+                    user_cycles: 40_000,
+                    segments: 1,
+                },
+            })
+        }
+
+        fn estimate(
+            &self,
+            customer_id: &str,
+            _private_inputs: &serde_json::Value,
+            _public_params: &serde_json::Value,
+            _nullifier: &Nullifier,
+        ) -> anyhow::Result<CycleEstimate> {
+            self.program(customer_id)?;
+            Ok(CycleEstimate {
+                total_cycles: 1 << 16,
+                user_cycles: 40_000,
+                segments: 1,
+            })
+        }
+    }
+
+    /// State around a backend, with a registry that can't be reached
+    fn mock_state(backend: MockBackend) -> Arc<AppState> {
+        Arc::new(AppState {
+            prover: RwLock::new(Box::new(backend)),
+            registry_client: RegistryClient::new("http://127.0.0.1:1".to_string()),
+            pending_programs: RwLock::new(BTreeSet::new()),
+            prover_backend: ProverBackend::default(),
+            metrics: ProofMetrics::default(),
+            proof_slots: Semaphore::new(1),
+        })
+    }
+
+    fn proof_request(public_params: serde_json::Value) -> GenerateProofRequest {
+        GenerateProofRequest {
+            customer_id: "customer-123".to_string(),
+            private_inputs: serde_json::json!({ "age": 30 }),
+            public_params,
+            nullifier: "07".repeat(32),
+            proof_encoding: ProofEncoding::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_proof_uses_loaded_program() {
+        let state = mock_state(MockBackend::with_program("customer-123", "image-abc"));
+
+        let ApiResponse { data: response } = generate_proof_handler(
+            State(state),
+            Json(proof_request(serde_json::json!({ "min_age": 18 }))),
+        )
+        .await
+        .unwrap();
+
+        assert!(response.success);
+        assert_eq!(response.proof.as_deref(), Some("cafebabe"));
+        assert_eq!(response.proof_encoding, Some(ProofEncoding::Hex));
+        assert_eq!(response.image_id.as_deref(), Some("image-abc"));
+        let outputs = response.outputs.unwrap();
+        assert_eq!(outputs["params"]["min_age"], 18);
+        assert_eq!(outputs["nullifier"], "07".repeat(32));
+    }
+
+    #[tokio::test]
+    async fn test_generate_proof_rejects_invalid_nullifier() {
+        let state = mock_state(MockBackend::with_program("customer-123", "image-abc"));
+        let mut request = proof_request(serde_json::json!({}));
+        request.nullifier = "07".repeat(31);
+
+        let err = generate_proof_handler(State(state), Json(request))
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+        assert!(err.message.contains("Invalid nullifier"));
+    }
+
+    #[tokio::test]
+    async fn test_base64_proof_round_trips() {
+        let state = mock_state(MockBackend::with_program("customer-123", "image-abc"));
+        let mut request = proof_request(serde_json::json!({}));
+        request.proof_encoding = ProofEncoding::Base64;
+
+        let ApiResponse { data: response } = generate_proof_handler(State(state), Json(request))
+            .await
+            .unwrap();
+
+        assert_eq!(response.proof_encoding, Some(ProofEncoding::Base64));
+        let proof = response.proof.unwrap();
+        assert_eq!(proof, "yv66vg==");
+        assert_eq!(ProofEncoding::Base64.decode(&proof).unwrap(), [0xca, 0xfe, 0xba, 0xbe]);
+    }
+
+    #[tokio::test]
+    async fn test_list_programs_reports_image_ids() {
+        let mut backend = MockBackend::with_program("customer-b", "image-b");
+        backend
+            .load_program(GuestProgram {
+                customer_id: "customer-a".to_string(),
+                image_id: "image-a".to_string(),
+                elf_path: "/path/to/a.elf".to_string(),
+                elf_binary: vec![0; 128],
+                dsl: None,
+            })
+            .unwrap();
+        let state = mock_state(backend);
+
+        let ApiResponse { data: response } = list_programs_handler(State(state)).await;
+
+        let listed: Vec<(&str, &str, usize)> = response
+            .programs
+            .iter()
+            .map(|p| (p.customer_id.as_str(), p.image_id.as_str(), p.elf_size))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![("customer-a", "image-a", 128), ("customer-b", "image-b", 0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generate_proof_waits_for_a_proof_slot() {
+        let state = mock_state(MockBackend::with_program("customer-123", "image-abc"));
+        let busy = state.proof_slots.acquire().await.unwrap();
+
+        let pending = tokio::spawn(generate_proof_handler(
+            State(state.clone()),
+            Json(proof_request(serde_json::json!({}))),
+        ));
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!pending.is_finished());
+
+        drop(busy);
+        let ApiResponse { data: response } = pending.await.unwrap().unwrap();
+        assert!(response.success);
+    }
+
+    #[tokio::test]
+    async fn test_generate_proof_records_latency() {
+        let state = mock_state(MockBackend::with_program("customer-123", "image-abc"));
+        assert_eq!(state.metrics.latency_count("customer-123", "image-abc"), 0);
+
+        generate_proof_handler(
+            State(state.clone()),
+            Json(proof_request(serde_json::json!({}))),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(state.metrics.latency_count("customer-123", "image-abc"), 1);
+        let response = metrics_handler(State(state)).await.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains(
+            r#"proof_generation_duration_seconds_count{customer_id="customer-123",image_id="image-abc"} 1"#
+        ));
+        assert!(text.contains(
+            r#"proof_generation_cycles{customer_id="customer-123",image_id="image-abc"} 65536"#
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_response_reports_receipt_kind() {
+        let state = mock_state(MockBackend::with_program("customer-123", "image-abc"));
+
+        let ApiResponse { data: response } = generate_proof_handler(
+            State(state),
+            Json(proof_request(serde_json::json!({}))),
+        )
+        .await
+        .unwrap();
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["receipt_kind"], "succinct");
+        assert_eq!(json["proof_encoding"], "hex");
+        assert_eq!(json["image_id"], "image-abc");
+
+        // A verifier can pick its decode path from the response alone
+        let kind: ReceiptKind = json["receipt_kind"].as_str().unwrap().parse().unwrap();
+        let encoding: ProofEncoding =
+            serde_json::from_value(json["proof_encoding"].clone()).unwrap();
+        assert_eq!(kind, ReceiptKind::Succinct);
+        assert_eq!(
+            encoding.decode(json["proof"].as_str().unwrap()).unwrap(),
+            [0xca, 0xfe, 0xba, 0xbe]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_proving_failure_reported_in_response() {
+        let mut backend = MockBackend::with_program("customer-123", "image-abc");
+        backend.fail_with = Some("guest panicked".to_string());
+
+        let ApiResponse { data: response } = generate_proof_handler(
+            State(mock_state(backend)),
+            Json(proof_request(serde_json::json!({}))),
+        )
+        .await
+        .unwrap();
+
+        assert!(!response.success);
+        assert!(response.proof.is_none());
+        assert!(response.receipt_kind.is_none());
+        assert_eq!(
+            response.error.as_deref(),
+            Some("Proof generation failed: guest panicked")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_estimate_and_status_with_mock_backend() {
+        let state = mock_state(MockBackend::with_program("customer-123", "image-abc"));
+
+        let ApiResponse { data: estimate } = estimate_handler(
+            State(state.clone()),
+            Json(proof_request(serde_json::json!({}))),
+        )
+        .await
+        .unwrap();
+        assert_eq!(estimate.customer_id, "customer-123");
+        assert_eq!(estimate.total_cycles, 1 << 16);
+        assert_eq!(estimate.segments, 1);
+
+        let ApiResponse { data: status } = status_handler(State(state)).await.unwrap();
+        assert_eq!(status["loaded_programs"], 1);
+        assert_eq!(status["registry_healthy"], false);
+    }
+
+    #[tokio::test]
+    async fn test_missing_field_rejected_before_proving() {
+        let dsl = DslParser::parse_str(
+            r#"{
+                "use_case": "age_check",
+                "private_inputs": { "type": "object", "fields": { "age": "u32" } },
+                "public_params": { "min_age": "u32" },
+                "validation_rules": [
+                    { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        // An empty ELF would fail proving, so a 400 means we never got that far
+        let mut prover = Prover::new();
+        prover
+            .load_program(GuestProgram {
+                customer_id: "customer-123".to_string(),
+                image_id: "image-abc".to_string(),
+                elf_path: "/path/to/guest.elf".to_string(),
+                elf_binary: vec![],
+                dsl: Some(dsl),
+            })
+            .unwrap();
+
+        let state = Arc::new(AppState {
+            prover: RwLock::new(Box::new(prover)),
+            registry_client: RegistryClient::new("http://localhost:8083".to_string()),
+            pending_programs: RwLock::new(BTreeSet::new()),
+            prover_backend: ProverBackend::default(),
+            metrics: ProofMetrics::default(),
+            proof_slots: Semaphore::new(1),
+        });
+
+        let request = GenerateProofRequest {
+            customer_id: "customer-123".to_string(),
+            private_inputs: serde_json::json!({}),
+            public_params: serde_json::json!({ "min_age": 18 }),
+            nullifier: "07".repeat(32),
+            proof_encoding: ProofEncoding::default(),
+        };
+
+        let err = generate_proof_handler(State(state), Json(request))
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+        assert!(err.message.contains("private_inputs.age: missing required field"));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_inputs_rejected_before_proving() {
+        // As above, the empty ELF means a 400 was returned before proving
+        let mut prover = Prover::new().with_limits(ExecutionLimits {
+            max_input_bytes: Some(1024),
+            ..ExecutionLimits::default()
+        });
+        prover
+            .load_program(GuestProgram {
+                customer_id: "customer-123".to_string(),
+                image_id: "image-abc".to_string(),
+                elf_path: "/path/to/guest.elf".to_string(),
+                elf_binary: vec![],
+                dsl: None,
+            })
+            .unwrap();
+        let state = Arc::new(AppState {
+            prover: RwLock::new(Box::new(prover)),
+            registry_client: RegistryClient::new("http://localhost:8083".to_string()),
+            pending_programs: RwLock::new(BTreeSet::new()),
+            prover_backend: ProverBackend::default(),
+            metrics: ProofMetrics::default(),
+            proof_slots: Semaphore::new(1),
+        });
+
+        let request = || GenerateProofRequest {
+            customer_id: "customer-123".to_string(),
+            private_inputs: serde_json::json!({ "document": "x".repeat(2048) }),
+            public_params: serde_json::json!({}),
+            nullifier: "07".repeat(32),
+            proof_encoding: ProofEncoding::default(),
+        };
+
+        let err = generate_proof_handler(State(state.clone()), Json(request()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+        assert!(
+            err.message.contains("exceeds the limit of 1024 bytes"),
+            "{}",
+            err.message
+        );
+
+        let err = estimate_handler(State(state), Json(request()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_omitted_param_filled_from_default() {
+        let dsl = DslParser::parse_str(
+            r#"{
+                "use_case": "age_check",
+                "private_inputs": { "type": "object", "fields": { "age": "u32" } },
+                "public_params": { "min_age": "u32" },
+                "param_defaults": { "min_age": 18 },
+                "validation_rules": [
+                    { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let mut prover = Prover::new();
+        prover
+            .load_program(GuestProgram {
+                customer_id: "customer-123".to_string(),
+                image_id: "image-abc".to_string(),
+                elf_path: "/path/to/guest.elf".to_string(),
+                elf_binary: vec![],
+                dsl: Some(dsl),
+            })
+            .unwrap();
+
+        let mut request = GenerateProofRequest {
+            customer_id: "customer-123".to_string(),
+            private_inputs: serde_json::json!({ "age": 30 }),
+            public_params: serde_json::json!({}),
+            nullifier: "07".repeat(32),
+            proof_encoding: ProofEncoding::default(),
+        };
+
+        prepare_inputs(&prover, &mut request).unwrap();
+        assert_eq!(request.public_params, serde_json::json!({ "min_age": 18 }));
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_prover_backend() {
+        let state = Arc::new(AppState {
+            prover: RwLock::new(Box::new(Prover::new())),
+            registry_client: RegistryClient::new("http://127.0.0.1:1".to_string()),
+            pending_programs: RwLock::new(BTreeSet::new()),
+            prover_backend: ProverBackend::detect(Some("local".to_string())),
+            metrics: ProofMetrics::default(),
+            proof_slots: Semaphore::new(1),
+        });
+
+        let ApiResponse { data: status } = status_handler(State(state)).await.unwrap();
+        let backend = &status["prover_backend"];
+        assert_eq!(backend["requested"], "local");
+        assert!(backend["selected"].is_string());
+        assert!(backend["cpu_threads"].as_u64().unwrap() >= 1);
+    }
+
+    async fn body_json(response: Response) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_health_uses_data_envelope() {
+        let state = mock_state(MockBackend::default());
+
+        let response = health_handler(State(state)).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = body_json(response).await;
+        assert_eq!(body["data"]["status"], "healthy");
+        assert_eq!(body["data"]["service"], "proof-generation-service");
+    }
+
+    #[tokio::test]
+    async fn test_errors_use_error_envelope() {
+        // Not loaded, and the registry can't be reached to fetch it
+        let state = mock_state(MockBackend::default());
+
+        let response = estimate_handler(State(state), Json(proof_request(serde_json::json!({}))))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let body = body_json(response).await;
+        assert!(body["error"].is_string());
+        assert!(body.get("data").is_none());
+    }
+}
@@ -0,0 +1,179 @@
+//! Pre-proving validation of proof inputs against a deployment's DSL schema
+//!
+//! The guest program deserializes `PrivateInputs`/`PublicParams` structs
+//! generated from the DSL, so a request whose JSON doesn't match those
+//! structs would only fail deep inside the zkVM. Checking the shape up front
+//! lets us reject it with a useful message instead of burning a proving cycle.
+
+use indexmap::IndexMap;
+use logic_compiler::codegen::type_gen::{to_snake_case, value_matches_type};
+use logic_compiler::{BusinessRulesDSL, InputSchema, ParamSchema};
+use serde_json::Value;
+
+/// Inputs did not match the deployment's expected schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputValidationError {
+    /// One entry per mismatch, e.g. `private_inputs.age: expected u32, got string`
+    pub errors: Vec<String>,
+}
+
+impl std::fmt::Display for InputValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid proof inputs: {}", self.errors.join("; "))
+    }
+}
+
+impl std::error::Error for InputValidationError {}
+
+/// Validate proof inputs against the DSL the guest program was compiled from
+pub fn validate_inputs(
+    dsl: &BusinessRulesDSL,
+    private_inputs: &Value,
+    public_params: &Value,
+) -> Result<(), InputValidationError> {
+    let mut errors = Vec::new();
+
+    match &dsl.private_inputs {
+        InputSchema::Object(obj) => {
+            check_object("private_inputs", &obj.fields, private_inputs, &mut errors);
+        }
+        InputSchema::Map(map) => match private_inputs.as_object() {
+            Some(values) => {
+                for (name, obj) in map {
+                    let key = to_snake_case(name);
+                    let path = format!("private_inputs.{}", key);
+                    match values.get(&key) {
+                        Some(value) => check_object(&path, &obj.fields, value, &mut errors),
+                        None => errors.push(format!("{}: missing required field", path)),
+                    }
+                }
+            }
+            None => errors.push(format!(
+                "private_inputs: expected object, got {}",
+                json_type(private_inputs)
+            )),
+        },
+    }
+
+    match &dsl.public_params {
+        ParamSchema::Map(fields) => {
+            check_object("public_params", fields, public_params, &mut errors);
+        }
+        ParamSchema::Object(obj) => {
+            check_object("public_params", &obj.fields, public_params, &mut errors);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(InputValidationError { errors })
+    }
+}
+
+/// Check that `value` is an object containing every field with the right type
+fn check_object(
+    path: &str,
+    fields: &IndexMap<String, String>,
+    value: &Value,
+    errors: &mut Vec<String>,
+) {
+    let Some(values) = value.as_object() else {
+        errors.push(format!("{}: expected object, got {}", path, json_type(value)));
+        return;
+    };
+
+    let mut names: Vec<&String> = fields.keys().collect();
+    names.sort();
+
+    for name in names {
+        let key = to_snake_case(name);
+        let type_str = &fields[name];
+        match values.get(&key) {
+            Some(field_value) if !value_matches_type(type_str, field_value) => {
+                errors.push(format!(
+                    "{}.{}: expected {}, got {}",
+                    path,
+                    key,
+                    type_str,
+                    json_type(field_value)
+                ))
+            }
+            Some(_) => {}
+            None => errors.push(format!("{}.{}: missing required field", path, key)),
+        }
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logic_compiler::DslParser;
+    use serde_json::json;
+
+    fn test_dsl() -> BusinessRulesDSL {
+        DslParser::parse_str(
+            r#"{
+                "use_case": "age_check",
+                "private_inputs": {
+                    "type": "object",
+                    "fields": { "name": "string", "age": "u32" }
+                },
+                "public_params": { "min_age": "u32" },
+                "validation_rules": [
+                    { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_valid_inputs() {
+        let result = validate_inputs(
+            &test_dsl(),
+            &json!({ "name": "alice", "age": 30 }),
+            &json!({ "min_age": 18 }),
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_missing_field() {
+        let err = validate_inputs(
+            &test_dsl(),
+            &json!({ "name": "alice" }),
+            &json!({ "min_age": 18 }),
+        )
+        .unwrap_err();
+        assert_eq!(err.errors, vec!["private_inputs.age: missing required field"]);
+    }
+
+    #[test]
+    fn test_wrong_types() {
+        let err = validate_inputs(
+            &test_dsl(),
+            &json!({ "name": "alice", "age": "thirty" }),
+            &json!({ "min_age": -1 }),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.errors,
+            vec![
+                "private_inputs.age: expected u32, got string",
+                "public_params.min_age: expected u32, got number",
+            ]
+        );
+    }
+}
@@ -3,8 +3,13 @@
 //! Hosts customer guest programs and generates RISC Zero proofs on their behalf.
 //! Integrates with Image ID Registry to fetch and load customer deployments.
 
+pub mod config;
 pub mod handlers;
+pub mod input_validation;
+pub mod metrics;
 pub mod models;
+pub mod preload;
+pub mod proof_cache;
 pub mod prover;
 pub mod registry_client;
 
@@ -16,21 +21,38 @@ use std::sync::Arc;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 
+pub use config::Config;
 pub use handlers::AppState;
-pub use models::{GenerateProofRequest, GenerateProofResponse, GuestProgram};
-pub use prover::{Prover, ProofResult};
+pub use input_validation::{validate_inputs, InputValidationError};
+pub use metrics::ProofMetrics;
+pub use models::{
+    EstimateResponse, GenerateProofRequest, GenerateProofResponse, GuestProgram, LoadedProgram,
+    ProgramsResponse, ProofEncoding,
+};
+pub use preload::{load_pending_programs, spawn_program_loader};
+pub use proof_cache::ProofCache;
+pub use prover::{
+    executor_env, CycleEstimate, ExecutionLimits, ProofResult, Prover, ProverBackend, ProverError,
+    ProvingBackend,
+};
 pub use registry_client::RegistryClient;
 
 /// Create the application router
-pub fn create_router(state: AppState) -> Router {
-    let shared_state = Arc::new(state);
+pub fn create_router(state: impl Into<Arc<AppState>>) -> Router {
+    let shared_state = state.into();
 
     Router::new()
         .route("/health", get(handlers::health_handler))
         .route("/api/status", get(handlers::status_handler))
+        .route("/metrics", get(handlers::metrics_handler))
         .route("/api/generate-proof", post(handlers::generate_proof_handler))
+        .route("/api/estimate", post(handlers::estimate_handler))
         .route("/api/load-program", post(handlers::load_program_handler))
+        .route("/api/programs", get(handlers::list_programs_handler))
         .with_state(shared_state)
         .layer(CorsLayer::permissive())
-        .layer(TraceLayer::new_for_http())
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(khafi_telemetry::http_request_span::<axum::body::Body>),
+        )
 }
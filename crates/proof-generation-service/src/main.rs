@@ -3,41 +3,63 @@
 //! REST API for generating RISC Zero proofs for customer guest programs
 
 use anyhow::{Context, Result};
-use proof_generation_service::{create_router, AppState, Prover, RegistryClient};
-use std::env;
-use tokio::sync::RwLock;
-use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use proof_generation_service::{
+    create_router, load_pending_programs, spawn_program_loader, AppState, Config, ProofCache,
+    ProofMetrics, Prover, ProverBackend, RegistryClient,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{RwLock, Semaphore};
+use tracing::{info, warn};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "proof_generation_service=debug,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
     // Load environment variables
     dotenvy::dotenv().ok();
 
+    // Initialize tracing (exports to OTLP when OTEL_EXPORTER_OTLP_ENDPOINT is set)
+    let _telemetry = khafi_telemetry::init_tracing("proof-generation-service", "proof_generation_service=debug,tower_http=debug")?;
+
     // Configuration
-    let registry_url = env::var("REGISTRY_URL")
-        .unwrap_or_else(|_| "http://127.0.0.1:8083".to_string());
-    let host = env::var("PROVER_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
-    let port = env::var("PROVER_PORT").unwrap_or_else(|_| "8084".to_string());
+    let config = Config::from_env().context("Invalid configuration")?;
 
     info!("Starting Proof Generation Service");
-    info!("Registry URL: {}", registry_url);
-    info!("Listening on {}:{}", host, port);
+    info!("Registry URL: {}", config.registry_url);
+    info!("Listening on {}", config.api_address());
 
-    // Initialize prover
-    let prover = Prover::new();
+    // Report which backend default_prover() picks (RISC0_PROVER overrides it)
+    let prover_backend = ProverBackend::detect(config.prover_backend.clone());
+    match &prover_backend.selected {
+        Some(selected) => info!(
+            "Prover backend: {} ({}, {} CPU threads)",
+            selected,
+            prover_backend.requested.as_deref().unwrap_or("automatic"),
+            prover_backend.cpu_threads
+        ),
+        None => warn!(
+            "Prover backend {} could not be initialized; proofs will fail",
+            prover_backend.requested.as_deref().unwrap_or("(automatic)")
+        ),
+    }
+    if prover_backend.dev_mode {
+        warn!("RISC0_DEV_MODE is set: receipts are NOT real proofs");
+    }
+
+    // Initialize prover (proof cache is opt-in, see PROOF_CACHE_ENABLED)
+    let proof_cache = ProofCache::from_env();
+    if proof_cache.is_some() {
+        info!("Proof cache enabled");
+    }
+    let limits = config.limits;
+    if let Some(limit) = limits.session_limit {
+        info!("Guest session limit: {} cycles", limit);
+    }
+    let prover = Prover::new()
+        .with_proof_cache(proof_cache)
+        .with_limits(limits);
 
     // Initialize registry client
-    let registry_client = RegistryClient::new(registry_url);
+    let registry_client = RegistryClient::new(config.registry_url.clone());
 
     // Check registry health
     info!("Checking Image ID Registry health...");
@@ -48,16 +70,34 @@ async fn main() -> Result<()> {
     }
 
     // Create application state
-    let state = AppState {
-        prover: RwLock::new(prover),
+    let state = Arc::new(AppState {
+        prover: RwLock::new(Box::new(prover)),
         registry_client,
-    };
+        pending_programs: RwLock::new(config.preload_customers.iter().cloned().collect()),
+        prover_backend,
+        metrics: ProofMetrics::default(),
+        proof_slots: Semaphore::new(config.max_concurrent_proofs),
+    });
+
+    // Preload programs; if the registry is down, keep retrying in the background
+    // and report degraded health until they're all loaded
+    let pending = load_pending_programs(&state).await;
+    if pending > 0 {
+        warn!(
+            "{} guest programs could not be preloaded, retrying every {}s",
+            pending, config.registry_retry_secs
+        );
+        spawn_program_loader(
+            state.clone(),
+            Duration::from_secs(config.registry_retry_secs),
+        );
+    }
 
     // Create router
     let app = create_router(state);
 
     // Bind and serve
-    let addr = format!("{}:{}", host, port);
+    let addr = config.api_address();
     let listener = tokio::net::TcpListener::bind(&addr)
         .await
         .context("Failed to bind to address")?;
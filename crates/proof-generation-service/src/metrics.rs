@@ -0,0 +1,194 @@
+//! Proving metrics exposed at `/metrics`
+//!
+//! Tracks, per customer and image ID, how long proof generation takes and
+//! how many cycles the last proof used, rendered in the Prometheus text
+//! exposition format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds (seconds) of the proof latency histogram buckets
+pub const LATENCY_BUCKETS: &[f64] = &[
+    0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0,
+];
+
+/// Proof generation metrics, labeled by customer and image ID
+#[derive(Default)]
+pub struct ProofMetrics {
+    series: Mutex<BTreeMap<Labels, Series>>,
+}
+
+/// `(customer_id, image_id)`
+type Labels = (String, String);
+
+#[derive(Default)]
+struct Series {
+    /// Observations per bucket in [`LATENCY_BUCKETS`] (not cumulative)
+    bucket_counts: Vec<u64>,
+    latency_sum_secs: f64,
+    latency_count: u64,
+    last_total_cycles: u64,
+}
+
+impl ProofMetrics {
+    /// Record a generated proof
+    pub fn record_proof(
+        &self,
+        customer_id: &str,
+        image_id: &str,
+        latency: Duration,
+        total_cycles: u64,
+    ) {
+        let mut series = self.series.lock().unwrap();
+        let series = series
+            .entry((customer_id.to_string(), image_id.to_string()))
+            .or_default();
+
+        let secs = latency.as_secs_f64();
+        series.bucket_counts.resize(LATENCY_BUCKETS.len(), 0);
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|&le| secs <= le) {
+            series.bucket_counts[bucket] += 1;
+        }
+        series.latency_sum_secs += secs;
+        series.latency_count += 1;
+        series.last_total_cycles = total_cycles;
+    }
+
+    /// Number of latency observations recorded for a customer's image
+    pub fn latency_count(&self, customer_id: &str, image_id: &str) -> u64 {
+        self.series
+            .lock()
+            .unwrap()
+            .get(&(customer_id.to_string(), image_id.to_string()))
+            .map_or(0, |series| series.latency_count)
+    }
+
+    /// Render all series in the Prometheus text format
+    pub fn render(&self) -> String {
+        let series = self.series.lock().unwrap();
+        let mut out = String::new();
+
+        out.push_str("# HELP proof_generation_duration_seconds Time spent generating a proof\n");
+        out.push_str("# TYPE proof_generation_duration_seconds histogram\n");
+        for ((customer_id, image_id), s) in series.iter() {
+            let labels = label_pairs(customer_id, image_id);
+            let mut cumulative = 0;
+            for (le, count) in LATENCY_BUCKETS.iter().zip(&s.bucket_counts) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "proof_generation_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, le, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "proof_generation_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, s.latency_count
+            );
+            let _ = writeln!(
+                out,
+                "proof_generation_duration_seconds_sum{{{}}} {}",
+                labels, s.latency_sum_secs
+            );
+            let _ = writeln!(
+                out,
+                "proof_generation_duration_seconds_count{{{}}} {}",
+                labels, s.latency_count
+            );
+        }
+
+        out.push_str("# HELP proof_generation_cycles Total cycles of the most recent proof\n");
+        out.push_str("# TYPE proof_generation_cycles gauge\n");
+        for ((customer_id, image_id), s) in series.iter() {
+            let _ = writeln!(
+                out,
+                "proof_generation_cycles{{{}}} {}",
+                label_pairs(customer_id, image_id),
+                s.last_total_cycles
+            );
+        }
+
+        out
+    }
+}
+
+fn label_pairs(customer_id: &str, image_id: &str) -> String {
+    format!(
+        "customer_id=\"{}\",image_id=\"{}\"",
+        escape_label(customer_id),
+        escape_label(image_id)
+    )
+}
+
+/// Escape a label value per the Prometheus text format
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_histogram_and_gauge() {
+        let metrics = ProofMetrics::default();
+        metrics.record_proof(
+            "customer-123",
+            "image-abc",
+            Duration::from_millis(750),
+            1 << 20,
+        );
+        metrics.record_proof(
+            "customer-123",
+            "image-abc",
+            Duration::from_secs(45),
+            1 << 22,
+        );
+
+        let text = metrics.render();
+        let labels = r#"customer_id="customer-123",image_id="image-abc""#;
+        for line in [
+            format!(
+                "proof_generation_duration_seconds_bucket{{{},le=\"0.5\"}} 0",
+                labels
+            ),
+            format!(
+                "proof_generation_duration_seconds_bucket{{{},le=\"1\"}} 1",
+                labels
+            ),
+            format!(
+                "proof_generation_duration_seconds_bucket{{{},le=\"30\"}} 1",
+                labels
+            ),
+            format!(
+                "proof_generation_duration_seconds_bucket{{{},le=\"60\"}} 2",
+                labels
+            ),
+            format!(
+                "proof_generation_duration_seconds_bucket{{{},le=\"+Inf\"}} 2",
+                labels
+            ),
+            format!("proof_generation_duration_seconds_sum{{{}}} 45.75", labels),
+            format!("proof_generation_duration_seconds_count{{{}}} 2", labels),
+            format!("proof_generation_cycles{{{}}} 4194304", labels),
+        ] {
+            assert!(text.contains(&line), "missing {:?} in:\n{}", line, text);
+        }
+    }
+
+    #[test]
+    fn test_label_values_escaped() {
+        let metrics = ProofMetrics::default();
+        metrics.record_proof("a\"b\\c\nd", "image", Duration::ZERO, 0);
+
+        assert!(metrics
+            .render()
+            .contains(r#"proof_generation_cycles{customer_id="a\"b\\c\nd",image_id="image"} 0"#));
+    }
+}
@@ -1,6 +1,14 @@
 //! Data models for Proof Generation Service
 
+use anyhow::Context;
+use base64::Engine;
+use khafi_common::ReceiptKind;
+use logic_compiler::BusinessRulesDSL;
 use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+/// Leading bytes of a gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
 
 /// Request to generate a proof
 #[derive(Debug, Deserialize)]
@@ -13,6 +21,43 @@ pub struct GenerateProofRequest {
 
     /// Public parameters (will be serialized and passed to guest program)
     pub public_params: serde_json::Value,
+
+    /// Nullifier of the payment this proof is for (64 hex chars); the guest
+    /// commits it to the journal for replay protection
+    pub nullifier: String,
+
+    /// Encoding of the returned proof (hex unless requested otherwise)
+    #[serde(default)]
+    pub proof_encoding: ProofEncoding,
+}
+
+/// Text encoding of the serialized receipt in a proof response
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProofEncoding {
+    /// Lowercase hex (twice the receipt size)
+    #[default]
+    Hex,
+    /// Standard padded base64 (a third smaller than hex)
+    Base64,
+}
+
+impl ProofEncoding {
+    /// Encode serialized receipt bytes
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        match self {
+            ProofEncoding::Hex => hex::encode(bytes),
+            ProofEncoding::Base64 => base64::engine::general_purpose::STANDARD.encode(bytes),
+        }
+    }
+
+    /// Decode a proof produced by [`encode`](Self::encode)
+    pub fn decode(&self, proof: &str) -> anyhow::Result<Vec<u8>> {
+        match self {
+            ProofEncoding::Hex => Ok(hex::decode(proof)?),
+            ProofEncoding::Base64 => Ok(base64::engine::general_purpose::STANDARD.decode(proof)?),
+        }
+    }
 }
 
 /// Response from proof generation
@@ -21,10 +66,18 @@ pub struct GenerateProofResponse {
     /// Whether proof generation succeeded
     pub success: bool,
 
-    /// Generated proof (hex-encoded)
+    /// Generated proof (serialized receipt, encoded per `proof_encoding`)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub proof: Option<String>,
 
+    /// Encoding of `proof`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof_encoding: Option<ProofEncoding>,
+
+    /// Proof system of the receipt in `proof`, so a verifier knows what to expect
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receipt_kind: Option<ReceiptKind>,
+
     /// Image ID used for this proof
     #[serde(skip_serializing_if = "Option::is_none")]
     pub image_id: Option<String>,
@@ -38,6 +91,60 @@ pub struct GenerateProofResponse {
     pub error: Option<String>,
 }
 
+/// Response from cycle estimation
+#[derive(Debug, Serialize)]
+pub struct EstimateResponse {
+    /// Customer identifier
+    pub customer_id: String,
+
+    /// Total cycles across all segments (what proving time scales with)
+    pub total_cycles: u64,
+
+    /// Cycles spent executing guest instructions
+    pub user_cycles: u64,
+
+    /// Number of segments the execution was split into
+    pub segments: usize,
+}
+
+/// A guest program currently loaded in the prover
+#[derive(Debug, Serialize)]
+pub struct LoadedProgram {
+    /// Customer identifier
+    pub customer_id: String,
+
+    /// RISC Zero Image ID
+    pub image_id: String,
+
+    /// Path the ELF was loaded from
+    pub elf_path: String,
+
+    /// Size of the (decompressed) ELF in bytes
+    pub elf_size: usize,
+
+    /// Whether request inputs are validated against the deployment's DSL
+    pub has_dsl: bool,
+}
+
+impl From<&GuestProgram> for LoadedProgram {
+    fn from(program: &GuestProgram) -> Self {
+        Self {
+            customer_id: program.customer_id.clone(),
+            image_id: program.image_id.clone(),
+            elf_path: program.elf_path.clone(),
+            elf_size: program.elf_binary.len(),
+            has_dsl: program.dsl.is_some(),
+        }
+    }
+}
+
+/// Response listing loaded guest programs
+#[derive(Debug, Serialize)]
+pub struct ProgramsResponse {
+    /// Loaded programs, ordered by customer ID
+    pub programs: Vec<LoadedProgram>,
+}
+
 /// Guest program deployment
 #[derive(Debug, Clone)]
 pub struct GuestProgram {
@@ -52,17 +159,74 @@ pub struct GuestProgram {
 
     /// Loaded ELF binary
     pub elf_binary: Vec<u8>,
+
+    /// DSL the guest program was compiled from, used to validate inputs
+    pub dsl: Option<BusinessRulesDSL>,
 }
 
 impl GuestProgram {
     /// Load a guest program from disk
+    ///
+    /// Gzip-compressed ELFs (as stored by the build service) are decompressed
+    /// transparently.
     pub fn load(customer_id: String, image_id: String, elf_path: String) -> anyhow::Result<Self> {
-        let elf_binary = std::fs::read(&elf_path)?;
+        let elf_binary = decompress_elf(std::fs::read(&elf_path)?)
+            .with_context(|| format!("Failed to decompress guest ELF: {}", elf_path))?;
         Ok(Self {
             customer_id,
             image_id,
             elf_path,
             elf_binary,
+            dsl: None,
         })
     }
+
+    /// Attach the DSL the guest program was compiled from
+    pub fn with_dsl(mut self, dsl: Option<BusinessRulesDSL>) -> Self {
+        self.dsl = dsl;
+        self
+    }
+}
+
+/// Return the raw ELF, gunzipping it if it was stored compressed
+fn decompress_elf(bytes: Vec<u8>) -> std::io::Result<Vec<u8>> {
+    if !bytes.starts_with(&GZIP_MAGIC) {
+        return Ok(bytes);
+    }
+
+    let mut elf = Vec::new();
+    flate2::read::GzDecoder::new(bytes.as_slice()).read_to_end(&mut elf)?;
+    Ok(elf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    #[test]
+    fn test_load_decompresses_gzip_elf() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(methods::GUEST_ELF).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert!(compressed.len() < methods::GUEST_ELF.len());
+
+        let path = std::env::temp_dir().join(format!("guest-{}.elf.gz", uuid::Uuid::new_v4()));
+        std::fs::write(&path, &compressed).unwrap();
+
+        let program = GuestProgram::load(
+            "customer-123".to_string(),
+            "image-abc".to_string(),
+            path.to_string_lossy().to_string(),
+        )
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(program.elf_binary, methods::GUEST_ELF);
+
+        // Decompression must not change the program's identity
+        let image_id = risc0_zkvm::compute_image_id(&program.elf_binary).unwrap();
+        assert_eq!(image_id, risc0_zkvm::sha::Digest::from(methods::GUEST_ID));
+    }
 }
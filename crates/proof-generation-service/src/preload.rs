@@ -0,0 +1,144 @@
+//! Startup loading of guest programs
+//!
+//! Programs listed in `PRELOAD_CUSTOMERS` are fetched from the registry when
+//! the service starts. If the registry is down at that point the service still
+//! starts, reports itself as degraded, and a background task keeps retrying
+//! until every listed program is loaded.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::handlers::{load_guest_program, AppState};
+
+/// Try to load every pending program, returning how many are still pending
+pub async fn load_pending_programs(state: &AppState) -> usize {
+    let pending: Vec<String> = state
+        .pending_programs
+        .read()
+        .await
+        .iter()
+        .cloned()
+        .collect();
+
+    for customer_id in pending {
+        let deployment = match state.registry_client.get_deployment(&customer_id).await {
+            Ok(Some(deployment)) => deployment,
+            Ok(None) => {
+                warn!(
+                    "No deployment found for preloaded customer: {}",
+                    customer_id
+                );
+                continue;
+            }
+            Err(e) => {
+                warn!(
+                    "Registry unavailable, will retry loading {}: {}",
+                    customer_id, e
+                );
+                continue;
+            }
+        };
+
+        let program = match load_guest_program(&deployment) {
+            Ok(program) => program,
+            Err(e) => {
+                warn!("Failed to load guest program for {}: {:#}", customer_id, e);
+                continue;
+            }
+        };
+
+        if let Err(e) = state.prover.write().await.load_program(program) {
+            warn!("Failed to load guest program for {}: {:#}", customer_id, e);
+            continue;
+        }
+
+        state.pending_programs.write().await.remove(&customer_id);
+        info!("Preloaded guest program for customer: {}", customer_id);
+    }
+
+    state.pending_programs.read().await.len()
+}
+
+/// Retry loading pending programs every `interval` until none are left
+pub fn spawn_program_loader(
+    state: Arc<AppState>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            if state.pending_programs.read().await.is_empty() {
+                info!("All preloaded guest programs loaded");
+                break;
+            }
+            tokio::time::sleep(interval).await;
+            load_pending_programs(&state).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{health_handler, ApiResponse};
+    use crate::{ProofMetrics, Prover, ProverBackend, RegistryClient};
+    use axum::{extract::State, routing::get, Json, Router};
+    use std::collections::BTreeSet;
+    use tokio::sync::{RwLock, Semaphore};
+
+    #[tokio::test]
+    async fn test_recovers_program_loading_when_registry_comes_up() {
+        // Reserve a port, then leave it closed so the registry is down
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let elf_path = std::env::temp_dir().join(format!("guest-{}.elf", uuid::Uuid::new_v4()));
+        std::fs::write(&elf_path, b"\x7fELF").unwrap();
+
+        let state = Arc::new(AppState {
+            prover: RwLock::new(Box::new(Prover::new())),
+            registry_client: RegistryClient::new(format!("http://{}", addr)),
+            pending_programs: RwLock::new(BTreeSet::from(["customer-123".to_string()])),
+            prover_backend: ProverBackend::default(),
+            metrics: ProofMetrics::default(),
+            proof_slots: Semaphore::new(1),
+        });
+
+        assert_eq!(load_pending_programs(&state).await, 1);
+        assert!(!state.prover.read().await.has_program("customer-123"));
+        let ApiResponse { data: health } = health_handler(State(state.clone())).await;
+        assert_eq!(health["status"], "degraded");
+
+        // Bring the registry up on the same address
+        let deployment = serde_json::json!({
+            "data": {
+                "deployment": {
+                    "customer_id": "customer-123",
+                    "image_id": "image-abc",
+                    "guest_program_path": elf_path.to_string_lossy(),
+                }
+            }
+        });
+        let registry = Router::new().route(
+            "/api/deployments/customer-123",
+            get(move || {
+                let deployment = deployment.clone();
+                async move { Json(deployment) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+        tokio::spawn(async move { axum::serve(listener, registry).await.unwrap() });
+
+        let loader = spawn_program_loader(state.clone(), Duration::from_millis(20));
+        tokio::time::timeout(Duration::from_secs(5), loader)
+            .await
+            .expect("loader should finish once the registry is up")
+            .unwrap();
+        std::fs::remove_file(&elf_path).unwrap();
+
+        assert!(state.prover.read().await.has_program("customer-123"));
+        let ApiResponse { data: health } = health_handler(State(state)).await;
+        assert_eq!(health["status"], "healthy");
+    }
+}
@@ -0,0 +1,179 @@
+//! Cache of generated proofs keyed by image ID and inputs
+//!
+//! Proving is deterministic given the guest ELF and its inputs, so a repeated
+//! request for the same image ID with the same inputs can return the stored
+//! receipt instead of re-running the prover. Cached entries hold the inputs'
+//! hash and the resulting proof, so the cache is disabled by default and must
+//! be opted into (`PROOF_CACHE_ENABLED=true`).
+
+use crate::prover::ProofResult;
+use khafi_common::Nullifier;
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Default maximum number of cached proofs
+pub const DEFAULT_MAX_ENTRIES: usize = 1000;
+
+/// Bounded in-memory proof cache; evicts the oldest entry when full
+pub struct ProofCache {
+    max_entries: usize,
+    inner: Mutex<CacheInner>,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    entries: HashMap<String, ProofResult>,
+    /// Insertion order, oldest first
+    order: VecDeque<String>,
+}
+
+impl ProofCache {
+    /// Create a cache holding at most `max_entries` proofs
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries: max_entries.max(1),
+            inner: Mutex::new(CacheInner::default()),
+        }
+    }
+
+    /// Create a cache from `PROOF_CACHE_ENABLED` / `PROOF_CACHE_MAX_ENTRIES`
+    ///
+    /// Returns `None` unless caching is explicitly enabled.
+    pub fn from_env() -> Option<Self> {
+        let enabled = std::env::var("PROOF_CACHE_ENABLED")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        if !enabled {
+            return None;
+        }
+
+        let max_entries = std::env::var("PROOF_CACHE_MAX_ENTRIES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_ENTRIES);
+
+        Some(Self::new(max_entries))
+    }
+
+    /// Cache key: SHA-256 over the image ID, canonicalized inputs and nullifier
+    ///
+    /// The guest commits the nullifier, so it is part of the proof's identity.
+    pub fn key(
+        image_id: &str,
+        private_inputs: &Value,
+        public_params: &Value,
+        nullifier: &Nullifier,
+    ) -> String {
+        let material = serde_json::json!([
+            image_id,
+            canonicalize(private_inputs),
+            canonicalize(public_params),
+            nullifier.to_hex(),
+        ]);
+        let digest = Impl::hash_bytes(material.to_string().as_bytes());
+        hex::encode(digest.as_bytes())
+    }
+
+    /// Look up a cached proof
+    pub fn get(&self, key: &str) -> Option<ProofResult> {
+        let inner = self.inner.lock().unwrap();
+        inner.entries.get(key).cloned()
+    }
+
+    /// Store a proof, evicting the oldest entry if the cache is full
+    pub fn insert(&self, key: String, result: ProofResult) {
+        let mut inner = self.inner.lock().unwrap();
+
+        if inner.entries.insert(key.clone(), result).is_some() {
+            return;
+        }
+        inner.order.push_back(key);
+
+        while inner.order.len() > self.max_entries {
+            if let Some(oldest) = inner.order.pop_front() {
+                inner.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// Number of cached proofs
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// Whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Recursively sort object keys so equivalent JSON hashes identically
+fn canonicalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let mut sorted = serde_json::Map::new();
+            for key in keys {
+                sorted.insert(key.clone(), canonicalize(&map[key]));
+            }
+            Value::Object(sorted)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonicalize).collect()),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn proof(id: &str) -> ProofResult {
+        ProofResult {
+            proof: format!("proof-{}", id).into_bytes(),
+            receipt_kind: khafi_common::ReceiptKind::Composite,
+            image_id: "image-abc".to_string(),
+            outputs: json!({ "compliance_result": true }),
+            cycles: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_key_ignores_field_order() {
+        let nullifier = Nullifier::new([1u8; 32]);
+        let key =
+            |image_id, inputs, nullifier| ProofCache::key(image_id, &inputs, &json!({}), nullifier);
+
+        let a = key("image-abc", json!({ "a": 1, "b": 2 }), &nullifier);
+        let b = key("image-abc", json!({ "b": 2, "a": 1 }), &nullifier);
+        assert_eq!(a, b);
+
+        let other_image = key("image-def", json!({ "a": 1, "b": 2 }), &nullifier);
+        assert_ne!(a, other_image);
+
+        let other_inputs = key("image-abc", json!({ "a": 1, "b": 3 }), &nullifier);
+        assert_ne!(a, other_inputs);
+
+        let other_nullifier = key(
+            "image-abc",
+            json!({ "a": 1, "b": 2 }),
+            &Nullifier::new([2u8; 32]),
+        );
+        assert_ne!(a, other_nullifier);
+    }
+
+    #[test]
+    fn test_evicts_oldest_entry() {
+        let cache = ProofCache::new(2);
+        cache.insert("k1".to_string(), proof("1"));
+        cache.insert("k2".to_string(), proof("2"));
+        cache.insert("k3".to_string(), proof("3"));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("k1").is_none());
+        assert_eq!(cache.get("k3").unwrap().proof, b"proof-3");
+    }
+}
@@ -1,14 +1,156 @@
 //! RISC Zero prover integration
 
 use crate::models::GuestProgram;
+use crate::proof_cache::ProofCache;
 use anyhow::{Context, Result};
-use risc0_zkvm::{default_prover, ExecutorEnv, ProverOpts, VerifierContext};
+use khafi_common::{Nullifier, ReceiptKind};
+#[cfg(feature = "groth16")]
+use risc0_zkvm::Receipt;
+use risc0_zkvm::{
+    default_executor, default_prover, is_dev_mode, ExecutorEnv, ProveInfo, ProverOpts,
+    VerifierContext,
+};
 use tracing::{debug, info};
 
+/// Operations the API needs from a prover
+///
+/// [`Prover`] is the RISC Zero implementation; handlers only see this trait,
+/// so they can be exercised against a backend that doesn't prove anything.
+pub trait ProvingBackend: Send + Sync {
+    /// Load a guest program for a customer
+    fn load_program(&mut self, program: GuestProgram) -> Result<()>;
+
+    /// Check if a customer has a loaded program
+    fn has_program(&self, customer_id: &str) -> bool;
+
+    /// Get a customer's loaded program
+    fn get_program(&self, customer_id: &str) -> Option<&GuestProgram>;
+
+    /// Get the number of loaded programs
+    fn program_count(&self) -> usize;
+
+    /// Get every loaded program, in no particular order
+    fn programs(&self) -> Vec<&GuestProgram>;
+
+    /// Generate a proof for a customer's inputs
+    ///
+    /// The guest commits `nullifier` to the journal, tying the proof to a payment.
+    fn generate_proof(
+        &self,
+        customer_id: &str,
+        private_inputs: &serde_json::Value,
+        public_params: &serde_json::Value,
+        nullifier: &Nullifier,
+    ) -> Result<ProofResult>;
+
+    /// Estimate the cost of proving a customer's inputs without proving them
+    fn estimate(
+        &self,
+        customer_id: &str,
+        private_inputs: &serde_json::Value,
+        public_params: &serde_json::Value,
+        nullifier: &Nullifier,
+    ) -> Result<CycleEstimate>;
+}
+
 /// Proof generator
 pub struct Prover {
     /// Cached guest programs by customer_id
     programs: std::collections::HashMap<String, GuestProgram>,
+
+    /// Previously generated proofs (disabled unless configured)
+    proof_cache: Option<ProofCache>,
+
+    /// Execution limits applied to every guest run
+    limits: ExecutionLimits,
+}
+
+/// Bounds on guest execution
+///
+/// Without a session limit a guest with an unexpectedly large cycle count
+/// keeps proving until it finishes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExecutionLimits {
+    /// Maximum total cycles per session (unbounded when `None`)
+    pub session_limit: Option<u64>,
+
+    /// Segment size as a power of two (risc0 default when `None`)
+    pub segment_limit_po2: Option<u32>,
+
+    /// Maximum size of the serialized private inputs and public params
+    /// together, in bytes (unbounded when `None`)
+    pub max_input_bytes: Option<usize>,
+}
+
+/// Default for `PROVER_MAX_INPUT_BYTES` (1 MiB)
+pub const DEFAULT_MAX_INPUT_BYTES: usize = 1024 * 1024;
+
+impl ExecutionLimits {
+    /// Read limits from `PROVER_SESSION_LIMIT` / `PROVER_SEGMENT_LIMIT_PO2` /
+    /// `PROVER_MAX_INPUT_BYTES` (0 disables the input bound)
+    pub fn from_env() -> Self {
+        Self {
+            session_limit: std::env::var("PROVER_SESSION_LIMIT")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            segment_limit_po2: std::env::var("PROVER_SEGMENT_LIMIT_PO2")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            max_input_bytes: match std::env::var("PROVER_MAX_INPUT_BYTES") {
+                Ok(v) => v.parse().ok().filter(|&limit| limit > 0),
+                Err(_) => Some(DEFAULT_MAX_INPUT_BYTES),
+            },
+        }
+    }
+}
+
+/// Prover backends `RISC0_PROVER` may request
+pub const PROVER_BACKENDS: &[&str] = &["local", "ipc", "bonsai"];
+
+/// The RISC Zero prover backend in use, as reported by `/api/status`
+///
+/// `default_prover()` picks a backend from `RISC0_PROVER`, Bonsai credentials
+/// and compiled features without saying which; this records what it picked.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ProverBackend {
+    /// Backend requested via `RISC0_PROVER` (`None` = automatic)
+    pub requested: Option<String>,
+
+    /// Backend `default_prover()` selected (`None` if it couldn't be created)
+    pub selected: Option<String>,
+
+    /// Whether `RISC0_DEV_MODE` is set, so receipts are fake
+    pub dev_mode: bool,
+
+    /// CPU threads available to a local prover
+    pub cpu_threads: usize,
+}
+
+impl ProverBackend {
+    /// Detect the backend `default_prover()` will use
+    pub fn detect(requested: Option<String>) -> Self {
+        // default_prover() panics when the requested backend can't be set up
+        let selected = std::panic::catch_unwind(|| default_prover().get_name()).ok();
+
+        Self {
+            requested,
+            selected,
+            dev_mode: is_dev_mode(),
+            cpu_threads: std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+        }
+    }
+}
+
+/// Errors from running a guest program
+#[derive(Debug, thiserror::Error)]
+pub enum ProverError {
+    #[error("Cycle limit exceeded: guest ran past the session limit of {limit} cycles")]
+    CycleLimitExceeded { limit: u64 },
+
+    #[error("Inputs too large: {size} bytes exceeds the limit of {limit} bytes")]
+    InputTooLarge { size: usize, limit: usize },
 }
 
 impl Prover {
@@ -16,11 +158,64 @@ impl Prover {
     pub fn new() -> Self {
         Self {
             programs: std::collections::HashMap::new(),
+            proof_cache: None,
+            limits: ExecutionLimits::default(),
         }
     }
 
+    /// Bound guest execution (session cycle limit, segment size)
+    pub fn with_limits(mut self, limits: ExecutionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Reuse proofs for identical (image ID, inputs) requests
+    pub fn with_proof_cache(mut self, cache: Option<ProofCache>) -> Self {
+        self.proof_cache = cache;
+        self
+    }
+
+    /// Generate a Groth16 proof suitable for on-chain verification
+    ///
+    /// Runs the guest with the stark-to-snark prover and returns the seal in
+    /// the layout expected by the RISC Zero Solidity verifier router, along
+    /// with the raw journal bytes the contract checks the seal against.
+    #[cfg(feature = "groth16")]
+    pub fn generate_groth16_proof(
+        &self,
+        customer_id: &str,
+        private_inputs: &serde_json::Value,
+        public_params: &serde_json::Value,
+        nullifier: &Nullifier,
+    ) -> Result<Groth16Proof> {
+        let program = self
+            .programs
+            .get(customer_id)
+            .with_context(|| format!("Guest program not found for customer: {}", customer_id))?;
+
+        info!("Generating Groth16 proof for customer: {}", customer_id);
+
+        let receipt = prove_receipt(
+            program,
+            private_inputs,
+            public_params,
+            nullifier,
+            &ProverOpts::groth16(),
+            self.limits,
+        )?
+        .receipt;
+
+        let proof = Groth16Proof::from_receipt(&receipt, &program.image_id)?;
+
+        info!("Groth16 proof generated successfully for customer: {}", customer_id);
+
+        Ok(proof)
+    }
+}
+
+impl ProvingBackend for Prover {
     /// Load a guest program for a customer
-    pub fn load_program(&mut self, program: GuestProgram) -> Result<()> {
+    fn load_program(&mut self, program: GuestProgram) -> Result<()> {
         info!(
             "Loading guest program for customer: {} (image_id: {})",
             program.customer_id, program.image_id
@@ -30,11 +225,12 @@ impl Prover {
     }
 
     /// Generate a proof for a customer's inputs
-    pub fn generate_proof(
+    fn generate_proof(
         &self,
         customer_id: &str,
         private_inputs: &serde_json::Value,
         public_params: &serde_json::Value,
+        nullifier: &Nullifier,
     ) -> Result<ProofResult> {
         // Get the guest program for this customer
         let program = self
@@ -42,35 +238,28 @@ impl Prover {
             .get(customer_id)
             .with_context(|| format!("Guest program not found for customer: {}", customer_id))?;
 
+        let cache_key = self
+            .proof_cache
+            .as_ref()
+            .map(|_| ProofCache::key(&program.image_id, private_inputs, public_params, nullifier));
+
+        if let (Some(cache), Some(key)) = (&self.proof_cache, &cache_key) {
+            if let Some(cached) = cache.get(key) {
+                info!("Returning cached proof for customer: {}", customer_id);
+                return Ok(cached);
+            }
+        }
+
         info!("Generating proof for customer: {}", customer_id);
-        debug!("Private inputs: {:?}", private_inputs);
-        debug!("Public params: {:?}", public_params);
-
-        // Prepare inputs for the guest program
-        // The guest program expects JSON strings as inputs
-        let private_json = serde_json::to_string(private_inputs)?;
-        let public_json = serde_json::to_string(public_params)?;
-
-        // Create executor environment
-        let env = ExecutorEnv::builder()
-            .write(&private_json)?
-            .write(&public_json)?
-            .build()
-            .context("Failed to build executor environment")?;
-
-        // Get the prover
-        let prover = default_prover();
-
-        // Prove execution
-        let prove_info = prover
-            .prove_with_ctx(
-                env,
-                &VerifierContext::default(),
-                &program.elf_binary,
-                &ProverOpts::default(),
-            )
-            .context("Failed to generate proof")?;
 
+        let prove_info = prove_receipt(
+            program,
+            private_inputs,
+            public_params,
+            nullifier,
+            &ProverOpts::default(),
+            self.limits,
+        )?;
         let receipt = prove_info.receipt;
 
         // Extract journal (public outputs)
@@ -89,6 +278,9 @@ impl Prover {
             })
         };
 
+        let receipt_kind = ReceiptKind::of(&receipt.inner)
+            .context("Prover returned an unsupported receipt kind")?;
+
         // Serialize receipt using bincode 2.x API
         let proof_bytes = bincode::serde::encode_to_vec(&receipt, bincode::config::standard())?;
 
@@ -98,34 +290,242 @@ impl Prover {
             proof_bytes.len()
         );
 
-        Ok(ProofResult {
-            proof: hex::encode(proof_bytes),
+        let result = ProofResult {
+            proof: proof_bytes,
+            receipt_kind,
             image_id: program.image_id.clone(),
             outputs,
-        })
+            cycles: CycleEstimate {
+                total_cycles: prove_info.stats.total_cycles,
+                user_cycles: prove_info.stats.user_cycles,
+                segments: prove_info.stats.segments,
+            },
+        };
+
+        if let (Some(cache), Some(key)) = (&self.proof_cache, cache_key) {
+            cache.insert(key, result.clone());
+        }
+
+        Ok(result)
+    }
+
+    /// Estimate the cost of proving a customer's inputs
+    ///
+    /// Runs the guest in the executor only, so this is much cheaper than
+    /// [`Prover::generate_proof`]. The session limit still applies.
+    fn estimate(
+        &self,
+        customer_id: &str,
+        private_inputs: &serde_json::Value,
+        public_params: &serde_json::Value,
+        nullifier: &Nullifier,
+    ) -> Result<CycleEstimate> {
+        let program = self
+            .programs
+            .get(customer_id)
+            .with_context(|| format!("Guest program not found for customer: {}", customer_id))?;
+
+        info!("Estimating cycles for customer: {}", customer_id);
+
+        let env = executor_env(private_inputs, public_params, nullifier, self.limits)?;
+        estimate_cycles(env, &program.elf_binary, self.limits)
     }
 
     /// Get the number of loaded programs
-    pub fn program_count(&self) -> usize {
+    fn program_count(&self) -> usize {
         self.programs.len()
     }
 
+    /// Get every loaded program
+    fn programs(&self) -> Vec<&GuestProgram> {
+        self.programs.values().collect()
+    }
+
     /// Check if a customer has a loaded program
-    pub fn has_program(&self, customer_id: &str) -> bool {
+    fn has_program(&self, customer_id: &str) -> bool {
         self.programs.contains_key(customer_id)
     }
+
+    /// Get a customer's loaded program
+    fn get_program(&self, customer_id: &str) -> Option<&GuestProgram> {
+        self.programs.get(customer_id)
+    }
+}
+
+/// Execute a guest program and prove it with the given prover options
+///
+/// Returns the receipt along with the session's executor stats.
+fn prove_receipt(
+    program: &GuestProgram,
+    private_inputs: &serde_json::Value,
+    public_params: &serde_json::Value,
+    nullifier: &Nullifier,
+    opts: &ProverOpts,
+    limits: ExecutionLimits,
+) -> Result<ProveInfo> {
+    let env = executor_env(private_inputs, public_params, nullifier, limits)?;
+
+    // Prove execution
+    let prove_info = default_prover()
+        .prove_with_ctx(env, &VerifierContext::default(), &program.elf_binary, opts)
+        .map_err(|e| limit_error(e, limits))
+        .context("Failed to generate proof")?;
+
+    Ok(prove_info)
+}
+
+/// Build the executor environment for a guest run
+///
+/// Generated guests read, in order: the private inputs and the public params
+/// as JSON strings, then the 32 nullifier bytes.
+pub fn executor_env(
+    private_inputs: &serde_json::Value,
+    public_params: &serde_json::Value,
+    nullifier: &Nullifier,
+    limits: ExecutionLimits,
+) -> Result<ExecutorEnv<'static>> {
+    debug!("Private inputs: {:?}", private_inputs);
+    debug!("Public params: {:?}", public_params);
+
+    // Prepare inputs for the guest program
+    // The guest program expects JSON strings as inputs
+    let private_json = serde_json::to_string(private_inputs)?;
+    let public_json = serde_json::to_string(public_params)?;
+
+    let size = private_json.len() + public_json.len();
+    if let Some(limit) = limits.max_input_bytes.filter(|&limit| size > limit) {
+        return Err(ProverError::InputTooLarge { size, limit }.into());
+    }
+
+    let mut builder = ExecutorEnv::builder();
+    builder
+        .write(&private_json)?
+        .write(&public_json)?
+        .write(nullifier.as_bytes())?
+        .session_limit(limits.session_limit);
+    if let Some(po2) = limits.segment_limit_po2 {
+        builder.segment_limit_po2(po2);
+    }
+    builder
+        .build()
+        .context("Failed to build executor environment")
+}
+
+/// Run a guest in the executor (no proving) and report its cycle counts
+pub fn estimate_cycles(
+    env: ExecutorEnv<'_>,
+    elf: &[u8],
+    limits: ExecutionLimits,
+) -> Result<CycleEstimate> {
+    let session = default_executor()
+        .execute(env, elf)
+        .map_err(|e| limit_error(e, limits))
+        .context("Failed to execute guest program")?;
+
+    Ok(CycleEstimate {
+        total_cycles: session.cycles(),
+        user_cycles: session.segments.iter().map(|s| s.cycles as u64).sum(),
+        segments: session.segments.len(),
+    })
+}
+
+/// Cycle counts from executing a guest
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct CycleEstimate {
+    /// Total cycles across all segments, including padding (drives proving cost)
+    pub total_cycles: u64,
+
+    /// Cycles spent executing guest instructions
+    pub user_cycles: u64,
+
+    /// Number of segments the session was split into
+    pub segments: usize,
+}
+
+/// Replace risc0's session limit error with [`ProverError::CycleLimitExceeded`]
+fn limit_error(err: anyhow::Error, limits: ExecutionLimits) -> anyhow::Error {
+    match limits.session_limit {
+        Some(limit) if is_session_limit_error(&err) => {
+            ProverError::CycleLimitExceeded { limit }.into()
+        }
+        _ => err,
+    }
+}
+
+fn is_session_limit_error(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|cause| cause.to_string().to_lowercase().contains("session limit exceeded"))
+}
+
+/// Compress an existing (composite or succinct) receipt into a Groth16 receipt
+#[cfg(feature = "groth16")]
+pub fn compress_to_groth16(receipt: &Receipt) -> Result<Receipt> {
+    default_prover()
+        .compress(&ProverOpts::groth16(), receipt)
+        .context("Failed to compress receipt to Groth16")
+}
+
+/// Encode a Groth16 receipt's seal for the RISC Zero Solidity verifier
+///
+/// The verifier router dispatches on a 4-byte selector taken from the
+/// verifier parameters digest, so the selector is prepended to the raw seal.
+#[cfg(feature = "groth16")]
+pub fn encode_groth16_seal(receipt: &Receipt) -> Result<Vec<u8>> {
+    let groth16 = receipt
+        .inner
+        .groth16()
+        .context("Receipt is not a Groth16 receipt")?;
+
+    let mut seal = groth16.verifier_parameters.as_bytes()[..4].to_vec();
+    seal.extend_from_slice(&groth16.seal);
+    Ok(seal)
+}
+
+/// Groth16 proof in a form that can be passed straight to a Solidity verifier
+#[cfg(feature = "groth16")]
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Groth16Proof {
+    /// 0x-prefixed hex seal (selector + Groth16 proof)
+    pub seal: String,
+
+    /// 0x-prefixed hex journal bytes committed by the guest
+    pub journal: String,
+
+    /// Image ID used for this proof
+    pub image_id: String,
+}
+
+#[cfg(feature = "groth16")]
+impl Groth16Proof {
+    /// Build the on-chain representation from a Groth16 receipt
+    pub fn from_receipt(receipt: &Receipt, image_id: &str) -> Result<Self> {
+        let seal = encode_groth16_seal(receipt)?;
+
+        Ok(Self {
+            seal: format!("0x{}", hex::encode(seal)),
+            journal: format!("0x{}", hex::encode(&receipt.journal.bytes)),
+            image_id: image_id.to_string(),
+        })
+    }
 }
 
 /// Result of proof generation
+#[derive(Debug, Clone)]
 pub struct ProofResult {
-    /// Hex-encoded proof (serialized Receipt)
-    pub proof: String,
+    /// Serialized Receipt (bincode, standard config)
+    pub proof: Vec<u8>,
+
+    /// Proof system of the receipt (composite unless the prover was asked to compress)
+    pub receipt_kind: ReceiptKind,
 
     /// Image ID used for this proof
     pub image_id: String,
 
     /// Public outputs from the guest program
     pub outputs: serde_json::Value,
+
+    /// Cycle counts of the proving session
+    pub cycles: CycleEstimate,
 }
 
 impl Default for Prover {
@@ -154,10 +554,218 @@ mod tests {
             image_id: "image-abc".to_string(),
             elf_path: "/path/to/guest.elf".to_string(),
             elf_binary: vec![],
+            dsl: None,
         };
 
         prover.load_program(program).unwrap();
         assert!(prover.has_program("customer-123"));
         assert_eq!(prover.program_count(), 1);
     }
+
+    fn prover_with_cache() -> Prover {
+        // An empty ELF can't be proven, so any success must come from the cache
+        let mut prover = Prover::new().with_proof_cache(Some(ProofCache::new(10)));
+        prover
+            .load_program(GuestProgram {
+                customer_id: "customer-123".to_string(),
+                image_id: "image-abc".to_string(),
+                elf_path: "/path/to/guest.elf".to_string(),
+                elf_binary: vec![],
+                dsl: None,
+            })
+            .unwrap();
+        prover
+    }
+
+    #[test]
+    fn test_proof_cache_hit_returns_same_proof() {
+        let prover = prover_with_cache();
+        let private_inputs = serde_json::json!({ "age": 30, "name": "alice" });
+        let public_params = serde_json::json!({ "min_age": 18 });
+
+        let cached = ProofResult {
+            proof: vec![0xca, 0xfe, 0xba, 0xbe],
+            receipt_kind: ReceiptKind::Composite,
+            image_id: "image-abc".to_string(),
+            outputs: serde_json::json!({ "compliance_result": true }),
+            cycles: CycleEstimate::default(),
+        };
+        let nullifier = Nullifier::new([7u8; 32]);
+        let key = ProofCache::key("image-abc", &private_inputs, &public_params, &nullifier);
+        prover.proof_cache.as_ref().unwrap().insert(key, cached);
+
+        // Same inputs in a different key order still hit
+        let result = prover
+            .generate_proof(
+                "customer-123",
+                &serde_json::json!({ "name": "alice", "age": 30 }),
+                &public_params,
+                &nullifier,
+            )
+            .unwrap();
+        assert_eq!(result.proof, [0xca, 0xfe, 0xba, 0xbe]);
+        assert_eq!(result.outputs["compliance_result"], true);
+    }
+
+    #[test]
+    fn test_proof_cache_miss_recomputes() {
+        let prover = prover_with_cache();
+        let public_params = serde_json::json!({ "min_age": 18 });
+
+        let nullifier = Nullifier::new([7u8; 32]);
+        let key = ProofCache::key(
+            "image-abc",
+            &serde_json::json!({ "age": 30 }),
+            &public_params,
+            &nullifier,
+        );
+        prover.proof_cache.as_ref().unwrap().insert(
+            key,
+            ProofResult {
+                proof: vec![0xca, 0xfe, 0xba, 0xbe],
+                receipt_kind: ReceiptKind::Composite,
+                image_id: "image-abc".to_string(),
+                outputs: serde_json::json!({}),
+                cycles: CycleEstimate::default(),
+            },
+        );
+
+        // Different inputs go to the prover, which fails on the empty ELF
+        let result = prover.generate_proof(
+            "customer-123",
+            &serde_json::json!({ "age": 31 }),
+            &public_params,
+            &nullifier,
+        );
+        assert!(result.is_err());
+
+        // So do the same inputs for another payment
+        let result = prover.generate_proof(
+            "customer-123",
+            &serde_json::json!({ "age": 30 }),
+            &public_params,
+            &Nullifier::new([8u8; 32]),
+        );
+        assert!(result.is_err());
+        assert_eq!(prover.proof_cache.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_session_limit_error_mapped() {
+        let limits = ExecutionLimits {
+            session_limit: Some(1024),
+            segment_limit_po2: None,
+            max_input_bytes: None,
+        };
+
+        let err = limit_error(anyhow::anyhow!("Session limit exceeded"), limits);
+        assert!(matches!(
+            err.downcast_ref::<ProverError>(),
+            Some(ProverError::CycleLimitExceeded { limit: 1024 })
+        ));
+
+        // Other failures pass through untouched
+        let err = limit_error(anyhow::anyhow!("Guest panicked"), limits);
+        assert!(err.downcast_ref::<ProverError>().is_none());
+    }
+
+    #[cfg(feature = "zkvm-tests")]
+    #[test]
+    fn test_guest_exceeding_session_limit_fails() {
+        let mut prover = Prover::new().with_limits(ExecutionLimits {
+            session_limit: Some(1),
+            segment_limit_po2: None,
+            max_input_bytes: None,
+        });
+        prover
+            .load_program(GuestProgram {
+                customer_id: "customer-123".to_string(),
+                image_id: risc0_zkvm::sha::Digest::from(methods::GUEST_ID).to_string(),
+                elf_path: "guest.elf".to_string(),
+                elf_binary: methods::GUEST_ELF.to_vec(),
+                dsl: None,
+            })
+            .unwrap();
+
+        let err = prover
+            .generate_proof(
+                "customer-123",
+                &serde_json::json!({}),
+                &serde_json::json!({}),
+                &Nullifier::new([7u8; 32]),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ProverError>(),
+            Some(ProverError::CycleLimitExceeded { limit: 1 })
+        ));
+    }
+
+    #[cfg(feature = "zkvm-tests")]
+    #[test]
+    #[allow(deprecated)]
+    fn test_estimate_reports_cycles() {
+        use khafi_common::{BusinessInputs, GuestInputs, ZcashInputs};
+
+        let inputs = GuestInputs {
+            zcash: ZcashInputs {
+                spending_key: vec![7u8; 32],
+                note: vec![],
+                merkle_path: vec![],
+                merkle_root: [0u8; 32],
+            },
+            nullifier: Nullifier::new([7u8; 32]),
+            business: BusinessInputs {
+                private_data: vec![],
+                public_params: vec![],
+            },
+        };
+
+        let env = ExecutorEnv::builder().write(&inputs).unwrap().build().unwrap();
+        let estimate =
+            estimate_cycles(env, methods::GUEST_ELF, ExecutionLimits::default()).unwrap();
+
+        assert!(estimate.total_cycles > 0);
+        assert!(estimate.user_cycles > 0);
+        assert!(estimate.user_cycles <= estimate.total_cycles);
+        assert!(estimate.segments >= 1);
+    }
+
+    #[cfg(feature = "groth16")]
+    #[test]
+    #[ignore] // Requires the stark-to-snark prover (Docker on x86_64)
+    #[allow(deprecated)]
+    fn test_compress_composite_to_groth16() {
+        use khafi_common::{BusinessInputs, GuestInputs, ZcashInputs};
+
+        let inputs = GuestInputs {
+            zcash: ZcashInputs {
+                spending_key: vec![7u8; 32],
+                note: vec![],
+                merkle_path: vec![],
+                merkle_root: [0u8; 32],
+            },
+            nullifier: Nullifier::new([7u8; 32]),
+            business: BusinessInputs {
+                private_data: vec![],
+                public_params: vec![],
+            },
+        };
+
+        let env = ExecutorEnv::builder().write(&inputs).unwrap().build().unwrap();
+        let composite = default_prover()
+            .prove(env, methods::GUEST_ELF)
+            .unwrap()
+            .receipt;
+        assert!(composite.inner.composite().is_ok());
+
+        let groth16 = compress_to_groth16(&composite).unwrap();
+        assert!(groth16.inner.groth16().is_ok());
+        groth16.verify(methods::GUEST_ID).unwrap();
+        assert_eq!(groth16.journal.bytes, composite.journal.bytes);
+
+        let proof = Groth16Proof::from_receipt(&groth16, "image-abc").unwrap();
+        assert!(proof.seal.starts_with("0x"));
+        assert_eq!(proof.journal, format!("0x{}", hex::encode(&composite.journal.bytes)));
+    }
 }
@@ -16,6 +16,20 @@ pub struct DeploymentInfo {
     pub customer_id: String,
     pub image_id: String,
     pub guest_program_path: String,
+
+    /// Compression applied to the stored ELF (e.g. "gzip"), if any
+    #[serde(default)]
+    pub elf_compression: Option<String>,
+
+    /// Original DSL JSON the guest program was compiled from
+    #[serde(default)]
+    pub dsl: Option<serde_json::Value>,
+}
+
+/// Registry success envelope (`{ "data": ... }`)
+#[derive(Debug, Deserialize)]
+struct ApiResponse<T> {
+    data: T,
 }
 
 /// Deployment response wrapper
@@ -39,7 +53,12 @@ impl RegistryClient {
 
         debug!("Fetching deployment from registry: {}", url);
 
-        let response = self.client.get(&url).send().await?;
+        let response = self
+            .client
+            .get(&url)
+            .headers(khafi_telemetry::trace_headers())
+            .send()
+            .await?;
 
         if response.status() == reqwest::StatusCode::NOT_FOUND {
             return Ok(None);
@@ -52,12 +71,12 @@ impl RegistryClient {
             );
         }
 
-        let deployment_response: DeploymentResponse = response
+        let deployment_response: ApiResponse<DeploymentResponse> = response
             .json()
             .await
             .context("Failed to parse deployment response")?;
 
-        Ok(Some(deployment_response.deployment))
+        Ok(Some(deployment_response.data.deployment))
     }
 
     /// Get deployment information by image ID
@@ -66,7 +85,12 @@ impl RegistryClient {
 
         debug!("Fetching deployment by image_id from registry: {}", url);
 
-        let response = self.client.get(&url).send().await?;
+        let response = self
+            .client
+            .get(&url)
+            .headers(khafi_telemetry::trace_headers())
+            .send()
+            .await?;
 
         if response.status() == reqwest::StatusCode::NOT_FOUND {
             return Ok(None);
@@ -79,12 +103,12 @@ impl RegistryClient {
             );
         }
 
-        let deployment_response: DeploymentResponse = response
+        let deployment_response: ApiResponse<DeploymentResponse> = response
             .json()
             .await
             .context("Failed to parse deployment response")?;
 
-        Ok(Some(deployment_response.deployment))
+        Ok(Some(deployment_response.data.deployment))
     }
 
     /// Check if registry is healthy
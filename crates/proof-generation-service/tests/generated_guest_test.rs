@@ -0,0 +1,158 @@
+//! Builds a guest generated from a DSL and drives it the way the service does
+//!
+//! Needs the RISC Zero toolchain: `cargo test -p proof-generation-service --features zkvm-tests`
+
+#![cfg(feature = "zkvm-tests")]
+
+use axum::{extract::State, Json};
+use khafi_common::{decode_journal, JournalEncoding, Nullifier};
+use logic_compiler::{BusinessRulesDSL, CodeGenerator, DslParser};
+use proof_generation_service::handlers::{generate_proof_handler, ApiResponse};
+use proof_generation_service::{
+    executor_env, AppState, ExecutionLimits, GenerateProofRequest, GuestProgram, ProofMetrics,
+    Prover, ProverBackend, ProvingBackend, RegistryClient,
+};
+use risc0_zkvm::default_executor;
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use tempfile::TempDir;
+use tokio::sync::{RwLock, Semaphore};
+
+const DSL: &str = r#"{
+    "use_case": "age_check",
+    "private_inputs": { "type": "object", "fields": { "age": "u32" } },
+    "public_params": { "min_age": "u32" },
+    "param_defaults": { "min_age": 18 },
+    "validation_rules": [
+        { "type": "range_check", "field": "age", "min_param": "min_age", "max": 150 }
+    ]
+}"#;
+
+/// Generate the SDK package and build its guest the way the build service does
+fn build_guest(dsl: &BusinessRulesDSL, dir: &Path) -> Vec<u8> {
+    let generator = CodeGenerator::new(dsl.clone());
+    generator
+        .generate_sdk_package(dir)
+        .expect("Failed to generate SDK package");
+
+    let methods_dir = dir.join("methods");
+    let output = Command::new("cargo")
+        .arg("risczero")
+        .arg("build")
+        .current_dir(&methods_dir)
+        .output()
+        .expect("Failed to execute cargo risczero build");
+    assert!(
+        output.status.success(),
+        "Guest build failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let elf_path = find_file(
+        &methods_dir.join("target/riscv-guest"),
+        &generator.guest_package_name(),
+    )
+    .expect("Guest ELF not found after build");
+    std::fs::read(elf_path).unwrap()
+}
+
+/// Find a file by name anywhere under `dir` (the layout varies across risc0 versions)
+fn find_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    for entry in std::fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if let Some(found) = find_file(&path, name) {
+                return Some(found);
+            }
+        } else if entry.file_name() == name {
+            return Some(path);
+        }
+    }
+    None
+}
+
+/// Run the guest with the environment the prover builds for it
+fn execute(elf: &[u8], age: u32, public_params: serde_json::Value, nullifier: [u8; 32]) -> Vec<u8> {
+    let env = executor_env(
+        &json!({ "age": age }),
+        &public_params,
+        &Nullifier::new(nullifier),
+        ExecutionLimits::default(),
+    )
+    .unwrap();
+
+    default_executor().execute(env, elf).unwrap().journal.bytes
+}
+
+#[tokio::test]
+async fn test_generated_guest_end_to_end() {
+    let dsl = DslParser::parse_str(DSL).expect("Failed to parse DSL");
+    let temp_dir = TempDir::new().unwrap();
+    let elf = build_guest(&dsl, temp_dir.path());
+
+    // The journal decodes as GuestOutputs, carrying the request's nullifier
+    let journal = execute(&elf, 30, json!({ "min_age": 18 }), [9u8; 32]);
+    let outputs = decode_journal(&journal, JournalEncoding::Auto)
+        .expect("Journal does not decode as GuestOutputs");
+    assert_eq!(outputs.nullifier, Nullifier::new([9u8; 32]));
+    assert!(outputs.compliance_result);
+    assert_eq!(outputs.metadata, b"range_check:age");
+
+    // A failing input still commits the nullifier, with no rule tags
+    let journal = execute(&elf, 12, json!({ "min_age": 18 }), [3u8; 32]);
+    let outputs = decode_journal(&journal, JournalEncoding::Json).unwrap();
+    assert_eq!(outputs.nullifier, Nullifier::new([3u8; 32]));
+    assert!(!outputs.compliance_result);
+    assert!(outputs.metadata.is_empty());
+
+    // An omitted min_age is filled in with the DSL default (18) before the
+    // rules run, so 16 passes only when the request lowers it
+    let journal = execute(&elf, 16, json!({ "min_age": 16 }), [4u8; 32]);
+    let outputs = decode_journal(&journal, JournalEncoding::Json).unwrap();
+    assert!(outputs.compliance_result);
+    let journal = execute(&elf, 16, json!({}), [4u8; 32]);
+    let outputs = decode_journal(&journal, JournalEncoding::Json).unwrap();
+    assert!(!outputs.compliance_result);
+
+    // Through /api/generate-proof (dev mode, so the receipt is fake but the
+    // guest still runs), leaving min_age to its default
+    std::env::set_var("RISC0_DEV_MODE", "1");
+    let mut prover = Prover::new();
+    prover
+        .load_program(GuestProgram {
+            customer_id: "customer-123".to_string(),
+            image_id: risc0_zkvm::compute_image_id(&elf).unwrap().to_string(),
+            elf_path: "guest.elf".to_string(),
+            elf_binary: elf,
+            dsl: Some(dsl),
+        })
+        .unwrap();
+    let state = Arc::new(AppState {
+        prover: RwLock::new(Box::new(prover)),
+        registry_client: RegistryClient::new("http://127.0.0.1:1".to_string()),
+        pending_programs: RwLock::new(BTreeSet::new()),
+        prover_backend: ProverBackend::default(),
+        metrics: ProofMetrics::default(),
+        proof_slots: Semaphore::new(1),
+    });
+
+    let request: GenerateProofRequest = serde_json::from_value(json!({
+        "customer_id": "customer-123",
+        "private_inputs": { "age": 16 },
+        "public_params": {},
+        "nullifier": "05".repeat(32),
+    }))
+    .unwrap();
+    let ApiResponse { data: response } = generate_proof_handler(State(state), Json(request))
+        .await
+        .unwrap();
+
+    assert!(response.success, "{:?}", response.error);
+    let journal = serde_json::to_vec(&response.outputs.unwrap()).unwrap();
+    let outputs = decode_journal(&journal, JournalEncoding::Json).unwrap();
+    assert_eq!(outputs.nullifier, Nullifier::new([5u8; 32]));
+    assert!(!outputs.compliance_result);
+}
@@ -0,0 +1,242 @@
+//! Khafi Telemetry
+//!
+//! Shared tracing setup for all Khafi services. Every service logs to stdout;
+//! when `OTEL_EXPORTER_OTLP_ENDPOINT` is set, spans are also exported over
+//! OTLP so a single request can be followed through deploy → build →
+//! register → prove → verify.
+//!
+//! Trace context crosses service boundaries as W3C `traceparent` headers:
+//! use [`trace_headers`] on outgoing HTTP requests, [`http_request_span`]
+//! as the `TraceLayer` span factory for incoming ones, and
+//! [`set_parent_from_map`] for the ExtAuth gRPC header map.
+
+use anyhow::{Context, Result};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::{global, KeyValue};
+use opentelemetry_http::{HeaderExtractor, HeaderInjector};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use std::collections::HashMap;
+use tracing::{Span, Subscriber};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+/// Tracing configuration for a service
+#[derive(Debug, Clone)]
+pub struct TelemetryConfig {
+    /// Service name reported to the trace backend
+    pub service_name: String,
+
+    /// Log filter used when `RUST_LOG` is not set
+    pub default_filter: String,
+
+    /// OTLP/HTTP collector base URL (e.g. `http://otel-collector:4318`)
+    pub otlp_endpoint: Option<String>,
+}
+
+impl TelemetryConfig {
+    /// Build configuration from environment variables
+    ///
+    /// OTLP export is enabled only when `OTEL_EXPORTER_OTLP_ENDPOINT` is set
+    /// and non-empty.
+    pub fn from_env(service_name: &str, default_filter: &str) -> Self {
+        let otlp_endpoint = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+
+        Self {
+            service_name: service_name.to_string(),
+            default_filter: default_filter.to_string(),
+            otlp_endpoint,
+        }
+    }
+}
+
+/// Keeps the OTLP pipeline alive; flushes pending spans when dropped
+pub struct TelemetryGuard {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl TelemetryGuard {
+    /// Whether spans are being exported over OTLP
+    pub fn otlp_enabled(&self) -> bool {
+        self.provider.is_some()
+    }
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to shut down OTLP tracer provider: {}", e);
+            }
+        }
+    }
+}
+
+/// Initialize the global tracing subscriber for a service
+///
+/// Hold on to the returned guard for the lifetime of the process.
+pub fn init_tracing(service_name: &str, default_filter: &str) -> Result<TelemetryGuard> {
+    let config = TelemetryConfig::from_env(service_name, default_filter);
+
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let (subscriber, provider) = build_subscriber(&config)?;
+    subscriber
+        .try_init()
+        .context("Failed to install tracing subscriber")?;
+
+    if let Some(endpoint) = &config.otlp_endpoint {
+        tracing::info!("Exporting traces via OTLP to {}", endpoint);
+    }
+
+    Ok(TelemetryGuard { provider })
+}
+
+/// Build the subscriber stack: env filter + fmt, plus OTLP when configured
+fn build_subscriber(
+    config: &TelemetryConfig,
+) -> Result<(impl Subscriber + Send + Sync, Option<SdkTracerProvider>)> {
+    let provider = match &config.otlp_endpoint {
+        Some(endpoint) => Some(build_tracer_provider(&config.service_name, endpoint)?),
+        None => None,
+    };
+
+    let otel_layer = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(config.service_name.clone()))
+    });
+
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(&config.default_filter));
+
+    let subscriber = tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer())
+        .with(otel_layer);
+
+    Ok((subscriber, provider))
+}
+
+/// Create a batching OTLP/HTTP tracer provider
+fn build_tracer_provider(service_name: &str, endpoint: &str) -> Result<SdkTracerProvider> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()
+        .context("Failed to create OTLP span exporter")?;
+
+    let resource = Resource::builder()
+        .with_service_name(service_name.to_string())
+        .with_attribute(KeyValue::new("service.namespace", "khafi-gateway"))
+        .build();
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource)
+        .build())
+}
+
+/// Headers carrying the current span's trace context, for outgoing HTTP requests
+pub fn trace_headers() -> http::HeaderMap {
+    let mut headers = http::HeaderMap::new();
+    let context = Span::current().context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(&mut headers))
+    });
+    headers
+}
+
+/// The current span's trace context as a plain map, for carrying through queues
+pub fn current_trace_context() -> HashMap<String, String> {
+    let mut carrier = HashMap::new();
+    let context = Span::current().context();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(&context, &mut carrier));
+    carrier
+}
+
+/// `TraceLayer` span factory that continues the caller's trace
+pub fn http_request_span<B>(request: &http::Request<B>) -> Span {
+    let span = tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+    );
+
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    let _ = span.set_parent(parent);
+
+    span
+}
+
+/// Continue a trace from a plain header map (e.g. Envoy ExtAuth `CheckRequest.headers`)
+/// or a context captured with [`current_trace_context`]
+pub fn set_parent_from_map(span: &Span, headers: &HashMap<String, String>) {
+    let parent = global::get_text_map_propagator(|propagator| propagator.extract(headers));
+    let _ = span.set_parent(parent);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::TraceContextExt;
+
+    fn config(otlp_endpoint: Option<&str>) -> TelemetryConfig {
+        TelemetryConfig {
+            service_name: "test-service".to_string(),
+            default_filter: "info".to_string(),
+            otlp_endpoint: otlp_endpoint.map(str::to_string),
+        }
+    }
+
+    /// Whether spans created under `subscriber` get a real OpenTelemetry context
+    fn spans_have_otel_context(subscriber: impl Subscriber + Send + Sync) -> bool {
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("test");
+            span.context().span().span_context().is_valid()
+        })
+    }
+
+    #[test]
+    fn test_subscriber_without_otlp() {
+        let (subscriber, provider) = build_subscriber(&config(None)).unwrap();
+        assert!(provider.is_none());
+        assert!(!spans_have_otel_context(subscriber));
+    }
+
+    #[test]
+    fn test_subscriber_with_otlp() {
+        let (subscriber, provider) =
+            build_subscriber(&config(Some("http://127.0.0.1:4318"))).unwrap();
+        assert!(provider.is_some());
+        assert!(spans_have_otel_context(subscriber));
+    }
+
+    #[test]
+    fn test_trace_context_round_trip() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+
+        let traceparent = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+        let mut headers = HashMap::new();
+        headers.insert("traceparent".to_string(), traceparent.to_string());
+
+        let (subscriber, _provider) =
+            build_subscriber(&config(Some("http://127.0.0.1:4318"))).unwrap();
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("check");
+            set_parent_from_map(&span, &headers);
+
+            let outgoing = span.in_scope(trace_headers);
+            let propagated = outgoing.get("traceparent").unwrap().to_str().unwrap();
+
+            // Same trace, new span
+            assert!(propagated.starts_with("00-0af7651916cd43dd8448eb211c80319c-"));
+            assert_ne!(propagated, traceparent);
+        });
+    }
+}
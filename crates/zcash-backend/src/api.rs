@@ -1,10 +1,17 @@
 //! REST API module for Zcash Backend
 //!
 //! Provides HTTP endpoints for querying payment status.
+//!
+//! Every response body is JSON: `{ "data": ... }` on success and
+//! `{ "error": "..." }` on failure.
 
 use axum::{
-    extract::{Path, State},
+    extract::{
+        rejection::{JsonRejection, QueryRejection},
+        Path, Query, Request, State,
+    },
     http::StatusCode,
+    middleware::{self, Next},
     response::{IntoResponse, Json, Response},
     routing::{get, post},
     Router,
@@ -13,15 +20,23 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tower_http::trace::TraceLayer;
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::storage::{ReceivedPayment, Storage};
+use crate::monitor::{MonitorHandle, SyncProgress};
+use crate::storage::{
+    ConfirmOutcome, PaymentStats, ReceivedPayment, ReleaseOutcome, ReserveOutcome, Storage,
+    RESERVATION_TTL_SECS,
+};
+use crate::units::{zatoshis_to_zec, AmountUnits};
+use khafi_admin::{AdminConfig, AdminLayer};
 use khafi_common::Nullifier;
 
 /// Shared application state
 #[derive(Clone)]
 pub struct AppState {
     pub storage: Arc<Mutex<Storage>>,
+    /// Running monitor, when this process owns one
+    pub monitor: Option<MonitorHandle>,
 }
 
 /// Payment status response
@@ -32,6 +47,7 @@ pub struct PaymentStatusResponse {
     pub amount: Option<u64>,
     pub block_height: Option<u32>,
     pub tx_id: Option<String>,
+    pub usd_value: Option<f64>,
 }
 
 /// Admin payment insertion request
@@ -43,12 +59,115 @@ pub struct InsertPaymentRequest {
     pub block_height: u32,
 }
 
+/// Confirm/release request: the token `reserve` handed out
+#[derive(Debug, Deserialize)]
+pub struct ReservationTokenRequest {
+    pub reservation_token: String,
+}
+
+/// Stats query parameters
+#[derive(Debug, Default, Deserialize)]
+pub struct StatsQuery {
+    /// Units for the amount fields (default ZEC)
+    #[serde(default)]
+    pub units: AmountUnits,
+}
+
 /// Stats response
 #[derive(Debug, Serialize)]
 pub struct StatsResponse {
     pub total_payments: usize,
     pub unused_payments: usize,
-    pub total_amount_zec: f64,
+    #[serde(flatten)]
+    pub amounts: StatsAmounts,
+    pub total_usd_value: f64,
+}
+
+/// Payment totals in the requested units
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum StatsAmounts {
+    Zec {
+        total_amount_zec: f64,
+        used_amount_zec: f64,
+        unused_amount_zec: f64,
+    },
+    Zatoshis {
+        total_amount_zatoshis: u64,
+        used_amount_zatoshis: u64,
+        unused_amount_zatoshis: u64,
+    },
+}
+
+impl StatsAmounts {
+    /// Report the totals from `stats` in `units`
+    pub fn new(stats: &PaymentStats, units: AmountUnits) -> Self {
+        match units {
+            AmountUnits::Zec => StatsAmounts::Zec {
+                total_amount_zec: zatoshis_to_zec(stats.total_amount),
+                used_amount_zec: zatoshis_to_zec(stats.used_amount),
+                unused_amount_zec: zatoshis_to_zec(stats.unused_amount),
+            },
+            AmountUnits::Zatoshis => StatsAmounts::Zatoshis {
+                total_amount_zatoshis: stats.total_amount,
+                used_amount_zatoshis: stats.used_amount,
+                unused_amount_zatoshis: stats.unused_amount,
+            },
+        }
+    }
+}
+
+/// Reindex response
+#[derive(Debug, Serialize)]
+pub struct ReindexResponse {
+    pub total_payments: usize,
+    pub unused_payments: usize,
+}
+
+/// Reservation response
+#[derive(Debug, Serialize)]
+pub struct ReservationResponse {
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl_secs: Option<u64>,
+    /// Present on `reserve`; required to confirm or release the reservation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reservation_token: Option<String>,
+}
+
+/// Health check response
+#[derive(Debug, Serialize)]
+pub struct HealthResponse {
+    pub status: &'static str,
+}
+
+/// Readiness response
+#[derive(Debug, Serialize)]
+pub struct ReadyResponse {
+    pub status: &'static str,
+    /// Block scanning progress, when this process runs the monitor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync: Option<SyncProgress>,
+}
+
+/// Not-ready response: the usual error body plus scanning progress
+#[derive(Debug, Serialize)]
+pub struct NotReadyResponse {
+    pub error: String,
+    pub sync: SyncProgress,
+}
+
+/// Admin payment insertion response
+#[derive(Debug, Serialize)]
+pub struct InsertPaymentResponse {
+    pub status: &'static str,
+    pub nullifier_hex: String,
+}
+
+/// Successful response envelope
+#[derive(Debug, Serialize)]
+pub struct DataResponse<T> {
+    pub data: T,
 }
 
 /// API error response
@@ -58,31 +177,125 @@ pub struct ErrorResponse {
 }
 
 /// Create the API router
-pub fn create_router(storage: Storage) -> Router {
+///
+/// `monitor` backs `POST /admin/poll`; without it the endpoint returns 503.
+/// The `/admin` routes sit behind an [`AdminLayer`] built from `admin`. The
+/// payment reservation routes require `payment_api_token` as a bearer token
+/// and return 503 without one.
+pub fn create_router(
+    storage: Storage,
+    monitor: Option<MonitorHandle>,
+    admin: AdminConfig,
+    payment_api_token: Option<String>,
+) -> Router {
     let state = AppState {
         storage: Arc::new(Mutex::new(storage)),
+        monitor,
     };
 
+    let admin_routes = Router::new()
+        .route("/admin/payment", post(insert_payment_handler))
+        .route("/admin/reindex", post(reindex_handler))
+        .route("/admin/poll", post(poll_handler))
+        .route_layer(AdminLayer::new(admin));
+
+    if payment_api_token.is_none() {
+        warn!("PAYMENT_API_TOKEN not set; payment reservation endpoints are disabled");
+    }
+    let reservation_routes = Router::new()
+        .route(
+            "/payment/{nullifier}/reserve",
+            post(reserve_payment_handler),
+        )
+        .route(
+            "/payment/{nullifier}/release",
+            post(release_payment_handler),
+        )
+        .route(
+            "/payment/{nullifier}/confirm",
+            post(confirm_payment_handler),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            payment_api_token.map(Arc::<str>::from),
+            require_payment_api_token,
+        ));
+
     Router::new()
         .route("/health", get(health_handler))
+        .route("/ready", get(ready_handler))
         .route("/payment/{nullifier}", get(get_payment_handler))
-        .route("/admin/payment", post(insert_payment_handler))
         .route("/stats", get(stats_handler))
-        .layer(TraceLayer::new_for_http())
+        .merge(reservation_routes)
+        .merge(admin_routes)
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(khafi_telemetry::http_request_span::<axum::body::Body>),
+        )
         .with_state(state)
 }
 
+/// Admit requests bearing the payment API token
+async fn require_payment_api_token(
+    State(token): State<Option<Arc<str>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(token) = token else {
+        return error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Payment API disabled: PAYMENT_API_TOKEN is not set",
+        );
+    };
+    if !khafi_admin::bearer_token_matches(request.headers(), &token) {
+        return error_response(
+            StatusCode::UNAUTHORIZED,
+            "Missing or invalid payment API token",
+        );
+    }
+    next.run(request).await
+}
+
 /// Health check endpoint
 async fn health_handler(State(state): State<AppState>) -> Response {
     let mut storage = state.storage.lock().await;
 
     match storage.health_check().await {
-        Ok(_) => (StatusCode::OK, "OK").into_response(),
-        Err(e) => (
+        Ok(_) => data_response(StatusCode::OK, HealthResponse { status: "healthy" }),
+        Err(e) => error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            &format!("Redis connection failed: {}", e),
+        ),
+    }
+}
+
+/// Readiness endpoint
+///
+/// GET /ready
+///
+/// Returns 503 while the monitor is catching up on historical blocks, since
+/// payments in blocks it hasn't reached yet would be reported as missing.
+async fn ready_handler(State(state): State<AppState>) -> Response {
+    let sync = state.monitor.as_ref().map(MonitorHandle::sync_progress);
+
+    match sync {
+        Some(sync) if sync.catching_up => (
             StatusCode::SERVICE_UNAVAILABLE,
-            format!("Redis connection failed: {}", e),
+            Json(NotReadyResponse {
+                error: format!(
+                    "Catching up: {} of {} blocks scanned",
+                    sync.blocks_done, sync.blocks_total
+                ),
+                sync,
+            }),
         )
             .into_response(),
+        _ => data_response(
+            StatusCode::OK,
+            ReadyResponse {
+                status: "ready",
+                sync,
+            },
+        ),
     }
 }
 
@@ -96,15 +309,7 @@ async fn get_payment_handler(
     // Parse nullifier
     let nullifier = match parse_nullifier(&nullifier_hex) {
         Ok(n) => n,
-        Err(e) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: format!("Invalid nullifier: {}", e),
-                }),
-            )
-                .into_response()
-        }
+        Err(e) => return invalid_nullifier(e),
     };
 
     let mut storage = state.storage.lock().await;
@@ -118,8 +323,9 @@ async fn get_payment_handler(
                 amount: Some(payment.amount),
                 block_height: Some(payment.block_height),
                 tx_id: Some(payment.tx_id),
+                usd_value: payment.usd_value,
             };
-            (StatusCode::OK, Json(response)).into_response()
+            data_response(StatusCode::OK, response)
         }
         Ok(None) => {
             let response = PaymentStatusResponse {
@@ -128,38 +334,181 @@ async fn get_payment_handler(
                 amount: None,
                 block_height: None,
                 tx_id: None,
+                usd_value: None,
             };
-            (StatusCode::OK, Json(response)).into_response()
+            data_response(StatusCode::OK, response)
         }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: format!("Storage error: {}", e),
-            }),
-        )
-            .into_response(),
+        Err(e) => storage_error(e),
+    }
+}
+
+/// Reserve a payment (two-phase commit - phase 1)
+///
+/// POST /payment/:nullifier/reserve
+async fn reserve_payment_handler(
+    State(state): State<AppState>,
+    Path(nullifier_hex): Path<String>,
+) -> Response {
+    let nullifier = match parse_nullifier(&nullifier_hex) {
+        Ok(n) => n,
+        Err(e) => return invalid_nullifier(e),
+    };
+
+    let mut storage = state.storage.lock().await;
+
+    match storage.reserve_payment(&nullifier).await {
+        Ok(ReserveOutcome::Reserved { token }) => data_response(
+            StatusCode::OK,
+            ReservationResponse {
+                status: "reserved",
+                ttl_secs: Some(RESERVATION_TTL_SECS),
+                reservation_token: Some(token),
+            },
+        ),
+        Ok(ReserveOutcome::NotFound) => error_response(StatusCode::NOT_FOUND, "Payment not found"),
+        Ok(ReserveOutcome::AlreadyUsed) => {
+            error_response(StatusCode::CONFLICT, "Payment already used")
+        }
+        Ok(ReserveOutcome::AlreadyReserved) => {
+            error_response(StatusCode::CONFLICT, "Payment already reserved")
+        }
+        Err(e) => storage_error(e),
+    }
+}
+
+/// Release a reservation so the payment can be reserved again
+///
+/// POST /payment/:nullifier/release
+async fn release_payment_handler(
+    State(state): State<AppState>,
+    Path(nullifier_hex): Path<String>,
+    payload: Result<Json<ReservationTokenRequest>, JsonRejection>,
+) -> Response {
+    let nullifier = match parse_nullifier(&nullifier_hex) {
+        Ok(n) => n,
+        Err(e) => return invalid_nullifier(e),
+    };
+    let Json(req) = match payload {
+        Ok(req) => req,
+        Err(rejection) => return error_response(rejection.status(), &rejection.body_text()),
+    };
+
+    let mut storage = state.storage.lock().await;
+
+    let status = match storage
+        .release_reservation(&nullifier, &req.reservation_token)
+        .await
+    {
+        Ok(ReleaseOutcome::Released) => "released",
+        Ok(ReleaseOutcome::NotReserved) => "not_reserved",
+        Ok(ReleaseOutcome::WrongToken) => return wrong_reservation_token(),
+        Err(e) => return storage_error(e),
+    };
+    data_response(
+        StatusCode::OK,
+        ReservationResponse {
+            status,
+            ttl_secs: None,
+            reservation_token: None,
+        },
+    )
+}
+
+/// Mark a reserved payment as used (two-phase commit - phase 2)
+///
+/// POST /payment/:nullifier/confirm
+async fn confirm_payment_handler(
+    State(state): State<AppState>,
+    Path(nullifier_hex): Path<String>,
+    payload: Result<Json<ReservationTokenRequest>, JsonRejection>,
+) -> Response {
+    let nullifier = match parse_nullifier(&nullifier_hex) {
+        Ok(n) => n,
+        Err(e) => return invalid_nullifier(e),
+    };
+    let Json(req) = match payload {
+        Ok(req) => req,
+        Err(rejection) => return error_response(rejection.status(), &rejection.body_text()),
+    };
+
+    let mut storage = state.storage.lock().await;
+
+    match storage
+        .confirm_payment(&nullifier, &req.reservation_token)
+        .await
+    {
+        Ok(ConfirmOutcome::Confirmed) => data_response(
+            StatusCode::OK,
+            ReservationResponse {
+                status: "confirmed",
+                ttl_secs: None,
+                reservation_token: None,
+            },
+        ),
+        Ok(ConfirmOutcome::NotFound) => error_response(StatusCode::NOT_FOUND, "Payment not found"),
+        Ok(ConfirmOutcome::AlreadyUsed) => {
+            error_response(StatusCode::CONFLICT, "Payment already used")
+        }
+        Ok(ConfirmOutcome::NotReserved) => {
+            error_response(StatusCode::CONFLICT, "Payment is not reserved")
+        }
+        Ok(ConfirmOutcome::WrongToken) => wrong_reservation_token(),
+        Err(e) => storage_error(e),
     }
 }
 
+fn wrong_reservation_token() -> Response {
+    error_response(
+        StatusCode::FORBIDDEN,
+        "Reservation is held by another caller",
+    )
+}
+
+fn invalid_nullifier(e: String) -> Response {
+    error_response(
+        StatusCode::BAD_REQUEST,
+        &format!("Invalid nullifier: {}", e),
+    )
+}
+
+fn storage_error(e: anyhow::Error) -> Response {
+    error_response(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        &format!("Storage error: {}", e),
+    )
+}
+
+fn data_response<T: Serialize>(status: StatusCode, data: T) -> Response {
+    (status, Json(DataResponse { data })).into_response()
+}
+
+fn error_response(status: StatusCode, error: &str) -> Response {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_string(),
+        }),
+    )
+        .into_response()
+}
+
 /// Insert payment manually (admin endpoint for testing)
 ///
 /// POST /admin/payment
 async fn insert_payment_handler(
     State(state): State<AppState>,
-    Json(req): Json<InsertPaymentRequest>,
+    payload: Result<Json<InsertPaymentRequest>, JsonRejection>,
 ) -> Response {
+    // Malformed bodies get the same JSON error shape as every other failure
+    let Json(req) = match payload {
+        Ok(req) => req,
+        Err(rejection) => return error_response(rejection.status(), &rejection.body_text()),
+    };
+
     // Parse nullifier
     let nullifier = match parse_nullifier(&req.nullifier_hex) {
         Ok(n) => n,
-        Err(e) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: format!("Invalid nullifier: {}", e),
-                }),
-            )
-                .into_response()
-        }
+        Err(e) => return invalid_nullifier(e),
     };
 
     // Create payment
@@ -170,29 +519,72 @@ async fn insert_payment_handler(
     match storage.insert_payment(&payment).await {
         Ok(true) => {
             info!("Manually inserted payment: {}", req.nullifier_hex);
-            (StatusCode::CREATED, "Payment inserted").into_response()
+            data_response(
+                StatusCode::CREATED,
+                InsertPaymentResponse {
+                    status: "inserted",
+                    nullifier_hex: req.nullifier_hex,
+                },
+            )
         }
-        Ok(false) => (
-            StatusCode::CONFLICT,
-            Json(ErrorResponse {
-                error: "Payment already exists".to_string(),
-            }),
-        )
-            .into_response(),
-        Err(e) => (
+        Ok(false) => error_response(StatusCode::CONFLICT, "Payment already exists"),
+        Err(e) => storage_error(e),
+    }
+}
+
+/// Rebuild the payment index sets from the `payment:*` hashes
+///
+/// POST /admin/reindex
+async fn reindex_handler(State(state): State<AppState>) -> Response {
+    let mut storage = state.storage.lock().await;
+
+    match storage.reindex_payments().await {
+        Ok(stats) => {
+            let response = ReindexResponse {
+                total_payments: stats.total_payments,
+                unused_payments: stats.unused_payments,
+            };
+            data_response(StatusCode::OK, response)
+        }
+        Err(e) => storage_error(e),
+    }
+}
+
+/// Run one monitor pass now instead of waiting for the polling interval
+///
+/// POST /admin/poll
+async fn poll_handler(State(state): State<AppState>) -> Response {
+    let Some(monitor) = state.monitor else {
+        return error_response(StatusCode::SERVICE_UNAVAILABLE, "Monitor is not running");
+    };
+
+    match monitor.poll_now().await {
+        Ok(summary) => {
+            info!(
+                "Admin poll processed {} block(s), {} new payment(s)",
+                summary.blocks_processed, summary.payments_stored
+            );
+            data_response(StatusCode::OK, summary)
+        }
+        Err(e) => error_response(
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: format!("Storage error: {}", e),
-            }),
-        )
-            .into_response(),
+            &format!("Poll failed: {:#}", e),
+        ),
     }
 }
 
 /// Get payment statistics
 ///
-/// GET /stats
-async fn stats_handler(State(state): State<AppState>) -> Response {
+/// GET /stats?units=zec|zatoshis
+async fn stats_handler(
+    State(state): State<AppState>,
+    query: Result<Query<StatsQuery>, QueryRejection>,
+) -> Response {
+    let Query(query) = match query {
+        Ok(query) => query,
+        Err(rejection) => return error_response(rejection.status(), &rejection.body_text()),
+    };
+
     let mut storage = state.storage.lock().await;
 
     match storage.get_stats().await {
@@ -200,17 +592,12 @@ async fn stats_handler(State(state): State<AppState>) -> Response {
             let response = StatsResponse {
                 total_payments: stats.total_payments,
                 unused_payments: stats.unused_payments,
-                total_amount_zec: stats.total_amount as f64 / 100_000_000.0,
+                amounts: StatsAmounts::new(&stats, query.units),
+                total_usd_value: stats.total_usd_value,
             };
-            (StatusCode::OK, Json(response)).into_response()
+            data_response(StatusCode::OK, response)
         }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: format!("Storage error: {}", e),
-            }),
-        )
-            .into_response(),
+        Err(e) => storage_error(e),
     }
 }
 
@@ -252,4 +639,37 @@ mod tests {
         let result = parse_nullifier(hex);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_stats_amount_units() {
+        let stats = PaymentStats {
+            total_payments: 3,
+            unused_payments: 1,
+            total_amount: 150_000_001,
+            used_amount: 100_000_000,
+            unused_amount: 50_000_001,
+            total_usd_value: 0.0,
+        };
+
+        let zec = serde_json::to_value(StatsAmounts::new(&stats, AmountUnits::Zec)).unwrap();
+        assert_eq!(
+            zec,
+            serde_json::json!({
+                "total_amount_zec": 1.50000001,
+                "used_amount_zec": 1.0,
+                "unused_amount_zec": 0.50000001
+            })
+        );
+
+        let zatoshis =
+            serde_json::to_value(StatsAmounts::new(&stats, AmountUnits::Zatoshis)).unwrap();
+        assert_eq!(
+            zatoshis,
+            serde_json::json!({
+                "total_amount_zatoshis": 150_000_001u64,
+                "used_amount_zatoshis": 100_000_000u64,
+                "unused_amount_zatoshis": 50_000_001u64
+            })
+        );
+    }
 }
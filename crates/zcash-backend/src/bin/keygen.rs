@@ -2,12 +2,13 @@
 //!
 //! Commands:
 //! - generate: Create new spending key, viewing key, and unified address
+//! - import: Derive viewing keys and unified address from an existing spending key
 //! - info: Check lightwalletd connection and chain status
 //! - address: Show address from FVK
 //! - export: Convert hex FVK to UFVK format for wallets
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use orchard::keys::{FullViewingKey, IncomingViewingKey, Scope, SpendingKey};
 use rand::RngCore;
 use tonic::transport::Channel;
@@ -24,16 +25,54 @@ use proto::{ChainSpec, Empty};
 
 #[derive(Parser)]
 #[command(name = "zcash-keygen")]
-#[command(about = "Zcash testnet key generation and wallet utility")]
+#[command(about = "Zcash key generation and wallet utility")]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Network that addresses and UFVKs are encoded for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Network {
+    Mainnet,
+    Testnet,
+}
+
+impl Network {
+    fn network_type(self) -> NetworkType {
+        match self {
+            Network::Mainnet => NetworkType::Main,
+            Network::Testnet => NetworkType::Test,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "testnet",
+        }
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Generate new spending key, viewing key, and unified address
-    Generate,
+    Generate {
+        /// Network to encode the unified address for
+        #[arg(long, value_enum, default_value = "testnet")]
+        network: Network,
+    },
+
+    /// Derive viewing keys and unified address from an existing spending key
+    Import {
+        /// Orchard spending key (hex encoded, 32 bytes)
+        #[arg(short, long)]
+        spending_key: String,
+
+        /// Network to encode the unified address for
+        #[arg(long, value_enum, default_value = "testnet")]
+        network: Network,
+    },
 
     /// Check lightwalletd connection and chain status
     Info {
@@ -47,6 +86,10 @@ enum Commands {
         /// Orchard Full Viewing Key (hex encoded, 96 bytes)
         #[arg(short, long)]
         fvk: String,
+
+        /// Network to encode the unified address for
+        #[arg(long, value_enum, default_value = "testnet")]
+        network: Network,
     },
 
     /// Export FVK to UFVK format (for importing into wallets like Zingo/YWallet)
@@ -54,6 +97,10 @@ enum Commands {
         /// Orchard Full Viewing Key (hex encoded, 96 bytes)
         #[arg(short, long)]
         fvk: String,
+
+        /// Network to encode the UFVK and address for
+        #[arg(long, value_enum, default_value = "testnet")]
+        network: Network,
     },
 
     /// Decode a UFVK back to its component keys
@@ -68,38 +115,78 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Generate => generate_keys()?,
+        Commands::Generate { network } => generate_keys(network)?,
+        Commands::Import {
+            spending_key,
+            network,
+        } => import_keys(&spending_key, network)?,
         Commands::Info { server } => info(&server).await?,
-        Commands::Address { fvk } => show_address(&fvk)?,
-        Commands::Export { fvk } => export_ufvk(&fvk)?,
+        Commands::Address { fvk, network } => show_address(&fvk, network)?,
+        Commands::Export { fvk, network } => export_ufvk(&fvk, network)?,
         Commands::Decode { ufvk } => decode_ufvk(&ufvk)?,
     }
 
     Ok(())
 }
 
-fn generate_keys() -> Result<()> {
-    println!("=== Zcash Testnet Key Generator ===\n");
+/// Keys derived from an Orchard spending key
+struct DerivedKeys {
+    spending_key: [u8; 32],
+    fvk: [u8; 96],
+    ivk: [u8; 64],
+    /// Default (index 0) Orchard-only unified address
+    unified_address: String,
+    network: Network,
+}
+
+fn generate_keys(network: Network) -> Result<()> {
+    println!("=== Zcash Key Generator ({}) ===\n", network.name());
 
     // Generate random 32 bytes for spending key
     let mut sk_bytes = [0u8; 32];
     rand::thread_rng().fill_bytes(&mut sk_bytes);
 
-    // Create spending key from random bytes
+    let keys = derive_keys(sk_bytes, network)
+        .map_err(|_| anyhow::anyhow!("Failed to generate valid spending key, please try again"))?;
+    print_keys(&keys);
+
+    Ok(())
+}
+
+fn import_keys(sk_hex: &str, network: Network) -> Result<()> {
+    println!("=== Import Zcash Spending Key ({}) ===\n", network.name());
+
+    let keys = derive_keys(parse_spending_key(sk_hex)?, network)?;
+    print_keys(&keys);
+
+    Ok(())
+}
+
+/// Derive the FVK, IVK and default unified address from spending key bytes
+fn derive_keys(sk_bytes: [u8; 32], network: Network) -> Result<DerivedKeys> {
     let sk = SpendingKey::from_bytes(sk_bytes);
     if sk.is_none().into() {
-        anyhow::bail!("Failed to generate valid spending key, please try again");
+        anyhow::bail!("Invalid Orchard spending key");
     }
     let sk = sk.unwrap();
 
     // Derive Full Viewing Key
     let fvk = FullViewingKey::from(&sk);
-    let fvk_bytes = fvk.to_bytes();
 
     // Derive Incoming Viewing Key (for receiving)
     let ivk: IncomingViewingKey = fvk.to_ivk(Scope::External);
 
-    // Generate a default address
+    Ok(DerivedKeys {
+        spending_key: sk_bytes,
+        fvk: fvk.to_bytes(),
+        ivk: ivk.to_bytes(),
+        unified_address: encode_address(&fvk, network)?,
+        network,
+    })
+}
+
+/// Encode the default (index 0) address of `fvk` as an Orchard-only unified address
+fn encode_address(fvk: &FullViewingKey, network: Network) -> Result<String> {
     let orchard_address = fvk.address_at(0u64, Scope::External);
     let orchard_raw = orchard_address.to_raw_address_bytes();
 
@@ -107,30 +194,37 @@ fn generate_keys() -> Result<()> {
     let ua = unified::Address::try_from_items(vec![unified::Receiver::Orchard(orchard_raw)])
         .map_err(|e| anyhow::anyhow!("Failed to create unified address: {}", e))?;
 
-    // Encode as testnet address
-    let ua_encoded = ua.encode(&NetworkType::Test);
+    Ok(ua.encode(&network.network_type()))
+}
+
+/// Encode `fvk` as an Orchard-only Unified Full Viewing Key
+fn encode_ufvk(fvk: &FullViewingKey, network: Network) -> Result<String> {
+    let ufvk = Ufvk::try_from_items(vec![Fvk::Orchard(fvk.to_bytes())])
+        .map_err(|e| anyhow::anyhow!("Failed to create UFVK: {}", e))?;
 
+    Ok(ufvk.encode(&network.network_type()))
+}
+
+fn print_keys(keys: &DerivedKeys) {
     println!("SPENDING KEY (keep secret!):");
-    println!("  {}", hex::encode(sk_bytes));
+    println!("  {}", hex::encode(keys.spending_key));
     println!();
 
     println!("FULL VIEWING KEY (for zcash-backend .env):");
-    println!("  {}", hex::encode(fvk_bytes));
+    println!("  {}", hex::encode(keys.fvk));
     println!();
 
     println!("INCOMING VIEWING KEY:");
-    println!("  {}", hex::encode(ivk.to_bytes()));
+    println!("  {}", hex::encode(keys.ivk));
     println!();
 
-    println!("UNIFIED ADDRESS (testnet):");
-    println!("  {}", ua_encoded);
+    println!("UNIFIED ADDRESS ({}):", keys.network.name());
+    println!("  {}", keys.unified_address);
     println!();
 
     println!("=== Copy to .env file ===");
-    println!("ORCHARD_FVK={}", hex::encode(fvk_bytes));
-    println!("PAYMENT_ADDRESS={}", ua_encoded);
-
-    Ok(())
+    println!("ORCHARD_FVK={}", hex::encode(keys.fvk));
+    println!("PAYMENT_ADDRESS={}", keys.unified_address);
 }
 
 async fn info(server: &str) -> Result<()> {
@@ -164,46 +258,32 @@ async fn info(server: &str) -> Result<()> {
     Ok(())
 }
 
-fn show_address(fvk_hex: &str) -> Result<()> {
+fn show_address(fvk_hex: &str, network: Network) -> Result<()> {
     println!("=== Address from FVK ===\n");
 
     let fvk = parse_orchard_fvk(fvk_hex)?;
+    let ua_encoded = encode_address(&fvk, network)?;
 
-    // Get the address
-    let address = fvk.address_at(0u64, Scope::External);
-    let orchard_raw = address.to_raw_address_bytes();
-    let ua = unified::Address::try_from_items(vec![unified::Receiver::Orchard(orchard_raw)])?;
-    let ua_encoded = ua.encode(&NetworkType::Test);
-
-    println!("Unified Address (testnet):");
+    println!("Unified Address ({}):", network.name());
     println!("  {}", ua_encoded);
     println!();
     println!("To check balance, use a wallet like Zingo or YWallet,");
-    println!("or view on https://testnet.zcashblockexplorer.com/");
+    match network {
+        Network::Mainnet => println!("or view on https://zcashblockexplorer.com/"),
+        Network::Testnet => println!("or view on https://testnet.zcashblockexplorer.com/"),
+    }
 
     Ok(())
 }
 
-fn export_ufvk(fvk_hex: &str) -> Result<()> {
-    println!("=== Export to UFVK Format ===\n");
+fn export_ufvk(fvk_hex: &str, network: Network) -> Result<()> {
+    println!("=== Export to UFVK Format ({}) ===\n", network.name());
 
     let orchard_fvk = parse_orchard_fvk(fvk_hex)?;
-
-    // Get the FVK bytes for encoding
-    let fvk_bytes = orchard_fvk.to_bytes();
-
-    // Create UFVK with just Orchard receiver
-    let ufvk = Ufvk::try_from_items(vec![Fvk::Orchard(fvk_bytes)])
-        .map_err(|e| anyhow::anyhow!("Failed to create UFVK: {}", e))?;
-
-    // Encode for testnet
-    let ufvk_encoded = ufvk.encode(&NetworkType::Test);
+    let ufvk_encoded = encode_ufvk(&orchard_fvk, network)?;
 
     // Also show the address
-    let address = orchard_fvk.address_at(0u64, Scope::External);
-    let orchard_raw = address.to_raw_address_bytes();
-    let ua = unified::Address::try_from_items(vec![unified::Receiver::Orchard(orchard_raw)])?;
-    let ua_encoded = ua.encode(&NetworkType::Test);
+    let ua_encoded = encode_address(&orchard_fvk, network)?;
 
     println!("UNIFIED FULL VIEWING KEY (UFVK):");
     println!("  {}", ufvk_encoded);
@@ -222,6 +302,14 @@ fn export_ufvk(fvk_hex: &str) -> Result<()> {
     Ok(())
 }
 
+/// Parse hex-encoded Orchard spending key bytes
+fn parse_spending_key(sk_hex: &str) -> Result<[u8; 32]> {
+    let sk_bytes = hex::decode(sk_hex.trim())?;
+    sk_bytes.try_into().map_err(|bytes: Vec<u8>| {
+        anyhow::anyhow!("Spending key must be 32 bytes, got {}", bytes.len())
+    })
+}
+
 /// Parse hex-encoded Orchard FVK
 fn parse_orchard_fvk(fvk_hex: &str) -> Result<FullViewingKey> {
     let fvk_bytes = hex::decode(fvk_hex)?;
@@ -245,16 +333,16 @@ fn decode_ufvk(ufvk_str: &str) -> Result<()> {
     let (ufvk, network) = if ufvk_str.starts_with("uviewtest") {
         let (net, ufvk) = Ufvk::decode(ufvk_str)
             .map_err(|e| anyhow::anyhow!("Failed to decode UFVK: {:?}", e))?;
-        (ufvk, format!("{:?}", net))
+        (ufvk, net)
     } else if ufvk_str.starts_with("uview") {
         let (net, ufvk) = Ufvk::decode(ufvk_str)
             .map_err(|e| anyhow::anyhow!("Failed to decode UFVK: {:?}", e))?;
-        (ufvk, format!("{:?}", net))
+        (ufvk, net)
     } else {
         anyhow::bail!("Invalid UFVK: must start with 'uview' or 'uviewtest'");
     };
 
-    println!("Network: {}", network);
+    println!("Network: {:?}", network);
     println!();
 
     // Extract components
@@ -275,7 +363,7 @@ fn decode_ufvk(ufvk_str: &str) -> Result<()> {
                     let orchard_raw = address.to_raw_address_bytes();
                     let ua = unified::Address::try_from_items(vec![unified::Receiver::Orchard(orchard_raw)]);
                     if let Ok(ua) = ua {
-                        let ua_encoded = ua.encode(&NetworkType::Test);
+                        let ua_encoded = ua.encode(&network);
                         println!();
                         println!("  Derived Address:");
                         println!("    {}", ua_encoded);
@@ -310,3 +398,61 @@ fn decode_ufvk(ufvk_str: &str) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_matches_generate() {
+        let sk_bytes = [0x42u8; 32];
+
+        // What `generate` derives from these bytes
+        let generated = derive_keys(sk_bytes, Network::Testnet).unwrap();
+        let sk = SpendingKey::from_bytes(sk_bytes).unwrap();
+        assert_eq!(generated.fvk, FullViewingKey::from(&sk).to_bytes());
+
+        // What `import` derives from their hex encoding
+        let sk_bytes = parse_spending_key(&hex::encode(sk_bytes)).unwrap();
+        let imported = derive_keys(sk_bytes, Network::Testnet).unwrap();
+        assert_eq!(imported.fvk, generated.fvk);
+        assert_eq!(imported.ivk, generated.ivk);
+        assert_eq!(imported.unified_address, generated.unified_address);
+        assert!(imported.unified_address.starts_with("utest1"));
+
+        // The imported FVK round-trips through the other subcommands' parser
+        let fvk = parse_orchard_fvk(&hex::encode(imported.fvk)).unwrap();
+        assert_eq!(fvk.to_bytes(), generated.fvk);
+    }
+
+    #[test]
+    fn test_network_selects_encoding_prefix() {
+        let sk = SpendingKey::from_bytes([0x42u8; 32]).unwrap();
+        let fvk = FullViewingKey::from(&sk);
+
+        let mainnet_address = encode_address(&fvk, Network::Mainnet).unwrap();
+        let testnet_address = encode_address(&fvk, Network::Testnet).unwrap();
+        assert!(mainnet_address.starts_with("u1"));
+        assert!(testnet_address.starts_with("utest1"));
+
+        let mainnet_ufvk = encode_ufvk(&fvk, Network::Mainnet).unwrap();
+        let testnet_ufvk = encode_ufvk(&fvk, Network::Testnet).unwrap();
+        assert!(mainnet_ufvk.starts_with("uview1"));
+        assert!(testnet_ufvk.starts_with("uviewtest1"));
+
+        // Both encodings carry the same key
+        let (network, decoded) = Ufvk::decode(&mainnet_ufvk).unwrap();
+        assert_eq!(network, NetworkType::Main);
+        assert_eq!(decoded.items(), vec![Fvk::Orchard(fvk.to_bytes())]);
+
+        let keys = derive_keys([0x42u8; 32], Network::Mainnet).unwrap();
+        assert_eq!(keys.unified_address, mainnet_address);
+    }
+
+    #[test]
+    fn test_parse_spending_key_rejects_wrong_length() {
+        assert!(parse_spending_key("not-hex").is_err());
+        let err = parse_spending_key(&hex::encode([1u8; 31])).unwrap_err();
+        assert!(err.to_string().contains("32 bytes"));
+    }
+}
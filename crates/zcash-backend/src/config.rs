@@ -5,12 +5,18 @@
 use anyhow::{Context, Result};
 use std::env;
 
+use crate::exchange_rate::DEFAULT_RATE_JSON_POINTER;
+use khafi_admin::AdminConfig;
+
 /// Application configuration
 #[derive(Debug, Clone)]
 pub struct Config {
     /// Redis connection URL
     pub redis_url: String,
 
+    /// Redis database index, overriding the one in `redis_url` (optional)
+    pub redis_db: Option<i64>,
+
     /// API server host
     pub api_host: String,
 
@@ -20,9 +26,25 @@ pub struct Config {
     /// Blockchain polling interval in seconds
     pub polling_interval_secs: u64,
 
+    /// Block to start scanning from when nothing has been processed yet
+    /// (defaults to the first block)
+    pub start_height: Option<u32>,
+
+    /// Blocks scanned between progress reports while catching up
+    pub catch_up_batch_blocks: u32,
+
     /// Whether to use mock Zcash node (for development/testing)
     pub mock_mode: bool,
 
+    /// Threads trial-decrypting each compact block (defaults to the CPU count)
+    pub decryption_workers: usize,
+
+    /// How payment memos carry the customer's nullifier
+    pub memo_format: MemoFormat,
+
+    /// Zcash network the addresses, viewing keys and lightwalletd belong to
+    pub network: Network,
+
     /// Lightwalletd gRPC endpoint (when not in mock mode)
     /// Example: "https://testnet.lightwalletd.com:9067"
     pub lightwalletd_url: Option<String>,
@@ -37,6 +59,125 @@ pub struct Config {
     /// Sapling Full Viewing Key (hex encoded)
     /// Used to decrypt incoming Sapling notes
     pub sapling_fvk: Option<String>,
+
+    /// Every address being monitored, each with its own viewing key
+    ///
+    /// Set with `PAYMENT_ADDRESSES` (comma separated) and optionally
+    /// `ORCHARD_FVKS` (one per address, same order). Defaults to the single
+    /// `PAYMENT_ADDRESS` / `ORCHARD_FVK` pair.
+    pub watched_addresses: Vec<WatchedAddress>,
+
+    /// Exchange rate API used to record each payment's USD value (optional)
+    pub exchange_rate_url: Option<String>,
+
+    /// JSON pointer to the ZEC/USD price in the exchange rate API's response
+    pub exchange_rate_json_pointer: String,
+
+    /// Fixed ZEC/USD rate to use instead of an API (development/testing)
+    pub exchange_rate_fixed_usd: Option<f64>,
+
+    /// Token and rate limit for the `/admin` endpoints
+    pub admin: AdminConfig,
+
+    /// Bearer token for the payment reservation endpoints; they are disabled without one
+    pub payment_api_token: Option<String>,
+}
+
+/// Zcash network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+}
+
+impl Network {
+    /// Name used in configuration and logs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "testnet",
+        }
+    }
+
+    /// Chain name lightwalletd reports for this network
+    pub fn lightwalletd_chain_name(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "main",
+            Network::Testnet => "test",
+        }
+    }
+}
+
+impl std::str::FromStr for Network {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "mainnet" | "main" => Ok(Network::Mainnet),
+            "testnet" | "test" => Ok(Network::Testnet),
+            other => anyhow::bail!("Unknown network '{}' (expected mainnet or testnet)", other),
+        }
+    }
+}
+
+impl std::fmt::Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How a payment's memo carries the customer's nullifier
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum MemoFormat {
+    /// Try `prefixed`, then `hex`, then `raw`
+    ///
+    /// Any memo with a non-zero first byte decodes as `raw`, so ordinary text
+    /// memos are mistaken for nullifiers; pick an exact format where possible.
+    #[default]
+    Auto,
+    /// First 32 bytes of the memo are the raw nullifier
+    Raw,
+    /// `nullifier:` followed by 64 hex characters
+    Prefixed,
+    /// The memo text is exactly 64 hex characters
+    Hex,
+    /// The given prefix followed by 64 hex characters
+    CustomPrefix(String),
+}
+
+impl std::str::FromStr for MemoFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Some(prefix) = s.strip_prefix("prefix:") {
+            if prefix.is_empty() {
+                anyhow::bail!("Memo format 'prefix:' needs a prefix (e.g. prefix:pay=)");
+            }
+            return Ok(MemoFormat::CustomPrefix(prefix.to_string()));
+        }
+
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(MemoFormat::Auto),
+            "raw" => Ok(MemoFormat::Raw),
+            "prefixed" => Ok(MemoFormat::Prefixed),
+            "hex" => Ok(MemoFormat::Hex),
+            other => anyhow::bail!(
+                "Unknown memo format '{}' (expected auto, raw, prefixed, hex or prefix:<prefix>)",
+                other
+            ),
+        }
+    }
+}
+
+/// A receiving address to monitor for payments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchedAddress {
+    /// Zcash payment address (unified address)
+    pub address: String,
+
+    /// Orchard Full Viewing Key for this address (hex encoded, 96 bytes)
+    pub orchard_fvk: Option<String>,
 }
 
 impl Config {
@@ -45,10 +186,27 @@ impl Config {
         // Load .env file if it exists (for local development)
         dotenv::dotenv().ok();
 
+        let payment_address =
+            env::var("PAYMENT_ADDRESS").unwrap_or_else(|_| "u1test_mock_address".to_string());
+        let orchard_fvk = env::var("ORCHARD_FVK").ok();
+
+        let watched_addresses = watched_addresses(
+            env::var("PAYMENT_ADDRESSES").ok().as_deref(),
+            env::var("ORCHARD_FVKS").ok().as_deref(),
+            &payment_address,
+            orchard_fvk.as_deref(),
+        )?;
+
         let config = Config {
             redis_url: env::var("REDIS_URL")
                 .unwrap_or_else(|_| "redis://localhost:6379".to_string()),
 
+            redis_db: env::var("REDIS_DB")
+                .ok()
+                .map(|v| v.parse())
+                .transpose()
+                .context("Invalid REDIS_DB")?,
+
             api_host: env::var("API_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
 
             api_port: env::var("API_PORT")
@@ -61,18 +219,57 @@ impl Config {
                 .parse()
                 .context("Invalid POLLING_INTERVAL_SECS")?,
 
+            start_height: env::var("START_HEIGHT")
+                .ok()
+                .map(|v| v.parse())
+                .transpose()
+                .context("Invalid START_HEIGHT")?,
+
+            catch_up_batch_blocks: env::var("CATCH_UP_BATCH_BLOCKS")
+                .unwrap_or_else(|_| "1000".to_string())
+                .parse()
+                .context("Invalid CATCH_UP_BATCH_BLOCKS")?,
+
             mock_mode: env::var("MOCK_MODE")
                 .unwrap_or_else(|_| "true".to_string())
                 .parse()
                 .context("Invalid MOCK_MODE (expected true/false)")?,
 
-            lightwalletd_url: env::var("LIGHTWALLETD_URL").ok(),
+            decryption_workers: match env::var("DECRYPTION_WORKERS") {
+                Ok(v) => v.parse().context("Invalid DECRYPTION_WORKERS")?,
+                Err(_) => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            },
 
-            payment_address: env::var("PAYMENT_ADDRESS")
-                .unwrap_or_else(|_| "u1test_mock_address".to_string()),
+            memo_format: match env::var("MEMO_FORMAT") {
+                Ok(v) => v.parse().context("Invalid MEMO_FORMAT")?,
+                Err(_) => MemoFormat::default(),
+            },
 
-            orchard_fvk: env::var("ORCHARD_FVK").ok(),
+            network: env::var("ZCASH_NETWORK")
+                .unwrap_or_else(|_| "testnet".to_string())
+                .parse()
+                .context("Invalid ZCASH_NETWORK")?,
+
+            lightwalletd_url: env::var("LIGHTWALLETD_URL").ok(),
+
+            payment_address,
+            orchard_fvk,
             sapling_fvk: env::var("SAPLING_FVK").ok(),
+            watched_addresses,
+
+            exchange_rate_url: env::var("EXCHANGE_RATE_URL").ok().filter(|s| !s.is_empty()),
+            exchange_rate_json_pointer: env::var("EXCHANGE_RATE_JSON_POINTER")
+                .unwrap_or_else(|_| DEFAULT_RATE_JSON_POINTER.to_string()),
+            exchange_rate_fixed_usd: env::var("EXCHANGE_RATE_FIXED_USD")
+                .ok()
+                .map(|v| v.parse())
+                .transpose()
+                .context("Invalid EXCHANGE_RATE_FIXED_USD")?,
+
+            admin: AdminConfig::from_env()?,
+            payment_api_token: env::var("PAYMENT_API_TOKEN")
+                .ok()
+                .filter(|t| !t.trim().is_empty()),
         };
 
         // Validate configuration
@@ -91,12 +288,24 @@ impl Config {
             anyhow::bail!("POLLING_INTERVAL_SECS must be greater than 0");
         }
 
+        if self.catch_up_batch_blocks == 0 {
+            anyhow::bail!("CATCH_UP_BATCH_BLOCKS must be greater than 0");
+        }
+
+        if self.decryption_workers == 0 {
+            anyhow::bail!("DECRYPTION_WORKERS must be greater than 0");
+        }
+
         // If not in mock mode, require lightwalletd and viewing key configuration
         if !self.mock_mode {
             if self.lightwalletd_url.is_none() {
                 anyhow::bail!("LIGHTWALLETD_URL is required when MOCK_MODE=false");
             }
-            if self.orchard_fvk.is_none() && self.sapling_fvk.is_none() {
+            let has_orchard_key = self
+                .watched_addresses
+                .iter()
+                .any(|w| w.orchard_fvk.is_some());
+            if !has_orchard_key && self.sapling_fvk.is_none() {
                 anyhow::bail!("ORCHARD_FVK or SAPLING_FVK is required when MOCK_MODE=false");
             }
         }
@@ -108,6 +317,56 @@ impl Config {
     pub fn api_address(&self) -> String {
         format!("{}:{}", self.api_host, self.api_port)
     }
+
+    /// Addresses being monitored, in configuration order
+    pub fn payment_addresses(&self) -> Vec<String> {
+        self.watched_addresses
+            .iter()
+            .map(|w| w.address.clone())
+            .collect()
+    }
+}
+
+/// Build the watched address list from `PAYMENT_ADDRESSES` / `ORCHARD_FVKS`,
+/// falling back to the single primary address and key
+fn watched_addresses(
+    addresses: Option<&str>,
+    fvks: Option<&str>,
+    payment_address: &str,
+    orchard_fvk: Option<&str>,
+) -> Result<Vec<WatchedAddress>> {
+    let split = |list: &str| -> Vec<String> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect()
+    };
+
+    let Some(addresses) = addresses.map(split).filter(|a| !a.is_empty()) else {
+        return Ok(vec![WatchedAddress {
+            address: payment_address.to_string(),
+            orchard_fvk: orchard_fvk.map(String::from),
+        }]);
+    };
+
+    let fvks = fvks.map(split).unwrap_or_default();
+    if !fvks.is_empty() && fvks.len() != addresses.len() {
+        anyhow::bail!(
+            "ORCHARD_FVKS has {} keys but PAYMENT_ADDRESSES has {} addresses",
+            fvks.len(),
+            addresses.len()
+        );
+    }
+
+    Ok(addresses
+        .into_iter()
+        .enumerate()
+        .map(|(idx, address)| WatchedAddress {
+            address,
+            orchard_fvk: fvks.get(idx).cloned(),
+        })
+        .collect())
 }
 
 #[cfg(test)]
@@ -120,8 +379,17 @@ mod tests {
         env::remove_var("API_HOST");
         env::remove_var("API_PORT");
         env::remove_var("REDIS_URL");
+        env::remove_var("REDIS_DB");
         env::remove_var("MOCK_MODE");
         env::remove_var("POLLING_INTERVAL_SECS");
+        env::remove_var("START_HEIGHT");
+        env::remove_var("CATCH_UP_BATCH_BLOCKS");
+        env::remove_var("ZCASH_NETWORK");
+        env::remove_var("DECRYPTION_WORKERS");
+        env::remove_var("MEMO_FORMAT");
+        env::remove_var("ADMIN_TOKEN");
+        env::remove_var("ADMIN_RATE_LIMIT_PER_MINUTE");
+        env::remove_var("PAYMENT_API_TOKEN");
 
         // Set minimal environment for testing
         env::set_var("PAYMENT_ADDRESS", "test_address");
@@ -129,10 +397,66 @@ mod tests {
         let config = Config::from_env().expect("Failed to load config");
 
         assert_eq!(config.redis_url, "redis://localhost:6379");
+        assert_eq!(config.redis_db, None);
         assert_eq!(config.api_host, "0.0.0.0");
         assert_eq!(config.api_port, 8081);
         assert_eq!(config.polling_interval_secs, 60);
+        assert_eq!(config.start_height, None);
+        assert_eq!(config.catch_up_batch_blocks, 1000);
         assert!(config.mock_mode);
+        assert_eq!(config.network, Network::Testnet);
+        assert!(config.decryption_workers >= 1);
+        assert_eq!(config.memo_format, MemoFormat::Auto);
+        assert!(config.admin.token.is_none());
+        assert!(config.payment_api_token.is_none());
+    }
+
+    #[test]
+    fn test_network_parsing() {
+        assert_eq!("mainnet".parse::<Network>().unwrap(), Network::Mainnet);
+        assert_eq!("Testnet".parse::<Network>().unwrap(), Network::Testnet);
+        assert_eq!("main".parse::<Network>().unwrap(), Network::Mainnet);
+        assert!("regtest".parse::<Network>().is_err());
+        assert_eq!(Network::Testnet.lightwalletd_chain_name(), "test");
+    }
+
+    #[test]
+    fn test_memo_format_parsing() {
+        assert_eq!("raw".parse::<MemoFormat>().unwrap(), MemoFormat::Raw);
+        assert_eq!(
+            " Prefixed ".parse::<MemoFormat>().unwrap(),
+            MemoFormat::Prefixed
+        );
+        assert_eq!("hex".parse::<MemoFormat>().unwrap(), MemoFormat::Hex);
+        assert_eq!(
+            "prefix:Pay=".parse::<MemoFormat>().unwrap(),
+            MemoFormat::CustomPrefix("Pay=".to_string())
+        );
+        assert!("prefix:".parse::<MemoFormat>().is_err());
+        assert!("base64".parse::<MemoFormat>().is_err());
+    }
+
+    #[test]
+    fn test_watched_addresses() {
+        // Single address by default
+        let watched = watched_addresses(None, None, "u1primary", Some("aa")).unwrap();
+        assert_eq!(
+            watched,
+            vec![WatchedAddress {
+                address: "u1primary".to_string(),
+                orchard_fvk: Some("aa".to_string()),
+            }]
+        );
+
+        // A list replaces the primary address, keys matched by position
+        let watched =
+            watched_addresses(Some("u1a, u1b"), Some("aa,bb"), "u1primary", None).unwrap();
+        assert_eq!(watched.len(), 2);
+        assert_eq!(watched[1].address, "u1b");
+        assert_eq!(watched[1].orchard_fvk.as_deref(), Some("bb"));
+
+        // Key count must match address count
+        assert!(watched_addresses(Some("u1a,u1b"), Some("aa"), "u1primary", None).is_err());
     }
 
     #[test]
@@ -0,0 +1,132 @@
+//! ZEC/USD exchange rate lookup
+//!
+//! Used by the monitor to record the fiat value of each payment at the time
+//! it was detected. Rates come from a configured HTTP API (any endpoint that
+//! returns the price somewhere in a JSON body) or a fixed value for testing.
+
+use anyhow::{Context, Result};
+use std::time::Duration;
+
+use crate::storage::ReceivedPayment;
+use crate::units::zatoshis_to_zec;
+
+/// Default location of the price in the rate API's response
+/// (CoinGecko's `simple/price?ids=zcash&vs_currencies=usd` format)
+pub const DEFAULT_RATE_JSON_POINTER: &str = "/zcash/usd";
+
+/// Source of ZEC/USD exchange rates
+pub enum ExchangeRateProvider {
+    /// Fetch the rate from an HTTP JSON API
+    Http {
+        client: reqwest::Client,
+        url: String,
+        /// JSON pointer to the price in the response body
+        json_pointer: String,
+    },
+
+    /// Always return the same rate
+    Fixed(f64),
+}
+
+impl ExchangeRateProvider {
+    /// Create a provider that queries `url` and reads the price at `json_pointer`
+    pub fn http(url: String, json_pointer: String) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        Ok(Self::Http {
+            client,
+            url,
+            json_pointer,
+        })
+    }
+
+    /// Current price of 1 ZEC in USD
+    pub async fn zec_usd(&self) -> Result<f64> {
+        match self {
+            Self::Http {
+                client,
+                url,
+                json_pointer,
+            } => {
+                let body: serde_json::Value = client
+                    .get(url)
+                    .send()
+                    .await
+                    .context("Exchange rate request failed")?
+                    .error_for_status()
+                    .context("Exchange rate API returned an error")?
+                    .json()
+                    .await
+                    .context("Invalid exchange rate response")?;
+
+                rate_from_json(&body, json_pointer)
+            }
+            Self::Fixed(rate) => Ok(*rate),
+        }
+    }
+}
+
+/// Extract a positive price from a JSON body
+fn rate_from_json(body: &serde_json::Value, json_pointer: &str) -> Result<f64> {
+    let value = body
+        .pointer(json_pointer)
+        .with_context(|| format!("Exchange rate response has no value at {}", json_pointer))?;
+
+    // Some APIs quote prices as strings
+    let rate = match value {
+        serde_json::Value::String(s) => s.parse().ok(),
+        other => other.as_f64(),
+    }
+    .with_context(|| format!("Exchange rate at {} is not a number", json_pointer))?;
+
+    if !rate.is_finite() || rate <= 0.0 {
+        anyhow::bail!("Exchange rate must be positive, got {}", rate);
+    }
+    Ok(rate)
+}
+
+/// Record each payment's USD value at the given ZEC/USD rate
+pub fn annotate_usd_value(payments: &mut [ReceivedPayment], zec_usd: f64) {
+    for payment in payments {
+        payment.usd_value = Some(zatoshis_to_zec(payment.amount) * zec_usd);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use khafi_common::Nullifier;
+
+    #[tokio::test]
+    async fn test_payments_annotated_with_stubbed_rate() {
+        let provider = ExchangeRateProvider::Fixed(40.0);
+        let rate = provider.zec_usd().await.unwrap();
+
+        let mut payments = vec![
+            ReceivedPayment::new(Nullifier::new([1u8; 32]), 50_000_000, "tx1".into(), 100),
+            ReceivedPayment::new(Nullifier::new([2u8; 32]), 250_000_000, "tx2".into(), 100),
+        ];
+        annotate_usd_value(&mut payments, rate);
+
+        assert_eq!(payments[0].usd_value, Some(20.0));
+        assert_eq!(payments[1].usd_value, Some(100.0));
+    }
+
+    #[test]
+    fn test_rate_from_json() {
+        let body = serde_json::json!({ "zcash": { "usd": 31.25 } });
+        assert_eq!(
+            rate_from_json(&body, DEFAULT_RATE_JSON_POINTER).unwrap(),
+            31.25
+        );
+
+        let body = serde_json::json!({ "data": { "price": "28.5" } });
+        assert_eq!(rate_from_json(&body, "/data/price").unwrap(), 28.5);
+
+        assert!(rate_from_json(&body, "/missing").is_err());
+        assert!(rate_from_json(&serde_json::json!({ "p": 0 }), "/p").is_err());
+    }
+}
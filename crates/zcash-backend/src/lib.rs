@@ -15,6 +15,8 @@
 //! - `mock_node`: Mock Zcash node for development/testing
 //! - `api`: REST API for payment queries
 //! - `config`: Configuration management
+//! - `exchange_rate`: ZEC/USD rates for recording payment values
+//! - `units`: Zatoshi/ZEC conversion for reporting amounts
 //!
 //! **Data Flow:**
 //! 1. User creates Zcash transaction → broadcasts to blockchain
@@ -25,14 +27,16 @@
 
 pub mod api;
 pub mod config;
+pub mod exchange_rate;
 pub mod lightwalletd_client;
 pub mod mock_node;
 pub mod monitor;
 pub mod note_decryption;
 pub mod parser;
 pub mod storage;
+pub mod units;
 
 // Re-export commonly used types
 pub use config::Config;
 pub use monitor::Monitor;
-pub use storage::{ReceivedPayment, Storage};
+pub use storage::{ConfirmOutcome, ReceivedPayment, ReleaseOutcome, ReserveOutcome, Storage};
@@ -4,6 +4,7 @@
 //! and retrieve blockchain data for payment verification.
 
 use anyhow::{Context, Result};
+use futures::stream::{BoxStream, StreamExt};
 use tonic::transport::Channel;
 use tracing::{debug, info};
 
@@ -17,6 +18,11 @@ pub mod proto {
 use proto::compact_tx_streamer_client::CompactTxStreamerClient;
 use proto::{BlockId, BlockRange, ChainSpec, Empty};
 
+/// Compact blocks in height order, as they arrive from lightwalletd
+///
+/// An error can follow blocks that were already delivered; those are still good.
+pub type CompactBlockStream = BoxStream<'static, Result<proto::CompactBlock>>;
+
 /// Lightwalletd client for production use
 pub struct LightwalletdClient {
     /// gRPC client
@@ -112,8 +118,11 @@ impl LightwalletdClient {
         Ok(Some(response))
     }
 
-    /// Get a range of blocks (streaming)
-    pub async fn get_block_range(&mut self, start: u32, end: u32) -> Result<Vec<MockBlock>> {
+    /// Stream the compact blocks from `start` to `end` (inclusive)
+    ///
+    /// Blocks are yielded as they arrive rather than collected, so a caller
+    /// can keep the ones received before a mid-stream error.
+    pub async fn get_block_range(&mut self, start: u32, end: u32) -> Result<CompactBlockStream> {
         let range = BlockRange {
             start: Some(BlockId {
                 height: start as u64,
@@ -125,26 +134,18 @@ impl LightwalletdClient {
             }),
         };
 
-        let mut stream = self
+        let stream = self
             .client
             .get_block_range(range)
             .await
             .context("Failed to get block range")?
             .into_inner();
 
-        let mut blocks = Vec::new();
-        while let Some(compact_block) = stream.message().await? {
-            blocks.push(self.convert_compact_block(compact_block));
-        }
-
-        info!(
-            "Lightwalletd: get_block_range({}, {}) -> {} blocks",
-            start,
-            end,
-            blocks.len()
-        );
+        debug!("Lightwalletd: get_block_range({}, {}) started", start, end);
 
-        Ok(blocks)
+        Ok(stream
+            .map(|block| block.context("Block range stream failed"))
+            .boxed())
     }
 
     /// Get server chain name
@@ -178,6 +179,7 @@ impl LightwalletdClient {
                 nullifier: hex::encode(&spend.nf),
                 amount: 0, // Sapling spends don't have amount in compact format
                 is_our_payment: false, // Will be determined by parser
+                to_address: None,
             });
         }
 
@@ -187,6 +189,7 @@ impl LightwalletdClient {
                 nullifier: hex::encode(&action.nullifier),
                 amount: 0, // Amount is encrypted, determined by decryption
                 is_our_payment: false, // Will be determined by parser
+                to_address: None,
             });
         }
 
@@ -220,6 +223,14 @@ impl ZcashNode {
         }
     }
 
+    /// Stream raw compact blocks (only available for Lightwalletd)
+    pub async fn get_block_range(&mut self, start: u32, end: u32) -> Result<CompactBlockStream> {
+        match self {
+            ZcashNode::Mock(_) => Ok(futures::stream::empty().boxed()), // Not available in mock mode
+            ZcashNode::Lightwalletd(client) => client.get_block_range(start, end).await,
+        }
+    }
+
     /// Get a raw compact block (only available for Lightwalletd)
     pub async fn get_compact_block(&mut self, height: u32) -> Result<Option<proto::CompactBlock>> {
         match self {
@@ -4,16 +4,17 @@
 
 use anyhow::Result;
 use tracing::{error, info};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod api;
 mod config;
+mod exchange_rate;
 mod lightwalletd_client;
 mod mock_node;
 mod monitor;
 mod note_decryption;
 mod parser;
 mod storage;
+mod units;
 
 use config::Config;
 use monitor::Monitor;
@@ -21,14 +22,8 @@ use storage::Storage;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info,zcash_backend=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    // Initialize tracing (exports to OTLP when OTEL_EXPORTER_OTLP_ENDPOINT is set)
+    let _telemetry = khafi_telemetry::init_tracing("zcash-backend", "info,zcash_backend=debug")?;
 
     info!("Starting Zcash Backend Service");
 
@@ -41,11 +36,25 @@ async fn main() -> Result<()> {
     info!("  Polling interval: {}s", config.polling_interval_secs);
 
     // Initialize storage for API server
-    let api_storage = Storage::new(&config.redis_url).await?;
+    let api_storage = Storage::new(&config.redis_url, config.redis_db).await?;
     info!("Connected to Redis for API");
 
+    // Create the monitor up front so the API can trigger polls through its handle
+    let monitor = match Monitor::new(config.clone()).await {
+        Ok(monitor) => monitor,
+        Err(e) => {
+            error!("Failed to create monitor: {:#}", e);
+            return Err(e);
+        }
+    };
+
     // Create API router
-    let app = api::create_router(api_storage);
+    let app = api::create_router(
+        api_storage,
+        Some(monitor.handle()),
+        config.admin.clone(),
+        config.payment_api_token.clone(),
+    );
 
     // Start API server in background
     let api_addr = config.api_address();
@@ -60,18 +69,10 @@ async fn main() -> Result<()> {
     });
 
     // Start blockchain monitor in background
-    let monitor_config = config.clone();
     let monitor_task = tokio::spawn(async move {
         info!("Starting blockchain monitor task");
-        match Monitor::new(monitor_config).await {
-            Ok(monitor) => {
-                if let Err(e) = monitor.start().await {
-                    error!("Monitor error: {:#}", e);
-                }
-            }
-            Err(e) => {
-                error!("Failed to create monitor: {:#}", e);
-            }
+        if let Err(e) = monitor.start().await {
+            error!("Monitor error: {:#}", e);
         }
     });
 
@@ -35,6 +35,10 @@ pub struct MockAction {
 
     /// Whether this payment is to our address
     pub is_our_payment: bool,
+
+    /// Recipient address, when known
+    #[serde(default)]
+    pub to_address: Option<String>,
 }
 
 /// Mock Zcash node client
@@ -42,16 +46,24 @@ pub struct MockNode {
     /// Current blockchain height
     current_height: Arc<Mutex<u32>>,
 
-    /// Address to check for payments
-    payment_address: String,
+    /// Addresses that receive mock payments
+    payment_addresses: Vec<String>,
 }
 
 impl MockNode {
     /// Create a new mock node
     pub fn new(payment_address: String) -> Self {
+        Self::with_addresses(vec![payment_address])
+    }
+
+    /// Create a mock node that pays each address in turn
+    ///
+    /// Payment blocks (every 10th) rotate through the addresses, so block
+    /// 100000 pays the first address, 100010 the second, and so on.
+    pub fn with_addresses(payment_addresses: Vec<String>) -> Self {
         Self {
             current_height: Arc::new(Mutex::new(100000)), // Start at height 100000
-            payment_address,
+            payment_addresses,
         }
     }
 
@@ -115,6 +127,10 @@ impl MockNode {
         // Generate deterministic nullifier based on block height
         let nullifier_bytes = self.generate_nullifier_bytes(height, 0);
         let nullifier_hex = hex::encode(nullifier_bytes);
+        let to_address = match self.payment_addresses.len() {
+            0 => None,
+            n => Some(self.payment_addresses[(height / 10) as usize % n].clone()),
+        };
 
         MockTransaction {
             txid: format!("mock_payment_tx_{:08x}", height),
@@ -122,6 +138,7 @@ impl MockNode {
                 nullifier: nullifier_hex,
                 amount: 10000000 + (height as u64 * 1000), // Variable amount
                 is_our_payment: true,
+                to_address,
             }],
         }
     }
@@ -137,6 +154,7 @@ impl MockNode {
                 nullifier: nullifier_hex,
                 amount: 5000000,
                 is_our_payment: false, // Not to our address
+                to_address: None,
             }],
         }
     }
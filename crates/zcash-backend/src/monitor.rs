@@ -2,18 +2,112 @@
 //!
 //! Continuously polls the Zcash node for new blocks and processes payments.
 
-use anyhow::Result;
-use std::time::Duration;
-use tokio::sync::Mutex;
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot, watch, Mutex};
 use tokio::time::sleep;
 use tracing::{debug, error, info, warn};
 
 use crate::config::Config;
-use crate::lightwalletd_client::{LightwalletdClient, ZcashNode};
+use crate::exchange_rate::{annotate_usd_value, ExchangeRateProvider};
+use crate::lightwalletd_client::{CompactBlockStream, LightwalletdClient, ZcashNode};
 use crate::mock_node::MockNode;
 use crate::note_decryption::NoteDecryptor;
 use crate::parser::Parser;
-use crate::storage::Storage;
+use crate::storage::{ReceivedPayment, Storage};
+use crate::units::zatoshis_to_zec;
+
+/// Reply channel for an operator-triggered poll
+type PollReply = oneshot::Sender<Result<PollSummary>>;
+
+/// Outcome of one block-scan pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PollSummary {
+    /// New blocks scanned in this pass
+    pub blocks_processed: u32,
+
+    /// Payments newly stored from those blocks
+    pub payments_stored: usize,
+
+    /// Last processed block height after the pass
+    pub processed_height: u32,
+}
+
+/// How far the monitor has got through the chain
+///
+/// Updated after every batch while catching up on historical blocks, and
+/// after every poll otherwise.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct SyncProgress {
+    /// Whether the monitor is still working through a backlog of blocks
+    pub catching_up: bool,
+
+    /// Last processed block height
+    pub processed_height: u32,
+
+    /// Chain height the current pass is scanning up to
+    pub target_height: u32,
+
+    /// Blocks scanned so far in the current pass
+    pub blocks_done: u32,
+
+    /// Blocks the current pass has to scan in total
+    pub blocks_total: u32,
+
+    /// Scan rate over the current pass, in blocks per second
+    pub blocks_per_sec: f64,
+}
+
+impl SyncProgress {
+    /// Progress of a pass from `start` to `target` that has reached `processed`
+    fn new(start: u32, processed: u32, target: u32, elapsed: Duration) -> Self {
+        let blocks_done = processed.saturating_sub(start);
+        let secs = elapsed.as_secs_f64();
+        Self {
+            catching_up: processed < target,
+            processed_height: processed,
+            target_height: target,
+            blocks_done,
+            blocks_total: target.saturating_sub(start),
+            blocks_per_sec: if secs > 0.0 {
+                blocks_done as f64 / secs
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// Handle for asking a running monitor to poll immediately
+#[derive(Clone)]
+pub struct MonitorHandle {
+    poll_tx: mpsc::Sender<PollReply>,
+    progress_rx: watch::Receiver<SyncProgress>,
+}
+
+impl MonitorHandle {
+    /// Run one block-scan pass now instead of waiting for the polling interval
+    ///
+    /// Waits for the pass to finish and returns what it processed.
+    pub async fn poll_now(&self) -> Result<PollSummary> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.poll_tx
+            .send(reply_tx)
+            .await
+            .map_err(|_| anyhow::anyhow!("Monitor is not running"))?;
+        reply_rx
+            .await
+            .map_err(|_| anyhow::anyhow!("Monitor stopped before polling"))?
+    }
+
+    /// Latest sync progress reported by the monitor
+    pub fn sync_progress(&self) -> SyncProgress {
+        *self.progress_rx.borrow()
+    }
+}
 
 /// Blockchain monitor
 pub struct Monitor {
@@ -24,25 +118,59 @@ pub struct Monitor {
     parser: Parser,
 
     /// Note decryptor for real blocks (when not in mock mode)
-    note_decryptor: Option<NoteDecryptor>,
+    note_decryptor: Option<Arc<NoteDecryptor>>,
 
     /// Storage client
     storage: Storage,
 
+    /// Exchange rate source for recording USD values (optional)
+    rate_provider: Option<ExchangeRateProvider>,
+
     /// Configuration
     config: Config,
 
     /// Last processed block height
     last_processed_height: u32,
+
+    /// Operator-triggered poll requests (see [`MonitorHandle`])
+    poll_tx: mpsc::Sender<PollReply>,
+    poll_rx: mpsc::Receiver<PollReply>,
+
+    /// Latest sync progress, shared with every [`MonitorHandle`]
+    progress_tx: watch::Sender<SyncProgress>,
 }
 
 impl Monitor {
     /// Create a new monitor
     pub async fn new(config: Config) -> Result<Self> {
+        // Create note decryptor for real mode; this also checks every watched
+        // address belongs to the configured network before we connect anywhere
+        let note_decryptor = if !config.mock_mode {
+            let decryptor = NoteDecryptor::for_addresses(
+                &config.watched_addresses,
+                config.sapling_fvk.as_deref(),
+                config.network,
+            )?
+            .with_workers(config.decryption_workers)?
+            .with_memo_format(config.memo_format.clone());
+            if decryptor.has_viewing_keys() {
+                info!(
+                    "Note decryptor initialized with {} viewing keys ({} workers)",
+                    config.network, config.decryption_workers
+                );
+                Some(Arc::new(decryptor))
+            } else {
+                warn!("No viewing keys configured, note decryption disabled");
+                None
+            }
+        } else {
+            None
+        };
+
         // Create appropriate node based on config
         let node = if config.mock_mode {
             info!("Using mock Zcash node");
-            ZcashNode::Mock(MockNode::new(config.payment_address.clone()))
+            ZcashNode::Mock(MockNode::with_addresses(config.payment_addresses()))
         } else {
             let url = config
                 .lightwalletd_url
@@ -50,68 +178,114 @@ impl Monitor {
                 .expect("LIGHTWALLETD_URL required when not in mock mode");
             info!("Connecting to lightwalletd at {}", url);
             let client = LightwalletdClient::new(url).await?;
+            if client.chain_name() != config.network.lightwalletd_chain_name() {
+                anyhow::bail!(
+                    "lightwalletd at {} serves the '{}' chain but ZCASH_NETWORK is {}",
+                    url,
+                    client.chain_name(),
+                    config.network
+                );
+            }
             ZcashNode::Lightwalletd(client)
         };
 
-        let parser = Parser::new(config.payment_address.clone());
+        let parser = Parser::with_addresses(config.payment_addresses());
 
-        // Create note decryptor for real mode
-        let note_decryptor = if !config.mock_mode {
-            let decryptor = NoteDecryptor::new(
-                config.orchard_fvk.as_deref(),
-                config.sapling_fvk.as_deref(),
-            )?;
-            if decryptor.has_viewing_keys() {
-                info!("Note decryptor initialized with viewing keys");
-                Some(decryptor)
-            } else {
-                warn!("No viewing keys configured, note decryption disabled");
-                None
+        let rate_provider = match (&config.exchange_rate_url, config.exchange_rate_fixed_usd) {
+            (_, Some(rate)) => {
+                info!("Recording USD values at a fixed rate of {} USD/ZEC", rate);
+                Some(ExchangeRateProvider::Fixed(rate))
             }
-        } else {
-            None
+            (Some(url), None) => {
+                info!("Recording USD values using rates from {}", url);
+                Some(ExchangeRateProvider::http(
+                    url.clone(),
+                    config.exchange_rate_json_pointer.clone(),
+                )?)
+            }
+            (None, None) => None,
         };
 
-        let mut storage = Storage::new(&config.redis_url).await?;
+        let mut storage = Storage::new(&config.redis_url, config.redis_db).await?;
 
-        // Get the latest block height from storage, or start from current chain height
-        let last_processed_height = storage.get_latest_block_height().await?.unwrap_or_else(|| {
-            info!("No previous block height found, will start from current chain height");
-            0
-        });
+        // Resume from the persisted cursor; older deployments only have the
+        // height of their last payment to go on
+        let last_processed_height = match storage.get_last_processed_height().await? {
+            Some(height) => height,
+            None => match storage.get_latest_block_height().await? {
+                Some(height) => height,
+                None => {
+                    let start_height = config.start_height.unwrap_or(1);
+                    info!(
+                        "No previous block height found, starting from block {}",
+                        start_height
+                    );
+                    start_height.saturating_sub(1)
+                }
+            },
+        };
 
         info!(
             "Monitor initialized, starting from block height {}",
             last_processed_height
         );
 
+        let (poll_tx, poll_rx) = mpsc::channel(8);
+        let (progress_tx, _) = watch::channel(SyncProgress {
+            processed_height: last_processed_height,
+            target_height: last_processed_height,
+            ..Default::default()
+        });
+
         Ok(Self {
             node: Mutex::new(node),
             parser,
             note_decryptor,
             storage,
+            rate_provider,
             config,
             last_processed_height,
+            poll_tx,
+            poll_rx,
+            progress_tx,
         })
     }
 
+    /// Handle for triggering polls once the monitor is running
+    pub fn handle(&self) -> MonitorHandle {
+        MonitorHandle {
+            poll_tx: self.poll_tx.clone(),
+            progress_rx: self.progress_tx.subscribe(),
+        }
+    }
+
     /// Start the monitoring loop
     ///
-    /// This runs indefinitely, polling for new blocks at the configured interval.
+    /// This runs indefinitely, polling for new blocks at the configured interval
+    /// or as soon as a [`MonitorHandle`] asks for a poll.
     pub async fn start(mut self) -> Result<()> {
         info!(
             "Starting blockchain monitor (polling every {} seconds)",
             self.config.polling_interval_secs
         );
 
+        let mut reply: Option<PollReply> = None;
         loop {
-            if let Err(e) = self.poll_once().await {
+            let result = self.poll_once().await;
+            if let Err(e) = &result {
                 error!("Error polling blockchain: {:#}", e);
                 // Continue despite errors - don't crash the monitor
             }
+            if let Some(reply) = reply.take() {
+                // The requester may have given up waiting
+                let _ = reply.send(result);
+            }
 
-            // Wait before next poll
-            sleep(Duration::from_secs(self.config.polling_interval_secs)).await;
+            // Wait before next poll, unless one is requested sooner
+            tokio::select! {
+                _ = sleep(Duration::from_secs(self.config.polling_interval_secs)) => {}
+                Some(request) = self.poll_rx.recv() => reply = Some(request),
+            }
 
             // In mock mode, advance the chain to simulate new blocks
             if self.config.mock_mode {
@@ -124,7 +298,7 @@ impl Monitor {
     }
 
     /// Poll for new blocks once
-    async fn poll_once(&mut self) -> Result<()> {
+    async fn poll_once(&mut self) -> Result<PollSummary> {
         // Get current blockchain height
         let current_height = {
             let mut node = self.node.lock().await;
@@ -136,95 +310,249 @@ impl Monitor {
                 "No new blocks (current: {}, last processed: {})",
                 current_height, self.last_processed_height
             );
-            return Ok(());
+            // A quiet chain is still a live one: refresh the tip timestamp so
+            // verifiers don't treat it as stale
+            self.storage.set_block_height(current_height).await?;
+            self.progress_tx.send_replace(SyncProgress::new(
+                self.last_processed_height,
+                self.last_processed_height,
+                self.last_processed_height,
+                Duration::ZERO,
+            ));
+            return Ok(PollSummary {
+                processed_height: self.last_processed_height,
+                ..Default::default()
+            });
         }
 
-        info!(
-            "Processing blocks {} to {}",
-            self.last_processed_height + 1,
-            current_height
-        );
-
-        // Process each new block
-        for height in (self.last_processed_height + 1)..=current_height {
-            self.process_block(height).await?;
+        // A backlog larger than one batch (first start, or a long outage) is
+        // worked through in batches, reporting progress after each one
+        let start_height = self.last_processed_height;
+        let batch_blocks = self.config.catch_up_batch_blocks;
+        let catching_up = current_height - start_height > batch_blocks;
+        if catching_up {
+            info!(
+                "Catching up on blocks {} to {} ({} blocks, {} per batch)",
+                start_height + 1,
+                current_height,
+                current_height - start_height,
+                batch_blocks
+            );
+        } else {
+            info!(
+                "Processing blocks {} to {}",
+                start_height + 1,
+                current_height
+            );
         }
 
-        self.last_processed_height = current_height;
+        // Process each new block; each one advances the persisted cursor
+        let started = Instant::now();
+        let mut payments_stored = 0;
+        while self.last_processed_height < current_height {
+            let batch_start = self.last_processed_height + 1;
+            let batch_end =
+                current_height.min(self.last_processed_height.saturating_add(batch_blocks));
+            payments_stored += self.process_blocks(batch_start, batch_end).await?;
+
+            // Update the chain block height in Redis (for confirmation counting).
+            // Written after every batch so a long catch-up keeps the tip fresh
+            self.storage.set_block_height(current_height).await?;
+
+            if catching_up {
+                let progress = SyncProgress::new(
+                    start_height,
+                    self.last_processed_height,
+                    current_height,
+                    started.elapsed(),
+                );
+                info!(
+                    "Catch-up progress: {}/{} blocks (height {}, {:.1} blocks/s)",
+                    progress.blocks_done,
+                    progress.blocks_total,
+                    progress.processed_height,
+                    progress.blocks_per_sec
+                );
+                self.progress_tx.send_replace(progress);
+
+                // Give the rest of the service a turn between batches
+                tokio::task::yield_now().await;
+            }
+        }
+        let blocks_processed = current_height - start_height;
+
+        self.progress_tx.send_replace(SyncProgress::new(
+            start_height,
+            current_height,
+            current_height,
+            started.elapsed(),
+        ));
+
+        Ok(PollSummary {
+            blocks_processed,
+            payments_stored,
+            processed_height: current_height,
+        })
+    }
 
-        // Update the chain block height in Redis (for confirmation counting)
-        self.storage.set_block_height(current_height).await?;
+    /// Process blocks `start..=end`, returning how many new payments were stored
+    async fn process_blocks(&mut self, start: u32, end: u32) -> Result<usize> {
+        if self.streams_blocks() {
+            self.process_block_range(start, end).await
+        } else {
+            let mut stored = 0;
+            for height in start..=end {
+                stored += self.process_block(height).await?;
+            }
+            Ok(stored)
+        }
+    }
 
-        Ok(())
+    /// Whether blocks come from lightwalletd's block stream (real mode with viewing keys)
+    fn streams_blocks(&self) -> bool {
+        !self.config.mock_mode && self.note_decryptor.is_some()
     }
 
-    /// Process a single block
-    async fn process_block(&mut self, height: u32) -> Result<()> {
+    /// Process a single block, returning how many new payments it stored
+    async fn process_block(&mut self, height: u32) -> Result<usize> {
         debug!("Processing block {}", height);
 
-        // Get payments - either from mock parser or real note decryption
         let payments = if self.config.mock_mode {
             // Mock mode: use the mock parser
             let block = {
                 let mut node = self.node.lock().await;
-                match node.get_block(height).await? {
-                    Some(block) => block,
-                    None => {
-                        warn!("Block {} not found, skipping", height);
-                        return Ok(());
-                    }
-                }
+                node.get_block(height).await?
             };
-            self.parser.parse_block(&block)?
-        } else {
-            // Real mode: use note decryption on compact blocks
-            if let Some(ref decryptor) = self.note_decryptor {
-                let compact_block = {
-                    let mut node = self.node.lock().await;
-                    match node.get_compact_block(height).await? {
-                        Some(block) => block,
-                        None => {
-                            warn!("Compact block {} not found, skipping", height);
-                            return Ok(());
-                        }
-                    }
-                };
-                decryptor.decrypt_block(&compact_block)?
-            } else {
-                // No viewing keys configured, can't decrypt
-                debug!("Skipping block {} - no viewing keys configured", height);
-                return Ok(());
+            match block {
+                Some(block) => self.parser.parse_block(&block)?,
+                None => {
+                    warn!("Block {} not found, skipping", height);
+                    Vec::new()
+                }
             }
+        } else {
+            // No viewing keys configured, can't decrypt
+            debug!("Skipping block {} - no viewing keys configured", height);
+            Vec::new()
         };
 
-        if payments.is_empty() {
-            debug!("No payments found in block {}", height);
-            return Ok(());
-        }
+        self.record_block(height, payments).await
+    }
 
-        info!("Found {} payment(s) in block {}", payments.len(), height);
+    /// Process blocks `start..=end` from lightwalletd's block stream
+    async fn process_block_range(&mut self, start: u32, end: u32) -> Result<usize> {
+        let stream = {
+            let mut node = self.node.lock().await;
+            node.get_block_range(start, end).await?
+        };
+        self.process_compact_blocks(stream, end).await
+    }
 
-        // Store each payment
-        for payment in payments {
-            match self.storage.insert_payment(&payment).await {
-                Ok(true) => {
-                    info!(
-                        "Stored payment: {} ZEC from tx {}",
-                        payment.amount as f64 / 100_000_000.0,
-                        payment.tx_id
-                    );
+    /// Decrypt and record streamed compact blocks up to `end`
+    ///
+    /// Each block is committed as it arrives. If the stream fails partway,
+    /// the blocks before the failure stay recorded and the error says where
+    /// it stopped; the next poll resumes from the following block.
+    async fn process_compact_blocks(
+        &mut self,
+        mut stream: CompactBlockStream,
+        end: u32,
+    ) -> Result<usize> {
+        let mut stored = 0;
+        while self.last_processed_height < end {
+            let expected = self.last_processed_height + 1;
+            let block = match stream.next().await {
+                Some(Ok(block)) => block,
+                Some(Err(e)) => {
+                    return Err(e.context(format!(
+                        "Block stream failed after block {}",
+                        self.last_processed_height
+                    )))
                 }
-                Ok(false) => {
-                    warn!("Payment already exists: {}", payment.nullifier.to_hex());
+                None => anyhow::bail!(
+                    "Block stream ended after block {} (expected blocks up to {})",
+                    self.last_processed_height,
+                    end
+                ),
+            };
+
+            let height = block.height as u32;
+            if height != expected {
+                anyhow::bail!(
+                    "Block stream sent block {} but block {} was expected",
+                    height,
+                    expected
+                );
+            }
+            debug!("Processing block {}", height);
+
+            // Trial decryption is CPU-bound; keep it off the async workers
+            let payments = match &self.note_decryptor {
+                Some(decryptor) => {
+                    let decryptor = Arc::clone(decryptor);
+                    tokio::task::spawn_blocking(move || decryptor.decrypt_block(&block))
+                        .await
+                        .context("Note decryption task failed")??
                 }
-                Err(e) => {
-                    error!("Failed to store payment: {:#}", e);
-                    // Continue processing other payments
+                None => Vec::new(),
+            };
+            stored += self.record_block(height, payments).await?;
+        }
+
+        Ok(stored)
+    }
+
+    /// Store a processed block's payments, returning how many were new
+    ///
+    /// The block's payments and the monitor cursor are committed together,
+    /// so an error leaves the cursor on the previous block.
+    async fn record_block(
+        &mut self,
+        height: u32,
+        mut payments: Vec<ReceivedPayment>,
+    ) -> Result<usize> {
+        if payments.is_empty() {
+            debug!("No payments found in block {}", height);
+        } else {
+            info!("Found {} payment(s) in block {}", payments.len(), height);
+
+            // Capture the fiat value at detection time; a failed lookup shouldn't
+            // hold up recording the payment itself
+            if let Some(provider) = &self.rate_provider {
+                match provider.zec_usd().await {
+                    Ok(rate) => annotate_usd_value(&mut payments, rate),
+                    Err(e) => warn!(
+                        "Failed to fetch exchange rate for block {}: {:#}",
+                        height, e
+                    ),
                 }
             }
         }
 
-        Ok(())
+        // Store the block's payments and advance the cursor in one transaction
+        let inserted = self
+            .storage
+            .commit_block(height, &payments)
+            .await
+            .with_context(|| format!("Failed to store block {}", height))?;
+        self.last_processed_height = height;
+
+        let mut stored = 0;
+        for (payment, inserted) in payments.iter().zip(inserted) {
+            if inserted {
+                stored += 1;
+                info!(
+                    "Stored payment: {} ZEC from tx {} to {}",
+                    zatoshis_to_zec(payment.amount),
+                    payment.tx_id,
+                    payment.address.as_deref().unwrap_or("unknown address")
+                );
+            } else {
+                warn!("Payment already exists: {}", payment.nullifier.to_hex());
+            }
+        }
+
+        Ok(stored)
     }
 }
 
@@ -249,4 +577,244 @@ mod tests {
         // Verify last_processed_height was updated
         assert!(monitor.last_processed_height > 0);
     }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis
+    async fn test_restart_resumes_from_cursor() {
+        use khafi_common::Nullifier;
+
+        std::env::set_var("REDIS_URL", "redis://localhost:6379");
+        std::env::set_var("MOCK_MODE", "true");
+        std::env::set_var("PAYMENT_ADDRESS", "test_address");
+
+        // A database of its own, so other monitor tests can't move the cursor
+        let mut config = Config::from_env().unwrap();
+        config.redis_db = Some(9);
+        let mut storage = Storage::new(&config.redis_url, config.redis_db)
+            .await
+            .unwrap();
+
+        // The last payment is well behind the last block scanned (mock tip: 100000)
+        let payment = ReceivedPayment::new(
+            Nullifier::new([0x57; 32]),
+            1000,
+            "tx_cursor".to_string(),
+            90_000,
+        );
+        storage.commit_block(90_000, &[payment]).await.unwrap();
+        storage.commit_block(99_990, &[]).await.unwrap();
+
+        let mut monitor = Monitor::new(config.clone()).await.unwrap();
+        assert_eq!(monitor.last_processed_height, 99_990);
+
+        let summary = monitor.poll_once().await.unwrap();
+        assert_eq!(summary.blocks_processed, 10);
+        assert_eq!(summary.processed_height, 100_000);
+        assert_eq!(
+            storage.get_last_processed_height().await.unwrap(),
+            Some(100_000)
+        );
+
+        // A restarted monitor picks up exactly where the first one stopped
+        let monitor = Monitor::new(config).await.unwrap();
+        assert_eq!(monitor.last_processed_height, 100_000);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis
+    async fn test_stream_error_keeps_earlier_blocks() {
+        use crate::config::Network;
+        use crate::lightwalletd_client::proto::CompactBlock;
+
+        std::env::set_var("REDIS_URL", "redis://localhost:6379");
+        std::env::set_var("MOCK_MODE", "true");
+        std::env::set_var("PAYMENT_ADDRESS", "test_address");
+
+        let mut config = Config::from_env().unwrap();
+        config.redis_db = Some(10);
+        let mut storage = Storage::new(&config.redis_url, config.redis_db)
+            .await
+            .unwrap();
+        storage.commit_block(500, &[]).await.unwrap();
+
+        let mut monitor = Monitor::new(config).await.unwrap();
+        monitor.note_decryptor = Some(Arc::new(
+            NoteDecryptor::new(None, None, Network::Testnet).unwrap(),
+        ));
+        assert_eq!(monitor.last_processed_height, 500);
+
+        // Three blocks arrive, then the connection drops
+        let blocks = (501..=503).map(|height| {
+            Ok(CompactBlock {
+                height,
+                ..Default::default()
+            })
+        });
+        let stream =
+            futures::stream::iter(blocks.chain([Err(anyhow::anyhow!("connection reset by peer"))]))
+                .boxed();
+
+        let err = monitor
+            .process_compact_blocks(stream, 510)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Block stream failed after block 503");
+        assert!(format!("{:#}", err).contains("connection reset by peer"));
+
+        // The blocks before the failure count, in memory and in Redis
+        assert_eq!(monitor.last_processed_height, 503);
+        assert_eq!(
+            storage.get_last_processed_height().await.unwrap(),
+            Some(503)
+        );
+
+        // The next poll picks up from 504
+        let rest = (504..=510).map(|height| {
+            Ok(CompactBlock {
+                height,
+                ..Default::default()
+            })
+        });
+        monitor
+            .process_compact_blocks(futures::stream::iter(rest).boxed(), 510)
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.get_last_processed_height().await.unwrap(),
+            Some(510)
+        );
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis
+    async fn test_catch_up_reports_progress() {
+        std::env::set_var("REDIS_URL", "redis://localhost:6379");
+        std::env::set_var("MOCK_MODE", "true");
+        std::env::set_var("PAYMENT_ADDRESS", "test_address");
+
+        let mut config = Config::from_env().unwrap();
+        config.redis_db = Some(11);
+        config.catch_up_batch_blocks = 100;
+        let mut storage = Storage::new(&config.redis_url, config.redis_db)
+            .await
+            .unwrap();
+        // 1000 blocks behind the mock tip (100000)
+        storage.commit_block(99_000, &[]).await.unwrap();
+
+        let mut monitor = Monitor::new(config).await.unwrap();
+        let mut progress_rx = monitor.progress_tx.subscribe();
+        let reports = tokio::spawn(async move {
+            let mut reports = Vec::new();
+            while progress_rx.changed().await.is_ok() {
+                let progress = *progress_rx.borrow_and_update();
+                reports.push(progress);
+                if !progress.catching_up {
+                    break;
+                }
+            }
+            reports
+        });
+
+        let summary = monitor.poll_once().await.unwrap();
+        assert_eq!(summary.blocks_processed, 1000);
+        let handle = monitor.handle();
+        drop(monitor);
+        let reports = reports.await.unwrap();
+
+        // One report per batch, each further along than the last
+        assert!(reports.len() >= 2, "{:?}", reports);
+        for pair in reports.windows(2) {
+            assert!(pair[1].blocks_done > pair[0].blocks_done, "{:?}", pair);
+            assert!(pair[1].processed_height > pair[0].processed_height);
+        }
+        assert!(reports.iter().all(|p| p.blocks_total == 1000));
+        assert!(reports[0].catching_up);
+
+        let last = reports.last().unwrap();
+        assert!(!last.catching_up);
+        assert_eq!(last.blocks_done, 1000);
+        assert_eq!(last.processed_height, 100_000);
+
+        // The handle sees the finished pass too
+        let final_progress = handle.sync_progress();
+        assert!(!final_progress.catching_up);
+        assert_eq!(final_progress.processed_height, 100_000);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis
+    async fn test_poll_without_new_blocks_refreshes_chain_tip() {
+        std::env::set_var("REDIS_URL", "redis://localhost:6379");
+        std::env::set_var("MOCK_MODE", "true");
+        std::env::set_var("PAYMENT_ADDRESS", "test_address");
+
+        let mut config = Config::from_env().unwrap();
+        config.redis_db = Some(12);
+        let mut storage = Storage::new(&config.redis_url, config.redis_db)
+            .await
+            .unwrap();
+        // Already at the mock tip (100000), with a tip timestamp an hour old
+        storage.commit_block(100_000, &[]).await.unwrap();
+        let client = redis::Client::open(config.redis_url.as_str()).unwrap();
+        let mut conn = client.get_multiplexed_async_connection().await.unwrap();
+        redis::cmd("SELECT")
+            .arg(12)
+            .query_async::<_, ()>(&mut conn)
+            .await
+            .unwrap();
+        let stale = chrono::Utc::now().timestamp() - 3600;
+        redis::cmd("SET")
+            .arg("chain:block_height_updated_at")
+            .arg(stale)
+            .query_async::<_, ()>(&mut conn)
+            .await
+            .unwrap();
+
+        let mut monitor = Monitor::new(config).await.unwrap();
+        let summary = monitor.poll_once().await.unwrap();
+        assert_eq!(summary.blocks_processed, 0);
+
+        let updated_at: i64 = redis::cmd("GET")
+            .arg("chain:block_height_updated_at")
+            .query_async(&mut conn)
+            .await
+            .unwrap();
+        assert!(updated_at > stale + 3000, "tip timestamp not refreshed");
+        assert_eq!(storage.get_block_height().await.unwrap(), Some(100_000));
+    }
+
+    #[tokio::test]
+    async fn test_mainnet_monitor_rejects_testnet_address() {
+        use crate::config::{Network, WatchedAddress};
+        use zcash_address::unified::{self, Encoding};
+        use zcash_protocol::consensus::NetworkType;
+
+        // A real testnet unified address and its viewing key
+        let sk = orchard::keys::SpendingKey::from_bytes([0x42; 32]).unwrap();
+        let fvk = orchard::keys::FullViewingKey::from(&sk);
+        let receiver = fvk
+            .address_at(0u64, orchard::keys::Scope::External)
+            .to_raw_address_bytes();
+        let ua =
+            unified::Address::try_from_items(vec![unified::Receiver::Orchard(receiver)]).unwrap();
+
+        std::env::set_var("PAYMENT_ADDRESS", "test_address");
+        let mut config = Config::from_env().unwrap();
+        config.mock_mode = false;
+        config.network = Network::Mainnet;
+        // Never reached: the address check fails before connecting
+        config.lightwalletd_url = Some("http://127.0.0.1:1".to_string());
+        config.watched_addresses = vec![WatchedAddress {
+            address: ua.encode(&NetworkType::Test),
+            orchard_fvk: Some(hex::encode(fvk.to_bytes())),
+        }];
+
+        let err = Monitor::new(config).await.err().unwrap();
+        assert!(
+            err.to_string()
+                .contains("testnet address but ZCASH_NETWORK is mainnet"),
+            "{}",
+            err
+        );
+    }
 }
@@ -6,9 +6,13 @@
 use anyhow::{Context, Result};
 use khafi_common::Nullifier;
 use orchard::keys::FullViewingKey as OrchardFVK;
+use rayon::prelude::*;
 // Note: OrchardDomain will be used when implementing full trial decryption
 use tracing::{debug, info, warn};
+use zcash_address::unified::{self, Container, Encoding};
+use zcash_protocol::consensus::NetworkType;
 
+use crate::config::{MemoFormat, Network, WatchedAddress};
 use crate::lightwalletd_client::proto::{CompactBlock, CompactOrchardAction, CompactTx};
 use crate::storage::ReceivedPayment;
 
@@ -25,42 +29,137 @@ pub struct DecryptedNote {
 
 /// Note decryptor for detecting incoming payments
 pub struct NoteDecryptor {
-    /// Orchard Full Viewing Key (if configured)
-    orchard_fvk: Option<OrchardFVK>,
+    /// Orchard Full Viewing Keys, each with the address it watches (if known)
+    orchard_keys: Vec<(Option<String>, OrchardFVK)>,
+
+    /// Network the keys and addresses belong to
+    network: Network,
+
+    /// Worker pool trial-decrypting a block's actions (serial when `None`)
+    pool: Option<rayon::ThreadPool>,
+
+    /// How memos carry the customer's nullifier
+    memo_format: MemoFormat,
 }
 
+/// Prefix of the `prefixed` memo format
+const MEMO_NULLIFIER_PREFIX: &str = "nullifier:";
+
 impl NoteDecryptor {
     /// Create a new note decryptor from hex-encoded viewing keys
-    pub fn new(orchard_fvk_hex: Option<&str>, _sapling_fvk_hex: Option<&str>) -> Result<Self> {
-        let orchard_fvk = if let Some(hex) = orchard_fvk_hex {
-            let bytes = hex::decode(hex).context("Invalid ORCHARD_FVK hex")?;
-            if bytes.len() != 96 {
-                anyhow::bail!("ORCHARD_FVK must be 96 bytes, got {}", bytes.len());
-            }
-            let mut fvk_bytes = [0u8; 96];
-            fvk_bytes.copy_from_slice(&bytes);
+    pub fn new(
+        orchard_fvk_hex: Option<&str>,
+        _sapling_fvk_hex: Option<&str>,
+        network: Network,
+    ) -> Result<Self> {
+        let orchard_keys = match orchard_fvk_hex {
+            Some(hex) => vec![(None, parse_orchard_fvk(hex)?)],
+            None => Vec::new(),
+        };
+
+        // TODO: Add Sapling FVK parsing when needed
 
-            let fvk = OrchardFVK::from_bytes(&fvk_bytes);
-            if fvk.is_none().into() {
-                anyhow::bail!("Invalid Orchard Full Viewing Key");
+        Ok(Self {
+            orchard_keys,
+            network,
+            pool: None,
+            memo_format: MemoFormat::default(),
+        })
+    }
+
+    /// Create a note decryptor for several watched addresses
+    ///
+    /// Payments decrypted with an address's key are tagged with that address.
+    /// Fails if an address isn't a unified address on `network`, or if its
+    /// viewing key doesn't belong to it - either would silently decrypt nothing.
+    pub fn for_addresses(
+        watched: &[WatchedAddress],
+        _sapling_fvk_hex: Option<&str>,
+        network: Network,
+    ) -> Result<Self> {
+        let mut orchard_keys = Vec::new();
+        for w in watched {
+            let fvk = w
+                .orchard_fvk
+                .as_deref()
+                .map(parse_orchard_fvk)
+                .transpose()
+                .with_context(|| format!("Invalid viewing key for {}", w.address))?;
+            check_address(&w.address, fvk.as_ref(), network)?;
+
+            if let Some(fvk) = fvk {
+                orchard_keys.push((Some(w.address.clone()), fvk));
             }
-            Some(fvk.unwrap())
+        }
+
+        Ok(Self {
+            orchard_keys,
+            network,
+            pool: None,
+            memo_format: MemoFormat::default(),
+        })
+    }
+
+    /// Trial-decrypt blocks on a pool of `workers` threads, started here
+    ///
+    /// One worker (or zero) decrypts on the calling thread.
+    pub fn with_workers(mut self, workers: usize) -> Result<Self> {
+        self.pool = if workers > 1 {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(workers)
+                .thread_name(|i| format!("note-decrypt-{}", i))
+                .build()
+                .context("Failed to start note decryption workers")?;
+            Some(pool)
         } else {
             None
         };
+        Ok(self)
+    }
 
-        // TODO: Add Sapling FVK parsing when needed
-
-        Ok(Self { orchard_fvk })
+    /// Only accept nullifiers in memos of the given format
+    pub fn with_memo_format(mut self, memo_format: MemoFormat) -> Self {
+        self.memo_format = memo_format;
+        self
     }
 
     /// Try to decrypt a compact block and extract payments to us
+    ///
+    /// Actions are trial-decrypted in parallel; payments come back in block
+    /// order whatever the worker count. This blocks until the whole block is
+    /// decrypted, so async callers should run it on a blocking thread.
     pub fn decrypt_block(&self, block: &CompactBlock) -> Result<Vec<ReceivedPayment>> {
-        let mut payments = Vec::new();
+        self.scan_block(block, |action, fvk| {
+            self.try_decrypt_orchard_action(action, fvk)
+        })
+    }
+
+    /// [`Self::decrypt_block`] with the per-action trial decryption supplied
+    fn scan_block<F>(&self, block: &CompactBlock, decrypt: F) -> Result<Vec<ReceivedPayment>>
+    where
+        F: Fn(&CompactOrchardAction, &OrchardFVK) -> Result<Option<DecryptedNote>> + Sync,
+    {
         let height = block.height as u32;
 
+        // One job per transaction, key and action, in the order they're matched
+        let jobs: Vec<(&CompactOrchardAction, &OrchardFVK)> = block
+            .vtx
+            .iter()
+            .flat_map(|tx| {
+                self.orchard_keys
+                    .iter()
+                    .flat_map(move |(_, fvk)| tx.actions.iter().map(move |action| (action, fvk)))
+            })
+            .collect();
+        let mut notes = self.trial_decrypt(&jobs, &decrypt)?.into_iter();
+
+        let mut payments = Vec::new();
         for tx in &block.vtx {
-            if let Some(payment) = self.try_decrypt_tx(tx, height)? {
+            let tx_notes = notes
+                .by_ref()
+                .take(self.orchard_keys.len() * tx.actions.len())
+                .collect();
+            if let Some(payment) = self.payment_from_notes(tx, height, tx_notes)? {
                 payments.push(payment);
             }
         }
@@ -72,36 +171,67 @@ impl NoteDecryptor {
         Ok(payments)
     }
 
-    /// Try to decrypt a transaction and extract payment if it's to us
-    fn try_decrypt_tx(&self, tx: &CompactTx, block_height: u32) -> Result<Option<ReceivedPayment>> {
-        let tx_hash = hex::encode(&tx.hash);
+    /// Run trial decryption jobs on the worker pool, keeping job order
+    fn trial_decrypt<F>(
+        &self,
+        jobs: &[(&CompactOrchardAction, &OrchardFVK)],
+        decrypt: &F,
+    ) -> Result<Vec<Option<DecryptedNote>>>
+    where
+        F: Fn(&CompactOrchardAction, &OrchardFVK) -> Result<Option<DecryptedNote>> + Sync,
+    {
+        match &self.pool {
+            // Indexed collects keep the results lined up with the jobs
+            Some(pool) if jobs.len() > 1 => pool.install(|| {
+                jobs.par_iter()
+                    .map(|(action, fvk)| decrypt(action, fvk))
+                    .collect()
+            }),
+            _ => jobs
+                .iter()
+                .map(|(action, fvk)| decrypt(action, fvk))
+                .collect(),
+        }
+    }
 
-        // Try Orchard actions first
-        if let Some(fvk) = &self.orchard_fvk {
-            for (idx, action) in tx.actions.iter().enumerate() {
-                if let Some(note) = self.try_decrypt_orchard_action(action, fvk)? {
-                    debug!(
-                        "Decrypted Orchard note: tx={}, action={}, value={}",
-                        tx_hash, idx, note.value
-                    );
-
-                    // Extract customer nullifier from memo
-                    let customer_nullifier = self.extract_nullifier_from_memo(&note.memo)?;
-
-                    if let Some(nullifier) = customer_nullifier {
-                        return Ok(Some(ReceivedPayment::new(
-                            nullifier,
-                            note.value,
-                            tx_hash.clone(),
-                            block_height,
-                        )));
-                    } else {
-                        warn!(
-                            "Payment detected but memo doesn't contain valid nullifier: tx={}",
-                            tx_hash
-                        );
-                    }
-                }
+    /// Extract the payment from a transaction's trial-decrypted notes, if it's to us
+    ///
+    /// `notes` has one entry per watched key and action, keys outermost.
+    fn payment_from_notes(
+        &self,
+        tx: &CompactTx,
+        block_height: u32,
+        notes: Vec<Option<DecryptedNote>>,
+    ) -> Result<Option<ReceivedPayment>> {
+        let tx_hash = hex::encode(&tx.hash);
+        let action_count = tx.actions.len().max(1);
+
+        // Orchard notes, with each watched address's key in turn
+        for (job, note) in notes.into_iter().enumerate() {
+            let Some(note) = note else { continue };
+            let (address, _) = &self.orchard_keys[job / action_count];
+            debug!(
+                "Decrypted Orchard note: tx={}, action={}, value={}",
+                tx_hash,
+                job % action_count,
+                note.value
+            );
+
+            // Extract customer nullifier from memo
+            let customer_nullifier = self.extract_nullifier_from_memo(&note.memo)?;
+
+            if let Some(nullifier) = customer_nullifier {
+                let payment =
+                    ReceivedPayment::new(nullifier, note.value, tx_hash.clone(), block_height);
+                return Ok(Some(match address {
+                    Some(address) => payment.with_address(address.clone()),
+                    None => payment,
+                }));
+            } else {
+                warn!(
+                    "Payment detected but memo doesn't contain valid nullifier: tx={}",
+                    tx_hash
+                );
             }
         }
 
@@ -146,59 +276,142 @@ impl NoteDecryptor {
         Ok(None)
     }
 
-    /// Extract customer nullifier from memo field
-    ///
-    /// Expected memo format:
-    /// - First 32 bytes: Customer's nullifier (hex would be 64 chars, but stored as raw bytes)
-    /// - Or: "nullifier:" prefix followed by 64 hex characters
+    /// Extract customer nullifier from memo field, in the configured [`MemoFormat`]
     fn extract_nullifier_from_memo(&self, memo: &[u8; 512]) -> Result<Option<Nullifier>> {
-        // Check if memo starts with raw 32-byte nullifier (non-zero)
-        if memo[0..32].iter().any(|&b| b != 0) {
-            let mut nullifier_bytes = [0u8; 32];
-            nullifier_bytes.copy_from_slice(&memo[0..32]);
-            return Ok(Some(Nullifier::new(nullifier_bytes)));
-        }
-
-        // Check for "nullifier:" prefix with hex encoding
-        let memo_str = String::from_utf8_lossy(memo);
-        let trimmed = memo_str.trim_matches(char::from(0)).trim();
-
-        if let Some(hex_str) = trimmed.strip_prefix("nullifier:") {
-            let hex_str = hex_str.trim();
-            if hex_str.len() == 64 {
-                if let Ok(bytes) = hex::decode(hex_str) {
-                    let mut nullifier_bytes = [0u8; 32];
-                    nullifier_bytes.copy_from_slice(&bytes);
-                    return Ok(Some(Nullifier::new(nullifier_bytes)));
-                }
-            }
-        }
-
-        // Check if memo is just a 64-character hex string
-        if trimmed.len() == 64 {
-            if let Ok(bytes) = hex::decode(trimmed) {
-                let mut nullifier_bytes = [0u8; 32];
-                nullifier_bytes.copy_from_slice(&bytes);
-                return Ok(Some(Nullifier::new(nullifier_bytes)));
-            }
-        }
+        let nullifier = match &self.memo_format {
+            MemoFormat::Auto => prefixed_nullifier(memo, MEMO_NULLIFIER_PREFIX)
+                .or_else(|| hex_nullifier(memo))
+                .or_else(|| raw_nullifier(memo)),
+            MemoFormat::Raw => raw_nullifier(memo),
+            MemoFormat::Prefixed => prefixed_nullifier(memo, MEMO_NULLIFIER_PREFIX),
+            MemoFormat::Hex => hex_nullifier(memo),
+            MemoFormat::CustomPrefix(prefix) => prefixed_nullifier(memo, prefix),
+        };
 
-        Ok(None)
+        Ok(nullifier)
     }
 
     /// Check if we have any viewing keys configured
     pub fn has_viewing_keys(&self) -> bool {
-        self.orchard_fvk.is_some()
+        !self.orchard_keys.is_empty()
+    }
+
+    /// Network the decryptor's keys belong to
+    pub fn network(&self) -> Network {
+        self.network
+    }
+}
+
+/// Raw 32-byte nullifier at the start of the memo (if those bytes aren't all zero)
+fn raw_nullifier(memo: &[u8; 512]) -> Option<Nullifier> {
+    if memo[0..32].iter().all(|&b| b == 0) {
+        return None;
+    }
+    let mut nullifier_bytes = [0u8; 32];
+    nullifier_bytes.copy_from_slice(&memo[0..32]);
+    Some(Nullifier::new(nullifier_bytes))
+}
+
+/// `prefix` followed by a 64-hex-character nullifier
+fn prefixed_nullifier(memo: &[u8; 512], prefix: &str) -> Option<Nullifier> {
+    let text = memo_text(memo);
+    let hex_str = text.strip_prefix(prefix)?.trim();
+    decode_hex_nullifier(hex_str)
+}
+
+/// Memo that is just a 64-hex-character nullifier
+fn hex_nullifier(memo: &[u8; 512]) -> Option<Nullifier> {
+    decode_hex_nullifier(&memo_text(memo))
+}
+
+/// Memo as text, without the zero padding or surrounding whitespace
+fn memo_text(memo: &[u8; 512]) -> String {
+    let memo_str = String::from_utf8_lossy(memo);
+    memo_str.trim_matches(char::from(0)).trim().to_string()
+}
+
+/// Decode exactly 64 hex characters as a nullifier
+fn decode_hex_nullifier(hex_str: &str) -> Option<Nullifier> {
+    if hex_str.len() != 64 {
+        return None;
+    }
+    let bytes = hex::decode(hex_str).ok()?;
+    let mut nullifier_bytes = [0u8; 32];
+    nullifier_bytes.copy_from_slice(&bytes);
+    Some(Nullifier::new(nullifier_bytes))
+}
+
+/// Address encoding network for a configured network
+fn network_type(network: Network) -> NetworkType {
+    match network {
+        Network::Mainnet => NetworkType::Main,
+        Network::Testnet => NetworkType::Test,
     }
 }
 
+/// Check that `address` is a unified address on `network` and, when a viewing
+/// key is given, that the address's Orchard receiver derives from it
+fn check_address(address: &str, fvk: Option<&OrchardFVK>, network: Network) -> Result<()> {
+    let (address_network, ua) = unified::Address::decode(address)
+        .map_err(|e| anyhow::anyhow!("{} is not a valid unified address: {}", address, e))?;
+
+    if address_network != network_type(network) {
+        let address_network = match address_network {
+            NetworkType::Main => "mainnet",
+            NetworkType::Test => "testnet",
+            NetworkType::Regtest => "regtest",
+        };
+        anyhow::bail!(
+            "{} is a {} address but ZCASH_NETWORK is {}",
+            address,
+            address_network,
+            network
+        );
+    }
+
+    let Some(fvk) = fvk else {
+        return Ok(());
+    };
+
+    let receiver = ua.items().into_iter().find_map(|receiver| match receiver {
+        unified::Receiver::Orchard(bytes) => Some(bytes),
+        _ => None,
+    });
+    let Some(receiver) = receiver else {
+        anyhow::bail!("{} has no Orchard receiver for its viewing key", address);
+    };
+
+    let receiver: Option<orchard::Address> =
+        orchard::Address::from_raw_address_bytes(&receiver).into();
+    match receiver {
+        Some(receiver) if fvk.scope_for_address(&receiver).is_some() => Ok(()),
+        _ => anyhow::bail!("Orchard viewing key does not match {}", address),
+    }
+}
+
+/// Parse a hex-encoded 96-byte Orchard Full Viewing Key
+fn parse_orchard_fvk(hex: &str) -> Result<OrchardFVK> {
+    let bytes = hex::decode(hex).context("Invalid ORCHARD_FVK hex")?;
+    if bytes.len() != 96 {
+        anyhow::bail!("ORCHARD_FVK must be 96 bytes, got {}", bytes.len());
+    }
+    let mut fvk_bytes = [0u8; 96];
+    fvk_bytes.copy_from_slice(&bytes);
+
+    let fvk = OrchardFVK::from_bytes(&fvk_bytes);
+    if fvk.is_none().into() {
+        anyhow::bail!("Invalid Orchard Full Viewing Key");
+    }
+    Ok(fvk.unwrap())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_extract_nullifier_raw_bytes() {
-        let decryptor = NoteDecryptor::new(None, None).unwrap();
+        let decryptor = NoteDecryptor::new(None, None, Network::Testnet).unwrap();
 
         let mut memo = [0u8; 512];
         // Put a test nullifier in first 32 bytes
@@ -216,7 +429,7 @@ mod tests {
 
     #[test]
     fn test_extract_nullifier_hex_string() {
-        let decryptor = NoteDecryptor::new(None, None).unwrap();
+        let decryptor = NoteDecryptor::new(None, None, Network::Testnet).unwrap();
 
         let mut memo = [0u8; 512];
         let hex_nullifier = "0102030405060708091011121314151617181920212223242526272829303132";
@@ -228,7 +441,7 @@ mod tests {
 
     #[test]
     fn test_extract_nullifier_with_prefix() {
-        let decryptor = NoteDecryptor::new(None, None).unwrap();
+        let decryptor = NoteDecryptor::new(None, None, Network::Testnet).unwrap();
 
         let mut memo = [0u8; 512];
         let prefixed = "nullifier:0102030405060708091011121314151617181920212223242526272829303132";
@@ -237,4 +450,197 @@ mod tests {
         let result = decryptor.extract_nullifier_from_memo(&memo).unwrap();
         assert!(result.is_some());
     }
+
+    /// A memo holding `text`, zero-padded
+    fn text_memo(text: &str) -> [u8; 512] {
+        let mut memo = [0u8; 512];
+        memo[..text.len()].copy_from_slice(text.as_bytes());
+        memo
+    }
+
+    #[test]
+    fn test_memo_formats() {
+        let hex_nullifier = "0102030405060708091011121314151617181920212223242526272829303132";
+        let expected = Nullifier::from_hex(hex_nullifier).unwrap();
+
+        let mut raw = [0u8; 512];
+        raw[..32].copy_from_slice(expected.as_bytes());
+        let prefixed = text_memo(&format!("nullifier:{}", hex_nullifier));
+        let bare_hex = text_memo(hex_nullifier);
+        let custom = text_memo(&format!("pay={}", hex_nullifier));
+        let text = text_memo("Thanks for the coffee! Order #1234, see you next week");
+
+        let parse = |format: MemoFormat, memo: &[u8; 512]| {
+            NoteDecryptor::new(None, None, Network::Testnet)
+                .unwrap()
+                .with_memo_format(format)
+                .extract_nullifier_from_memo(memo)
+                .unwrap()
+        };
+
+        // Each exact format accepts only its own memos
+        for (format, accepted) in [
+            (MemoFormat::Raw, &raw),
+            (MemoFormat::Prefixed, &prefixed),
+            (MemoFormat::Hex, &bare_hex),
+            (MemoFormat::CustomPrefix("pay=".to_string()), &custom),
+        ] {
+            assert_eq!(parse(format.clone(), accepted), Some(expected.clone()));
+            for memo in [&raw, &prefixed, &bare_hex, &custom] {
+                if memo != accepted && format != MemoFormat::Raw {
+                    assert_eq!(parse(format.clone(), memo), None, "{:?}", format);
+                }
+            }
+        }
+
+        // A text memo isn't a nullifier under the prefixed format...
+        assert_eq!(parse(MemoFormat::Prefixed, &text), None);
+        assert_eq!(parse(MemoFormat::Hex, &text), None);
+        // ...but auto-detection falls back to reading its first 32 bytes
+        assert!(parse(MemoFormat::Auto, &text).is_some());
+
+        // Auto-detection decodes text formats before trying raw bytes
+        for memo in [&raw, &prefixed, &bare_hex] {
+            assert_eq!(parse(MemoFormat::Auto, memo), Some(expected.clone()));
+        }
+    }
+
+    /// A unified address for `network` and its hex FVK, derived from a fixed key
+    fn address_and_fvk(seed: u8, network: NetworkType) -> (String, String) {
+        let sk = orchard::keys::SpendingKey::from_bytes([seed; 32]).unwrap();
+        let fvk = OrchardFVK::from(&sk);
+        let receiver = fvk
+            .address_at(0u64, orchard::keys::Scope::External)
+            .to_raw_address_bytes();
+        let ua =
+            unified::Address::try_from_items(vec![unified::Receiver::Orchard(receiver)]).unwrap();
+        (ua.encode(&network), hex::encode(fvk.to_bytes()))
+    }
+
+    #[test]
+    fn test_for_addresses_checks_network_and_key() {
+        let (address, fvk) = address_and_fvk(0x42, NetworkType::Test);
+        let watched = |address: &str, fvk: &str| {
+            vec![WatchedAddress {
+                address: address.to_string(),
+                orchard_fvk: Some(fvk.to_string()),
+            }]
+        };
+
+        let decryptor =
+            NoteDecryptor::for_addresses(&watched(&address, &fvk), None, Network::Testnet).unwrap();
+        assert!(decryptor.has_viewing_keys());
+        assert_eq!(decryptor.network(), Network::Testnet);
+
+        // Testnet address on a mainnet deployment
+        let err = NoteDecryptor::for_addresses(&watched(&address, &fvk), None, Network::Mainnet)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("testnet address"));
+
+        // Viewing key for a different wallet
+        let (_, other_fvk) = address_and_fvk(0x43, NetworkType::Test);
+        let err =
+            NoteDecryptor::for_addresses(&watched(&address, &other_fvk), None, Network::Testnet)
+                .err()
+                .unwrap();
+        assert!(err.to_string().contains("does not match"));
+    }
+
+    /// Stand-in for trial decryption: actions whose ciphertext starts with
+    /// 0xAA are ours, worth their second byte in ZEC, with `cmx` as the memo
+    fn fake_decrypt(
+        action: &CompactOrchardAction,
+        _fvk: &OrchardFVK,
+    ) -> Result<Option<DecryptedNote>> {
+        if action.ciphertext.first() != Some(&0xAA) {
+            return Ok(None);
+        }
+        let mut memo = [0u8; 512];
+        memo[..32].copy_from_slice(&action.cmx);
+        Ok(Some(DecryptedNote {
+            value: u64::from(action.ciphertext[1]) * 100_000_000,
+            memo,
+            nullifier: [0u8; 32],
+        }))
+    }
+
+    /// A block of 40 transactions with 25 actions each; every fifth
+    /// transaction pays us, some after an action whose memo has no nullifier
+    fn dense_block() -> CompactBlock {
+        let vtx = (0..40u8)
+            .map(|t| {
+                let actions = (0..25u8)
+                    .map(|a| {
+                        let ours = t % 5 == 0 && a == t % 25;
+                        let no_memo = t % 10 == 0 && a + 1 == t % 25;
+                        let mut ciphertext = vec![0u8; 52];
+                        if ours || no_memo {
+                            ciphertext[0] = 0xAA;
+                            ciphertext[1] = t;
+                        }
+                        CompactOrchardAction {
+                            nullifier: vec![a; 32],
+                            cmx: if no_memo {
+                                vec![0; 32]
+                            } else {
+                                vec![t + 1; 32]
+                            },
+                            ephemeral_key: vec![0; 32],
+                            ciphertext,
+                        }
+                    })
+                    .collect();
+                CompactTx {
+                    hash: vec![t; 32],
+                    actions,
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        CompactBlock {
+            height: 2_500_000,
+            vtx,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_dense_block_parallel_matches_serial() {
+        let (address, fvk) = address_and_fvk(0x42, NetworkType::Test);
+        let watched = vec![WatchedAddress {
+            address: address.clone(),
+            orchard_fvk: Some(fvk),
+        }];
+        let decryptor = NoteDecryptor::for_addresses(&watched, None, Network::Testnet).unwrap();
+        let block = dense_block();
+
+        let serial = decryptor.scan_block(&block, fake_decrypt).unwrap();
+        assert_eq!(serial.len(), 8);
+        for (payment, t) in serial.iter().zip((0..40u8).step_by(5)) {
+            assert_eq!(payment.tx_id, hex::encode([t; 32]));
+            assert_eq!(payment.nullifier.as_bytes(), &[t + 1; 32]);
+            assert_eq!(payment.amount, u64::from(t) * 100_000_000);
+            assert_eq!(payment.block_height, 2_500_000);
+            assert_eq!(payment.address.as_deref(), Some(address.as_str()));
+        }
+
+        // Worker counts that divide the 1000 actions evenly and unevenly
+        for workers in [2, 7, 64] {
+            let decryptor = NoteDecryptor::for_addresses(&watched, None, Network::Testnet)
+                .unwrap()
+                .with_workers(workers)
+                .unwrap();
+            let parallel = decryptor.scan_block(&block, fake_decrypt).unwrap();
+
+            let found = |payments: &[ReceivedPayment]| -> Vec<(String, Nullifier, u64)> {
+                payments
+                    .iter()
+                    .map(|p| (p.tx_id.clone(), p.nullifier.clone(), p.amount))
+                    .collect()
+            };
+            assert_eq!(found(&parallel), found(&serial), "workers={}", workers);
+        }
+    }
 }
@@ -11,14 +11,19 @@ use tracing::debug;
 
 /// Parser for extracting payments from blocks
 pub struct Parser {
-    /// The payment address we're monitoring for
-    payment_address: String,
+    /// The payment addresses we're monitoring for
+    payment_addresses: Vec<String>,
 }
 
 impl Parser {
     /// Create a new parser
     pub fn new(payment_address: String) -> Self {
-        Self { payment_address }
+        Self::with_addresses(vec![payment_address])
+    }
+
+    /// Create a parser watching several addresses
+    pub fn with_addresses(payment_addresses: Vec<String>) -> Self {
+        Self { payment_addresses }
     }
 
     /// Parse a block and extract payments to our address
@@ -54,11 +59,25 @@ impl Parser {
     ) -> Result<Option<ReceivedPayment>> {
         // Check each action in the transaction
         for action in &tx.actions {
-            if action.is_our_payment {
-                // This is a payment to our address
-                let payment = self.parse_action(action, tx, block_height)?;
-                return Ok(Some(payment));
+            if !action.is_our_payment {
+                continue;
             }
+
+            // Attribute the payment to the watched address it was sent to
+            let address = match &action.to_address {
+                Some(to) if self.payment_addresses.contains(to) => to.clone(),
+                Some(to) => {
+                    debug!("Skipping payment to unwatched address {}", to);
+                    continue;
+                }
+                None => match self.payment_addresses.first() {
+                    Some(primary) => primary.clone(),
+                    None => continue,
+                },
+            };
+
+            let payment = self.parse_action(action, tx, block_height)?;
+            return Ok(Some(payment.with_address(address)));
         }
 
         Ok(None)
@@ -128,6 +147,32 @@ mod tests {
         assert_eq!(payments.len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_payment_to_second_address_is_tagged() {
+        let addresses = vec!["u1first".to_string(), "u1second".to_string()];
+        let mock_node = MockNode::with_addresses(addresses.clone());
+        let parser = Parser::with_addresses(addresses);
+
+        // Payment blocks alternate between the two addresses
+        for _ in 0..10 {
+            mock_node.advance_chain().await;
+        }
+        let block = mock_node.get_block(100010).await.unwrap().unwrap();
+        let payments = parser.parse_block(&block).unwrap();
+
+        assert_eq!(payments.len(), 1);
+        assert_eq!(payments[0].address.as_deref(), Some("u1second"));
+
+        let block = mock_node.get_block(100000).await.unwrap().unwrap();
+        let payments = parser.parse_block(&block).unwrap();
+        assert_eq!(payments[0].address.as_deref(), Some("u1first"));
+
+        // A parser that only watches the first address ignores the second's payments
+        let first_only = Parser::new("u1first".to_string());
+        let block = mock_node.get_block(100010).await.unwrap().unwrap();
+        assert!(first_only.parse_block(&block).unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_nullifier_parsing() {
         let mock_node = MockNode::new("test_address".to_string());
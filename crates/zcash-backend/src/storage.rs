@@ -5,14 +5,109 @@
 //! - payments:all → Set of all nullifiers
 //! - payments:unused → Set of unused nullifiers
 //! - payments:by_height → Sorted set (score=block_height, member=nullifier)
+//! - reserved:{nullifier_hex} → Reservation token with TTL (two-phase commit)
+//! - payments:reserved → Set of reserved nullifiers
+//! - monitor:last_processed → Last block height the monitor finished (resume point)
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
+use khafi_common::redis_url::connection_info;
 use khafi_common::Nullifier;
 use redis::{aio::ConnectionManager, AsyncCommands};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info, warn};
 
+/// How long a reservation holds a payment before it lapses
+pub const RESERVATION_TTL_SECS: u64 = 300;
+
+/// Atomically mark a reserved payment used if the caller holds the reservation
+///
+/// KEYS: payment hash, reservation key, `payments:unused`, `payments:reserved`
+/// ARGV: nullifier hex, reservation token, `used_at` timestamp
+///
+/// Returns 1 if confirmed, 0 if already used, -1 if the payment doesn't exist,
+/// -2 if it isn't reserved and -3 if another token holds the reservation.
+const CONFIRM_PAYMENT_SCRIPT: &str = r#"
+local used = redis.call('HGET', KEYS[1], 'used')
+if not used then
+    return -1
+end
+if used == 'true' then
+    return 0
+end
+local holder = redis.call('GET', KEYS[2])
+if not holder then
+    return -2
+end
+if holder ~= ARGV[2] then
+    return -3
+end
+redis.call('HSET', KEYS[1], 'used', 'true', 'used_at', ARGV[3])
+redis.call('SREM', KEYS[3], ARGV[1])
+redis.call('DEL', KEYS[2])
+redis.call('SREM', KEYS[4], ARGV[1])
+return 1
+"#;
+
+/// Atomically drop a reservation if the caller holds it
+///
+/// KEYS: reservation key, `payments:reserved`
+/// ARGV: nullifier hex, reservation token
+///
+/// Returns 1 if released, 0 if not reserved and -1 if another token holds it.
+const RELEASE_RESERVATION_SCRIPT: &str = r#"
+local holder = redis.call('GET', KEYS[1])
+if not holder then
+    return 0
+end
+if holder ~= ARGV[2] then
+    return -1
+end
+redis.call('DEL', KEYS[1])
+redis.call('SREM', KEYS[2], ARGV[1])
+return 1
+"#;
+
+/// Result of trying to reserve a payment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReserveOutcome {
+    /// The payment is now reserved for this caller, who must present `token`
+    /// to confirm or release it
+    Reserved { token: String },
+    /// No payment with this nullifier
+    NotFound,
+    /// The payment has already been used
+    AlreadyUsed,
+    /// Another caller holds a reservation
+    AlreadyReserved,
+}
+
+/// Result of trying to confirm a reserved payment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmOutcome {
+    /// The payment is now marked as used
+    Confirmed,
+    /// No payment with this nullifier
+    NotFound,
+    /// The payment has already been used
+    AlreadyUsed,
+    /// The payment isn't reserved (never reserved, released, or expired)
+    NotReserved,
+    /// The reservation belongs to another caller
+    WrongToken,
+}
+
+/// Result of trying to release a reservation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReleaseOutcome {
+    /// The reservation was dropped
+    Released,
+    /// The payment isn't reserved (never reserved, released, or expired)
+    NotReserved,
+    /// The reservation belongs to another caller
+    WrongToken,
+}
+
 /// Represents a received Zcash payment
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReceivedPayment {
@@ -36,6 +131,14 @@ pub struct ReceivedPayment {
 
     /// When the nullifier was marked as used
     pub used_at: Option<DateTime<Utc>>,
+
+    /// Watched address the payment was received on
+    #[serde(default)]
+    pub address: Option<String>,
+
+    /// USD value of the payment at detection time (when a rate source is configured)
+    #[serde(default)]
+    pub usd_value: Option<f64>,
 }
 
 impl ReceivedPayment {
@@ -49,8 +152,16 @@ impl ReceivedPayment {
             timestamp: Utc::now(),
             used: false,
             used_at: None,
+            address: None,
+            usd_value: None,
         }
     }
+
+    /// Attribute the payment to the watched address it was sent to
+    pub fn with_address(mut self, address: impl Into<String>) -> Self {
+        self.address = Some(address.into());
+        self
+    }
 }
 
 /// Payment statistics
@@ -59,6 +170,21 @@ pub struct PaymentStats {
     pub total_payments: usize,
     pub unused_payments: usize,
     pub total_amount: u64,
+    /// Zatoshis in payments already used for API access
+    pub used_amount: u64,
+    /// Zatoshis in payments not yet used (outstanding liability)
+    pub unused_amount: u64,
+    /// Sum of recorded USD values (payments without one are skipped)
+    pub total_usd_value: f64,
+}
+
+/// Result of rebuilding the payment indexes
+#[derive(Debug, Serialize)]
+pub struct ReindexStats {
+    /// Payment hashes found (now in `payments:all` / `payments:by_height`)
+    pub total_payments: usize,
+    /// Payments not yet used (now in `payments:unused`)
+    pub unused_payments: usize,
 }
 
 /// Redis storage client
@@ -68,10 +194,13 @@ pub struct Storage {
 
 impl Storage {
     /// Create a new storage client
-    pub async fn new(redis_url: &str) -> Result<Self> {
-        info!("Connecting to Redis at {}", redis_url);
+    ///
+    /// `redis_db` selects the database index, overriding any `/<db>` in the URL.
+    pub async fn new(redis_url: &str, redis_db: Option<i64>) -> Result<Self> {
+        let info = connection_info(redis_url, redis_db).context("Invalid Redis URL")?;
+        info!("Connecting to Redis at {} (db {})", redis_url, info.redis.db);
 
-        let client = redis::Client::open(redis_url).context("Failed to create Redis client")?;
+        let client = redis::Client::open(info).context("Failed to create Redis client")?;
 
         let conn = ConnectionManager::new(client)
             .await
@@ -97,18 +226,7 @@ impl Storage {
 
         // Store payment as hash
         self.conn
-            .hset_multiple(
-                &payment_key,
-                &[
-                    ("nullifier", nullifier_hex.as_str()),
-                    ("amount", &payment.amount.to_string()),
-                    ("tx_id", &payment.tx_id),
-                    ("block_height", &payment.block_height.to_string()),
-                    ("timestamp", &payment.timestamp.to_rfc3339()),
-                    ("used", "false"),
-                    ("used_at", ""),
-                ],
-            )
+            .hset_multiple(&payment_key, &payment_fields(payment))
             .await?;
 
         // Add to indexes
@@ -172,11 +290,110 @@ impl Storage {
                 .filter(|s| !s.is_empty())
                 .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
                 .map(|dt| dt.with_timezone(&Utc)),
+            address: map.get("address").filter(|s| !s.is_empty()).cloned(),
+            usd_value: map.get("usd_value").and_then(|s| s.parse().ok()),
         };
 
         Ok(Some(payment))
     }
 
+    /// Insert all payments from a block in one round trip
+    ///
+    /// Existing payments (and repeats within the batch) are skipped; the new
+    /// ones are written atomically. Returns one flag per payment, `true` if
+    /// it was inserted.
+    pub async fn insert_payments_batch(
+        &mut self,
+        payments: &[ReceivedPayment],
+    ) -> Result<Vec<bool>> {
+        self.write_payments(payments, None).await
+    }
+
+    /// Record a processed block: store its payments and advance the monitor cursor
+    ///
+    /// Payments and cursor are written in one transaction, so after a crash
+    /// the monitor never resumes past a block whose payments weren't stored.
+    /// Returns one flag per payment, as [`Storage::insert_payments_batch`].
+    pub async fn commit_block(
+        &mut self,
+        height: u32,
+        payments: &[ReceivedPayment],
+    ) -> Result<Vec<bool>> {
+        self.write_payments(payments, Some(height)).await
+    }
+
+    /// Last block height the monitor recorded with [`Storage::commit_block`]
+    pub async fn get_last_processed_height(&mut self) -> Result<Option<u32>> {
+        let height: Option<String> = self.conn.get("monitor:last_processed").await?;
+        Ok(height.and_then(|h| h.parse().ok()))
+    }
+
+    /// Write new payments, and the monitor cursor when given, atomically
+    async fn write_payments(
+        &mut self,
+        payments: &[ReceivedPayment],
+        processed_height: Option<u32>,
+    ) -> Result<Vec<bool>> {
+        if payments.is_empty() && processed_height.is_none() {
+            return Ok(Vec::new());
+        }
+
+        let nullifiers: Vec<String> = payments.iter().map(|p| p.nullifier.to_hex()).collect();
+
+        let exists: Vec<bool> = if nullifiers.is_empty() {
+            Vec::new()
+        } else {
+            let mut check = redis::pipe();
+            for nullifier_hex in &nullifiers {
+                check.exists(format!("payment:{}", nullifier_hex));
+            }
+            check.query_async(&mut self.conn).await?
+        };
+
+        let mut seen = std::collections::HashSet::new();
+        let mut inserted = Vec::with_capacity(payments.len());
+        let mut write = redis::pipe();
+        write.atomic();
+
+        for ((payment, nullifier_hex), exists) in payments.iter().zip(&nullifiers).zip(exists) {
+            let is_new = !exists && seen.insert(nullifier_hex.as_str());
+            inserted.push(is_new);
+            if !is_new {
+                debug!("Payment {} already exists, skipping", nullifier_hex);
+                continue;
+            }
+
+            write
+                .hset_multiple(
+                    format!("payment:{}", nullifier_hex),
+                    &payment_fields(payment),
+                )
+                .ignore()
+                .sadd("payments:all", nullifier_hex)
+                .ignore()
+                .sadd("payments:unused", nullifier_hex)
+                .ignore()
+                .zadd(
+                    "payments:by_height",
+                    nullifier_hex,
+                    payment.block_height as i64,
+                )
+                .ignore();
+        }
+
+        if let Some(height) = processed_height {
+            write
+                .set("monitor:last_processed", height.to_string())
+                .ignore();
+        }
+
+        if processed_height.is_some() || inserted.iter().any(|&new| new) {
+            write.query_async::<_, ()>(&mut self.conn).await?;
+        }
+
+        Ok(inserted)
+    }
+
     /// Check if a payment exists
     pub async fn check_exists(&mut self, nullifier: &Nullifier) -> Result<bool> {
         let nullifier_hex = nullifier.to_hex();
@@ -218,6 +435,172 @@ impl Storage {
         Ok(true)
     }
 
+    /// Reserve an unused payment (two-phase commit - phase 1)
+    ///
+    /// The reservation lapses after [`RESERVATION_TTL_SECS`] unless confirmed
+    /// or released first.
+    pub async fn reserve_payment(&mut self, nullifier: &Nullifier) -> Result<ReserveOutcome> {
+        let nullifier_hex = nullifier.to_hex();
+        let payment_key = format!("payment:{}", nullifier_hex);
+        let reserved_key = format!("reserved:{}", nullifier_hex);
+
+        let used: Option<String> = self.conn.hget(&payment_key, "used").await?;
+        match used.as_deref() {
+            None => return Ok(ReserveOutcome::NotFound),
+            Some("true") => return Ok(ReserveOutcome::AlreadyUsed),
+            _ => {}
+        }
+
+        // SET NX with TTL - atomic reservation, holding the caller's token
+        let token = hex::encode(rand::random::<[u8; 16]>());
+        let set_result: Option<String> = redis::cmd("SET")
+            .arg(&reserved_key)
+            .arg(&token)
+            .arg("NX")
+            .arg("EX")
+            .arg(RESERVATION_TTL_SECS)
+            .query_async(&mut self.conn)
+            .await?;
+        if set_result.is_none() {
+            debug!("Payment {} already reserved", nullifier_hex);
+            return Ok(ReserveOutcome::AlreadyReserved);
+        }
+
+        self.conn
+            .sadd::<_, _, ()>("payments:reserved", &nullifier_hex)
+            .await?;
+
+        info!("Payment reserved: {}", nullifier_hex);
+        Ok(ReserveOutcome::Reserved { token })
+    }
+
+    /// Mark a reserved payment as used (two-phase commit - phase 2)
+    ///
+    /// `token` must be the one [`Storage::reserve_payment`] handed out.
+    pub async fn confirm_payment(
+        &mut self,
+        nullifier: &Nullifier,
+        token: &str,
+    ) -> Result<ConfirmOutcome> {
+        let nullifier_hex = nullifier.to_hex();
+
+        let outcome: i64 = redis::Script::new(CONFIRM_PAYMENT_SCRIPT)
+            .key(format!("payment:{}", nullifier_hex))
+            .key(format!("reserved:{}", nullifier_hex))
+            .key("payments:unused")
+            .key("payments:reserved")
+            .arg(&nullifier_hex)
+            .arg(token)
+            .arg(Utc::now().to_rfc3339())
+            .invoke_async(&mut self.conn)
+            .await?;
+
+        let outcome = match outcome {
+            1 => ConfirmOutcome::Confirmed,
+            0 => ConfirmOutcome::AlreadyUsed,
+            -1 => ConfirmOutcome::NotFound,
+            -2 => ConfirmOutcome::NotReserved,
+            _ => ConfirmOutcome::WrongToken,
+        };
+        if outcome == ConfirmOutcome::Confirmed {
+            info!("Payment confirmed as used: {}", nullifier_hex);
+        }
+        Ok(outcome)
+    }
+
+    /// Release a reservation (on failure), so the payment can be reserved again
+    ///
+    /// `token` must be the one [`Storage::reserve_payment`] handed out.
+    pub async fn release_reservation(
+        &mut self,
+        nullifier: &Nullifier,
+        token: &str,
+    ) -> Result<ReleaseOutcome> {
+        let nullifier_hex = nullifier.to_hex();
+
+        let outcome: i64 = redis::Script::new(RELEASE_RESERVATION_SCRIPT)
+            .key(format!("reserved:{}", nullifier_hex))
+            .key("payments:reserved")
+            .arg(&nullifier_hex)
+            .arg(token)
+            .invoke_async(&mut self.conn)
+            .await?;
+
+        let outcome = match outcome {
+            1 => ReleaseOutcome::Released,
+            0 => ReleaseOutcome::NotReserved,
+            _ => ReleaseOutcome::WrongToken,
+        };
+        if outcome == ReleaseOutcome::Released {
+            info!("Payment reservation released: {}", nullifier_hex);
+        }
+        Ok(outcome)
+    }
+
+    /// Rebuild `payments:all`, `payments:unused` and `payments:by_height`
+    /// from the `payment:*` hashes
+    ///
+    /// Recovery path for indexes that drifted from the source hashes (e.g.
+    /// a crash between writes). The indexes are replaced atomically.
+    pub async fn reindex_payments(&mut self) -> Result<ReindexStats> {
+        let mut nullifiers = Vec::new();
+        {
+            let mut keys = self.conn.scan_match::<_, String>("payment:*").await?;
+            while let Some(key) = keys.next_item().await {
+                if let Some(nullifier_hex) = key.strip_prefix("payment:") {
+                    nullifiers.push(nullifier_hex.to_string());
+                }
+            }
+        }
+
+        let mut read = redis::pipe();
+        for nullifier_hex in &nullifiers {
+            read.hget(
+                format!("payment:{}", nullifier_hex),
+                &["used", "block_height"],
+            );
+        }
+        let fields: Vec<(Option<String>, Option<String>)> = if nullifiers.is_empty() {
+            Vec::new()
+        } else {
+            read.query_async(&mut self.conn).await?
+        };
+
+        let mut write = redis::pipe();
+        write
+            .atomic()
+            .del(&["payments:all", "payments:unused", "payments:by_height"])
+            .ignore();
+
+        let mut unused_payments = 0;
+        for (nullifier_hex, (used, block_height)) in nullifiers.iter().zip(fields) {
+            let block_height: i64 = block_height.and_then(|h| h.parse().ok()).unwrap_or(0);
+
+            write
+                .sadd("payments:all", nullifier_hex)
+                .ignore()
+                .zadd("payments:by_height", nullifier_hex, block_height)
+                .ignore();
+
+            if used.as_deref() != Some("true") {
+                write.sadd("payments:unused", nullifier_hex).ignore();
+                unused_payments += 1;
+            }
+        }
+        write.query_async::<_, ()>(&mut self.conn).await?;
+
+        info!(
+            "Rebuilt payment indexes: {} payments, {} unused",
+            nullifiers.len(),
+            unused_payments
+        );
+
+        Ok(ReindexStats {
+            total_payments: nullifiers.len(),
+            unused_payments,
+        })
+    }
+
     /// Get payment statistics
     pub async fn get_stats(&mut self) -> Result<PaymentStats> {
         let total_payments: usize = self.conn.scard("payments:all").await?;
@@ -226,16 +609,30 @@ impl Storage {
         // Calculate total amount (requires fetching all payments)
         let all_nullifiers: Vec<String> = self.conn.smembers("payments:all").await?;
         let mut total_amount = 0u64;
+        let mut used_amount = 0u64;
+        let mut unused_amount = 0u64;
+        let mut total_usd_value = 0.0;
 
         for nullifier_hex in all_nullifiers {
             let payment_key = format!("payment:{}", nullifier_hex);
-            if let Ok(Some(amount_str)) = self
+            if let Ok((amount, used, usd_value)) = self
                 .conn
-                .hget::<_, _, Option<String>>(&payment_key, "amount")
+                .hget::<_, _, (Option<String>, Option<String>, Option<String>)>(
+                    &payment_key,
+                    &["amount", "used", "usd_value"],
+                )
                 .await
             {
-                if let Ok(amount) = amount_str.parse::<u64>() {
+                if let Some(amount) = amount.and_then(|s| s.parse::<u64>().ok()) {
                     total_amount += amount;
+                    if used.as_deref() == Some("true") {
+                        used_amount += amount;
+                    } else {
+                        unused_amount += amount;
+                    }
+                }
+                if let Some(usd) = usd_value.and_then(|s| s.parse::<f64>().ok()) {
+                    total_usd_value += usd;
                 }
             }
         }
@@ -244,10 +641,17 @@ impl Storage {
             total_payments,
             unused_payments,
             total_amount,
+            used_amount,
+            unused_amount,
+            total_usd_value,
         })
     }
 
-    /// Get the latest block height we've processed
+    /// Height of the most recent stored payment
+    ///
+    /// Blocks without payments don't show up here; the monitor resumes from
+    /// [`Storage::get_last_processed_height`] and only falls back to this for
+    /// data written before the cursor existed.
     pub async fn get_latest_block_height(&mut self) -> Result<Option<u32>> {
         // Get the highest score (block height) from the sorted set
         let result: Vec<(String, i64)> = self
@@ -263,9 +667,16 @@ impl Storage {
     }
 
     /// Set the current blockchain height (for confirmation counting)
+    ///
+    /// Also records when the height was observed (`chain:block_height_updated_at`,
+    /// unix seconds) so readers can detect a stalled backend.
     pub async fn set_block_height(&mut self, height: u32) -> Result<()> {
+        let updated_at = Utc::now().timestamp();
         self.conn
-            .set::<_, _, ()>("chain:block_height", height)
+            .mset::<_, _, ()>(&[
+                ("chain:block_height", height.to_string()),
+                ("chain:block_height_updated_at", updated_at.to_string()),
+            ])
             .await
             .context("Failed to set block height")?;
         debug!("Updated chain block height to {}", height);
@@ -288,6 +699,24 @@ impl Storage {
     }
 }
 
+/// Hash fields for a newly stored payment
+fn payment_fields(payment: &ReceivedPayment) -> Vec<(&'static str, String)> {
+    vec![
+        ("nullifier", payment.nullifier.to_hex()),
+        ("amount", payment.amount.to_string()),
+        ("tx_id", payment.tx_id.clone()),
+        ("block_height", payment.block_height.to_string()),
+        ("timestamp", payment.timestamp.to_rfc3339()),
+        ("used", "false".to_string()),
+        ("used_at", String::new()),
+        ("address", payment.address.clone().unwrap_or_default()),
+        (
+            "usd_value",
+            payment.usd_value.map(|v| v.to_string()).unwrap_or_default(),
+        ),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -298,7 +727,7 @@ mod tests {
     #[tokio::test]
     #[ignore] // Only run when Redis is available
     async fn test_insert_and_get_payment() {
-        let mut storage = Storage::new("redis://localhost:6379")
+        let mut storage = Storage::new("redis://localhost:6379", None)
             .await
             .expect("Failed to connect to Redis");
 
@@ -324,10 +753,45 @@ mod tests {
         assert!(!retrieved.used);
     }
 
+    #[tokio::test]
+    #[ignore] // Only run when Redis is available
+    async fn test_insert_payments_batch_skips_duplicates() {
+        let mut storage = Storage::new("redis://localhost:6379", None)
+            .await
+            .expect("Failed to connect to Redis");
+
+        let payments: Vec<ReceivedPayment> = [3u8, 4, 5]
+            .iter()
+            .map(|&b| ReceivedPayment::new(Nullifier::new([b; 32]), 1000, format!("tx_{}", b), 200))
+            .collect();
+        for payment in &payments {
+            let key = format!("payment:{}", payment.nullifier.to_hex());
+            storage.conn.del::<_, ()>(&key).await.unwrap();
+        }
+
+        // The first payment is already known
+        assert!(storage.insert_payment(&payments[0]).await.unwrap());
+
+        let inserted = storage.insert_payments_batch(&payments).await.unwrap();
+        assert_eq!(inserted, vec![false, true, true]);
+
+        let stored = storage
+            .get_payment(&payments[2].nullifier)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.tx_id, "tx_5");
+        assert!(!stored.used);
+
+        // Re-inserting the same batch adds nothing
+        let inserted = storage.insert_payments_batch(&payments).await.unwrap();
+        assert_eq!(inserted, vec![false, false, false]);
+    }
+
     #[tokio::test]
     #[ignore]
     async fn test_mark_used() {
-        let mut storage = Storage::new("redis://localhost:6379")
+        let mut storage = Storage::new("redis://localhost:6379", None)
             .await
             .expect("Failed to connect to Redis");
 
@@ -350,4 +814,99 @@ mod tests {
         let marked_again = storage.mark_used(&nullifier).await.unwrap();
         assert!(!marked_again);
     }
+
+    #[tokio::test]
+    #[ignore] // Only run when Redis is available
+    async fn test_stats_split_used_and_unused_amounts() {
+        let mut storage = Storage::new("redis://localhost:6379", None)
+            .await
+            .expect("Failed to connect to Redis");
+
+        let nullifier = Nullifier::new([8u8; 32]);
+        let key = format!("payment:{}", nullifier.to_hex());
+        storage.conn.del::<_, ()>(&key).await.unwrap();
+
+        let before = storage.get_stats().await.unwrap();
+        let payment = ReceivedPayment::new(nullifier.clone(), 2500, "tx_stats".into(), 400);
+        storage.insert_payment(&payment).await.unwrap();
+
+        let inserted = storage.get_stats().await.unwrap();
+        assert_eq!(inserted.unused_amount, before.unused_amount + 2500);
+        assert_eq!(inserted.used_amount, before.used_amount);
+
+        // Using the payment moves its amount from unused to used
+        storage.mark_used(&nullifier).await.unwrap();
+        let used = storage.get_stats().await.unwrap();
+        assert_eq!(used.unused_amount, before.unused_amount);
+        assert_eq!(used.used_amount, before.used_amount + 2500);
+
+        // Marking it again changes nothing
+        storage.mark_used(&nullifier).await.unwrap();
+        let again = storage.get_stats().await.unwrap();
+        assert_eq!(again.used_amount, used.used_amount);
+    }
+
+    #[tokio::test]
+    #[ignore] // Only run when Redis is available
+    async fn test_reindex_restores_corrupted_indexes() {
+        let mut storage = Storage::new("redis://localhost:6379", None)
+            .await
+            .expect("Failed to connect to Redis");
+
+        let unused = Nullifier::new([6u8; 32]);
+        let used = Nullifier::new([7u8; 32]);
+        for (nullifier, height) in [(&unused, 300), (&used, 301)] {
+            let key = format!("payment:{}", nullifier.to_hex());
+            storage.conn.del::<_, ()>(&key).await.unwrap();
+            let payment =
+                ReceivedPayment::new(nullifier.clone(), 1000, "tx_reindex".into(), height);
+            storage.insert_payment(&payment).await.unwrap();
+        }
+        storage.mark_used(&used).await.unwrap();
+
+        // Simulate a crash mid-write: indexes lose one payment and gain a stale entry
+        storage
+            .conn
+            .srem::<_, _, ()>("payments:all", unused.to_hex())
+            .await
+            .unwrap();
+        storage
+            .conn
+            .zrem::<_, _, ()>("payments:by_height", unused.to_hex())
+            .await
+            .unwrap();
+        storage
+            .conn
+            .sadd::<_, _, ()>("payments:unused", used.to_hex())
+            .await
+            .unwrap();
+
+        let stats = storage.reindex_payments().await.unwrap();
+        assert!(stats.total_payments >= 2);
+
+        let in_all: bool = storage
+            .conn
+            .sismember("payments:all", unused.to_hex())
+            .await
+            .unwrap();
+        assert!(in_all);
+        let height: Option<i64> = storage
+            .conn
+            .zscore("payments:by_height", unused.to_hex())
+            .await
+            .unwrap();
+        assert_eq!(height, Some(300));
+        let used_listed_unused: bool = storage
+            .conn
+            .sismember("payments:unused", used.to_hex())
+            .await
+            .unwrap();
+        assert!(!used_listed_unused);
+        let unused_listed: bool = storage
+            .conn
+            .sismember("payments:unused", unused.to_hex())
+            .await
+            .unwrap();
+        assert!(unused_listed);
+    }
 }
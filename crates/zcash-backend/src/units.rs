@@ -0,0 +1,74 @@
+//! Payment amount units
+//!
+//! Amounts are stored in zatoshis; ZEC values are only produced for display.
+
+use serde::Deserialize;
+
+/// Zatoshis in one ZEC
+pub const ZATOSHIS_PER_ZEC: u64 = 100_000_000;
+
+/// Convert a zatoshi amount to ZEC
+///
+/// Exact to the zatoshi for any amount up to the 21M ZEC supply: the result
+/// is the closest `f64` to the decimal value, so formatting it with 8
+/// decimal places gives back the original zatoshis.
+pub fn zatoshis_to_zec(zatoshis: u64) -> f64 {
+    zatoshis as f64 / ZATOSHIS_PER_ZEC as f64
+}
+
+/// Units amounts are reported in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AmountUnits {
+    /// Decimal ZEC
+    #[default]
+    Zec,
+    /// Integer zatoshis, exactly as stored
+    Zatoshis,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zatoshis_to_zec() {
+        assert_eq!(zatoshis_to_zec(0), 0.0);
+        assert_eq!(zatoshis_to_zec(ZATOSHIS_PER_ZEC), 1.0);
+        assert_eq!(zatoshis_to_zec(150_000_000), 1.5);
+        assert_eq!(zatoshis_to_zec(21_000_000 * ZATOSHIS_PER_ZEC), 21_000_000.0);
+    }
+
+    #[test]
+    fn test_zatoshis_to_zec_rounding() {
+        // A single zatoshi either side of a whole ZEC stays distinguishable
+        assert_eq!(zatoshis_to_zec(1), 0.00000001);
+        assert_eq!(zatoshis_to_zec(99_999_999), 0.99999999);
+        assert_eq!(zatoshis_to_zec(100_000_001), 1.00000001);
+        assert!(zatoshis_to_zec(100_000_001) > zatoshis_to_zec(ZATOSHIS_PER_ZEC));
+        assert_eq!(format!("{:.8}", zatoshis_to_zec(1)), "0.00000001");
+        assert_eq!(format!("{:.8}", zatoshis_to_zec(99_999_999)), "0.99999999");
+
+        // Converting back loses nothing up to the full supply
+        for zatoshis in [
+            1,
+            12_345_678,
+            123_456_789,
+            1_234_567_890_123_456,
+            21_000_000 * ZATOSHIS_PER_ZEC - 1,
+        ] {
+            let zec = zatoshis_to_zec(zatoshis);
+            assert_eq!((zec * ZATOSHIS_PER_ZEC as f64).round() as u64, zatoshis);
+        }
+    }
+
+    #[test]
+    fn test_amount_units_from_query() {
+        let units: AmountUnits = serde_json::from_str("\"zatoshis\"").unwrap();
+        assert_eq!(units, AmountUnits::Zatoshis);
+        let units: AmountUnits = serde_json::from_str("\"zec\"").unwrap();
+        assert_eq!(units, AmountUnits::Zec);
+        assert!(serde_json::from_str::<AmountUnits>("\"btc\"").is_err());
+        assert_eq!(AmountUnits::default(), AmountUnits::Zec);
+    }
+}
@@ -6,6 +6,7 @@
 //! - Redis running on localhost:6379
 //! - Run with: cargo test --package zcash-backend -- --ignored
 
+use khafi_admin::AdminConfig;
 use khafi_common::Nullifier;
 use zcash_backend::{config::Config, monitor::Monitor, storage::Storage};
 
@@ -22,7 +23,7 @@ async fn test_monitor_initialization() {
     let config = Config::from_env().expect("Failed to load config");
 
     // Create storage to verify Redis connection
-    let mut storage = Storage::new(&config.redis_url)
+    let mut storage = Storage::new(&config.redis_url, config.redis_db)
         .await
         .expect("Failed to connect to Redis");
 
@@ -48,7 +49,7 @@ async fn test_monitor_initialization() {
 async fn test_storage_operations() {
     use zcash_backend::storage::ReceivedPayment;
 
-    let mut storage = Storage::new("redis://localhost:6379")
+    let mut storage = Storage::new("redis://localhost:6379", None)
         .await
         .expect("Failed to connect to Redis");
 
@@ -98,3 +99,427 @@ async fn test_storage_operations() {
     let stats = storage.get_stats().await.expect("Failed to get stats");
     assert!(stats.total_payments > 0);
 }
+
+/// Bearer token the admin endpoints accept in these tests
+const ADMIN_TOKEN: &str = "test-admin-token";
+
+fn admin_config() -> AdminConfig {
+    AdminConfig {
+        token: Some(ADMIN_TOKEN.to_string()),
+        ..AdminConfig::default()
+    }
+}
+
+/// Bearer token the payment reservation endpoints accept in these tests
+const PAYMENT_API_TOKEN: &str = "test-payment-token";
+
+/// Router with admin and payment API tokens configured
+fn test_router(storage: Storage) -> axum::Router {
+    zcash_backend::api::create_router(
+        storage,
+        None,
+        admin_config(),
+        Some(PAYMENT_API_TOKEN.to_string()),
+    )
+}
+
+/// POST to a payment reservation endpoint and return the status and JSON body
+///
+/// `reservation_token` is sent as the confirm/release request body.
+async fn post_reservation(
+    app: &axum::Router,
+    nullifier: &Nullifier,
+    action: &str,
+    reservation_token: Option<&str>,
+) -> (axum::http::StatusCode, serde_json::Value) {
+    let body = match reservation_token {
+        Some(token) => serde_json::json!({ "reservation_token": token }).to_string(),
+        None => String::new(),
+    };
+    let request = axum::http::Request::builder()
+        .method("POST")
+        .uri(format!("/payment/{}/{}", nullifier.to_hex(), action))
+        .header("authorization", format!("Bearer {}", PAYMENT_API_TOKEN))
+        .header("content-type", "application/json")
+        .body(axum::body::Body::from(body))
+        .unwrap();
+
+    request_json(app, request).await
+}
+
+/// Reserve a payment and return its reservation token
+async fn reserve(app: &axum::Router, nullifier: &Nullifier) -> String {
+    let (status, body) = post_reservation(app, nullifier, "reserve", None).await;
+    assert_eq!(status, axum::http::StatusCode::OK, "{}", body);
+    body["data"]["reservation_token"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+/// Insert a fresh unused payment for the reservation tests
+async fn fresh_payment(storage: &mut Storage, byte: u8) -> Nullifier {
+    use zcash_backend::storage::ReceivedPayment;
+
+    // Unique per run so repeated runs don't collide with earlier payments
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let mut bytes = [byte; 32];
+    bytes[..16].copy_from_slice(&nanos.to_le_bytes());
+    let nullifier = Nullifier::new(bytes);
+
+    let payment = ReceivedPayment::new(nullifier.clone(), 1_000_000, "tx_reserve".into(), 300);
+    assert!(storage.insert_payment(&payment).await.unwrap());
+    nullifier
+}
+
+#[tokio::test]
+#[ignore] // Requires Redis to be running
+async fn test_reserve_then_confirm_via_api() {
+    use axum::http::StatusCode;
+
+    let mut storage = Storage::new("redis://localhost:6379", None).await.unwrap();
+    let nullifier = fresh_payment(&mut storage, 0xa1).await;
+    let app = test_router(storage);
+
+    // Confirming requires a reservation
+    let (status, _) = post_reservation(&app, &nullifier, "confirm", Some("none")).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let token = reserve(&app, &nullifier).await;
+    let (status, _) = post_reservation(&app, &nullifier, "reserve", None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // Only the reservation's holder can confirm it
+    let (status, _) = post_reservation(&app, &nullifier, "confirm", Some("guess")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = post_reservation(&app, &nullifier, "confirm", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = post_reservation(&app, &nullifier, "confirm", Some(&token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "confirmed");
+
+    // Once used, the payment can't be reserved again
+    let (status, _) = post_reservation(&app, &nullifier, "reserve", None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let mut storage = Storage::new("redis://localhost:6379", None).await.unwrap();
+    let payment = storage.get_payment(&nullifier).await.unwrap().unwrap();
+    assert!(payment.used);
+}
+
+#[tokio::test]
+#[ignore] // Requires Redis to be running
+async fn test_stats_follow_payments_confirmed_by_verifier() {
+    use zk_verification_service::payment::{PaymentChecker, PaymentConfig};
+
+    let mut storage = Storage::new("redis://localhost:6379", None).await.unwrap();
+    let nullifier = fresh_payment(&mut storage, 0xa3).await;
+    let before = storage.get_stats().await.unwrap();
+
+    // The verification service confirms payments straight against Redis,
+    // bypassing this backend's API
+    let checker =
+        PaymentChecker::new("redis://localhost:6379", None, PaymentConfig::default()).unwrap();
+    checker.reserve_payment(&nullifier).await.unwrap();
+    assert!(checker.confirm_payment(&nullifier).await.unwrap());
+
+    let after = storage.get_stats().await.unwrap();
+    assert_eq!(after.used_amount, before.used_amount + 1_000_000);
+    assert_eq!(after.unused_amount, before.unused_amount - 1_000_000);
+    assert_eq!(after.total_amount, before.total_amount);
+}
+
+#[tokio::test]
+#[ignore] // Requires Redis to be running
+async fn test_reserve_then_release_via_api() {
+    use axum::http::StatusCode;
+
+    let mut storage = Storage::new("redis://localhost:6379", None).await.unwrap();
+    let nullifier = fresh_payment(&mut storage, 0xa2).await;
+    let app = test_router(storage);
+
+    let token = reserve(&app, &nullifier).await;
+    // Another caller can't release someone else's reservation
+    let (status, _) = post_reservation(&app, &nullifier, "release", Some("guess")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, body) = post_reservation(&app, &nullifier, "release", Some(&token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "released");
+
+    // Released payments can be reserved again and stay unused
+    let token = reserve(&app, &nullifier).await;
+    let (status, _) = post_reservation(&app, &nullifier, "release", Some(&token)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, body) = post_reservation(&app, &nullifier, "release", Some(&token)).await;
+    assert_eq!(body["data"]["status"], "not_reserved");
+
+    let mut storage = Storage::new("redis://localhost:6379", None).await.unwrap();
+    let payment = storage.get_payment(&nullifier).await.unwrap().unwrap();
+    assert!(!payment.used);
+
+    // Unknown payments can't be reserved
+    let unknown = Nullifier::new([0xa3; 32]);
+    let app = test_router(storage);
+    let (status, _) = post_reservation(&app, &unknown, "reserve", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[ignore] // Requires Redis to be running
+async fn test_reservation_endpoints_require_token() {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+
+    let mut storage = Storage::new("redis://localhost:6379", None).await.unwrap();
+    let nullifier = fresh_payment(&mut storage, 0xa4).await;
+    let uri = format!("/payment/{}/reserve", nullifier.to_hex());
+    let reserve = |token: Option<&str>| {
+        let mut request = Request::post(&uri);
+        if let Some(token) = token {
+            request = request.header("authorization", format!("Bearer {}", token));
+        }
+        request.body(Body::empty()).unwrap()
+    };
+
+    let app = test_router(storage);
+    let (status, body) = request_json(&app, reserve(None)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"], "Missing or invalid payment API token");
+    let (status, _) = request_json(&app, reserve(Some(ADMIN_TOKEN))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Without a configured token the endpoints are off entirely
+    let storage = Storage::new("redis://localhost:6379", None).await.unwrap();
+    let app = zcash_backend::api::create_router(storage, None, admin_config(), None);
+    let (status, body) = request_json(&app, reserve(Some(PAYMENT_API_TOKEN))).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(body["error"]
+        .as_str()
+        .unwrap()
+        .contains("PAYMENT_API_TOKEN"));
+
+    // Reading payment status stays public
+    let (status, _) = request_json(
+        &app,
+        Request::get(format!("/payment/{}", nullifier.to_hex()))
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+#[ignore] // Requires Redis to be running
+async fn test_admin_poll_advances_processed_height() {
+    use axum::http::StatusCode;
+    use tower::ServiceExt;
+    use zcash_backend::storage::ReceivedPayment;
+
+    // Dedicated database so the starting height is known
+    let mut config = Config::from_env().expect("Failed to load config");
+    config.mock_mode = true;
+    config.polling_interval_secs = 3600;
+    config.redis_db = Some(14);
+
+    let client = redis::Client::open(config.redis_url.as_str()).unwrap();
+    let mut conn = client.get_multiplexed_async_connection().await.unwrap();
+    redis::cmd("SELECT")
+        .arg(14)
+        .query_async::<_, ()>(&mut conn)
+        .await
+        .unwrap();
+    redis::cmd("FLUSHDB")
+        .query_async::<_, ()>(&mut conn)
+        .await
+        .unwrap();
+
+    // Resume just below the mock node's starting height
+    let mut storage = Storage::new(&config.redis_url, config.redis_db)
+        .await
+        .unwrap();
+    let payment = ReceivedPayment::new(
+        Nullifier::new([0xb1; 32]),
+        1_000_000,
+        "tx_poll".into(),
+        99_999,
+    );
+    assert!(storage.insert_payment(&payment).await.unwrap());
+
+    let monitor = Monitor::new(config).await.unwrap();
+    let app =
+        zcash_backend::api::create_router(storage, Some(monitor.handle()), admin_config(), None);
+    tokio::spawn(monitor.start());
+
+    let poll = || async {
+        let request = axum::http::Request::builder()
+            .method("POST")
+            .uri("/admin/poll")
+            .header("authorization", format!("Bearer {}", ADMIN_TOKEN))
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+    };
+
+    let first = poll().await;
+    let second = poll().await;
+
+    assert!(second["data"]["blocks_processed"].as_u64().unwrap() >= 1);
+    assert!(
+        second["data"]["processed_height"].as_u64().unwrap()
+            > first["data"]["processed_height"].as_u64().unwrap()
+    );
+
+    // Caught up, so /ready reports the height the last poll reached
+    let (status, ready) = request_json(
+        &app,
+        axum::http::Request::get("/ready")
+            .body(axum::body::Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ready["data"]["status"], "ready");
+    assert_eq!(ready["data"]["sync"]["catching_up"], false);
+    assert_eq!(
+        ready["data"]["sync"]["processed_height"],
+        second["data"]["processed_height"]
+    );
+}
+
+/// Send a request and return the status and JSON body
+async fn request_json(
+    app: &axum::Router,
+    request: axum::http::Request<axum::body::Body>,
+) -> (axum::http::StatusCode, serde_json::Value) {
+    use tower::ServiceExt;
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/json",
+        "{} response is not JSON",
+        status
+    );
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+#[ignore] // Requires Redis to be running
+async fn test_responses_use_json_envelope() {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+
+    let mut storage = Storage::new("redis://localhost:6379", None).await.unwrap();
+    let nullifier = fresh_payment(&mut storage, 0xc1).await;
+    let app = test_router(storage);
+
+    let (status, body) =
+        request_json(&app, Request::get("/health").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "healthy");
+
+    let (status, body) = request_json(
+        &app,
+        Request::get(format!("/payment/{}", nullifier.to_hex()))
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["exists"], true);
+
+    // Error paths carry only an error message
+    let (status, body) = request_json(
+        &app,
+        Request::get("/payment/not-hex")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("Invalid nullifier"));
+    assert!(body.get("data").is_none());
+
+    let insert = |payload: String| {
+        Request::post("/admin/payment")
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {}", ADMIN_TOKEN))
+            .body(Body::from(payload))
+            .unwrap()
+    };
+    let duplicate = serde_json::json!({
+        "nullifier_hex": nullifier.to_hex(),
+        "amount": 1_000_000,
+        "tx_id": "tx_envelope",
+        "block_height": 300,
+    });
+    let (status, body) = request_json(&app, insert(duplicate.to_string())).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["error"], "Payment already exists");
+
+    let (status, body) = request_json(&app, insert("{".to_string())).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].is_string());
+}
+
+#[tokio::test]
+#[ignore] // Requires Redis to be running
+async fn test_admin_endpoints_require_token() {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+
+    let storage = Storage::new("redis://localhost:6379", None).await.unwrap();
+    let app = zcash_backend::api::create_router(
+        storage,
+        None,
+        AdminConfig {
+            token: Some(ADMIN_TOKEN.to_string()),
+            rate_limit_per_minute: 3,
+        },
+        None,
+    );
+
+    let reindex = |token: Option<&str>| {
+        let mut request = Request::post("/admin/reindex");
+        if let Some(token) = token {
+            request = request.header("authorization", format!("Bearer {}", token));
+        }
+        request.body(Body::empty()).unwrap()
+    };
+
+    let (status, body) = request_json(&app, reindex(None)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"], "Missing or invalid admin token");
+
+    let (status, _) = request_json(&app, reindex(Some("wrong"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, body) = request_json(&app, reindex(Some(ADMIN_TOKEN))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["data"]["total_payments"].is_number());
+
+    // The three requests above used up the quota
+    let (status, _) = request_json(&app, reindex(Some(ADMIN_TOKEN))).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    // Public endpoints are neither authenticated nor limited
+    let (status, _) =
+        request_json(&app, Request::get("/health").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::OK);
+}
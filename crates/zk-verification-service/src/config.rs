@@ -1,7 +1,36 @@
 //! Configuration management for ZK Verification Service
 
 use crate::payment::PaymentConfig;
+use chrono::{DateTime, Duration, Utc};
+use khafi_common::redis_url::connection_info;
+use khafi_common::{Error, JournalEncoding, Nullifier, Result};
 use methods::GUEST_ID;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// Default gRPC port (Envoy's ext_authz cluster points here)
+const DEFAULT_GRPC_PORT: u16 = 50051;
+
+/// Default header carrying the hex-encoded receipt
+const DEFAULT_RECEIPT_HEADER: &str = "x-zk-receipt";
+
+/// Default header naming the receipt's encoding (`hex` or `base64`)
+const DEFAULT_RECEIPT_ENCODING_HEADER: &str = "x-zk-receipt-encoding";
+
+/// Default header naming the receipt's proof system (optional hint)
+const DEFAULT_RECEIPT_KIND_HEADER: &str = "x-zk-receipt-kind";
+
+/// Default header carrying the hex-encoded nullifier
+const DEFAULT_NULLIFIER_HEADER: &str = "x-zk-nullifier";
+
+/// Default maximum receipt size (16 MiB)
+const DEFAULT_MAX_RECEIPT_BYTES: usize = 16 * 1024 * 1024;
+
+/// Default time a retired image ID keeps verifying (24 hours)
+const DEFAULT_IMAGE_ID_GRACE_SECS: i64 = 24 * 60 * 60;
+
+/// Hard upper bound on bytes bincode may read while decoding a receipt (64 MiB)
+pub const RECEIPT_DECODE_LIMIT: usize = 64 * 1024 * 1024;
 
 /// Service configuration
 #[derive(Clone)]
@@ -9,32 +38,278 @@ pub struct Config {
     /// Redis URL for nullifier storage
     pub redis_url: String,
 
+    /// Redis database index, overriding the one in `redis_url` (optional)
+    pub redis_db: Option<i64>,
+
     /// Expected Image ID for proof verification
     pub image_id: [u8; 32],
 
+    /// Image IDs of previous guest programs, still accepted while a rotation settles
+    pub retired_image_ids: Vec<RetiredImageId>,
+
+    /// How long a retired image ID keeps verifying after it was retired
+    pub image_id_grace_period: Duration,
+
     /// Payment verification configuration
     pub payment: PaymentConfig,
+
+    /// gRPC server bind host
+    pub grpc_host: String,
+
+    /// gRPC server port
+    pub grpc_port: u16,
+
+    /// HTTP/JSON gateway port (gateway disabled when unset)
+    pub http_port: Option<u16>,
+
+    /// Request header carrying the receipt (lowercase, as Envoy forwards it)
+    pub receipt_header: String,
+
+    /// Request header naming the receipt's encoding (hex when absent)
+    pub receipt_encoding_header: String,
+
+    /// Request header naming the receipt's kind (not checked when absent)
+    pub receipt_kind_header: String,
+
+    /// Request header carrying the nullifier (lowercase, as Envoy forwards it)
+    pub nullifier_header: String,
+
+    /// Maximum accepted receipt size in bytes (before hex/base64 encoding)
+    pub max_receipt_bytes: usize,
+
+    /// Scope replay protection to this service's image ID instead of globally
+    pub nullifier_per_image: bool,
+
+    /// Encoding of `GuestOutputs` in receipt journals
+    pub journal_encoding: JournalEncoding,
+
+    /// Reject journals that aren't exactly one encoded `GuestOutputs`
+    pub strict_journal: bool,
+
+    /// Nullifiers allowed to be replayed (staging/QA only)
+    pub replay_bypass: HashSet<Nullifier>,
+
+    /// Skip replay protection for every nullifier (staging/QA only, never in prod)
+    pub disable_replay_protection: bool,
 }
 
 impl Config {
     /// Load configuration from environment variables
-    pub fn from_env() -> Self {
+    ///
+    /// Fails if `REDIS_DB` is set but isn't a database index.
+    pub fn from_env() -> Result<Self> {
         // Get Redis URL from environment or use default
         let redis_url =
             std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string());
+        let redis_db = parse_redis_db(std::env::var("REDIS_DB").ok())?;
 
         // Convert GUEST_ID from [u32; 8] to [u8; 32]
         let image_id = image_id_to_bytes(&GUEST_ID);
 
+        // Rotation: "hex@rfc3339" entries for image IDs retired at that time
+        let retired_image_ids = std::env::var("RETIRED_IMAGE_IDS")
+            .map(|v| parse_retired_image_ids(&v))
+            .unwrap_or_default();
+        let image_id_grace_period = Duration::seconds(
+            std::env::var("IMAGE_ID_GRACE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_IMAGE_ID_GRACE_SECS),
+        );
+
         // Load payment config from environment
         let payment = PaymentConfig::from_env();
 
-        Self {
+        // gRPC bind address
+        let grpc_host = std::env::var("GRPC_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
+        let grpc_port = std::env::var("GRPC_PORT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_GRPC_PORT);
+        let http_port = std::env::var("HTTP_PORT").ok().and_then(|v| v.parse().ok());
+
+        // Header names, for proxies that rename the defaults
+        let receipt_header = header_name("RECEIPT_HEADER", DEFAULT_RECEIPT_HEADER);
+        let receipt_encoding_header =
+            header_name("RECEIPT_ENCODING_HEADER", DEFAULT_RECEIPT_ENCODING_HEADER);
+        let receipt_kind_header = header_name("RECEIPT_KIND_HEADER", DEFAULT_RECEIPT_KIND_HEADER);
+        let nullifier_header = header_name("NULLIFIER_HEADER", DEFAULT_NULLIFIER_HEADER);
+
+        // Receipt size limit, capped by the hard bincode decode limit
+        let max_receipt_bytes = std::env::var("MAX_RECEIPT_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_RECEIPT_BYTES)
+            .min(RECEIPT_DECODE_LIMIT);
+
+        // Replay scope: "global" (default) or "image"
+        let nullifier_per_image = std::env::var("NULLIFIER_SCOPE")
+            .map(|v| v.eq_ignore_ascii_case("image"))
+            .unwrap_or(false);
+
+        // Journal encoding: "auto" (default), "bincode" or "json"
+        let journal_encoding = std::env::var("JOURNAL_ENCODING")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default();
+        let strict_journal = std::env::var("STRICT_JOURNAL_DECODE")
+            .map(|v| v.to_lowercase() == "true" || v == "1")
+            .unwrap_or(false);
+
+        // Replay bypass for QA: comma-separated hex nullifiers, or a global switch
+        let replay_bypass = std::env::var("REPLAY_BYPASS_NULLIFIERS")
+            .map(|v| parse_nullifier_list(&v))
+            .unwrap_or_default();
+        let disable_replay_protection = std::env::var("DISABLE_REPLAY_PROTECTION")
+            .map(|v| v.to_lowercase() == "true" || v == "1")
+            .unwrap_or(false);
+
+        Ok(Self {
             redis_url,
+            redis_db,
             image_id,
+            retired_image_ids,
+            image_id_grace_period,
             payment,
+            grpc_host,
+            grpc_port,
+            http_port,
+            receipt_header,
+            receipt_encoding_header,
+            receipt_kind_header,
+            nullifier_header,
+            max_receipt_bytes,
+            nullifier_per_image,
+            journal_encoding,
+            strict_journal,
+            replay_bypass,
+            disable_replay_protection,
+        })
+    }
+
+    /// Whether `nullifier` skips the replay check
+    pub fn replay_bypassed(&self, nullifier: &Nullifier) -> bool {
+        self.disable_replay_protection || self.replay_bypass.contains(nullifier)
+    }
+
+    /// Image ID to verify a receipt claiming `image_id` against, at `now`
+    ///
+    /// A retired image ID is accepted until its grace period ends; any other
+    /// ID is held to the current one. Returns `None` for an expired retired ID.
+    pub fn accepted_image_id(&self, image_id: &[u8; 32], now: DateTime<Utc>) -> Option<[u8; 32]> {
+        match self
+            .retired_image_ids
+            .iter()
+            .find(|retired| &retired.image_id == image_id)
+        {
+            Some(retired) if now < retired.retired_at + self.image_id_grace_period => {
+                Some(retired.image_id)
+            }
+            Some(_) => None,
+            None => Some(self.image_id),
         }
     }
+
+    /// Namespace for replay nullifiers (the hex image ID when scoped per image)
+    pub fn nullifier_namespace(&self) -> Option<String> {
+        self.nullifier_per_image.then(|| hex::encode(self.image_id))
+    }
+
+    /// Get the gRPC server address
+    pub fn grpc_address(&self) -> String {
+        format!("{}:{}", self.grpc_host, self.grpc_port)
+    }
+
+    /// Get the HTTP gateway address, if the gateway is enabled
+    pub fn http_address(&self) -> Option<String> {
+        self.http_port
+            .map(|port| format!("{}:{}", self.grpc_host, port))
+    }
+}
+
+/// Open a Redis client, overriding the URL's database index when `redis_db` is set
+pub fn open_redis(redis_url: &str, redis_db: Option<i64>) -> Result<redis::Client> {
+    let info = connection_info(redis_url, redis_db).map_err(|e| Error::Redis(e.to_string()))?;
+    redis::Client::open(info).map_err(|e| Error::Redis(e.to_string()))
+}
+
+/// Read a header name from `var`, lowercased to match Envoy's forwarded headers
+fn header_name(var: &str, default: &str) -> String {
+    std::env::var(var)
+        .ok()
+        .map(|v| v.trim().to_ascii_lowercase())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| default.to_string())
+}
+
+/// Parse `REDIS_DB`, rejecting values that aren't a database index
+fn parse_redis_db(value: Option<String>) -> Result<Option<i64>> {
+    value
+        .map(|v| v.trim().parse())
+        .transpose()
+        .map_err(|e| Error::Other(anyhow::anyhow!("Invalid REDIS_DB: {}", e)))
+}
+
+/// Parse a comma-separated list of hex nullifiers, skipping invalid entries
+fn parse_nullifier_list(list: &str) -> HashSet<Nullifier> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| match Nullifier::from_hex(s) {
+            Ok(nullifier) => Some(nullifier),
+            Err(e) => {
+                tracing::warn!("Ignoring invalid replay bypass nullifier {}: {}", s, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Parse a comma-separated list of `hex@rfc3339` retired image IDs, skipping invalid entries
+fn parse_retired_image_ids(list: &str) -> Vec<RetiredImageId> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| match RetiredImageId::from_str(s) {
+            Ok(retired) => Some(retired),
+            Err(e) => {
+                tracing::warn!("Ignoring invalid retired image ID {}: {}", s, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// An image ID from a previous guest program and when it was replaced
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetiredImageId {
+    /// Image ID of the retired guest program
+    pub image_id: [u8; 32],
+
+    /// When the replacement was deployed
+    pub retired_at: DateTime<Utc>,
+}
+
+impl FromStr for RetiredImageId {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, String> {
+        let (image_id, retired_at) = s
+            .split_once('@')
+            .ok_or_else(|| "expected <image_id>@<rfc3339 time>".to_string())?;
+        let image_id = hex::decode(image_id)
+            .map_err(|e| e.to_string())?
+            .try_into()
+            .map_err(|_| "image ID must be 32 bytes".to_string())?;
+        let retired_at = DateTime::parse_from_rfc3339(retired_at)
+            .map_err(|e| e.to_string())?
+            .with_timezone(&Utc);
+
+        Ok(Self {
+            image_id,
+            retired_at,
+        })
+    }
 }
 
 /// Convert RISC Zero Image ID format ([u32; 8]) to bytes ([u8; 32])
@@ -54,10 +329,169 @@ mod tests {
     #[test]
     fn test_config_from_env() {
         // Should not panic
-        let config = Config::from_env();
+        let config = Config::from_env().unwrap();
         assert_eq!(config.image_id.len(), 32);
     }
 
+    #[test]
+    fn test_config_grpc_address() {
+        std::env::set_var("GRPC_HOST", "127.0.0.1");
+        std::env::set_var("GRPC_PORT", "50052");
+
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.grpc_host, "127.0.0.1");
+        assert_eq!(config.grpc_port, 50052);
+        assert_eq!(config.grpc_address(), "127.0.0.1:50052");
+
+        std::env::remove_var("GRPC_HOST");
+        std::env::remove_var("GRPC_PORT");
+    }
+
+    #[test]
+    fn test_config_http_address() {
+        std::env::remove_var("HTTP_PORT");
+        assert_eq!(Config::from_env().unwrap().http_address(), None);
+
+        std::env::set_var("HTTP_PORT", "8090");
+        let config = Config::from_env().unwrap();
+        assert_eq!(
+            config.http_address(),
+            Some(format!("{}:8090", config.grpc_host))
+        );
+
+        std::env::remove_var("HTTP_PORT");
+    }
+
+    #[test]
+    fn test_config_header_names() {
+        std::env::remove_var("RECEIPT_HEADER");
+        std::env::remove_var("NULLIFIER_HEADER");
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.receipt_header, "x-zk-receipt");
+        assert_eq!(config.receipt_encoding_header, "x-zk-receipt-encoding");
+        assert_eq!(config.receipt_kind_header, "x-zk-receipt-kind");
+        assert_eq!(config.nullifier_header, "x-zk-nullifier");
+
+        std::env::set_var("RECEIPT_HEADER", "X-Proof");
+        let config = Config::from_env().unwrap();
+        assert_eq!(config.receipt_header, "x-proof");
+
+        std::env::remove_var("RECEIPT_HEADER");
+    }
+
+    #[test]
+    fn test_parse_nullifier_list() {
+        let a = "01".repeat(32);
+        let b = "02".repeat(32);
+        let list = parse_nullifier_list(&format!(" {}, not-hex,{},", a, b));
+
+        assert_eq!(list.len(), 2);
+        assert!(list.contains(&Nullifier::from_hex(&a).unwrap()));
+        assert!(list.contains(&Nullifier::from_hex(&b).unwrap()));
+    }
+
+    #[test]
+    fn test_config_max_receipt_bytes() {
+        std::env::set_var("MAX_RECEIPT_BYTES", "1024");
+        assert_eq!(Config::from_env().unwrap().max_receipt_bytes, 1024);
+
+        // Never above the bincode decode limit
+        std::env::set_var("MAX_RECEIPT_BYTES", usize::MAX.to_string());
+        assert_eq!(
+            Config::from_env().unwrap().max_receipt_bytes,
+            RECEIPT_DECODE_LIMIT
+        );
+
+        std::env::remove_var("MAX_RECEIPT_BYTES");
+    }
+
+    #[test]
+    fn test_nullifier_namespace() {
+        let mut config = Config::from_env().unwrap();
+
+        config.nullifier_per_image = false;
+        assert_eq!(config.nullifier_namespace(), None);
+
+        config.nullifier_per_image = true;
+        assert_eq!(
+            config.nullifier_namespace(),
+            Some(hex::encode(config.image_id))
+        );
+    }
+
+    #[test]
+    fn test_config_journal_encoding() {
+        std::env::set_var("JOURNAL_ENCODING", "json");
+        assert_eq!(
+            Config::from_env().unwrap().journal_encoding,
+            JournalEncoding::Json
+        );
+
+        std::env::remove_var("JOURNAL_ENCODING");
+        assert_eq!(
+            Config::from_env().unwrap().journal_encoding,
+            JournalEncoding::Auto
+        );
+    }
+
+    #[test]
+    fn test_parse_redis_db() {
+        assert_eq!(parse_redis_db(None).unwrap(), None);
+        assert_eq!(parse_redis_db(Some("3".to_string())).unwrap(), Some(3));
+
+        // A typo must not silently fall back to the URL's database
+        let err = parse_redis_db(Some("fifteen".to_string())).unwrap_err();
+        assert!(err.to_string().contains("REDIS_DB"));
+    }
+
+    #[test]
+    fn test_parse_retired_image_ids() {
+        let a = "aa".repeat(32);
+        let b = "bb".repeat(32);
+        let list = parse_retired_image_ids(&format!(
+            "{}@2026-01-01T00:00:00Z, {}, {}@yesterday,{}@2026-02-01T12:00:00+02:00",
+            a, a, b, b
+        ));
+
+        assert_eq!(
+            list,
+            vec![
+                RetiredImageId {
+                    image_id: [0xaa; 32],
+                    retired_at: "2026-01-01T00:00:00Z".parse().unwrap(),
+                },
+                RetiredImageId {
+                    image_id: [0xbb; 32],
+                    retired_at: "2026-02-01T10:00:00Z".parse().unwrap(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_retired_image_id_expires_after_grace_period() {
+        let mut config = Config::from_env().unwrap();
+        let retired_at: DateTime<Utc> = "2026-01-01T00:00:00Z".parse().unwrap();
+        config.retired_image_ids = vec![RetiredImageId {
+            image_id: [0xaa; 32],
+            retired_at,
+        }];
+        config.image_id_grace_period = Duration::hours(1);
+
+        let within = retired_at + Duration::minutes(59);
+        let after = retired_at + Duration::hours(1);
+        assert_eq!(
+            config.accepted_image_id(&[0xaa; 32], within),
+            Some([0xaa; 32])
+        );
+        assert_eq!(config.accepted_image_id(&[0xaa; 32], after), None);
+
+        // The current image ID, and unknown ones, are held to the current ID
+        let current = config.image_id;
+        assert_eq!(config.accepted_image_id(&current, after), Some(current));
+        assert_eq!(config.accepted_image_id(&[0xbb; 32], after), Some(current));
+    }
+
     #[test]
     fn test_image_id_conversion() {
         let test_id: [u32; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
@@ -0,0 +1,488 @@
+//! HTTP/JSON gateway in front of the authorization service
+//!
+//! For integrators that can't speak the Envoy ExtAuth gRPC protocol.
+//! `POST /api/verify` takes the receipt and nullifier as JSON, runs exactly the
+//! checks the gRPC `Check` call runs (replay, payment, proof), and returns the
+//! decision as JSON: `{ "data": ... }` when allowed, `{ "error": "..." }` otherwise.
+//! `POST /api/verify-batch` takes an array of the same items and always answers
+//! 200 with a decision per item.
+
+use axum::{
+    extract::{DefaultBodyLimit, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use khafi_common::Nullifier;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::service::proto::{self, CheckRequest};
+use crate::service::{AuthorizationService, ReceiptEncoding};
+use khafi_common::ReceiptKind;
+
+/// Request body for `POST /api/verify`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyRequest {
+    /// Hex- or base64-encoded receipt (same value as the receipt header)
+    pub receipt: String,
+
+    /// Encoding of `receipt` (hex when omitted)
+    #[serde(default)]
+    pub receipt_encoding: ReceiptEncoding,
+
+    /// Kind of `receipt`, as reported by the prover (not checked when omitted)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receipt_kind: Option<ReceiptKind>,
+
+    /// Hex-encoded nullifier (same value as the nullifier header)
+    pub nullifier: String,
+}
+
+/// Authorization decision
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyResponse {
+    /// Whether the request is authorized
+    pub allowed: bool,
+
+    /// Human-readable reason for the decision
+    pub message: String,
+
+    /// Verified nullifier, present when allowed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nullifier: Option<String>,
+}
+
+/// Per-item decisions for `POST /api/verify-batch`, in request order
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyBatchResponse {
+    pub results: Vec<VerifyResponse>,
+}
+
+/// Maximum number of items in one `POST /api/verify-batch` request
+pub const MAX_BATCH_ITEMS: usize = 32;
+
+/// Successful response envelope
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiResponse<T> {
+    pub data: T,
+}
+
+/// Error response envelope
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+fn data_response<T: Serialize>(status: StatusCode, data: T) -> Response {
+    (status, Json(ApiResponse { data })).into_response()
+}
+
+fn error_response(status: StatusCode, error: String) -> Response {
+    (status, Json(ErrorResponse { error })).into_response()
+}
+
+/// Create the HTTP gateway router
+pub fn router(service: Arc<AuthorizationService>) -> Router {
+    // Room for a hex-encoded receipt at the configured size limit plus the JSON around it
+    let body_limit = service.config().max_receipt_bytes * 2 + 4096;
+
+    Router::new()
+        .route("/health", get(health_handler))
+        .route(
+            "/api/verify",
+            post(verify_handler).layer(DefaultBodyLimit::max(body_limit)),
+        )
+        .route(
+            "/api/verify-batch",
+            post(verify_batch_handler).layer(DefaultBodyLimit::max(body_limit)),
+        )
+        .with_state(service)
+}
+
+async fn health_handler() -> Response {
+    data_response(
+        StatusCode::OK,
+        serde_json::json!({
+            "status": "healthy",
+            "service": "zk-verification-service"
+        }),
+    )
+}
+
+/// Verify a receipt and nullifier, returning 200 when allowed and 403 when denied
+#[tracing::instrument(skip_all)]
+async fn verify_handler(
+    State(service): State<Arc<AuthorizationService>>,
+    Json(request): Json<VerifyRequest>,
+) -> Response {
+    match verify_item(&service, request, "/api/verify").await {
+        Ok(response) => data_response(StatusCode::OK, response),
+        Err((status, message)) => error_response(status, message),
+    }
+}
+
+/// Verify several receipts, returning a decision per item
+///
+/// Items are checked in order. A nullifier that already appeared earlier in
+/// the batch is denied without being checked again, so only its first use
+/// can be allowed.
+#[tracing::instrument(skip_all, fields(items = requests.len()))]
+async fn verify_batch_handler(
+    State(service): State<Arc<AuthorizationService>>,
+    Json(requests): Json<Vec<VerifyRequest>>,
+) -> Response {
+    if requests.len() > MAX_BATCH_ITEMS {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Batch has {} items (max {})",
+                requests.len(),
+                MAX_BATCH_ITEMS
+            ),
+        );
+    }
+
+    let mut seen = HashSet::new();
+    let mut results = Vec::with_capacity(requests.len());
+    for request in requests {
+        // Compare parsed nullifiers so case differences don't slip through;
+        // unparseable ones are rejected by the check itself
+        if let Ok(nullifier) = Nullifier::from_hex(&request.nullifier) {
+            if !seen.insert(nullifier.to_hex()) {
+                results.push(denied("Duplicate nullifier in batch".to_string()));
+                continue;
+            }
+        }
+
+        let result = match verify_item(&service, request, "/api/verify-batch").await {
+            Ok(response) => response,
+            Err((_, message)) => denied(message),
+        };
+        results.push(result);
+    }
+
+    data_response(StatusCode::OK, VerifyBatchResponse { results })
+}
+
+fn denied(message: String) -> VerifyResponse {
+    VerifyResponse {
+        allowed: false,
+        message,
+        nullifier: None,
+    }
+}
+
+/// Run the authorization checks for one item, mapping denials to an HTTP status
+async fn verify_item(
+    service: &AuthorizationService,
+    request: VerifyRequest,
+    path: &str,
+) -> Result<VerifyResponse, (StatusCode, String)> {
+    let config = service.config();
+    let mut headers = HashMap::from([
+        (config.receipt_header.clone(), request.receipt),
+        (
+            config.receipt_encoding_header.clone(),
+            request.receipt_encoding.as_str().to_string(),
+        ),
+        (config.nullifier_header.clone(), request.nullifier),
+    ]);
+    if let Some(kind) = request.receipt_kind {
+        headers.insert(
+            config.receipt_kind_header.clone(),
+            kind.as_str().to_string(),
+        );
+    }
+    let check = CheckRequest {
+        headers,
+        body: String::new(),
+        path: path.to_string(),
+    };
+
+    match service.authorize(check).await {
+        Ok(response) => {
+            let response = response.into_inner();
+            if response.status != proto::StatusCode::Ok as i32 {
+                return Err((StatusCode::FORBIDDEN, response.message));
+            }
+            Ok(VerifyResponse {
+                allowed: true,
+                message: response.message,
+                nullifier: response.metadata.get("x-payment-nullifier").cloned(),
+            })
+        }
+        Err(status) => {
+            let http_status = match status.code() {
+                tonic::Code::InvalidArgument | tonic::Code::Unauthenticated => {
+                    StatusCode::BAD_REQUEST
+                }
+                tonic::Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+                tonic::Code::PermissionDenied => StatusCode::FORBIDDEN,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            Err((http_status, status.message().to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use axum::body::Body;
+    use tower::ServiceExt; // for `oneshot`
+
+    async fn test_router() -> Router {
+        let mut config = Config::from_env().unwrap();
+        config.payment.require_payment = false;
+        router(Arc::new(AuthorizationService::new(config).await.unwrap()))
+    }
+
+    async fn post_json<T: Serialize>(
+        app: Router,
+        uri: &str,
+        body: &T,
+    ) -> (StatusCode, serde_json::Value) {
+        let response = app
+            .oneshot(
+                axum::http::Request::builder()
+                    .method("POST")
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_vec(body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    async fn post_verify(app: Router, request: &VerifyRequest) -> (StatusCode, serde_json::Value) {
+        post_json(app, "/api/verify", request).await
+    }
+
+    fn unproven_request(nullifier: &str) -> VerifyRequest {
+        VerifyRequest {
+            receipt: "00".to_string(),
+            receipt_encoding: ReceiptEncoding::Hex,
+            receipt_kind: None,
+            nullifier: nullifier.to_string(),
+        }
+    }
+
+    /// Prove the guest for a fresh nullifier (dev-mode receipt) and build a request
+    #[cfg(feature = "zkvm-tests")]
+    #[allow(deprecated)]
+    fn proven_request() -> VerifyRequest {
+        use khafi_common::{BusinessInputs, GuestInputs, Nullifier, Receipt, ZcashInputs};
+
+        std::env::set_var("RISC0_DEV_MODE", "1");
+
+        // The guest derives its nullifier from the first 32 bytes of the spending key
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let mut bytes = [0u8; 32];
+        bytes[..16].copy_from_slice(&nanos.to_le_bytes());
+        let nullifier = Nullifier::new(bytes);
+
+        let inputs = GuestInputs {
+            zcash: ZcashInputs {
+                spending_key: bytes.to_vec(),
+                note: vec![],
+                merkle_path: vec![],
+                merkle_root: [0u8; 32],
+            },
+            nullifier: nullifier.clone(),
+            business: BusinessInputs {
+                private_data: vec![1],
+                public_params: vec![1],
+            },
+        };
+
+        let env = risc0_zkvm::ExecutorEnv::builder()
+            .write(&inputs)
+            .unwrap()
+            .build()
+            .unwrap();
+        let receipt = risc0_zkvm::default_prover()
+            .prove(env, methods::GUEST_ELF)
+            .unwrap()
+            .receipt;
+
+        let config = bincode::config::standard();
+        let inner = bincode::serde::encode_to_vec(&receipt, config).unwrap();
+        let receipt = Receipt::new(inner, Config::from_env().unwrap().image_id);
+
+        VerifyRequest {
+            receipt: hex::encode(bincode::serde::encode_to_vec(&receipt, config).unwrap()),
+            receipt_encoding: ReceiptEncoding::Hex,
+            // Dev mode skips proving and produces fake receipts
+            receipt_kind: Some(ReceiptKind::Fake),
+            nullifier: nullifier.to_hex(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_nullifier_rejected() {
+        let request = unproven_request("not-hex");
+
+        let (status, body) = post_verify(test_router().await, &request).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"]
+            .as_str()
+            .unwrap()
+            .contains("Invalid nullifier format"));
+        assert!(body.get("data").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_batch_denies_invalid_and_duplicate_items() {
+        let nullifier = "ab".repeat(32);
+        let batch = vec![
+            unproven_request("not-hex"),
+            unproven_request(&nullifier),
+            unproven_request(&nullifier.to_uppercase()),
+        ];
+
+        let (status, body) = post_json(test_router().await, "/api/verify-batch", &batch).await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let response: VerifyBatchResponse = serde_json::from_value(body["data"].clone()).unwrap();
+        assert_eq!(response.results.len(), 3);
+        assert!(response.results.iter().all(|result| !result.allowed));
+        assert!(response.results[0]
+            .message
+            .contains("Invalid nullifier format"));
+        // The second item fails on its receipt (or Redis), not as a duplicate
+        assert_ne!(response.results[1].message, "Duplicate nullifier in batch");
+        assert_eq!(response.results[2].message, "Duplicate nullifier in batch");
+    }
+
+    #[tokio::test]
+    async fn test_oversized_batch_rejected() {
+        let batch: Vec<_> = (0..=MAX_BATCH_ITEMS)
+            .map(|_| unproven_request("not-hex"))
+            .collect();
+
+        let (status, body) = post_json(test_router().await, "/api/verify-batch", &batch).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("Batch has 33 items"));
+    }
+
+    #[tokio::test]
+    async fn test_health_uses_data_envelope() {
+        let response = test_router()
+            .await
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri("/health")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["data"]["status"], "healthy");
+        assert_eq!(body["data"]["service"], "zk-verification-service");
+    }
+
+    #[cfg(feature = "zkvm-tests")]
+    #[tokio::test]
+    #[ignore] // Requires Redis to be running
+    async fn test_valid_proof_allowed() {
+        let request = proven_request();
+
+        let (status, body) = post_verify(test_router().await, &request).await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let response: VerifyResponse = serde_json::from_value(body["data"].clone()).unwrap();
+        assert!(response.allowed);
+        assert_eq!(response.nullifier, Some(request.nullifier));
+    }
+
+    #[cfg(feature = "zkvm-tests")]
+    #[test]
+    fn test_receipt_kind_hint_checked() {
+        use crate::service::check_receipt_kind;
+        use khafi_common::Receipt;
+
+        let request = proven_request();
+        let bytes = hex::decode(&request.receipt).unwrap();
+        let (receipt, _): (Receipt, usize) =
+            bincode::serde::decode_from_slice(&bytes, bincode::config::standard()).unwrap();
+
+        // The hint matches what the receipt holds, and a wrong one is named as such
+        assert_eq!(receipt.kind().unwrap(), ReceiptKind::Fake);
+        assert!(check_receipt_kind(&receipt, ReceiptKind::Fake).is_ok());
+        let status = check_receipt_kind(&receipt, ReceiptKind::Groth16).unwrap_err();
+        assert_eq!(
+            status.message(),
+            "Receipt kind mismatch: expected groth16, got fake"
+        );
+    }
+
+    #[cfg(feature = "zkvm-tests")]
+    #[tokio::test]
+    #[ignore] // Requires Redis to be running
+    async fn test_replay_denied() {
+        let request = proven_request();
+        let app = test_router().await;
+
+        let (status, _) = post_verify(app.clone(), &request).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = post_verify(app, &request).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["error"], "Nullifier replay detected");
+    }
+
+    #[cfg(feature = "zkvm-tests")]
+    #[tokio::test]
+    #[ignore] // Requires Redis to be running
+    async fn test_batch_with_valid_invalid_and_duplicate() {
+        let valid = proven_request();
+        let duplicate = VerifyRequest {
+            receipt: valid.receipt.clone(),
+            receipt_encoding: ReceiptEncoding::Hex,
+            receipt_kind: valid.receipt_kind,
+            nullifier: valid.nullifier.clone(),
+        };
+        let batch = vec![valid, unproven_request("not-hex"), duplicate];
+
+        let (status, body) = post_json(test_router().await, "/api/verify-batch", &batch).await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let response: VerifyBatchResponse = serde_json::from_value(body["data"].clone()).unwrap();
+        assert!(response.results[0].allowed);
+        assert_eq!(
+            response.results[0].nullifier,
+            Some(batch[0].nullifier.clone())
+        );
+        assert!(!response.results[1].allowed);
+        assert!(response.results[1]
+            .message
+            .contains("Invalid nullifier format"));
+        assert!(!response.results[2].allowed);
+        assert_eq!(response.results[2].message, "Duplicate nullifier in batch");
+    }
+}
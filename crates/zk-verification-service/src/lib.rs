@@ -4,6 +4,7 @@
 //! Implements Envoy's ExtAuth interface with optional payment verification.
 
 pub mod config;
+pub mod http;
 pub mod nullifier;
 pub mod payment;
 pub mod service;
@@ -2,38 +2,68 @@
 //!
 //! gRPC service that implements Envoy ExtAuth protocol for ZK proof verification
 
-mod config;
-mod nullifier;
-mod payment;
-mod service;
-
-use config::Config;
-use service::AuthorizationService;
+use std::sync::Arc;
 use tonic::transport::Server;
+use zk_verification_service::config::Config;
+use zk_verification_service::http;
+use zk_verification_service::payment::PaymentChecker;
+use zk_verification_service::service::AuthorizationService;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize tracing/logging
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
-        )
-        .init();
+    // Initialize tracing (exports to OTLP when OTEL_EXPORTER_OTLP_ENDPOINT is set)
+    let _telemetry = khafi_telemetry::init_tracing("zk-verification-service", "info")?;
 
     tracing::info!("Starting ZK Verification Service...");
 
     // Load configuration
-    let config = Config::from_env();
+    let config = Config::from_env()?;
     tracing::info!("Redis URL: {}", config.redis_url);
     tracing::info!("Image ID: {}", hex::encode(config.image_id));
 
+    // Replay bypass is for staging/QA only; make it impossible to miss in the logs
+    if config.disable_replay_protection {
+        tracing::warn!(
+            "REPLAY PROTECTION DISABLED for all nullifiers - never run this in production"
+        );
+    } else if !config.replay_bypass.is_empty() {
+        tracing::warn!(
+            "REPLAY PROTECTION BYPASSED for {} nullifier(s) - never run this in production",
+            config.replay_bypass.len()
+        );
+    }
+
+    // Server address
+    let addr = config.grpc_address().parse()?;
+
+    // Prune expired entries from the payments:reserved tracking set
+    if config.payment.require_payment {
+        let sweeper =
+            PaymentChecker::new(&config.redis_url, config.redis_db, config.payment.clone())?;
+        sweeper.spawn_reservation_sweeper();
+        tracing::info!(
+            "Reservation sweeper running every {}s",
+            config.payment.reservation_sweep_interval_secs
+        );
+    }
+
     // Create authorization service
-    let auth_service = AuthorizationService::new(config).await?;
+    let http_addr = config.http_address();
+    let auth_service = Arc::new(AuthorizationService::new(config).await?);
     tracing::info!("Authorization service initialized");
 
-    // Server address
-    let addr = "0.0.0.0:50051".parse()?;
+    // Optional HTTP/JSON gateway for integrators that don't speak ExtAuth gRPC
+    if let Some(http_addr) = http_addr {
+        let listener = tokio::net::TcpListener::bind(&http_addr).await?;
+        let app = http::router(auth_service.clone());
+        tracing::info!("HTTP gateway listening on {}", http_addr);
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                tracing::error!("HTTP gateway error: {}", e);
+            }
+        });
+    }
+
     tracing::info!("ZK Verification Service listening on {}", addr);
 
     // Start gRPC server
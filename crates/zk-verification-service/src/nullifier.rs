@@ -3,9 +3,13 @@
 use khafi_common::{Error, Nullifier, Result};
 use redis::AsyncCommands;
 
+use crate::config::open_redis;
+
 /// Nullifier checker with Redis backend
 pub struct NullifierChecker {
     redis_client: redis::Client,
+    /// Scopes replay keys (e.g. to an image ID); `None` keeps one global set
+    namespace: Option<String>,
 }
 
 impl NullifierChecker {
@@ -13,10 +17,21 @@ impl NullifierChecker {
     ///
     /// # Arguments
     /// * `redis_url` - Redis connection URL (e.g., "redis://localhost:6379")
-    pub fn new(redis_url: &str) -> Result<Self> {
-        let redis_client =
-            redis::Client::open(redis_url).map_err(|e| Error::Redis(e.to_string()))?;
-        Ok(Self { redis_client })
+    /// * `redis_db` - Database index overriding the one in the URL
+    pub fn new(redis_url: &str, redis_db: Option<i64>) -> Result<Self> {
+        let redis_client = open_redis(redis_url, redis_db)?;
+        Ok(Self {
+            redis_client,
+            namespace: None,
+        })
+    }
+
+    /// Scope replay protection to a namespace (e.g. the deployment's image ID)
+    ///
+    /// A nullifier used under one namespace can still be used once under another.
+    pub fn with_namespace(mut self, namespace: Option<String>) -> Self {
+        self.namespace = namespace;
+        self
     }
 
     /// Check if a nullifier has been used before and mark it as used
@@ -37,7 +52,7 @@ impl NullifierChecker {
             .await
             .map_err(|e| Error::Redis(e.to_string()))?;
 
-        let key = format!("nullifier:{}", nullifier.to_hex());
+        let key = self.replay_key(nullifier);
 
         // SET NX - set if not exists (atomic operation)
         // Returns true if the key was set (didn't exist before)
@@ -57,6 +72,14 @@ impl NullifierChecker {
 
         Ok(result)
     }
+
+    /// Redis key recording that a nullifier has been used
+    fn replay_key(&self, nullifier: &Nullifier) -> String {
+        match &self.namespace {
+            Some(namespace) => format!("nullifier:{}:{}", namespace, nullifier.to_hex()),
+            None => format!("nullifier:{}", nullifier.to_hex()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -66,7 +89,7 @@ mod tests {
     #[tokio::test]
     #[ignore] // Requires Redis to be running
     async fn test_nullifier_check_and_set() {
-        let checker = NullifierChecker::new("redis://localhost:6379").unwrap();
+        let checker = NullifierChecker::new("redis://localhost:6379", None).unwrap();
         let nullifier = Nullifier::new([42u8; 32]);
 
         // First time should return true (new)
@@ -77,4 +100,42 @@ mod tests {
         let is_new = checker.check_and_set(&nullifier).await.unwrap();
         assert!(!is_new);
     }
+
+    #[test]
+    fn test_replay_key_namespace() {
+        let nullifier = Nullifier::new([1u8; 32]);
+
+        let global = NullifierChecker::new("redis://localhost:6379", None).unwrap();
+        assert_eq!(
+            global.replay_key(&nullifier),
+            format!("nullifier:{}", nullifier.to_hex())
+        );
+
+        let scoped = NullifierChecker::new("redis://localhost:6379", None)
+            .unwrap()
+            .with_namespace(Some("image-abc".to_string()));
+        assert_eq!(
+            scoped.replay_key(&nullifier),
+            format!("nullifier:image-abc:{}", nullifier.to_hex())
+        );
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis to be running
+    async fn test_nullifier_scoped_per_customer() {
+        let customer_a = NullifierChecker::new("redis://localhost:6379", Some(15))
+            .unwrap()
+            .with_namespace(Some(format!("image-a-{}", std::process::id())));
+        let customer_b = NullifierChecker::new("redis://localhost:6379", Some(15))
+            .unwrap()
+            .with_namespace(Some(format!("image-b-{}", std::process::id())));
+        let nullifier = Nullifier::new([43u8; 32]);
+
+        // The same nullifier is accepted once for each customer
+        assert!(customer_a.check_and_set(&nullifier).await.unwrap());
+        assert!(customer_b.check_and_set(&nullifier).await.unwrap());
+
+        // But replay within one customer is still blocked
+        assert!(!customer_a.check_and_set(&nullifier).await.unwrap());
+    }
 }
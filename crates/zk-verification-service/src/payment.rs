@@ -3,21 +3,72 @@
 //! Verifies Zcash payments exist in Redis and manages payment reservations
 //! to prevent double-spending during proof generation.
 
+use async_trait::async_trait;
 use khafi_common::{Error, Nullifier, Result};
 use redis::AsyncCommands;
-use tracing::{debug, info, warn};
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+use crate::config::open_redis;
 
 /// Reservation TTL - payments reserved for 5 minutes max
 const RESERVATION_TTL_SECS: u64 = 300;
 
+/// Atomically mark a payment used and drop its reservation
+///
+/// KEYS: payment hash, reservation key, `payments:unused`, `payments:reserved`
+/// ARGV: nullifier hex, `used_at` timestamp
+///
+/// Returns 1 if the payment transitioned to used, 0 if it was already used,
+/// and -1 if the payment doesn't exist.
+const CONFIRM_PAYMENT_SCRIPT: &str = r#"
+local used = redis.call('HGET', KEYS[1], 'used')
+if not used then
+    return -1
+end
+redis.call('DEL', KEYS[2])
+redis.call('SREM', KEYS[4], ARGV[1])
+if used == 'true' then
+    return 0
+end
+redis.call('HSET', KEYS[1], 'used', 'true', 'used_at', ARGV[2])
+redis.call('SREM', KEYS[3], ARGV[1])
+return 1
+"#;
+
+/// Stop tracking reservations whose key has expired, atomically per member
+///
+/// KEYS: `payments:reserved`, then the reservation key of each nullifier
+/// ARGV: the nullifier hexes, in the same order as their reservation keys
+///
+/// Returns the number of nullifiers removed from `payments:reserved`.
+const SWEEP_RESERVATIONS_SCRIPT: &str = r#"
+local removed = 0
+for i, nullifier in ipairs(ARGV) do
+    if redis.call('EXISTS', KEYS[i + 1]) == 0 then
+        removed = removed + redis.call('SREM', KEYS[1], nullifier)
+    end
+end
+return removed
+"#;
+
+/// Tracked reservations checked per sweep round trip
+const SWEEP_BATCH_SIZE: usize = 100;
+
 /// Default minimum payment amount in zatoshis (0.001 ZEC)
 pub const DEFAULT_MIN_PAYMENT_AMOUNT: u64 = 100_000;
 
 /// Default minimum confirmations required
 pub const DEFAULT_MIN_CONFIRMATIONS: u32 = 1;
 
+/// Default interval between sweeps of expired reservations
+pub const DEFAULT_RESERVATION_SWEEP_INTERVAL_SECS: u64 = 60;
+
+/// Default maximum age of the chain tip before it is considered stale (10 minutes)
+pub const DEFAULT_MAX_CHAIN_TIP_AGE_SECS: u64 = 600;
+
 /// Payment information from Redis
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PaymentInfo {
     /// Amount in zatoshis
     pub amount: u64,
@@ -38,6 +89,12 @@ pub struct PaymentConfig {
     pub min_payment_amount: u64,
     /// Minimum confirmations required
     pub min_confirmations: u32,
+    /// Maximum age in seconds of the chain tip written by the Zcash Backend
+    pub max_chain_tip_age_secs: u64,
+    /// Seconds between sweeps of expired entries in `payments:reserved`
+    pub reservation_sweep_interval_secs: u64,
+    /// Customer whose `payment_policy:{customer_id}` overrides these defaults
+    pub customer_id: Option<String>,
 }
 
 impl Default for PaymentConfig {
@@ -46,6 +103,9 @@ impl Default for PaymentConfig {
             require_payment: false,
             min_payment_amount: DEFAULT_MIN_PAYMENT_AMOUNT,
             min_confirmations: DEFAULT_MIN_CONFIRMATIONS,
+            max_chain_tip_age_secs: DEFAULT_MAX_CHAIN_TIP_AGE_SECS,
+            reservation_sweep_interval_secs: DEFAULT_RESERVATION_SWEEP_INTERVAL_SECS,
+            customer_id: None,
         }
     }
 }
@@ -67,29 +127,298 @@ impl PaymentConfig {
             .and_then(|v| v.parse().ok())
             .unwrap_or(DEFAULT_MIN_CONFIRMATIONS);
 
+        let max_chain_tip_age_secs = std::env::var("MAX_CHAIN_TIP_AGE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_CHAIN_TIP_AGE_SECS);
+
+        let reservation_sweep_interval_secs = std::env::var("RESERVATION_SWEEP_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&v| v > 0)
+            .unwrap_or(DEFAULT_RESERVATION_SWEEP_INTERVAL_SECS);
+
+        let customer_id = std::env::var("CUSTOMER_ID").ok().filter(|v| !v.is_empty());
+
         Self {
             require_payment,
             min_payment_amount,
             min_confirmations,
+            max_chain_tip_age_secs,
+            reservation_sweep_interval_secs,
+            customer_id,
         }
     }
 }
 
-/// Payment checker with Redis backend
-pub struct PaymentChecker {
+/// Storage behind payment verification and the two-phase reservation
+///
+/// [`RedisPaymentStore`] reads what the Zcash Backend writes; the checker's
+/// rules (amount, confirmations, reservation state) live in [`PaymentChecker`]
+/// so they can be exercised against any implementation.
+#[async_trait]
+pub trait PaymentStore: Send + Sync + 'static {
+    /// Payment recorded for a nullifier, if any
+    async fn get_payment(&self, nullifier_hex: &str) -> Result<Option<PaymentInfo>>;
+
+    /// Whether a live reservation exists for a nullifier
+    async fn is_reserved(&self, nullifier_hex: &str) -> Result<bool>;
+
+    /// Reserve a nullifier for `ttl_secs` and track it in `payments:reserved`
+    ///
+    /// Returns `false` without changing anything if it is already reserved.
+    async fn reserve(&self, nullifier_hex: &str, ttl_secs: u64) -> Result<bool>;
+
+    /// Atomically mark a payment used and drop its reservation
+    ///
+    /// Returns `Some(true)` if the payment transitioned to used, `Some(false)`
+    /// if it was already used, and `None` if it doesn't exist.
+    async fn confirm(&self, nullifier_hex: &str, used_at: &str) -> Result<Option<bool>>;
+
+    /// Drop a reservation and stop tracking it
+    async fn release(&self, nullifier_hex: &str) -> Result<()>;
+
+    /// Stop tracking nullifiers in `payments:reserved` whose reservation lapsed
+    ///
+    /// A nullifier reserved again in the meantime stays tracked. Returns the
+    /// number of nullifiers removed.
+    async fn untrack_expired_reservations(&self) -> Result<usize>;
+
+    /// A customer's `min_confirmations` override, if one is stored
+    ///
+    /// Fails if a stored override isn't a valid confirmation count.
+    async fn min_confirmations_policy(&self, customer_id: &str) -> Result<Option<u32>>;
+
+    /// Store a customer's `min_confirmations` override
+    async fn set_min_confirmations_policy(
+        &self,
+        customer_id: &str,
+        min_confirmations: u32,
+    ) -> Result<()>;
+
+    /// Chain tip height and the unix time it was last updated
+    async fn chain_tip(&self) -> Result<(Option<u32>, Option<i64>)>;
+}
+
+/// Payment store backed by the Redis keys the Zcash Backend maintains
+pub struct RedisPaymentStore {
     redis_client: redis::Client,
+}
+
+impl RedisPaymentStore {
+    /// Create a new Redis payment store
+    pub fn new(redis_url: &str, redis_db: Option<i64>) -> Result<Self> {
+        let redis_client = open_redis(redis_url, redis_db)?;
+        Ok(Self { redis_client })
+    }
+
+    async fn get_connection(&self) -> Result<redis::aio::MultiplexedConnection> {
+        self.redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| Error::Redis(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl PaymentStore for RedisPaymentStore {
+    async fn get_payment(&self, nullifier_hex: &str) -> Result<Option<PaymentInfo>> {
+        let mut conn = self.get_connection().await?;
+        let payment_key = format!("payment:{}", nullifier_hex);
+
+        let exists: bool = conn
+            .exists(&payment_key)
+            .await
+            .map_err(|e| Error::Redis(e.to_string()))?;
+
+        if !exists {
+            return Ok(None);
+        }
+
+        let fields: Vec<(String, String)> = conn
+            .hgetall(&payment_key)
+            .await
+            .map_err(|e| Error::Redis(e.to_string()))?;
+
+        Ok(Some(parse_payment_fields(&fields)))
+    }
+
+    async fn is_reserved(&self, nullifier_hex: &str) -> Result<bool> {
+        let mut conn = self.get_connection().await?;
+        conn.exists(format!("reserved:{}", nullifier_hex))
+            .await
+            .map_err(|e| Error::Redis(e.to_string()))
+    }
+
+    async fn reserve(&self, nullifier_hex: &str, ttl_secs: u64) -> Result<bool> {
+        let mut conn = self.get_connection().await?;
+        let reserved_key = format!("reserved:{}", nullifier_hex);
+
+        // SET NX with TTL - atomic reservation
+        let set_result: Option<String> = redis::cmd("SET")
+            .arg(&reserved_key)
+            .arg("1")
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl_secs)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| Error::Redis(e.to_string()))?;
+
+        if set_result.is_none() {
+            return Ok(false);
+        }
+
+        // Add to reserved set for tracking
+        conn.sadd::<_, _, ()>("payments:reserved", nullifier_hex)
+            .await
+            .map_err(|e| Error::Redis(e.to_string()))?;
+
+        Ok(true)
+    }
+
+    async fn confirm(&self, nullifier_hex: &str, used_at: &str) -> Result<Option<bool>> {
+        let mut conn = self.get_connection().await?;
+        let payment_key = format!("payment:{}", nullifier_hex);
+        let reserved_key = format!("reserved:{}", nullifier_hex);
+
+        let outcome: i64 = redis::Script::new(CONFIRM_PAYMENT_SCRIPT)
+            .key(&payment_key)
+            .key(&reserved_key)
+            .key("payments:unused")
+            .key("payments:reserved")
+            .arg(nullifier_hex)
+            .arg(used_at)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| Error::Redis(e.to_string()))?;
+
+        Ok(match outcome {
+            1 => Some(true),
+            0 => Some(false),
+            _ => None,
+        })
+    }
+
+    async fn release(&self, nullifier_hex: &str) -> Result<()> {
+        let mut conn = self.get_connection().await?;
+
+        conn.del::<_, ()>(format!("reserved:{}", nullifier_hex))
+            .await
+            .map_err(|e| Error::Redis(e.to_string()))?;
+        conn.srem::<_, _, ()>("payments:reserved", nullifier_hex)
+            .await
+            .map_err(|e| Error::Redis(e.to_string()))
+    }
+
+    async fn untrack_expired_reservations(&self) -> Result<usize> {
+        let mut conn = self.get_connection().await?;
+        let script = redis::Script::new(SWEEP_RESERVATIONS_SCRIPT);
+
+        // Page through the set so neither the scan nor a script call blocks
+        // Redis for long; SSCAN may repeat members, which is harmless here
+        let mut cursor: u64 = 0;
+        let mut removed = 0;
+        loop {
+            let (next, batch): (u64, Vec<String>) = redis::cmd("SSCAN")
+                .arg("payments:reserved")
+                .arg(cursor)
+                .arg("COUNT")
+                .arg(SWEEP_BATCH_SIZE)
+                .query_async(&mut conn)
+                .await
+                .map_err(|e| Error::Redis(e.to_string()))?;
+
+            if !batch.is_empty() {
+                let mut invocation = script.key("payments:reserved");
+                for nullifier_hex in &batch {
+                    invocation.key(format!("reserved:{}", nullifier_hex));
+                }
+                for nullifier_hex in &batch {
+                    invocation.arg(nullifier_hex);
+                }
+                let swept: usize = invocation
+                    .invoke_async(&mut conn)
+                    .await
+                    .map_err(|e| Error::Redis(e.to_string()))?;
+                removed += swept;
+            }
+
+            if next == 0 {
+                return Ok(removed);
+            }
+            cursor = next;
+        }
+    }
+
+    async fn min_confirmations_policy(&self, customer_id: &str) -> Result<Option<u32>> {
+        let mut conn = self.get_connection().await?;
+        let key = policy_key(customer_id);
+        let policy: Option<String> = conn
+            .hget(&key, "min_confirmations")
+            .await
+            .map_err(|e| Error::Redis(e.to_string()))?;
+
+        // A corrupt policy must not silently fall back to the (laxer) default
+        policy
+            .map(|v| {
+                v.parse().map_err(|_| {
+                    Error::Redis(format!("Invalid min_confirmations in {}: '{}'", key, v))
+                })
+            })
+            .transpose()
+    }
+
+    async fn set_min_confirmations_policy(
+        &self,
+        customer_id: &str,
+        min_confirmations: u32,
+    ) -> Result<()> {
+        let mut conn = self.get_connection().await?;
+        conn.hset::<_, _, _, ()>(
+            policy_key(customer_id),
+            "min_confirmations",
+            min_confirmations,
+        )
+        .await
+        .map_err(|e| Error::Redis(e.to_string()))
+    }
+
+    async fn chain_tip(&self) -> Result<(Option<u32>, Option<i64>)> {
+        let mut conn = self.get_connection().await?;
+
+        let (height, updated_at): (Option<String>, Option<String>) = redis::cmd("MGET")
+            .arg("chain:block_height")
+            .arg("chain:block_height_updated_at")
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| Error::Redis(e.to_string()))?;
+
+        Ok((
+            height.and_then(|h| h.parse().ok()),
+            updated_at.and_then(|t| t.parse().ok()),
+        ))
+    }
+}
+
+/// Payment checker, backed by Redis unless another store is supplied
+pub struct PaymentChecker<S = RedisPaymentStore> {
+    store: S,
     config: PaymentConfig,
 }
 
 impl PaymentChecker {
     /// Create a new payment checker
-    pub fn new(redis_url: &str, config: PaymentConfig) -> Result<Self> {
-        let redis_client =
-            redis::Client::open(redis_url).map_err(|e| Error::Redis(e.to_string()))?;
-        Ok(Self {
-            redis_client,
-            config,
-        })
+    pub fn new(redis_url: &str, redis_db: Option<i64>, config: PaymentConfig) -> Result<Self> {
+        let store = RedisPaymentStore::new(redis_url, redis_db)?;
+        Ok(Self::with_store(store, config))
+    }
+}
+
+impl<S: PaymentStore> PaymentChecker<S> {
+    /// Create a payment checker over an existing store
+    pub fn with_store(store: S, config: PaymentConfig) -> Self {
+        Self { store, config }
     }
 
     /// Check if payment verification is required
@@ -106,28 +435,13 @@ impl PaymentChecker {
     /// * `Ok(PaymentInfo)` - Payment found and valid
     /// * `Err` - Payment not found or invalid
     pub async fn check_payment(&self, nullifier: &Nullifier) -> Result<PaymentInfo> {
-        let mut conn = self.get_connection().await?;
         let nullifier_hex = nullifier.to_hex();
-        let payment_key = format!("payment:{}", nullifier_hex);
 
         // Check if payment exists
-        let exists: bool = conn
-            .exists(&payment_key)
-            .await
-            .map_err(|e| Error::Redis(e.to_string()))?;
-
-        if !exists {
+        let Some(info) = self.store.get_payment(&nullifier_hex).await? else {
             debug!("Payment not found for nullifier: {}", nullifier_hex);
             return Err(Error::Zcash("Payment not found".to_string()));
-        }
-
-        // Get payment details as hash
-        let fields: Vec<(String, String)> = conn
-            .hgetall(&payment_key)
-            .await
-            .map_err(|e| Error::Redis(e.to_string()))?;
-
-        let info = self.parse_payment_fields(&fields)?;
+        };
 
         // Check if already used
         if info.used {
@@ -136,13 +450,7 @@ impl PaymentChecker {
         }
 
         // Check if already reserved by another request
-        let reserved_key = format!("reserved:{}", nullifier_hex);
-        let is_reserved: bool = conn
-            .exists(&reserved_key)
-            .await
-            .map_err(|e| Error::Redis(e.to_string()))?;
-
-        if is_reserved {
+        if self.store.is_reserved(&nullifier_hex).await? {
             warn!("Payment is reserved by another request: {}", nullifier_hex);
             return Err(Error::Zcash(
                 "Payment is reserved by another request".to_string(),
@@ -162,17 +470,18 @@ impl PaymentChecker {
         }
 
         // Check confirmations
+        let min_confirmations = self.min_confirmations().await?;
         let current_height = self.get_current_block_height().await?;
         let confirmations = current_height.saturating_sub(info.block_height);
 
-        if confirmations < self.config.min_confirmations {
+        if confirmations < min_confirmations {
             warn!(
                 "Payment has {} confirmations, need at least {}",
-                confirmations, self.config.min_confirmations
+                confirmations, min_confirmations
             );
             return Err(Error::Zcash(format!(
                 "Payment has {} confirmations, need at least {}",
-                confirmations, self.config.min_confirmations
+                confirmations, min_confirmations
             )));
         }
 
@@ -184,6 +493,43 @@ impl PaymentChecker {
         Ok(info)
     }
 
+    /// Minimum confirmations for this checker's customer
+    ///
+    /// Uses the `min_confirmations` field of `payment_policy:{customer_id}`
+    /// when one is stored, otherwise the global `min_confirmations`.
+    async fn min_confirmations(&self) -> Result<u32> {
+        let Some(customer_id) = &self.config.customer_id else {
+            return Ok(self.config.min_confirmations);
+        };
+
+        Ok(self
+            .store
+            .min_confirmations_policy(customer_id)
+            .await?
+            .unwrap_or(self.config.min_confirmations))
+    }
+
+    /// Store a customer's minimum confirmations override
+    ///
+    /// # Arguments
+    /// * `customer_id` - Customer the policy applies to
+    /// * `min_confirmations` - Confirmations required for that customer's payments
+    pub async fn set_min_confirmations_policy(
+        &self,
+        customer_id: &str,
+        min_confirmations: u32,
+    ) -> Result<()> {
+        self.store
+            .set_min_confirmations_policy(customer_id, min_confirmations)
+            .await?;
+
+        info!(
+            "Customer {} now requires {} confirmations",
+            customer_id, min_confirmations
+        );
+        Ok(())
+    }
+
     /// Reserve a payment with TTL (two-phase commit - phase 1)
     ///
     /// # Arguments
@@ -193,68 +539,52 @@ impl PaymentChecker {
     /// * `Ok(())` - Reservation successful
     /// * `Err` - Already reserved or Redis error
     pub async fn reserve_payment(&self, nullifier: &Nullifier) -> Result<()> {
-        let mut conn = self.get_connection().await?;
         let nullifier_hex = nullifier.to_hex();
-        let reserved_key = format!("reserved:{}", nullifier_hex);
-
-        // SET NX with TTL - atomic reservation
-        let set_result: Option<String> = redis::cmd("SET")
-            .arg(&reserved_key)
-            .arg("1")
-            .arg("NX")
-            .arg("EX")
-            .arg(RESERVATION_TTL_SECS)
-            .query_async(&mut conn)
-            .await
-            .map_err(|e| Error::Redis(e.to_string()))?;
 
-        if set_result.is_none() {
+        if !self
+            .store
+            .reserve(&nullifier_hex, RESERVATION_TTL_SECS)
+            .await?
+        {
             warn!("Payment already reserved: {}", nullifier_hex);
             return Err(Error::Zcash("Payment already reserved".to_string()));
         }
 
-        // Add to reserved set for tracking
-        conn.sadd::<_, _, ()>("payments:reserved", &nullifier_hex)
-            .await
-            .map_err(|e| Error::Redis(e.to_string()))?;
-
         info!("Payment reserved: {}", nullifier_hex);
         Ok(())
     }
 
     /// Confirm payment usage (two-phase commit - phase 2)
     ///
-    /// Called after successful proof generation
+    /// Called after successful proof generation. Idempotent: confirming an
+    /// already-used payment leaves its `used_at` untouched, so a confirmation
+    /// retried after a crash is safe.
     ///
     /// # Arguments
     /// * `nullifier` - The nullifier to confirm
-    pub async fn confirm_payment(&self, nullifier: &Nullifier) -> Result<()> {
-        let mut conn = self.get_connection().await?;
+    ///
+    /// # Returns
+    /// * `Ok(true)` - Payment transitioned to used
+    /// * `Ok(false)` - Payment was already confirmed
+    /// * `Err` - Payment not found or Redis error
+    pub async fn confirm_payment(&self, nullifier: &Nullifier) -> Result<bool> {
         let nullifier_hex = nullifier.to_hex();
-        let payment_key = format!("payment:{}", nullifier_hex);
-        let reserved_key = format!("reserved:{}", nullifier_hex);
-
-        // Mark as used
         let now = chrono::Utc::now().to_rfc3339();
-        conn.hset_multiple::<_, _, _, ()>(&payment_key, &[("used", "true"), ("used_at", &now)])
-            .await
-            .map_err(|e| Error::Redis(e.to_string()))?;
-
-        // Remove from unused set
-        conn.srem::<_, _, ()>("payments:unused", &nullifier_hex)
-            .await
-            .map_err(|e| Error::Redis(e.to_string()))?;
 
-        // Remove reservation
-        conn.del::<_, ()>(&reserved_key)
-            .await
-            .map_err(|e| Error::Redis(e.to_string()))?;
-        conn.srem::<_, _, ()>("payments:reserved", &nullifier_hex)
-            .await
-            .map_err(|e| Error::Redis(e.to_string()))?;
-
-        info!("Payment confirmed as used: {}", nullifier_hex);
-        Ok(())
+        match self.store.confirm(&nullifier_hex, &now).await? {
+            Some(true) => {
+                info!("Payment confirmed as used: {}", nullifier_hex);
+                Ok(true)
+            }
+            Some(false) => {
+                debug!("Payment already confirmed: {}", nullifier_hex);
+                Ok(false)
+            }
+            None => {
+                warn!("Cannot confirm nonexistent payment: {}", nullifier_hex);
+                Err(Error::Zcash("Payment not found".to_string()))
+            }
+        }
     }
 
     /// Release reservation (on failure)
@@ -264,63 +594,335 @@ impl PaymentChecker {
     /// # Arguments
     /// * `nullifier` - The nullifier to release
     pub async fn release_reservation(&self, nullifier: &Nullifier) -> Result<()> {
-        let mut conn = self.get_connection().await?;
         let nullifier_hex = nullifier.to_hex();
-        let reserved_key = format!("reserved:{}", nullifier_hex);
-
-        conn.del::<_, ()>(&reserved_key)
-            .await
-            .map_err(|e| Error::Redis(e.to_string()))?;
-        conn.srem::<_, _, ()>("payments:reserved", &nullifier_hex)
-            .await
-            .map_err(|e| Error::Redis(e.to_string()))?;
+        self.store.release(&nullifier_hex).await?;
 
         info!("Payment reservation released: {}", nullifier_hex);
         Ok(())
     }
 
+    /// Remove `payments:reserved` members whose `reserved:` key has expired
+    ///
+    /// Reservation keys expire via TTL, but the tracking set is only cleaned
+    /// on confirm/release, so abandoned reservations linger there.
+    ///
+    /// # Returns
+    /// * `Ok(count)` - Number of stale set members removed
+    pub async fn sweep_expired_reservations(&self) -> Result<usize> {
+        let removed = self.store.untrack_expired_reservations().await?;
+
+        if removed > 0 {
+            info!("Swept {} expired payment reservations", removed);
+        }
+        Ok(removed)
+    }
+
+    /// Run `sweep_expired_reservations` in the background at the configured interval
+    pub fn spawn_reservation_sweeper(self) -> tokio::task::JoinHandle<()> {
+        let interval = Duration::from_secs(self.config.reservation_sweep_interval_secs);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                if let Err(e) = self.sweep_expired_reservations().await {
+                    error!("Error sweeping payment reservations: {}", e);
+                }
+            }
+        })
+    }
+
     /// Get current block height from Redis (set by Zcash Backend)
+    ///
+    /// Fails closed if the height was last updated more than
+    /// `max_chain_tip_age_secs` ago, since confirmations counted against a
+    /// stale tip are meaningless.
     pub async fn get_current_block_height(&self) -> Result<u32> {
-        let mut conn = self.get_connection().await?;
+        let (height, updated_at) = self.store.chain_tip().await?;
 
-        let height: Option<String> = conn
-            .get("chain:block_height")
-            .await
-            .map_err(|e| Error::Redis(e.to_string()))?;
+        let height =
+            height.ok_or_else(|| Error::Zcash("Block height not available".to_string()))?;
 
-        height
-            .and_then(|h| h.parse().ok())
-            .ok_or_else(|| Error::Zcash("Block height not available".to_string()))
+        check_chain_tip_fresh(
+            updated_at,
+            chrono::Utc::now().timestamp(),
+            self.config.max_chain_tip_age_secs,
+        )?;
+
+        Ok(height)
     }
+}
 
-    async fn get_connection(&self) -> Result<redis::aio::MultiplexedConnection> {
-        self.redis_client
-            .get_multiplexed_async_connection()
-            .await
-            .map_err(|e| Error::Redis(e.to_string()))
+/// Parse the fields of a `payment:{nullifier}` hash
+fn parse_payment_fields(fields: &[(String, String)]) -> PaymentInfo {
+    let map: std::collections::HashMap<_, _> = fields.iter().cloned().collect();
+
+    PaymentInfo {
+        amount: map.get("amount").and_then(|s| s.parse().ok()).unwrap_or(0),
+        block_height: map
+            .get("block_height")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0),
+        used: map.get("used").map(|s| s == "true").unwrap_or(false),
+        tx_id: map.get("tx_id").cloned().unwrap_or_default(),
     }
+}
 
-    fn parse_payment_fields(&self, fields: &[(String, String)]) -> Result<PaymentInfo> {
-        let map: std::collections::HashMap<_, _> = fields.iter().cloned().collect();
-
-        Ok(PaymentInfo {
-            amount: map
-                .get("amount")
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(0),
-            block_height: map
-                .get("block_height")
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(0),
-            used: map.get("used").map(|s| s == "true").unwrap_or(false),
-            tx_id: map.get("tx_id").cloned().unwrap_or_default(),
-        })
+/// Redis key holding a customer's payment policy overrides
+fn policy_key(customer_id: &str) -> String {
+    format!("payment_policy:{}", customer_id)
+}
+
+/// Check that the chain tip was updated within `max_age_secs` of `now`
+///
+/// A missing timestamp is treated as stale.
+fn check_chain_tip_fresh(updated_at: Option<i64>, now: i64, max_age_secs: u64) -> Result<()> {
+    let Some(updated_at) = updated_at else {
+        warn!("Chain tip has no update timestamp");
+        return Err(Error::Zcash(
+            "Chain data stale: block height has no update timestamp".to_string(),
+        ));
+    };
+
+    let age = now.saturating_sub(updated_at).max(0) as u64;
+    if age > max_age_secs {
+        warn!(
+            "Chain tip is {}s old, maximum allowed is {}s",
+            age, max_age_secs
+        );
+        return Err(Error::Zcash(format!(
+            "Chain data stale: block height last updated {}s ago (max {}s)",
+            age, max_age_secs
+        )));
     }
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::{HashMap, HashSet};
+    use std::sync::Mutex;
+
+    /// In-memory payment store; reservations never expire on their own
+    #[derive(Default)]
+    struct MemoryPaymentStore {
+        state: Mutex<MemoryState>,
+    }
+
+    #[derive(Default)]
+    struct MemoryState {
+        payments: HashMap<String, PaymentInfo>,
+        reserved: HashSet<String>,
+        tracked: HashSet<String>,
+        policies: HashMap<String, u32>,
+        chain_tip: (Option<u32>, Option<i64>),
+    }
+
+    impl MemoryPaymentStore {
+        /// Store with a fresh chain tip at `height`
+        fn at_height(height: u32) -> Self {
+            let store = Self::default();
+            store.state.lock().unwrap().chain_tip =
+                (Some(height), Some(chrono::Utc::now().timestamp()));
+            store
+        }
+
+        /// Let a reservation lapse the way its TTL would, leaving it tracked
+        fn expire_reservation(&self, nullifier: &Nullifier) {
+            self.state
+                .lock()
+                .unwrap()
+                .reserved
+                .remove(&nullifier.to_hex());
+        }
+
+        fn add_payment(&self, nullifier: &Nullifier, amount: u64, block_height: u32) {
+            self.state.lock().unwrap().payments.insert(
+                nullifier.to_hex(),
+                PaymentInfo {
+                    amount,
+                    block_height,
+                    used: false,
+                    tx_id: "tx".to_string(),
+                },
+            );
+        }
+    }
+
+    #[async_trait]
+    impl PaymentStore for MemoryPaymentStore {
+        async fn get_payment(&self, nullifier_hex: &str) -> Result<Option<PaymentInfo>> {
+            Ok(self
+                .state
+                .lock()
+                .unwrap()
+                .payments
+                .get(nullifier_hex)
+                .cloned())
+        }
+
+        async fn is_reserved(&self, nullifier_hex: &str) -> Result<bool> {
+            Ok(self.state.lock().unwrap().reserved.contains(nullifier_hex))
+        }
+
+        async fn reserve(&self, nullifier_hex: &str, _ttl_secs: u64) -> Result<bool> {
+            let mut state = self.state.lock().unwrap();
+            if !state.reserved.insert(nullifier_hex.to_string()) {
+                return Ok(false);
+            }
+            state.tracked.insert(nullifier_hex.to_string());
+            Ok(true)
+        }
+
+        async fn confirm(&self, nullifier_hex: &str, _used_at: &str) -> Result<Option<bool>> {
+            let mut state = self.state.lock().unwrap();
+            if !state.payments.contains_key(nullifier_hex) {
+                return Ok(None);
+            }
+            state.reserved.remove(nullifier_hex);
+            state.tracked.remove(nullifier_hex);
+
+            let payment = state.payments.get_mut(nullifier_hex).unwrap();
+            let transitioned = !payment.used;
+            payment.used = true;
+            Ok(Some(transitioned))
+        }
+
+        async fn release(&self, nullifier_hex: &str) -> Result<()> {
+            let mut state = self.state.lock().unwrap();
+            state.reserved.remove(nullifier_hex);
+            state.tracked.remove(nullifier_hex);
+            Ok(())
+        }
+
+        async fn untrack_expired_reservations(&self) -> Result<usize> {
+            let mut state = self.state.lock().unwrap();
+            let MemoryState {
+                reserved, tracked, ..
+            } = &mut *state;
+            let before = tracked.len();
+            tracked.retain(|nullifier_hex| reserved.contains(nullifier_hex));
+            Ok(before - tracked.len())
+        }
+
+        async fn min_confirmations_policy(&self, customer_id: &str) -> Result<Option<u32>> {
+            Ok(self
+                .state
+                .lock()
+                .unwrap()
+                .policies
+                .get(customer_id)
+                .copied())
+        }
+
+        async fn set_min_confirmations_policy(
+            &self,
+            customer_id: &str,
+            min_confirmations: u32,
+        ) -> Result<()> {
+            self.state
+                .lock()
+                .unwrap()
+                .policies
+                .insert(customer_id.to_string(), min_confirmations);
+            Ok(())
+        }
+
+        async fn chain_tip(&self) -> Result<(Option<u32>, Option<i64>)> {
+            Ok(self.state.lock().unwrap().chain_tip)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reserve_then_confirm() {
+        let store = MemoryPaymentStore::at_height(110);
+        let nullifier = Nullifier::new([1u8; 32]);
+        store.add_payment(&nullifier, 1_000_000, 100);
+        let checker = PaymentChecker::with_store(store, PaymentConfig::default());
+
+        checker.check_payment(&nullifier).await.unwrap();
+        checker.reserve_payment(&nullifier).await.unwrap();
+
+        // A second request can't check or reserve the same payment meanwhile
+        let err = checker.check_payment(&nullifier).await.unwrap_err();
+        assert!(err.to_string().contains("reserved by another request"));
+        let err = checker.reserve_payment(&nullifier).await.unwrap_err();
+        assert!(err.to_string().contains("already reserved"));
+
+        assert!(checker.confirm_payment(&nullifier).await.unwrap());
+        assert!(!checker.confirm_payment(&nullifier).await.unwrap());
+
+        let err = checker.check_payment(&nullifier).await.unwrap_err();
+        assert!(err.to_string().contains("already used"));
+        assert_eq!(checker.sweep_expired_reservations().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_release_allows_retry() {
+        let store = MemoryPaymentStore::at_height(110);
+        let nullifier = Nullifier::new([2u8; 32]);
+        store.add_payment(&nullifier, 1_000_000, 100);
+        let checker = PaymentChecker::with_store(store, PaymentConfig::default());
+
+        checker.reserve_payment(&nullifier).await.unwrap();
+        checker.release_reservation(&nullifier).await.unwrap();
+
+        checker.check_payment(&nullifier).await.unwrap();
+        checker.reserve_payment(&nullifier).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sweep_untracks_only_lapsed_reservations() {
+        let store = MemoryPaymentStore::at_height(110);
+        let lapsed = Nullifier::new([9u8; 32]);
+        let live = Nullifier::new([10u8; 32]);
+        let renewed = Nullifier::new([11u8; 32]);
+        for nullifier in [&lapsed, &live, &renewed] {
+            store.add_payment(nullifier, 1_000_000, 100);
+        }
+        let checker = PaymentChecker::with_store(store, PaymentConfig::default());
+
+        for nullifier in [&lapsed, &live, &renewed] {
+            checker.reserve_payment(nullifier).await.unwrap();
+        }
+        checker.store.expire_reservation(&lapsed);
+        // Lapsed, then reserved again before the sweep ran
+        checker.store.expire_reservation(&renewed);
+        checker.reserve_payment(&renewed).await.unwrap();
+
+        assert_eq!(checker.sweep_expired_reservations().await.unwrap(), 1);
+        assert_eq!(checker.sweep_expired_reservations().await.unwrap(), 0);
+
+        let tracked = checker.store.state.lock().unwrap().tracked.clone();
+        assert!(!tracked.contains(&lapsed.to_hex()));
+        assert!(tracked.contains(&live.to_hex()));
+        assert!(tracked.contains(&renewed.to_hex()));
+    }
+
+    #[tokio::test]
+    async fn test_check_payment_rejections() {
+        let store = MemoryPaymentStore::at_height(110);
+        let small = Nullifier::new([3u8; 32]);
+        let recent = Nullifier::new([4u8; 32]);
+        store.add_payment(&small, DEFAULT_MIN_PAYMENT_AMOUNT - 1, 100);
+        store.add_payment(&recent, 1_000_000, 110);
+        let checker = PaymentChecker::with_store(store, PaymentConfig::default());
+
+        let err = checker
+            .check_payment(&Nullifier::new([5u8; 32]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Payment not found"));
+
+        let err = checker.check_payment(&small).await.unwrap_err();
+        assert!(err.to_string().contains("below minimum"));
+
+        let err = checker.check_payment(&recent).await.unwrap_err();
+        assert!(err.to_string().contains("need at least 1"));
+
+        let missing = Nullifier::new([6u8; 32]);
+        assert!(checker.confirm_payment(&missing).await.is_err());
+    }
 
     #[test]
     fn test_payment_config_defaults() {
@@ -328,6 +930,10 @@ mod tests {
         assert!(!config.require_payment);
         assert_eq!(config.min_payment_amount, DEFAULT_MIN_PAYMENT_AMOUNT);
         assert_eq!(config.min_confirmations, DEFAULT_MIN_CONFIRMATIONS);
+        assert_eq!(
+            config.max_chain_tip_age_secs,
+            DEFAULT_MAX_CHAIN_TIP_AGE_SECS
+        );
     }
 
     #[test]
@@ -336,10 +942,180 @@ mod tests {
         std::env::remove_var("REQUIRE_PAYMENT");
         std::env::remove_var("MIN_PAYMENT_AMOUNT");
         std::env::remove_var("MIN_CONFIRMATIONS");
+        std::env::remove_var("MAX_CHAIN_TIP_AGE_SECS");
 
         let config = PaymentConfig::from_env();
         assert!(!config.require_payment);
         assert_eq!(config.min_payment_amount, DEFAULT_MIN_PAYMENT_AMOUNT);
         assert_eq!(config.min_confirmations, DEFAULT_MIN_CONFIRMATIONS);
+        assert_eq!(
+            config.max_chain_tip_age_secs,
+            DEFAULT_MAX_CHAIN_TIP_AGE_SECS
+        );
+    }
+
+    #[test]
+    fn test_stale_chain_tip_rejected() {
+        let now = 1_700_000_000;
+
+        assert!(check_chain_tip_fresh(Some(now - 30), now, 600).is_ok());
+
+        let err = check_chain_tip_fresh(Some(now - 601), now, 600).unwrap_err();
+        assert!(err.to_string().contains("Chain data stale"));
+
+        let err = check_chain_tip_fresh(None, now, 600).unwrap_err();
+        assert!(err.to_string().contains("Chain data stale"));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis to be running
+    async fn test_sweeper_prunes_expired_reservation() {
+        let checker =
+            PaymentChecker::new("redis://localhost:6379", Some(15), PaymentConfig::default())
+                .unwrap();
+        let nullifier = Nullifier::new([7u8; 32]);
+        let nullifier_hex = nullifier.to_hex();
+
+        checker.release_reservation(&nullifier).await.unwrap();
+        checker.reserve_payment(&nullifier).await.unwrap();
+
+        // Shorten the TTL and let it lapse
+        let mut conn = checker.store.get_connection().await.unwrap();
+        conn.pexpire::<_, ()>(format!("reserved:{}", nullifier_hex), 10)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let removed = checker.sweep_expired_reservations().await.unwrap();
+        assert!(removed >= 1);
+
+        let still_tracked: bool = conn
+            .sismember("payments:reserved", &nullifier_hex)
+            .await
+            .unwrap();
+        assert!(!still_tracked);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis to be running
+    async fn test_confirm_payment_twice_is_idempotent() {
+        let checker =
+            PaymentChecker::new("redis://localhost:6379", Some(15), PaymentConfig::default())
+                .unwrap();
+        let nullifier = Nullifier::new([8u8; 32]);
+        let nullifier_hex = nullifier.to_hex();
+        let payment_key = format!("payment:{}", nullifier_hex);
+
+        let mut conn = checker.store.get_connection().await.unwrap();
+        conn.del::<_, ()>(&payment_key).await.unwrap();
+        conn.hset_multiple::<_, _, _, ()>(
+            &payment_key,
+            &[
+                ("amount", "1000000"),
+                ("block_height", "100"),
+                ("used", "false"),
+            ],
+        )
+        .await
+        .unwrap();
+        conn.sadd::<_, _, ()>("payments:unused", &nullifier_hex)
+            .await
+            .unwrap();
+
+        checker.release_reservation(&nullifier).await.unwrap();
+        checker.reserve_payment(&nullifier).await.unwrap();
+
+        assert!(checker.confirm_payment(&nullifier).await.unwrap());
+        let used_at: String = conn.hget(&payment_key, "used_at").await.unwrap();
+
+        // A retried confirmation reports it was already confirmed and changes nothing
+        assert!(!checker.confirm_payment(&nullifier).await.unwrap());
+        let used_at_again: String = conn.hget(&payment_key, "used_at").await.unwrap();
+        assert_eq!(used_at, used_at_again);
+
+        let used: String = conn.hget(&payment_key, "used").await.unwrap();
+        assert_eq!(used, "true");
+        let unused: bool = conn
+            .sismember("payments:unused", &nullifier_hex)
+            .await
+            .unwrap();
+        assert!(!unused);
+        let reserved: bool = conn
+            .exists(format!("reserved:{}", nullifier_hex))
+            .await
+            .unwrap();
+        assert!(!reserved);
+
+        // Confirming a payment that doesn't exist is an error, not a silent write
+        let missing = Nullifier::new([9u8; 32]);
+        conn.del::<_, ()>(format!("payment:{}", missing.to_hex()))
+            .await
+            .unwrap();
+        assert!(checker.confirm_payment(&missing).await.is_err());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis to be running
+    async fn test_customer_policy_requires_more_confirmations() {
+        let global =
+            PaymentChecker::new("redis://localhost:6379", Some(15), PaymentConfig::default())
+                .unwrap();
+        let strict = PaymentChecker::new(
+            "redis://localhost:6379",
+            Some(15),
+            PaymentConfig {
+                customer_id: Some("strict-customer".to_string()),
+                ..PaymentConfig::default()
+            },
+        )
+        .unwrap();
+        global
+            .set_min_confirmations_policy("strict-customer", 6)
+            .await
+            .unwrap();
+
+        // Payment 3 blocks deep: enough for the default, not for the strict policy
+        let nullifier = Nullifier::new([10u8; 32]);
+        let payment_key = format!("payment:{}", nullifier.to_hex());
+        let mut conn = global.store.get_connection().await.unwrap();
+        conn.del::<_, ()>(&payment_key).await.unwrap();
+        conn.hset_multiple::<_, _, _, ()>(
+            &payment_key,
+            &[
+                ("amount", "1000000"),
+                ("block_height", "100"),
+                ("used", "false"),
+            ],
+        )
+        .await
+        .unwrap();
+        global.release_reservation(&nullifier).await.unwrap();
+        conn.set::<_, _, ()>("chain:block_height", 103)
+            .await
+            .unwrap();
+        conn.set::<_, _, ()>(
+            "chain:block_height_updated_at",
+            chrono::Utc::now().timestamp(),
+        )
+        .await
+        .unwrap();
+
+        assert!(global.check_payment(&nullifier).await.is_ok());
+
+        let err = strict.check_payment(&nullifier).await.unwrap_err();
+        assert!(err.to_string().contains("need at least 6"));
+
+        // An unparseable policy fails the check instead of using the default
+        conn.hset::<_, _, _, ()>("payment_policy:strict-customer", "min_confirmations", "six")
+            .await
+            .unwrap();
+        let err = strict.check_payment(&nullifier).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Invalid min_confirmations in payment_policy:strict-customer"));
+        global
+            .set_min_confirmations_policy("strict-customer", 6)
+            .await
+            .unwrap();
     }
 }
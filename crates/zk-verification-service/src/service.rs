@@ -1,10 +1,15 @@
 //! Authorization service implementation for Envoy ExtAuth
 
-use khafi_common::{Nullifier, Receipt};
+use base64::Engine;
+use khafi_common::{GuestOutputs, Nullifier, Receipt, ReceiptKind};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
 use tonic::{Request, Response, Status};
+use tracing::Instrument;
 
-use crate::config::Config;
+use crate::config::{Config, RECEIPT_DECODE_LIMIT};
 use crate::nullifier::NullifierChecker;
 use crate::payment::PaymentChecker;
 
@@ -28,8 +33,10 @@ pub struct AuthorizationService {
 impl AuthorizationService {
     /// Create a new authorization service
     pub async fn new(config: Config) -> Result<Self, Box<dyn std::error::Error>> {
-        let nullifier_checker = NullifierChecker::new(&config.redis_url)?;
-        let payment_checker = PaymentChecker::new(&config.redis_url, config.payment.clone())?;
+        let nullifier_checker = NullifierChecker::new(&config.redis_url, config.redis_db)?
+            .with_namespace(config.nullifier_namespace());
+        let payment_checker =
+            PaymentChecker::new(&config.redis_url, config.redis_db, config.payment.clone())?;
 
         Ok(Self {
             nullifier_checker,
@@ -39,44 +46,64 @@ impl AuthorizationService {
     }
 
     /// Convert this service into a tonic gRPC server
-    pub fn into_service(self) -> AuthorizationServer<Self> {
-        AuthorizationServer::new(self)
+    ///
+    /// Takes an `Arc` so the HTTP gateway can share the same service.
+    pub fn into_service(self: Arc<Self>) -> AuthorizationServer<Self> {
+        AuthorizationServer::from_arc(self)
+    }
+
+    /// Service configuration
+    pub fn config(&self) -> &Config {
+        &self.config
     }
 
     /// Verify a RISC Zero proof
     ///
     /// # Arguments
-    /// * `receipt_hex` - Hex-encoded Receipt bytes
+    /// * `receipt` - Hex- or base64-encoded Receipt bytes
+    /// * `encoding` - Which of the two `receipt` uses
+    /// * `kind` - Receipt kind the prover reported, checked before verifying (optional)
     ///
     /// # Returns
-    /// * `Ok(nullifier)` - Proof verified successfully, returns the nullifier
+    /// * `Ok(outputs)` - Proof verified successfully, returns the decoded journal
     /// * `Err(Status)` - Verification failed
-    async fn verify_proof(&self, receipt_hex: &str) -> Result<Nullifier, Status> {
-        // Decode hex-encoded receipt bytes
-        let receipt_bytes = hex::decode(receipt_hex)
-            .map_err(|e| Status::invalid_argument(format!("Invalid receipt hex: {}", e)))?;
-
-        // Deserialize Receipt
-        let (receipt, _): (Receipt, usize) =
-            bincode::serde::decode_from_slice(&receipt_bytes, bincode::config::standard())
-                .map_err(|e| {
-                    Status::invalid_argument(format!("Failed to deserialize receipt: {}", e))
-                })?;
+    async fn verify_proof(
+        &self,
+        receipt: &str,
+        encoding: ReceiptEncoding,
+        kind: Option<ReceiptKind>,
+    ) -> Result<GuestOutputs, Status> {
+        let receipt = decode_receipt(receipt, encoding, self.config.max_receipt_bytes)?;
+        if let Some(kind) = kind {
+            check_receipt_kind(&receipt, kind)?;
+        }
 
-        // Verify proof and decode outputs in one step
-        let outputs = receipt
-            .verify_and_decode(&self.config.image_id)
-            .map_err(|e| {
-                tracing::warn!("Proof verification failed: {}", e);
-                Status::permission_denied(format!("Proof verification failed: {}", e))
+        // Receipts from a recently retired guest still verify against its image ID
+        let image_id = self
+            .config
+            .accepted_image_id(&receipt.image_id, chrono::Utc::now())
+            .ok_or_else(|| {
+                tracing::warn!("Receipt for retired image ID {}", receipt.image_id_hex());
+                Status::permission_denied(format!(
+                    "Image ID {} was retired and its grace period has ended",
+                    receipt.image_id_hex()
+                ))
             })?;
 
+        // Verify proof and decode outputs in one step
+        let outputs = if self.config.strict_journal {
+            receipt.verify_and_decode_strict(&image_id, self.config.journal_encoding)
+        } else {
+            receipt.verify_and_decode_with(&image_id, self.config.journal_encoding)
+        }
+        .map_err(|e| {
+            tracing::warn!("Proof verification failed: {}", e);
+            Status::permission_denied(format!("Proof verification failed: {}", e))
+        })?;
+
         // Check compliance result
         if !outputs.compliance_result {
-            tracing::warn!("Business logic validation failed");
-            return Err(Status::permission_denied(
-                "Business logic validation failed",
-            ));
+            return Err(validation_failed(&outputs));
         }
 
         tracing::debug!(
@@ -84,12 +111,9 @@ impl AuthorizationService {
             outputs.nullifier.to_hex()
         );
 
-        Ok(outputs.nullifier)
+        Ok(outputs)
     }
-}
 
-#[tonic::async_trait]
-impl Authorization for AuthorizationService {
     /// Check authorization based on ZK proof and nullifier
     ///
     /// The verification flow is:
@@ -97,28 +121,47 @@ impl Authorization for AuthorizationService {
     /// 2. If payment required: verify payment exists and reserve it
     /// 3. Verify ZK proof (expensive)
     /// 4. Verify nullifier consistency between header and proof
-    /// 5. Return success with nullifier in response metadata
-    async fn check(
-        &self,
-        request: Request<CheckRequest>,
-    ) -> Result<Response<CheckResponse>, Status> {
-        let req = request.into_inner();
-
+    /// 5. Return success with nullifier and guest metadata in response metadata
+    ///
+    /// Shared by the gRPC `Check` call and the HTTP gateway.
+    pub async fn authorize(&self, req: CheckRequest) -> Result<Response<CheckResponse>, Status> {
         tracing::debug!(
             "Received authorization check request for path: {:?}",
             req.path
         );
 
-        // Extract x-zk-receipt header
-        let receipt_hex = req.headers.get("x-zk-receipt").ok_or_else(|| {
-            tracing::warn!("Missing x-zk-receipt header");
-            Status::unauthenticated("Missing x-zk-receipt header")
+        // Extract receipt header (x-zk-receipt unless configured otherwise)
+        let receipt_header = &self.config.receipt_header;
+        let receipt = req.headers.get(receipt_header).ok_or_else(|| {
+            tracing::warn!("Missing {} header", receipt_header);
+            Status::unauthenticated(format!("Missing {} header", receipt_header))
         })?;
 
-        // Extract x-zk-nullifier header
-        let nullifier_hex = req.headers.get("x-zk-nullifier").ok_or_else(|| {
-            tracing::warn!("Missing x-zk-nullifier header");
-            Status::unauthenticated("Missing x-zk-nullifier header")
+        // Receipts are hex-encoded unless the encoding header says otherwise
+        let receipt_encoding = match req.headers.get(&self.config.receipt_encoding_header) {
+            Some(value) => value.parse().map_err(|e: khafi_common::Error| {
+                tracing::warn!("Invalid receipt encoding: {}", e);
+                Status::invalid_argument(e.to_string())
+            })?,
+            None => ReceiptEncoding::Hex,
+        };
+
+        // The receipt kind is an optional hint, checked against the receipt itself
+        let receipt_kind = req
+            .headers
+            .get(&self.config.receipt_kind_header)
+            .map(|value| value.parse::<ReceiptKind>())
+            .transpose()
+            .map_err(|e| {
+                tracing::warn!("Invalid receipt kind: {}", e);
+                Status::invalid_argument(e.to_string())
+            })?;
+
+        // Extract nullifier header (x-zk-nullifier unless configured otherwise)
+        let nullifier_header = &self.config.nullifier_header;
+        let nullifier_hex = req.headers.get(nullifier_header).ok_or_else(|| {
+            tracing::warn!("Missing {} header", nullifier_header);
+            Status::unauthenticated(format!("Missing {} header", nullifier_header))
         })?;
 
         // Parse nullifier
@@ -128,14 +171,21 @@ impl Authorization for AuthorizationService {
         })?;
 
         // Check for replay attack (must do this BEFORE proof verification to save computation)
-        let is_new = self
-            .nullifier_checker
-            .check_and_set(&nullifier)
-            .await
-            .map_err(|e| {
-                tracing::error!("Redis error: {}", e);
-                Status::unavailable(format!("Nullifier checker unavailable: {}", e))
-            })?;
+        let is_new = if self.config.replay_bypassed(&nullifier) {
+            tracing::warn!(
+                "REPLAY PROTECTION BYPASSED for nullifier {} (staging/QA only)",
+                nullifier.to_hex()
+            );
+            true
+        } else {
+            self.nullifier_checker
+                .check_and_set(&nullifier)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Redis error: {}", e);
+                    Status::unavailable(format!("Nullifier checker unavailable: {}", e))
+                })?
+        };
 
         if !is_new {
             tracing::warn!("Nullifier replay detected: {}", nullifier.to_hex());
@@ -185,8 +235,11 @@ impl Authorization for AuthorizationService {
         };
 
         // Verify the proof
-        let verified_nullifier = match self.verify_proof(receipt_hex).await {
-            Ok(n) => n,
+        let outputs = match self
+            .verify_proof(receipt, receipt_encoding, receipt_kind)
+            .await
+        {
+            Ok(outputs) => outputs,
             Err(status) => {
                 // Proof verification failed - release payment reservation if we made one
                 if payment_reserved {
@@ -203,7 +256,7 @@ impl Authorization for AuthorizationService {
         };
 
         // Verify the nullifier from the proof matches the one in the header
-        if verified_nullifier.0 != nullifier.0 {
+        if outputs.nullifier.0 != nullifier.0 {
             tracing::warn!("Nullifier mismatch: header != proof");
             // Release payment reservation if we made one
             if payment_reserved {
@@ -220,10 +273,16 @@ impl Authorization for AuthorizationService {
 
         // All checks passed - confirm payment usage
         if payment_reserved {
-            if let Err(e) = self.payment_checker.confirm_payment(&nullifier).await {
-                tracing::error!("Failed to confirm payment: {}", e);
-                // Payment confirmation failure is not fatal - the reservation will expire
-                // and the payment can be retried. Log the error but proceed.
+            match self.payment_checker.confirm_payment(&nullifier).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    tracing::warn!("Payment was already confirmed: {}", nullifier.to_hex());
+                }
+                Err(e) => {
+                    tracing::error!("Failed to confirm payment: {}", e);
+                    // Payment confirmation failure is not fatal - the reservation will expire
+                    // and the payment can be retried. Log the error but proceed.
+                }
             }
         }
 
@@ -232,17 +291,458 @@ impl Authorization for AuthorizationService {
             nullifier.to_hex()
         );
 
-        // Create response metadata with nullifier for downstream services
-        let mut metadata = HashMap::new();
-        metadata.insert(
-            "x-payment-nullifier".to_string(),
-            nullifier.to_hex(),
-        );
-
         Ok(Response::new(CheckResponse {
             status: StatusCode::Ok as i32,
             message: "Proof verified successfully".to_string(),
-            metadata,
+            metadata: response_metadata(&outputs),
         }))
     }
 }
+
+/// Denial for a proof whose business validation failed
+///
+/// Names the failing rule when the guest reports it, which it only does if
+/// the customer's DSL opted in with `report_failed_rule`.
+fn validation_failed(outputs: &GuestOutputs) -> Status {
+    let message = match outputs.failed_rule_index() {
+        Some(index) => format!("Business logic validation failed at rule {}", index),
+        None => "Business logic validation failed".to_string(),
+    };
+    tracing::warn!("{}", message);
+    Status::permission_denied(message)
+}
+
+/// Response metadata forwarded to downstream services
+///
+/// Always carries the nullifier. Non-empty guest metadata (compliance
+/// attestations) is forwarded as `x-zk-metadata` when it's printable text,
+/// otherwise hex-encoded as `x-zk-metadata-hex` so it stays a valid header.
+fn response_metadata(outputs: &GuestOutputs) -> HashMap<String, String> {
+    let mut metadata = HashMap::new();
+    metadata.insert(
+        "x-payment-nullifier".to_string(),
+        outputs.nullifier.to_hex(),
+    );
+
+    if !outputs.metadata.is_empty() {
+        match std::str::from_utf8(&outputs.metadata) {
+            Ok(text) if !text.chars().any(|c| c.is_control()) => {
+                metadata.insert("x-zk-metadata".to_string(), text.to_string());
+            }
+            _ => {
+                metadata.insert(
+                    "x-zk-metadata-hex".to_string(),
+                    hex::encode(&outputs.metadata),
+                );
+            }
+        }
+    }
+
+    metadata
+}
+
+/// Text encoding of the receipt header
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReceiptEncoding {
+    /// Hex (the default)
+    #[default]
+    Hex,
+    /// Standard padded base64
+    Base64,
+}
+
+impl ReceiptEncoding {
+    /// Value of the encoding header for this encoding
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReceiptEncoding::Hex => "hex",
+            ReceiptEncoding::Base64 => "base64",
+        }
+    }
+
+    /// Number of bytes an encoded receipt of `encoded_len` characters decodes to
+    fn decoded_len(&self, encoded_len: usize) -> usize {
+        match self {
+            ReceiptEncoding::Hex => encoded_len / 2,
+            ReceiptEncoding::Base64 => encoded_len / 4 * 3,
+        }
+    }
+
+    /// Decode the receipt header into serialized receipt bytes
+    fn decode(&self, receipt: &str) -> Result<Vec<u8>, Status> {
+        match self {
+            ReceiptEncoding::Hex => hex::decode(receipt)
+                .map_err(|e| Status::invalid_argument(format!("Invalid receipt hex: {}", e))),
+            ReceiptEncoding::Base64 => base64::engine::general_purpose::STANDARD
+                .decode(receipt)
+                .map_err(|e| Status::invalid_argument(format!("Invalid receipt base64: {}", e))),
+        }
+    }
+}
+
+impl FromStr for ReceiptEncoding {
+    type Err = khafi_common::Error;
+
+    fn from_str(s: &str) -> khafi_common::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "hex" => Ok(Self::Hex),
+            "base64" => Ok(Self::Base64),
+            other => Err(khafi_common::Error::Other(anyhow::anyhow!(
+                "Unknown receipt encoding: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Decode a hex- or base64-encoded, bincode-serialized receipt
+///
+/// The size is checked against `max_bytes` before any decoding work,
+/// and bincode itself is bounded by [`RECEIPT_DECODE_LIMIT`] so a forged
+/// length prefix can't trigger a huge allocation.
+fn decode_receipt(
+    receipt: &str,
+    encoding: ReceiptEncoding,
+    max_bytes: usize,
+) -> Result<Receipt, Status> {
+    let decoded_len = encoding.decoded_len(receipt.len());
+    if decoded_len > max_bytes {
+        tracing::warn!(
+            "Receipt too large: {} bytes (max {})",
+            decoded_len,
+            max_bytes
+        );
+        return Err(Status::invalid_argument(format!(
+            "Receipt too large: {} bytes (max {})",
+            decoded_len, max_bytes
+        )));
+    }
+
+    let receipt_bytes = encoding.decode(receipt)?;
+
+    // Deserialize Receipt
+    let config = bincode::config::standard().with_limit::<RECEIPT_DECODE_LIMIT>();
+    let (receipt, _): (Receipt, usize) = bincode::serde::decode_from_slice(&receipt_bytes, config)
+        .map_err(|e| Status::invalid_argument(format!("Failed to deserialize receipt: {}", e)))?;
+
+    Ok(receipt)
+}
+
+/// Check a receipt is of the kind its sender claims
+///
+/// Catches a prover and verifier that disagree on receipt format with a clear
+/// message, rather than a generic verification failure.
+pub(crate) fn check_receipt_kind(receipt: &Receipt, expected: ReceiptKind) -> Result<(), Status> {
+    let actual = receipt
+        .kind()
+        .map_err(|e| Status::invalid_argument(format!("Failed to read receipt kind: {}", e)))?;
+
+    if actual != expected {
+        tracing::warn!(
+            "Receipt kind mismatch: expected {}, got {}",
+            expected,
+            actual
+        );
+        return Err(Status::invalid_argument(format!(
+            "Receipt kind mismatch: expected {}, got {}",
+            expected, actual
+        )));
+    }
+
+    Ok(())
+}
+
+#[tonic::async_trait]
+impl Authorization for AuthorizationService {
+    /// Check authorization, continuing the caller's trace from the forwarded headers
+    async fn check(
+        &self,
+        request: Request<CheckRequest>,
+    ) -> Result<Response<CheckResponse>, Status> {
+        let req = request.into_inner();
+
+        let span = tracing::info_span!("authz_check", path = %req.path);
+        khafi_telemetry::set_parent_from_map(&span, &req.headers);
+
+        self.authorize(req).instrument(span).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oversized_receipt_rejected() {
+        // Not valid hex either: if decoding were attempted we'd get a hex error instead
+        let receipt_hex = "zz".repeat(1025);
+
+        let status = decode_receipt(&receipt_hex, ReceiptEncoding::Hex, 1024).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert!(status.message().starts_with("Receipt too large"));
+    }
+
+    fn check_request(headers: &[(&str, &str)]) -> CheckRequest {
+        CheckRequest {
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: String::new(),
+            path: "/api/prove".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_header_names() {
+        let mut config = Config::from_env().unwrap();
+        config.receipt_header = "x-proof".to_string();
+        config.nullifier_header = "x-payment-id".to_string();
+        let service = AuthorizationService::new(config).await.unwrap();
+
+        // Default header names are no longer read
+        let status = service
+            .authorize(check_request(&[
+                ("x-zk-receipt", "00"),
+                ("x-zk-nullifier", "00"),
+            ]))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        assert_eq!(status.message(), "Missing x-proof header");
+
+        let status = service
+            .authorize(check_request(&[("x-proof", "00")]))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        assert_eq!(status.message(), "Missing x-payment-id header");
+
+        // Renamed headers are read: the nullifier gets parsed (and rejected)
+        let status = service
+            .authorize(check_request(&[("x-proof", "00"), ("x-payment-id", "zz")]))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert!(status.message().starts_with("Invalid nullifier format"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_receipt_encoding_rejected() {
+        let service = AuthorizationService::new(Config::from_env().unwrap())
+            .await
+            .unwrap();
+
+        let status = service
+            .authorize(check_request(&[
+                ("x-zk-receipt", "00"),
+                ("x-zk-receipt-encoding", "base32"),
+            ]))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(status.message(), "Unknown receipt encoding: base32");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_receipt_kind_rejected() {
+        let service = AuthorizationService::new(Config::from_env().unwrap())
+            .await
+            .unwrap();
+
+        let status = service
+            .authorize(check_request(&[
+                ("x-zk-receipt", "00"),
+                ("x-zk-receipt-kind", "stark"),
+            ]))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(status.message(), "Unknown receipt kind: stark");
+    }
+
+    #[test]
+    fn test_receipt_kind_of_unreadable_receipt() {
+        // Not a serialized RISC Zero receipt, so there is no kind to compare
+        let receipt = Receipt::new(vec![0xca, 0xfe, 0xba, 0xbe], [7u8; 32]);
+
+        let status = check_receipt_kind(&receipt, ReceiptKind::Succinct).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert!(status.message().starts_with("Failed to read receipt kind"));
+    }
+
+    #[tokio::test]
+    async fn test_bypassed_nullifier_skips_replay_check() {
+        let bypassed = "01".repeat(32);
+        let mut config = Config::from_env().unwrap();
+        config.replay_bypass = [Nullifier::from_hex(&bypassed).unwrap()].into();
+        let service = AuthorizationService::new(config).await.unwrap();
+
+        // Never touches Redis, so every attempt reaches proof verification
+        for _ in 0..2 {
+            let response = service
+                .authorize(check_request(&[
+                    ("x-zk-receipt", "00"),
+                    ("x-zk-nullifier", &bypassed),
+                ]))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(response.status, StatusCode::PermissionDenied as i32);
+            assert!(response
+                .message
+                .starts_with("Failed to deserialize receipt"));
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis to be running
+    async fn test_replay_bypass_only_for_listed_nullifiers() {
+        let bypassed = "01".repeat(32);
+        // Fresh nullifier per run so earlier runs don't count as replays
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let mut bytes = [0u8; 32];
+        bytes[..16].copy_from_slice(&nanos.to_le_bytes());
+        let normal = hex::encode(bytes);
+        let mut config = Config::from_env().unwrap();
+        config.replay_bypass = [Nullifier::from_hex(&bypassed).unwrap()].into();
+        let service = AuthorizationService::new(config).await.unwrap();
+
+        let attempt = |nullifier: String| {
+            let service = &service;
+            async move {
+                service
+                    .authorize(check_request(&[
+                        ("x-zk-receipt", "00"),
+                        ("x-zk-nullifier", &nullifier),
+                    ]))
+                    .await
+                    .unwrap()
+                    .into_inner()
+                    .message
+            }
+        };
+
+        // The listed nullifier can be replayed...
+        for _ in 0..2 {
+            assert_ne!(attempt(bypassed.clone()).await, "Nullifier replay detected");
+        }
+
+        // ...while a normal one is rejected the second time
+        assert_ne!(attempt(normal.clone()).await, "Nullifier replay detected");
+        assert_eq!(attempt(normal).await, "Nullifier replay detected");
+    }
+
+    #[test]
+    fn test_guest_metadata_propagated() {
+        let mut outputs = GuestOutputs::success(Nullifier::new([5u8; 32]));
+        let metadata = response_metadata(&outputs);
+        assert_eq!(metadata.len(), 1);
+        assert_eq!(metadata["x-payment-nullifier"], "05".repeat(32));
+
+        outputs.metadata = b"age_verified_over_18".to_vec();
+        let metadata = response_metadata(&outputs);
+        assert_eq!(metadata["x-payment-nullifier"], "05".repeat(32));
+        assert_eq!(metadata["x-zk-metadata"], "age_verified_over_18");
+
+        // Binary metadata can't go in a header as-is
+        outputs.metadata = vec![0x00, 0xff, 0x10];
+        let metadata = response_metadata(&outputs);
+        assert_eq!(metadata["x-zk-metadata-hex"], "00ff10");
+        assert!(!metadata.contains_key("x-zk-metadata"));
+    }
+
+    #[test]
+    fn test_failing_rule_index_surfaced() {
+        let nullifier = Nullifier::new([5u8; 32]);
+
+        let outputs =
+            GuestOutputs::with_metadata(nullifier.clone(), false, b"failed_rule:3".to_vec());
+        let status = validation_failed(&outputs);
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+        assert_eq!(
+            status.message(),
+            "Business logic validation failed at rule 3"
+        );
+
+        // Guests that don't opt in report nothing beyond the failure
+        let status = validation_failed(&GuestOutputs::failure(nullifier));
+        assert_eq!(status.message(), "Business logic validation failed");
+    }
+
+    #[tokio::test]
+    async fn test_retired_image_id_accepted_during_grace_period() {
+        let retired_id = [9u8; 32];
+        let receipt = Receipt::new(vec![0xca, 0xfe, 0xba, 0xbe], retired_id);
+        let receipt_hex = hex::encode(
+            bincode::serde::encode_to_vec(&receipt, bincode::config::standard()).unwrap(),
+        );
+
+        let verify = |retired_at| {
+            let mut config = Config::from_env().unwrap();
+            config.retired_image_ids = vec![crate::config::RetiredImageId {
+                image_id: retired_id,
+                retired_at,
+            }];
+            config.image_id_grace_period = chrono::Duration::hours(1);
+            let receipt_hex = receipt_hex.clone();
+            async move {
+                let service = AuthorizationService::new(config).await.unwrap();
+                service
+                    .verify_proof(&receipt_hex, ReceiptEncoding::Hex, None)
+                    .await
+                    .unwrap_err()
+            }
+        };
+
+        // Within the window the receipt reaches verification against the retired ID
+        // (and fails only because it isn't a real proof)
+        let status = verify(chrono::Utc::now() - chrono::Duration::minutes(30)).await;
+        assert!(
+            status.message().starts_with("Proof verification failed"),
+            "{}",
+            status.message()
+        );
+
+        // After the window it's rejected outright
+        let status = verify(chrono::Utc::now() - chrono::Duration::hours(2)).await;
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+        assert_eq!(
+            status.message(),
+            format!(
+                "Image ID {} was retired and its grace period has ended",
+                "09".repeat(32)
+            )
+        );
+    }
+
+    #[test]
+    fn test_receipt_within_limit_is_decoded() {
+        let status = decode_receipt("zz", ReceiptEncoding::Hex, 1024).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert!(status.message().starts_with("Invalid receipt hex"));
+    }
+
+    #[test]
+    fn test_base64_receipt_round_trips() {
+        let receipt = Receipt::new(vec![0xca, 0xfe, 0xba, 0xbe], [7u8; 32]);
+        let config = bincode::config::standard();
+        let receipt_bytes = bincode::serde::encode_to_vec(&receipt, config).unwrap();
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&receipt_bytes);
+        assert!(encoded.len() < hex::encode(&receipt_bytes).len());
+
+        let decoded = decode_receipt(&encoded, ReceiptEncoding::Base64, 1024).unwrap();
+        assert_eq!(
+            bincode::serde::encode_to_vec(&decoded, config).unwrap(),
+            receipt_bytes
+        );
+
+        // Base64 isn't accepted as hex
+        let status = decode_receipt(&encoded, ReceiptEncoding::Hex, 1024).unwrap_err();
+        assert!(status.message().starts_with("Invalid receipt hex"));
+    }
+}
@@ -44,7 +44,9 @@ async function fetchAPI<T>(
       );
     }
 
-    return await response.json();
+    // Successful responses are wrapped as { data: ... }
+    const body = await response.json();
+    return body.data;
   } catch (error) {
     if (error instanceof ApiError) {
       throw error;
@@ -30,7 +30,12 @@ export type ValidationRule =
   | AgeVerificationRule
   | BlacklistCheckRule
   | ArrayIntersectionCheckRule
-  | CustomRule;
+  | ChecksumCheckRule
+  | IsoCountryCheckRule
+  | DateRangeCheckRule
+  | CustomRule
+  | NotRule
+  | AnyOfRule;
 
 export interface SignatureCheckRule {
   type: 'signature_check';
@@ -74,17 +79,65 @@ export interface ArrayIntersectionCheckRule {
   must_be_empty?: boolean;
 }
 
+export interface ChecksumCheckRule {
+  type: 'checksum_check';
+  description?: string;
+  field: string;
+  algorithm: 'luhn' | 'mod97';
+}
+
+export interface IsoCountryCheckRule {
+  type: 'iso_country_check';
+  description?: string;
+  field: string;
+  set_param?: string;
+  exclude?: boolean;
+}
+
+export interface DateRangeCheckRule {
+  type: 'date_range_check';
+  description?: string;
+  date_field: string;
+  reference_date_param: string;
+  after?: string;
+  after_param?: string;
+  before?: string;
+  before_param?: string;
+  within_days?: number;
+  within_days_param?: string;
+}
+
 export interface CustomRule {
   type: 'custom';
   description?: string;
   code: string;
 }
 
+export interface NotRule {
+  type: 'not';
+  description?: string;
+  rule: ValidationRule;
+}
+
+export interface AnyOfRule {
+  type: 'any_of';
+  description?: string;
+  rules: ValidationRule[];
+}
+
 // API Response Types
 
+export interface DslValidationError {
+  rule_index: number | null;
+  field: string | null;
+  message: string;
+  code: string;
+}
+
 export interface ValidateResponse {
   valid: boolean;
   error?: string;
+  errors?: DslValidationError[];
   parsed_dsl?: BusinessRulesDSL;
 }
 